@@ -0,0 +1,70 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::hint::black_box;
+use std::path::Path;
+
+use p2000_rust::enrich;
+use p2000_rust::location::LocationLookup;
+use p2000_rust::lookup::Lookup;
+use p2000_rust::parser::Parser;
+
+fn sample_lines() -> Vec<String> {
+    std::fs::read_to_string("data/p2000-1.txt")
+        .expect("Benchmarks run from the crate root")
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+fn parse(c: &mut Criterion) {
+    let lines = sample_lines();
+    let parser = Parser::new();
+    let mut group = c.benchmark_group("parse");
+    group.throughput(Throughput::Elements(lines.len() as u64));
+    group.bench_function("parse_line", |b| {
+        b.iter(|| {
+            for line in &lines {
+                let _ = black_box(parser.parse_line(black_box(line)));
+            }
+        })
+    });
+    group.finish();
+}
+
+fn enrichment(c: &mut Criterion) {
+    let parser = Parser::new();
+    let messages: Vec<_> = sample_lines()
+        .iter()
+        .filter_map(|line| parser.parse_line(line).ok())
+        .collect();
+    let lookup = Lookup::load(
+        Path::new("data/capcodelist.csv"),
+        Path::new("data/abbrevations.txt"),
+    )
+    .unwrap();
+    let location_lookup = LocationLookup::load(
+        Path::new("data/Observations.csv"),
+        Path::new("data/RegioSCodes.csv"),
+    )
+    .unwrap();
+
+    let mut group = c.benchmark_group("enrich");
+    group.throughput(Throughput::Elements(messages.len() as u64));
+    group.bench_function("enrich", |b| {
+        b.iter(|| {
+            for msg in &messages {
+                black_box(enrich::enrich(msg, &lookup, &location_lookup));
+            }
+        })
+    });
+    group.bench_function("find_location_by_text", |b| {
+        b.iter(|| {
+            for msg in &messages {
+                black_box(location_lookup.find_location_by_text(&msg.content));
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, parse, enrichment);
+criterion_main!(benches);
@@ -0,0 +1,44 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use std::path::Path;
+use std::sync::OnceLock;
+
+use p2000_rust::enrich;
+use p2000_rust::location::LocationLookup;
+use p2000_rust::lookup::Lookup;
+use p2000_rust::parser::Parser;
+
+struct Tables {
+    parser: Parser,
+    lookup: Lookup,
+    location_lookup: LocationLookup,
+}
+
+fn tables() -> &'static Tables {
+    static TABLES: OnceLock<Tables> = OnceLock::new();
+    TABLES.get_or_init(|| {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("../data");
+        Tables {
+            parser: Parser::new(),
+            lookup: Lookup::load(&data.join("capcodelist.csv"), &data.join("abbrevations.txt"))
+                .expect("Failed to load capcodes"),
+            location_lookup: LocationLookup::load(
+                &data.join("Observations.csv"),
+                &data.join("RegioSCodes.csv"),
+            )
+            .expect("Failed to load locations"),
+        }
+    })
+}
+
+// Same path as the daemon: decode lossily, then parse and enrich every line
+fuzz_target!(|data: &[u8]| {
+    let tables = tables();
+    for line in String::from_utf8_lossy(data).lines() {
+        if let Ok(msg) = tables.parser.parse_line(line) {
+            let _ = msg.to_line();
+            enrich::enrich(&msg, &tables.lookup, &tables.location_lookup);
+        }
+    }
+});
@@ -0,0 +1,293 @@
+//! Street addresses in message content, and how often each comes back: a fire
+//! alarm (OMS) that keeps going off or a problem location rises to the top of
+//! `p2000-rust addresses`.
+
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::io::Write;
+
+use crate::lookup::normalize_code;
+use crate::query::OutputFormat;
+use crate::tokenize::{tokenize, Token, TokenKind};
+
+pub const USAGE: &str = "Usage: p2000-rust addresses [--min <n>] [query options]
+Lists addresses seen at least <n> times (default 2), most frequent first. Takes the options of \
+`p2000-rust query`; --since defaults to 30d and --limit caps the addresses listed.";
+
+/// Word endings of Dutch street names
+const STREET_SUFFIXES: &[&str] = &[
+    "straat", "laan", "weg", "plein", "kade", "dijk", "singel", "gracht", "dreef", "hof", "park", "pad",
+    "steeg", "markt", "baan", "ring", "allee", "plantsoen", "wal", "kanaal", "haven",
+];
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Address {
+    /// As written, including leading name parts: `Admiraal De Ruijterweg`
+    pub street: String,
+    pub postcode: Option<String>,
+}
+
+/// The first street in `content`: a word with a street ending, or any
+/// capitalized word right before a postcode, together with the capitalized
+/// words before it.
+pub fn extract(content: &str) -> Option<Address> {
+    let tokens = tokenize(content);
+    let postcode_after = |i: usize| tokens.get(i + 1).filter(|t| t.kind == TokenKind::Postcode);
+    let end = tokens.iter().enumerate().position(|(i, token)| {
+        token.kind == TokenKind::Word
+            && (has_street_suffix(token.text) || (postcode_after(i).is_some() && is_name_part(token)))
+    })?;
+    let mut start = end;
+    while start > 0 && tokens[start].spaced && is_name_part(&tokens[start - 1]) {
+        start -= 1;
+    }
+    let street = content[tokens[start].span.start..tokens[end].span.end].to_string();
+    Some(Address {
+        street,
+        postcode: postcode_after(end).map(|t| t.text.to_string()),
+    })
+}
+
+fn has_street_suffix(word: &str) -> bool {
+    let lower = word.to_lowercase();
+    STREET_SUFFIXES.iter().any(|suffix| lower.len() > suffix.len() && lower.ends_with(suffix))
+}
+
+/// Capitalized but not an abbreviation: `Hendrik`, `De`, not `BR` or `A1`.
+fn is_name_part(token: &Token) -> bool {
+    token.kind == TokenKind::Word
+        && token.text.chars().next().is_some_and(char::is_uppercase)
+        && token.text.chars().any(char::is_lowercase)
+}
+
+/// The messages about one incident: those naming the same street in the same
+/// place, like the updates on a fire that keeps growing.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Incident {
+    /// Street and place: `Nassaulaan, Vlaardingen`
+    pub address: String,
+    // Lowercased street and place
+    key: (String, String),
+}
+
+impl Incident {
+    /// The incident `content` is about, when it names a street.
+    pub fn of(content: &str, place: Option<&str>) -> Option<Self> {
+        let street = extract(content)?.street;
+        let place = place.unwrap_or("");
+        Some(Incident {
+            address: match place {
+                "" => street.clone(),
+                place => format!("{}, {}", street, place),
+            },
+            key: (street.to_lowercase(), place.to_lowercase()),
+        })
+    }
+
+    /// Whether a message with `content` at `place` is about this incident.
+    pub fn includes(&self, content: &str, place: Option<&str>) -> bool {
+        Incident::of(content, place).is_some_and(|other| other.key == self.key)
+    }
+}
+
+/// How big an incident has grown by one of its messages: the distinct
+/// capcodes and services paged for it so far. More units paged over time is a
+/// fair sign of a growing incident.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Growth {
+    /// Messages about the incident so far, this one included
+    pub messages: usize,
+    pub capcodes: usize,
+    pub services: usize,
+    /// This message paged capcodes that earlier ones did not
+    pub escalated: bool,
+}
+
+/// Running sizes of incidents, fed their messages in order of arrival.
+#[derive(Debug, Default)]
+pub struct IncidentSizes {
+    incidents: HashMap<(String, String), Paged>,
+}
+
+#[derive(Debug, Default)]
+struct Paged {
+    messages: usize,
+    capcodes: HashSet<String>,
+    services: HashSet<String>,
+}
+
+impl IncidentSizes {
+    /// Adds a message about `incident` paging `capcodes` of `services`, and
+    /// returns the size of the incident with it.
+    pub fn add<'a>(
+        &mut self,
+        incident: &Incident,
+        capcodes: impl IntoIterator<Item = &'a str>,
+        services: impl IntoIterator<Item = &'a str>,
+    ) -> Growth {
+        let paged = self.incidents.entry(incident.key.clone()).or_default();
+        let before = paged.capcodes.len();
+        paged.capcodes.extend(capcodes.into_iter().map(normalize_code));
+        paged.services.extend(services.into_iter().filter(|s| !s.is_empty()).map(str::to_lowercase));
+        paged.messages += 1;
+        Growth {
+            messages: paged.messages,
+            capcodes: paged.capcodes.len(),
+            services: paged.services.len(),
+            escalated: paged.messages > 1 && paged.capcodes.len() > before,
+        }
+    }
+}
+
+/// An address and when it was seen.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct AddressCount {
+    /// Street and place: `Nassaulaan, Vlaardingen`
+    pub address: String,
+    pub count: usize,
+    pub first_seen: DateTime<Local>,
+    pub last_seen: DateTime<Local>,
+    /// Content of the latest message, to see what keeps happening there
+    pub last_content: String,
+}
+
+/// Addresses in `messages`, given as time, content and place, seen at least
+/// `min_count` times; most frequent first, then most recently seen. The same
+/// street in different places counts separately.
+pub fn count_addresses<'a>(
+    messages: impl IntoIterator<Item = (DateTime<Local>, &'a str, Option<&'a str>)>,
+    min_count: usize,
+) -> Vec<AddressCount> {
+    let mut counts: HashMap<(String, String), AddressCount> = HashMap::new();
+    for (time, content, place) in messages {
+        let Some(incident) = Incident::of(content, place) else {
+            continue;
+        };
+        let entry = counts.entry(incident.key).or_insert_with(|| AddressCount {
+            address: incident.address,
+            count: 0,
+            first_seen: time,
+            last_seen: time,
+            last_content: content.to_string(),
+        });
+        entry.count += 1;
+        entry.first_seen = entry.first_seen.min(time);
+        if time >= entry.last_seen {
+            entry.last_seen = time;
+            entry.last_content = content.to_string();
+        }
+    }
+    let mut counts: Vec<AddressCount> = counts.into_values().filter(|c| c.count >= min_count.max(1)).collect();
+    counts.sort_by(|a, b| {
+        b.count
+            .cmp(&a.count)
+            .then_with(|| b.last_seen.cmp(&a.last_seen))
+            .then_with(|| a.address.cmp(&b.address))
+    });
+    counts
+}
+
+pub fn print(counts: &[AddressCount], format: OutputFormat, out: &mut impl Write) -> Result<()> {
+    let time = |t: &DateTime<Local>| t.format("%Y-%m-%d %H:%M:%S").to_string();
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, counts)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["address", "count", "first_seen", "last_seen", "last_content"])?;
+            for c in counts {
+                writer.write_record([
+                    c.address.clone(),
+                    c.count.to_string(),
+                    time(&c.first_seen),
+                    time(&c.last_seen),
+                    c.last_content.clone(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table => {
+            writeln!(out, "{:>5} | {:<19} | {:<19} | Address", "Count", "First seen", "Last seen")?;
+            for c in counts {
+                writeln!(
+                    out,
+                    "{:>5} | {:<19} | {:<19} | {}",
+                    c.count,
+                    time(&c.first_seen),
+                    time(&c.last_seen),
+                    c.address
+                )?;
+            }
+            writeln!(out, "{} addresses", counts.len())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_and_count_addresses() {
+        let street = |content: &str| extract(content).map(|a| a.street);
+        assert_eq!(
+            extract("A1 (DIA: ja) AMBU 17128 Nassaulaan 3135ZH Vlaardingen VLAARD bon 573"),
+            Some(Address {
+                street: "Nassaulaan".to_string(),
+                postcode: Some("3135ZH".to_string()),
+            })
+        );
+        assert_eq!(street("A2 13110 Admiraal De Ruijterweg 1055 Amsterdam 299").as_deref(), Some("Admiraal De Ruijterweg"));
+        assert_eq!(street("P 1 BLB-02 BR woning Hertogstraat Weert 234431").as_deref(), Some("Hertogstraat"));
+        assert_eq!(street("A1 (DIA: ja) AMBU 17142 Lakerveld 3085BJ Rotterdam").as_deref(), Some("Lakerveld"));
+        assert_eq!(street("A2 Utrecht 630"), None);
+
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        let messages = [
+            (at("2026-01-05 03:10"), "P 2 BDH-01 OMS brandmelding Lange Houtstraat Den Haag", Some("Den Haag")),
+            (at("2026-01-07 03:12"), "P 2 BDH-01 OMS brandmelding Lange houtstraat Den Haag", Some("Den Haag")),
+            (at("2026-01-06 14:00"), "A1 Hertogstraat Weert", Some("Weert")),
+            (at("2026-01-06 15:00"), "A1 Hertogstraat Nijmegen", Some("Nijmegen")),
+        ];
+        let counts = count_addresses(messages, 1);
+        assert_eq!(counts.len(), 3);
+        assert_eq!(counts[0].address, "Lange Houtstraat, Den Haag");
+        assert_eq!((counts[0].count, counts[0].first_seen, counts[0].last_seen), (2, at("2026-01-05 03:10"), at("2026-01-07 03:12")));
+        assert_eq!(counts[1].address, "Hertogstraat, Nijmegen");
+        assert_eq!(count_addresses(messages, 2).len(), 1);
+
+        let incident = Incident::of(messages[0].1, messages[0].2).unwrap();
+        assert!(incident.includes(messages[1].1, messages[1].2));
+        assert!(!incident.includes(messages[1].1, Some("Delft")));
+        assert!(Incident::of("A2 Utrecht 630", Some("Utrecht")).is_none());
+
+        let mut sizes = IncidentSizes::default();
+        let first = sizes.add(&incident, ["001123117", "001123118"], ["Brandweer"]);
+        assert_eq!((first.messages, first.capcodes, first.services, first.escalated), (1, 2, 1, false));
+        let repeat = sizes.add(&incident, ["1123117"], ["brandweer"]);
+        assert_eq!((repeat.capcodes, repeat.escalated), (2, false));
+        let grown = sizes.add(&incident, ["001123119", "002029568"], ["Brandweer", "Ambulance"]);
+        assert_eq!(
+            grown,
+            Growth {
+                messages: 3,
+                capcodes: 4,
+                services: 2,
+                escalated: true,
+            }
+        );
+
+        let mut table = Vec::new();
+        print(&counts[..1], OutputFormat::Table, &mut table).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "Count | First seen          | Last seen           | Address\n    \
+             2 | 2026-01-05 03:10:00 | 2026-01-07 03:12:00 | Lange Houtstraat, Den Haag\n1 addresses\n"
+        );
+    }
+}
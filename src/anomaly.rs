@@ -0,0 +1,173 @@
+//! Volume spikes: a region sending far more messages in an hour than it
+//! usually does, often the first sign of a large incident before any GRIP
+//! message goes out.
+
+use chrono::{DateTime, Duration, DurationRound, Local};
+use serde::Deserialize;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+
+/// Hours of history a region needs before it can spike
+const MIN_HISTORY_HOURS: usize = 6;
+
+/// `[anomaly]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct AnomalyConfig {
+    /// Standard deviations above the region's hourly mean that count as a
+    /// spike; 0 disables detection
+    pub threshold: f64,
+    /// Hours of history the mean and standard deviation are taken over
+    pub window_hours: usize,
+    /// Fewer messages in an hour never count as a spike, however quiet the region
+    pub min_count: usize,
+    /// Alert on spikes: the terminal bell in live mode, the sinks in the daemon
+    pub alert: bool,
+}
+
+impl Default for AnomalyConfig {
+    fn default() -> Self {
+        AnomalyConfig {
+            threshold: 3.0,
+            window_hours: 48,
+            min_count: 10,
+            alert: false,
+        }
+    }
+}
+
+/// An hour in which a region passed its usual volume.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Spike {
+    pub region: String,
+    pub hour: DateTime<Local>,
+    pub count: usize,
+    pub mean: f64,
+    pub stddev: f64,
+}
+
+impl fmt::Display for Spike {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "Volume spike in {}: {} messages since {} (usually {:.1} ± {:.1} an hour)",
+            self.region,
+            self.count,
+            self.hour.format("%H:%M"),
+            self.mean,
+            self.stddev
+        )
+    }
+}
+
+#[derive(Debug)]
+struct RegionVolume {
+    hour: DateTime<Local>,
+    count: usize,
+    // Counts of the hours before `hour`, oldest first
+    history: VecDeque<usize>,
+    // The current hour was already reported
+    reported: bool,
+}
+
+pub struct VolumeDetector {
+    config: AnomalyConfig,
+    regions: HashMap<String, RegionVolume>,
+}
+
+impl VolumeDetector {
+    /// `None` when detection is disabled.
+    pub fn new(config: &AnomalyConfig) -> Option<Self> {
+        (config.threshold > 0.0 && config.window_hours > 0).then(|| VolumeDetector {
+            config: config.clone(),
+            regions: HashMap::new(),
+        })
+    }
+
+    /// Counts a message; returns the spike it completes, once per region and hour.
+    /// Messages from an hour before the region's latest are ignored.
+    pub fn observe(&mut self, region: &str, time: DateTime<Local>) -> Option<Spike> {
+        let hour = time.duration_trunc(Duration::hours(1)).unwrap_or(time);
+        let window = self.config.window_hours;
+        let volume = self.regions.entry(region.to_string()).or_insert_with(|| RegionVolume {
+            hour,
+            count: 0,
+            history: VecDeque::new(),
+            reported: false,
+        });
+        if hour > volume.hour {
+            // The hour that ended, then one empty hour per silent one
+            let silent = ((hour - volume.hour).num_hours() - 1).clamp(0, window as i64) as usize;
+            let ended = std::iter::once(volume.count).chain(std::iter::repeat_n(0, silent));
+            volume.history.extend(ended);
+            while volume.history.len() > window {
+                volume.history.pop_front();
+            }
+            volume.hour = hour;
+            volume.count = 0;
+            volume.reported = false;
+        } else if hour < volume.hour {
+            return None;
+        }
+        volume.count += 1;
+        if volume.reported || volume.count < self.config.min_count || volume.history.len() < MIN_HISTORY_HOURS {
+            return None;
+        }
+        let n = volume.history.len() as f64;
+        let mean = volume.history.iter().sum::<usize>() as f64 / n;
+        let variance = volume.history.iter().map(|&c| (c as f64 - mean).powi(2)).sum::<f64>() / n;
+        let stddev = variance.sqrt();
+        if (volume.count as f64) <= mean + self.config.threshold * stddev {
+            return None;
+        }
+        volume.reported = true;
+        Some(Spike {
+            region: region.to_string(),
+            hour,
+            count: volume.count,
+            mean,
+            stddev,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spike_against_rolling_hours() {
+        let config = AnomalyConfig {
+            min_count: 5,
+            ..Default::default()
+        };
+        let mut detector = VolumeDetector::new(&config).unwrap();
+        let start = crate::query::parse_time("2026-01-01 00:10").unwrap();
+        // Two or three messages an hour for a day
+        for hour in 0..24 {
+            for minute in 0..2 + hour % 2 {
+                let time = start + Duration::hours(hour) + Duration::minutes(minute);
+                assert_eq!(detector.observe("Utrecht", time), None);
+            }
+        }
+        // Busy but within the usual spread elsewhere: no history yet
+        let busy = start + Duration::hours(24);
+        for minute in 0..20 {
+            assert_eq!(detector.observe("Haaglanden", busy + Duration::minutes(minute)), None);
+        }
+
+        let spikes: Vec<Spike> = (0..20)
+            .filter_map(|minute| detector.observe("Utrecht", busy + Duration::minutes(minute)))
+            .collect();
+        assert_eq!(spikes.len(), 1);
+        assert_eq!(spikes[0].count, 5);
+        assert_eq!(
+            spikes[0].to_string(),
+            "Volume spike in Utrecht: 5 messages since 00:00 (usually 2.5 ± 0.5 an hour)"
+        );
+
+        // The next hour is judged on its own
+        assert_eq!(detector.observe("Utrecht", busy + Duration::hours(1)), None);
+        assert!(VolumeDetector::new(&AnomalyConfig { threshold: 0.0, ..config }).is_none());
+    }
+}
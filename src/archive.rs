@@ -0,0 +1,852 @@
+use anyhow::Result;
+#[cfg(feature = "archive")]
+use anyhow::Context;
+use chrono::{DateTime, Utc};
+#[cfg(feature = "archive")]
+use chrono::{Local, TimeZone};
+#[cfg(feature = "archive")]
+use flate2::{write::GzEncoder, Compression};
+#[cfg(feature = "archive")]
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Deserialize;
+#[cfg(feature = "archive")]
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+use crate::enrich::Enrichment;
+#[cfg(feature = "archive")]
+use crate::enrich;
+use crate::location::LocationLookup;
+use crate::lookup::Lookup;
+#[cfg(feature = "archive")]
+use crate::parser::{line_hash, Parser};
+use crate::parser::P2000Message;
+use crate::state::IncidentStatus;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ArchiveConfig {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub retention: RetentionConfig,
+    /// Seal every this many messages into a hash-chained segment; see [`Archive::verify`]
+    #[serde(default)]
+    pub segment_size: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    #[default]
+    Delete,
+    /// Move expired rows into gzipped FLEX logs before deleting them
+    Compress,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub max_age_days: Option<u32>,
+    pub action: RetentionAction,
+    /// Where compressed logs go; defaults to `<archive>.old/`
+    pub compress_dir: Option<PathBuf>,
+    pub max_size_mb: Option<u64>,
+    pub check_minutes: u64,
+    pub vacuum_hours: u64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        RetentionConfig {
+            max_age_days: None,
+            action: RetentionAction::Delete,
+            compress_dir: None,
+            max_size_mb: None,
+            check_minutes: 60,
+            vacuum_hours: 24,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct RetentionReport {
+    pub expired: usize,
+    pub compressed_to: Option<PathBuf>,
+    pub trimmed_for_size: usize,
+}
+
+/// `prev` of the first segment
+#[cfg(feature = "archive")]
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// Result of [`Archive::verify`].
+#[derive(Debug, Default)]
+pub struct VerifyReport {
+    pub messages: usize,
+    /// Archived before messages were hashed; only sealed segments cover them
+    pub unhashed: usize,
+    pub segments: usize,
+    /// Messages not in a sealed segment yet, or whose segment expired
+    pub unsealed: usize,
+    /// Whether the first segment follows segments removed by retention
+    pub trimmed: bool,
+    pub problems: Vec<String>,
+}
+
+impl VerifyReport {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl std::fmt::Display for VerifyReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} messages", self.messages)?;
+        if self.unhashed > 0 {
+            write!(f, " ({} from before hashing)", self.unhashed)?;
+        }
+        write!(f, ", {} segments, {} outside a segment", self.segments, self.unsealed)?;
+        if self.trimmed {
+            write!(f, ", chain starts after expired segments")?;
+        }
+        if self.is_ok() {
+            return writeln!(f, ": ok");
+        }
+        writeln!(f, ": {} problems", self.problems.len())?;
+        for problem in &self.problems {
+            writeln!(f, "  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+/// Kept in `PRAGMA user_version`. Version 1 stores the region derived from all
+/// capcodes instead of the first one's; see [`Archive::update_regions`].
+#[cfg(feature = "archive")]
+const SCHEMA_VERSION: i64 = 1;
+
+/// SQLite store of every received message.
+#[cfg(feature = "archive")]
+pub struct Archive {
+    conn: Connection,
+    path: PathBuf,
+    segment_size: Option<usize>,
+}
+
+#[cfg(feature = "archive")]
+impl Archive {
+    pub fn open(path: &Path) -> Result<Self> {
+        let conn = Connection::open(path)
+            .with_context(|| format!("Failed to open archive {}", path.display()))?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS messages (
+                 id INTEGER PRIMARY KEY,
+                 timestamp INTEGER NOT NULL,
+                 protocol TEXT NOT NULL,
+                 radio_address TEXT NOT NULL,
+                 frequency TEXT NOT NULL,
+                 capcodes TEXT NOT NULL,
+                 message_type TEXT NOT NULL,
+                 content TEXT NOT NULL,
+                 priority TEXT,
+                 incident_code TEXT,
+                 location TEXT NOT NULL,
+                 service TEXT,
+                 region TEXT,
+                 place TEXT,
+                 raw TEXT NOT NULL,
+                 hash TEXT,
+                 receiver TEXT,
+                 receiver_location TEXT,
+                 receiver_antenna TEXT
+             );
+             CREATE INDEX IF NOT EXISTS idx_messages_timestamp ON messages(timestamp);
+             CREATE TABLE IF NOT EXISTS notes (
+                 raw TEXT PRIMARY KEY,
+                 note TEXT NOT NULL,
+                 updated INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS incident_status (
+                 raw TEXT PRIMARY KEY,
+                 status TEXT NOT NULL,
+                 updated INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS segments (
+                 id INTEGER PRIMARY KEY,
+                 first_id INTEGER NOT NULL,
+                 last_id INTEGER NOT NULL,
+                 count INTEGER NOT NULL,
+                 digest TEXT NOT NULL,
+                 prev TEXT NOT NULL,
+                 chain TEXT NOT NULL,
+                 sealed INTEGER NOT NULL
+             );",
+        )?;
+        // Columns added since the first archives
+        for column in ["hash", "receiver", "receiver_location", "receiver_antenna"] {
+            let present = conn
+                .prepare("SELECT 1 FROM pragma_table_info('messages') WHERE name = ?1")?
+                .exists([column])?;
+            if !present {
+                conn.execute_batch(&format!("ALTER TABLE messages ADD COLUMN {} TEXT", column))?;
+            }
+        }
+        // A new archive has nothing to update
+        let empty = !conn.prepare("SELECT 1 FROM messages")?.exists([])?;
+        if empty {
+            conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+        }
+        Ok(Archive {
+            conn,
+            path: path.to_path_buf(),
+            segment_size: None,
+        })
+    }
+
+    /// Seals every `size` inserted messages into a segment chained to the one
+    /// before it; `None` leaves messages unsealed.
+    pub fn with_segment_size(mut self, size: Option<usize>) -> Self {
+        self.segment_size = size.filter(|&size| size > 0);
+        self
+    }
+
+    pub fn connection(&self) -> &Connection {
+        &self.conn
+    }
+
+    /// Derives the region of messages archived before schema version 1 again,
+    /// and returns how many changed; nothing to do on later archives.
+    pub fn update_regions(&self, lookup: &Lookup, location_lookup: &LocationLookup) -> Result<usize> {
+        let version: i64 = self.conn.query_row("PRAGMA user_version", [], |r| r.get(0))?;
+        if version >= SCHEMA_VERSION {
+            return Ok(0);
+        }
+        let tx = self.conn.unchecked_transaction()?;
+        let mut stmt = self.conn.prepare("SELECT id, raw, region FROM messages")?;
+        let rows: Vec<(i64, String, Option<String>)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        let parser = Parser::new();
+        let mut updated = 0;
+        for (id, raw, region) in rows {
+            let Ok(msg) = parser.parse_line(&raw) else {
+                continue;
+            };
+            let derived = enrich::enrich(&msg, lookup, location_lookup).region;
+            if derived != region {
+                self.conn.execute("UPDATE messages SET region = ?1 WHERE id = ?2", params![derived, id])?;
+                updated += 1;
+            }
+        }
+        self.conn.execute_batch(&format!("PRAGMA user_version = {}", SCHEMA_VERSION))?;
+        tx.commit()?;
+        Ok(updated)
+    }
+
+    pub fn insert(&self, msg: &P2000Message, enrichment: &Enrichment) -> Result<i64> {
+        let primary = enrichment.primary_capcode();
+        self.conn.execute(
+            "INSERT INTO messages (timestamp, protocol, radio_address, frequency, capcodes,
+                 message_type, content, priority, incident_code, location, service, region, place, raw, hash,
+                 receiver, receiver_location, receiver_antenna)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+            params![
+                msg.timestamp.timestamp(),
+                msg.protocol,
+                msg.radio_address,
+                msg.frequency,
+                msg.capcodes.join(" "),
+                msg.message_type.as_str(),
+                msg.content,
+                msg.priority,
+                msg.incident_code,
+                msg.location,
+                primary.map(|c| c.service.as_str()),
+                enrichment.region,
+                enrichment.location.as_ref().map(|l| l.found_place.trim()),
+                msg.to_line(),
+                msg.content_hash(),
+                msg.receiver.as_ref().map(|r| r.callsign.as_str()),
+                msg.receiver.as_ref().map(|r| r.location.as_str()),
+                msg.receiver.as_ref().map(|r| r.antenna.as_str()),
+            ],
+        )?;
+        let id = self.conn.last_insert_rowid();
+        if let Some(size) = self.segment_size {
+            if self.unsealed_count()? >= size {
+                self.seal_segment()?;
+            }
+        }
+        Ok(id)
+    }
+
+    fn last_sealed_id(&self) -> Result<i64> {
+        Ok(self
+            .conn
+            .query_row("SELECT COALESCE(MAX(last_id), 0) FROM segments", [], |r| r.get(0))?)
+    }
+
+    fn unsealed_count(&self) -> Result<usize> {
+        let count: i64 = self.conn.query_row(
+            "SELECT COUNT(*) FROM messages WHERE id > ?1",
+            params![self.last_sealed_id()?],
+            |r| r.get(0),
+        )?;
+        Ok(count as usize)
+    }
+
+    /// Seals the messages after the last segment into a new one; `None` when
+    /// there are none.
+    pub fn seal_segment(&self) -> Result<Option<i64>> {
+        let after = self.last_sealed_id()?;
+        let prev: String = self
+            .conn
+            .query_row("SELECT chain FROM segments ORDER BY id DESC LIMIT 1", [], |r| r.get(0))
+            .optional()?
+            .unwrap_or_else(|| GENESIS.to_string());
+        let mut stmt = self.conn.prepare("SELECT id, raw FROM messages WHERE id > ?1 ORDER BY id")?;
+        let rows: Vec<(i64, String)> = stmt
+            .query_map(params![after], |r| Ok((r.get(0)?, r.get(1)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        let (Some((first, _)), Some((last, _))) = (rows.first(), rows.last()) else {
+            return Ok(None);
+        };
+        let digest = segment_digest(rows.iter().map(|(_, raw)| raw.as_str()));
+        self.conn.execute(
+            "INSERT INTO segments (first_id, last_id, count, digest, prev, chain, sealed)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![first, last, rows.len() as i64, digest, prev, chain_hash(&prev, &digest), Utc::now().timestamp()],
+        )?;
+        Ok(Some(self.conn.last_insert_rowid()))
+    }
+
+    /// Checks every message against its hash and every segment against its
+    /// messages and the segment before it.
+    pub fn verify(&self) -> Result<VerifyReport> {
+        let mut report = VerifyReport::default();
+        let mut stmt = self.conn.prepare("SELECT id, content, raw, hash FROM messages ORDER BY id")?;
+        let messages: Vec<(i64, String, String, Option<String>)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        report.messages = messages.len();
+        for (id, content, raw, hash) in &messages {
+            match hash {
+                Some(hash) if *hash != line_hash(raw) => {
+                    report.problems.push(format!("message {}: line does not match its hash", id))
+                }
+                Some(_) => {}
+                None => report.unhashed += 1,
+            }
+            // The content column is searched and shown; it must be the line's
+            if raw.splitn(7, '|').nth(6) != Some(content.as_str()) {
+                report.problems.push(format!("message {}: content differs from its line", id));
+            }
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT id, first_id, last_id, count, digest, prev, chain FROM segments ORDER BY id")?;
+        let segments: Vec<(i64, i64, i64, i64, String, String, String)> = stmt
+            .query_map([], |r| Ok((r.get(0)?, r.get(1)?, r.get(2)?, r.get(3)?, r.get(4)?, r.get(5)?, r.get(6)?)))?
+            .collect::<rusqlite::Result<_>>()?;
+        report.segments = segments.len();
+        report.trimmed = segments.first().is_some_and(|first| first.5 != GENESIS);
+        let mut covered = 0;
+        let mut previous: Option<(i64, String)> = None;
+        for (id, first, last, count, digest, prev, chain) in segments {
+            let name = format!("segment {} (messages {}-{})", id, first, last);
+            if let Some((previous_last, previous_chain)) = &previous {
+                if prev != *previous_chain || first <= *previous_last {
+                    report.problems.push(format!("{}: not linked to the segment before it", name));
+                }
+            }
+            if chain != chain_hash(&prev, &digest) {
+                report.problems.push(format!("{}: chain hash does not match", name));
+            }
+            let lines: Vec<&str> = messages
+                .iter()
+                .filter(|(msg_id, ..)| (first..=last).contains(msg_id))
+                .map(|(_, _, raw, _)| raw.as_str())
+                .collect();
+            covered += lines.len();
+            if lines.len() as i64 != count {
+                report
+                    .problems
+                    .push(format!("{}: {} of {} messages present", name, lines.len(), count));
+            } else if segment_digest(lines.into_iter()) != digest {
+                report.problems.push(format!("{}: messages changed since sealing", name));
+            }
+            previous = Some((last, chain));
+        }
+        report.unsealed = report.messages - covered.min(report.messages);
+        Ok(report)
+    }
+
+    /// Records the user's status for a message, keyed by its feed line so it also
+    /// applies when the message is loaded from a log file instead of the archive.
+    pub fn set_status(&self, msg: &P2000Message, status: IncidentStatus) -> Result<()> {
+        if status == IncidentStatus::Open {
+            self.conn
+                .execute("DELETE FROM incident_status WHERE raw = ?1", params![msg.to_line()])?;
+        } else {
+            self.conn.execute(
+                "INSERT OR REPLACE INTO incident_status (raw, status, updated) VALUES (?1, ?2, ?3)",
+                params![msg.to_line(), status.as_str(), Utc::now().timestamp()],
+            )?;
+        }
+        Ok(())
+    }
+
+    pub fn status(&self, msg: &P2000Message) -> Result<IncidentStatus> {
+        let status: Option<String> = self
+            .conn
+            .query_row(
+                "SELECT status FROM incident_status WHERE raw = ?1",
+                params![msg.to_line()],
+                |row| row.get(0),
+            )
+            .optional()?;
+        Ok(status.as_deref().and_then(IncidentStatus::parse).unwrap_or_default())
+    }
+
+    /// Saves the user's note on a message (keyed like the status); `None` removes it.
+    pub fn set_note(&self, msg: &P2000Message, note: Option<&str>) -> Result<()> {
+        match note {
+            Some(note) => self.conn.execute(
+                "INSERT OR REPLACE INTO notes (raw, note, updated) VALUES (?1, ?2, ?3)",
+                params![msg.to_line(), note, Utc::now().timestamp()],
+            )?,
+            None => self
+                .conn
+                .execute("DELETE FROM notes WHERE raw = ?1", params![msg.to_line()])?,
+        };
+        Ok(())
+    }
+
+    pub fn note(&self, msg: &P2000Message) -> Result<Option<String>> {
+        Ok(self
+            .conn
+            .query_row("SELECT note FROM notes WHERE raw = ?1", params![msg.to_line()], |row| row.get(0))
+            .optional()?)
+    }
+
+    /// Bytes in use, excluding free pages that a vacuum would reclaim.
+    pub fn used_bytes(&self) -> Result<u64> {
+        let page_size: i64 = self.conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+        let pages: i64 = self.conn.query_row("PRAGMA page_count", [], |r| r.get(0))?;
+        let free: i64 = self.conn.query_row("PRAGMA freelist_count", [], |r| r.get(0))?;
+        Ok(((pages - free).max(0) * page_size) as u64)
+    }
+
+    /// Moves everything from the write-ahead log into the database file; call before exiting.
+    /// Seals the messages since the last segment when segments are on.
+    pub fn checkpoint(&self) -> Result<()> {
+        if self.segment_size.is_some() {
+            self.seal_segment()?;
+        }
+        self.conn
+            .query_row("PRAGMA wal_checkpoint(TRUNCATE)", [], |_| Ok(()))?;
+        Ok(())
+    }
+
+    pub fn vacuum(&self) -> Result<()> {
+        self.conn.execute_batch("VACUUM")?;
+        Ok(())
+    }
+
+    /// Removes expired messages, oldest first, and trims for size, in one
+    /// transaction with writing the compressed copy. Messages go by id from the
+    /// oldest on, stopping at the first one still kept, so the segments that
+    /// remain are whole and [`verify`](Self::verify) still checks their chain.
+    pub fn apply_retention(&self, policy: &RetentionConfig, now: DateTime<Utc>) -> Result<RetentionReport> {
+        let mut report = RetentionReport::default();
+        let tx = self.conn.unchecked_transaction()?;
+
+        if let Some(days) = policy.max_age_days {
+            let cutoff = (now - chrono::Duration::days(days as i64)).timestamp();
+            let kept_from: Option<i64> = self.conn.query_row(
+                "SELECT MIN(id) FROM messages WHERE timestamp >= ?1",
+                params![cutoff],
+                |r| r.get(0),
+            )?;
+            let before = kept_from.unwrap_or(i64::MAX);
+            if policy.action == RetentionAction::Compress {
+                report.compressed_to = self.compress_before(before, cutoff, policy)?;
+            }
+            report.expired = self.conn.execute("DELETE FROM messages WHERE id < ?1", params![before])?;
+        }
+
+        if let Some(max_mb) = policy.max_size_mb {
+            let cap = max_mb * 1024 * 1024;
+            while self.used_bytes()? > cap {
+                // Drop the oldest 5% (at least 100 rows) per round
+                let count: i64 = self.conn.query_row("SELECT COUNT(*) FROM messages", [], |r| r.get(0))?;
+                if count == 0 {
+                    break;
+                }
+                let batch = (count / 20).max(100);
+                report.trimmed_for_size += self.conn.execute(
+                    "DELETE FROM messages WHERE id IN (SELECT id FROM messages ORDER BY id LIMIT ?1)",
+                    params![batch],
+                )?;
+            }
+        }
+
+        if report.expired > 0 || report.trimmed_for_size > 0 {
+            // Segments that lost messages can no longer be verified; the chain
+            // continues from the first complete one
+            self.conn.execute(
+                "DELETE FROM segments WHERE count > (SELECT COUNT(*) FROM messages
+                     WHERE messages.id BETWEEN segments.first_id AND segments.last_id)",
+                [],
+            )?;
+        }
+
+        tx.commit()?;
+        Ok(report)
+    }
+
+    /// Writes the messages with an id below `before` to a gzipped log named
+    /// after `cutoff`.
+    fn compress_before(&self, before: i64, cutoff: i64, policy: &RetentionConfig) -> Result<Option<PathBuf>> {
+        let mut stmt = self.conn.prepare("SELECT raw FROM messages WHERE id < ?1 ORDER BY id")?;
+        let lines: Vec<String> = stmt
+            .query_map(params![before], |r| r.get(0))?
+            .collect::<rusqlite::Result<_>>()?;
+        if lines.is_empty() {
+            return Ok(None);
+        }
+
+        let dir = policy.compress_dir.clone().unwrap_or_else(|| {
+            let mut dir = self.path.clone().into_os_string();
+            dir.push(".old");
+            PathBuf::from(dir)
+        });
+        std::fs::create_dir_all(&dir)?;
+        let cutoff_local: DateTime<Local> = Local
+            .timestamp_opt(cutoff, 0)
+            .single()
+            .unwrap_or_else(Local::now);
+        let file = dir.join(format!(
+            "p2000-before-{}.log.gz",
+            cutoff_local.format("%Y%m%d-%H%M%S")
+        ));
+
+        let mut encoder = GzEncoder::new(std::fs::File::create(&file)?, Compression::default());
+        for line in &lines {
+            writeln!(encoder, "{}", line)?;
+        }
+        encoder.finish()?;
+        Ok(Some(file))
+    }
+}
+
+/// Stand-in without the `archive` feature: `open` fails, so there is never an instance.
+#[cfg(not(feature = "archive"))]
+pub struct Archive {
+    never: std::convert::Infallible,
+}
+
+#[cfg(not(feature = "archive"))]
+impl Archive {
+    pub fn open(_path: &Path) -> Result<Self> {
+        anyhow::bail!("This build has no archive; rebuild with --features archive")
+    }
+
+    pub fn insert(&self, _msg: &P2000Message, _enrichment: &Enrichment) -> Result<i64> {
+        match self.never {}
+    }
+
+    pub fn update_regions(&self, _lookup: &Lookup, _location_lookup: &LocationLookup) -> Result<usize> {
+        match self.never {}
+    }
+
+    pub fn set_status(&self, _msg: &P2000Message, _status: IncidentStatus) -> Result<()> {
+        match self.never {}
+    }
+
+    pub fn status(&self, _msg: &P2000Message) -> Result<IncidentStatus> {
+        match self.never {}
+    }
+
+    pub fn set_note(&self, _msg: &P2000Message, _note: Option<&str>) -> Result<()> {
+        match self.never {}
+    }
+
+    pub fn note(&self, _msg: &P2000Message) -> Result<Option<String>> {
+        match self.never {}
+    }
+
+    pub fn with_segment_size(self, _size: Option<usize>) -> Self {
+        match self.never {}
+    }
+
+    pub fn verify(&self) -> Result<VerifyReport> {
+        match self.never {}
+    }
+
+    pub fn checkpoint(&self) -> Result<()> {
+        match self.never {}
+    }
+
+    pub fn vacuum(&self) -> Result<()> {
+        match self.never {}
+    }
+
+    pub fn apply_retention(&self, _policy: &RetentionConfig, _now: DateTime<Utc>) -> Result<RetentionReport> {
+        match self.never {}
+    }
+
+    pub fn query(&self, _filter: &crate::query::QueryFilter) -> Result<Vec<crate::query::ArchivedMessage>> {
+        match self.never {}
+    }
+}
+
+/// Hash over a segment's lines, in id order.
+#[cfg(feature = "archive")]
+fn segment_digest<'a>(lines: impl Iterator<Item = &'a str>) -> String {
+    let hashes: Vec<String> = lines.map(line_hash).collect();
+    line_hash(&hashes.join("\n"))
+}
+
+#[cfg(feature = "archive")]
+fn chain_hash(prev: &str, digest: &str) -> String {
+    line_hash(&format!("{}{}", prev, digest))
+}
+
+/// Runs retention and vacuum on their configured intervals; call after each insert.
+pub struct RetentionScheduler {
+    policy: RetentionConfig,
+    last_check: Option<Instant>,
+    last_vacuum: Instant,
+}
+
+impl RetentionScheduler {
+    pub fn new(policy: RetentionConfig) -> Self {
+        RetentionScheduler {
+            policy,
+            last_check: None,
+            last_vacuum: Instant::now(),
+        }
+    }
+
+    pub fn tick(&mut self, archive: &Archive) -> Result<()> {
+        let check_due = self.last_check.is_none_or(|t| {
+            t.elapsed() >= Duration::from_secs(self.policy.check_minutes * 60)
+        });
+        if check_due {
+            self.last_check = Some(Instant::now());
+            let report = archive.apply_retention(&self.policy, Utc::now())?;
+            if report.expired > 0 || report.trimmed_for_size > 0 {
+                eprintln!(
+                    "Archive retention: {} expired, {} trimmed for size{}",
+                    report.expired,
+                    report.trimmed_for_size,
+                    report
+                        .compressed_to
+                        .map(|p| format!(", moved to {}", p.display()))
+                        .unwrap_or_default()
+                );
+            }
+        }
+
+        if self.policy.vacuum_hours > 0
+            && self.last_vacuum.elapsed() >= Duration::from_secs(self.policy.vacuum_hours * 3600)
+        {
+            self.last_vacuum = Instant::now();
+            archive.vacuum()?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(all(test, feature = "archive"))]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_retention_compress() {
+        let dir = std::env::temp_dir().join(format!("p2000_archive_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = Archive::open(&dir.join("archive.db")).unwrap().with_segment_size(Some(1));
+
+        // The third came in late; it stays with the newer ones around it
+        let parser = Parser::new();
+        for line in [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-10 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-02 20:01:29|1600/2/K/A|00.052|002029568|ALN|A1 Tilburg rit: 632",
+            "FLEX|2026-01-11 20:01:35|1600/2/K/A|00.053|000923993|ALN|A2 Delft 12",
+        ] {
+            archive.insert(&parser.parse_line(line).unwrap(), &Enrichment::default()).unwrap();
+        }
+
+        let policy = RetentionConfig {
+            max_age_days: Some(5),
+            action: RetentionAction::Compress,
+            ..Default::default()
+        };
+        let now = Utc.with_ymd_and_hms(2026, 1, 12, 0, 0, 0).unwrap();
+        let report = archive.apply_retention(&policy, now).unwrap();
+        assert_eq!(report.expired, 1);
+        let verified = archive.verify().unwrap();
+        assert!(verified.is_ok(), "{}", verified);
+        assert_eq!((verified.messages, verified.segments, verified.trimmed), (3, 3, true));
+
+        let compressed = report.compressed_to.expect("No compressed file");
+        let mut text = String::new();
+        std::io::Read::read_to_string(
+            &mut flate2::read::GzDecoder::new(std::fs::File::open(compressed).unwrap()),
+            &mut text,
+        )
+        .unwrap();
+        assert_eq!(
+            text,
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461\n"
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_region_of_all_capcodes_is_archived() {
+        let dir = std::env::temp_dir().join(format!("p2000_region_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = "\"1123117\";\"Ambulance\";\"Brabant-Zuidoost\";\"Duizel\";\"Post Duizel\";\"\"\n\
+                    \"0726119\";\"Ambulance\";\"Utrecht\";\"Utrecht\";\"Post Overvecht\";\"\"\n\
+                    \"0726120\";\"Ambulance\";\"Utrecht\";\"Utrecht\";\"Post Zuilen\";\"\"\n";
+        std::fs::write(dir.join("capcodes.csv"), list).unwrap();
+        std::fs::write(dir.join("abbreviations.txt"), "").unwrap();
+        let lookup = Lookup::load(&dir.join("capcodes.csv"), &dir.join("abbreviations.txt")).unwrap();
+        let location_lookup = LocationLookup::default();
+
+        let path = dir.join("archive.db");
+        let archive = Archive::open(&path).unwrap();
+        let msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117 000726119 000726120|ALN|A1 Rit: 461")
+            .unwrap();
+        archive.insert(&msg, &enrich::enrich(&msg, &lookup, &location_lookup)).unwrap();
+        let region = || -> Option<String> {
+            let archive = Archive::open(&path).unwrap();
+            archive.connection().query_row("SELECT region FROM messages", [], |r| r.get(0)).unwrap()
+        };
+        assert_eq!(region().as_deref(), Some("Utrecht"));
+        assert_eq!(archive.update_regions(&lookup, &location_lookup).unwrap(), 0);
+
+        // As archived before the schema had a version
+        archive
+            .connection()
+            .execute_batch("UPDATE messages SET region = 'Brabant-Zuidoost'; PRAGMA user_version = 0;")
+            .unwrap();
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.update_regions(&lookup, &location_lookup).unwrap(), 1);
+        assert_eq!(region().as_deref(), Some("Utrecht"));
+        assert_eq!(archive.update_regions(&lookup, &location_lookup).unwrap(), 0);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_status_and_notes_survive_reopen() {
+        let dir = std::env::temp_dir().join(format!("p2000_status_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("archive.db");
+        let msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461")
+            .unwrap();
+
+        Archive::open(&path).unwrap().set_status(&msg, IncidentStatus::Resolved).unwrap();
+        let archive = Archive::open(&path).unwrap();
+        assert_eq!(archive.status(&msg).unwrap(), IncidentStatus::Resolved);
+        archive.set_status(&msg, IncidentStatus::Open).unwrap();
+        assert_eq!(archive.status(&msg).unwrap(), IncidentStatus::Open);
+
+        archive.set_note(&msg, Some("Cancelled en route")).unwrap();
+        assert_eq!(archive.note(&msg).unwrap().as_deref(), Some("Cancelled en route"));
+        archive.set_note(&msg, None).unwrap();
+        assert_eq!(archive.note(&msg).unwrap(), None);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_receiver_is_archived() {
+        let dir = std::env::temp_dir().join(format!("p2000_receiver_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = Archive::open(&dir.join("archive.db")).unwrap();
+        let receiver = crate::parser::Receiver {
+            callsign: "PA3XYZ".to_string(),
+            location: "Vlaardingen".to_string(),
+            antenna: "discone".to_string(),
+        };
+
+        let parser = Parser::new();
+        let mut msg = parser.parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461").unwrap();
+        let untagged = msg.clone();
+        msg.receiver = Some(std::sync::Arc::new(receiver.clone()));
+        archive.insert(&msg, &Enrichment::default()).unwrap();
+        archive.insert(&untagged, &Enrichment::default()).unwrap();
+
+        let rows = archive
+            .query(&crate::query::QueryFilter {
+                receiver: Some("pa3xyz".to_string()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].receiver.as_ref(), Some(&receiver));
+        assert_eq!(rows[0].hash.as_deref(), Some(untagged.content_hash().as_str()));
+        assert_eq!(receiver.to_string(), "PA3XYZ (Vlaardingen, discone)");
+
+        // The same criterion as a structured search
+        let rows = archive
+            .query(&crate::query::QueryFilter {
+                query: Some("prio:A1 AND NOT receiver:pa3xyz".parse().unwrap()),
+                ..Default::default()
+            })
+            .unwrap();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].receiver, None);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_verify_detects_tampering() {
+        let dir = std::env::temp_dir().join(format!("p2000_verify_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = Archive::open(&dir.join("archive.db")).unwrap().with_segment_size(Some(2));
+
+        let parser = Parser::new();
+        for line in [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:01:29|1600/2/K/A|00.052|002029568|ALN|A1 Tilburg rit: 632",
+        ] {
+            archive.insert(&parser.parse_line(line).unwrap(), &Enrichment::default()).unwrap();
+        }
+        let report = archive.verify().unwrap();
+        assert!(report.is_ok(), "{}", report);
+        assert_eq!((report.segments, report.unsealed), (1, 1));
+        archive.checkpoint().unwrap();
+        assert_eq!(archive.verify().unwrap().unsealed, 0);
+
+        let conn = archive.connection();
+        conn.execute("UPDATE messages SET content = 'A2 Tilburg', raw = REPLACE(raw, 'A1 Tilburg rit: 632', 'A2 Tilburg') WHERE id = 3", [])
+            .unwrap();
+        conn.execute("DELETE FROM messages WHERE id = 1", []).unwrap();
+        let report = archive.verify().unwrap();
+        assert_eq!(
+            report.problems,
+            [
+                "message 3: line does not match its hash",
+                "segment 1 (messages 1-2): 1 of 2 messages present",
+                "segment 2 (messages 3-3): messages changed since sealing",
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,143 @@
+//! Alert sounds: rules name a WAV or Ogg Vorbis file that plays when they match.
+//! Playback needs the `audio` feature; without it `AudioPlayer::start` fails.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+
+use crate::parser::P2000Message;
+use crate::enrich::Enrichment;
+use crate::rules::Rule;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct AudioConfig {
+    /// Start muted; the TUI toggles with `M`
+    pub muted: bool,
+}
+
+pub struct AudioPlayer {
+    rules: Vec<Rule>,
+    muted: bool,
+    // Playback runs on its own thread because the output stream is not `Send`
+    sounds: mpsc::Sender<PathBuf>,
+}
+
+impl AudioPlayer {
+    /// Opens the default output device. Fails if a rule's sound file is missing,
+    /// so typos show up at startup rather than during an incident.
+    pub fn start(config: &AudioConfig, rules: &[Rule]) -> Result<Self> {
+        let rules: Vec<Rule> = rules.iter().filter(|r| r.sound.is_some()).cloned().collect();
+        for rule in &rules {
+            let path = rule.sound.as_deref().unwrap_or(Path::new(""));
+            if !path.is_file() {
+                bail!("Sound for rule '{}' not found: {}", rule.name, path.display());
+            }
+        }
+        Ok(AudioPlayer {
+            rules,
+            muted: config.muted,
+            sounds: spawn_output()?,
+        })
+    }
+
+    /// Plays the sound of the first matching rule that has one.
+    pub fn alert(&self, msg: &P2000Message, enrichment: &Enrichment) {
+        if self.muted {
+            return;
+        }
+        if let Some(sound) = sound_for(&self.rules, msg, enrichment) {
+            // A closed channel means the output thread failed; nothing more to do
+            let _ = self.sounds.send(sound.to_path_buf());
+        }
+    }
+
+    pub fn is_muted(&self) -> bool {
+        self.muted
+    }
+
+    /// Returns whether sound is now muted.
+    pub fn toggle_mute(&mut self) -> bool {
+        self.muted = !self.muted;
+        self.muted
+    }
+}
+
+fn sound_for<'a>(rules: &'a [Rule], msg: &P2000Message, enrichment: &Enrichment) -> Option<&'a Path> {
+    rules
+        .iter()
+        .filter(|rule| rule.alerts(msg, enrichment))
+        .find_map(|rule| rule.sound.as_deref())
+}
+
+#[cfg(feature = "audio")]
+fn spawn_output() -> Result<mpsc::Sender<PathBuf>> {
+    use rodio::Source;
+
+    let (sounds, requests) = mpsc::channel::<PathBuf>();
+    let (ready, opened) = mpsc::channel();
+    std::thread::spawn(move || {
+        let (_stream, handle) = match rodio::OutputStream::try_default() {
+            Ok(output) => {
+                let _ = ready.send(Ok(()));
+                output
+            }
+            Err(e) => {
+                let _ = ready.send(Err(anyhow::anyhow!("No audio output: {}", e)));
+                return;
+            }
+        };
+        for path in requests {
+            // Errors have nowhere to go while the TUI owns the screen; start() checked the files exist
+            let Ok(file) = std::fs::File::open(&path) else {
+                continue;
+            };
+            if let Ok(source) = rodio::Decoder::new(std::io::BufReader::new(file)) {
+                let _ = handle.play_raw(source.convert_samples());
+            }
+        }
+    });
+    opened.recv()??;
+    Ok(sounds)
+}
+
+#[cfg(not(feature = "audio"))]
+fn spawn_output() -> Result<mpsc::Sender<PathBuf>> {
+    bail!("This build has no audio; rebuild with --features audio")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_first_rule_with_sound_wins() {
+        let rules = vec![
+            Rule {
+                name: "any-a1".to_string(),
+                priorities: vec!["A1".to_string()],
+                ..Default::default()
+            },
+            Rule {
+                name: "rea".to_string(),
+                keywords: vec!["reanimatie".to_string()],
+                sound: Some(PathBuf::from("rea.wav")),
+                ..Default::default()
+            },
+            Rule {
+                name: "mine".to_string(),
+                capcodes: vec!["1420028".to_string()],
+                sound: Some(PathBuf::from("mine.wav")),
+                ..Default::default()
+            },
+        ];
+        let msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568 001420028|ALN|A1 Reanimatie Nassaulaan Vlaardingen")
+            .unwrap();
+        let enrichment = Enrichment::default();
+        assert_eq!(sound_for(&rules, &msg, &enrichment), Some(Path::new("rea.wav")));
+        assert_eq!(sound_for(&rules[..1], &msg, &enrichment), None);
+    }
+}
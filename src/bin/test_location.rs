@@ -102,7 +102,7 @@ mod location {
             }
 
             // Sort place names by length (longest first) for matching priority
-            place_names.sort_by(|a, b| b.len().cmp(&a.len()));
+            place_names.sort_by_key(|name| std::cmp::Reverse(name.len()));
 
             Ok(LocationLookup {
                 locations,
@@ -135,6 +135,8 @@ mod location {
             None
         }
 
+        // Kept in step with the library copy, though main doesn't print it
+        #[allow(dead_code)]
         pub fn format_info(&self, info: &LocationInfo) -> String {
             let mut parts = vec![];
             if !info.place.is_empty() {
@@ -0,0 +1,160 @@
+//! Binary cache of parsed log files.
+//!
+//! Parsing a multi-GB log takes a while, so after the first parse the messages
+//! are written to `<file>.p2k` together with the length and CRC32 of the
+//! source and the line format it was parsed with. Re-opening the same,
+//! unchanged file with the same format loads that cache instead.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::intern::Interner;
+use crate::parser::{LineFormat, MessageType, P2000Message};
+
+const MAGIC: &[u8; 4] = b"P2K\0";
+/// Bump whenever `P2000Message`, `CacheFile` or the parser output changes
+const VERSION: u32 = 8;
+
+/// Length and CRC32 of a file, to notice when it changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct SourceHash {
+    len: u64,
+    crc32: u32,
+}
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    source: SourceHash,
+    /// See [`format_key`]
+    format: String,
+    messages: Vec<P2000Message>,
+}
+
+/// The line format the messages were parsed with, as compared between runs.
+fn format_key(format: &LineFormat) -> String {
+    format!("{:?}", format)
+}
+
+pub fn cache_path(source: &Path) -> PathBuf {
+    let mut path = source.as_os_str().to_owned();
+    path.push(".p2k");
+    PathBuf::from(path)
+}
+
+pub(crate) fn hash_file(path: &Path) -> Result<SourceHash> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hasher = crc32fast::Hasher::new();
+    let mut buf = vec![0u8; 1 << 16];
+    let mut len = 0u64;
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.update(&buf[..n]);
+        len += n as u64;
+    }
+    Ok(SourceHash {
+        len,
+        crc32: hasher.finalize(),
+    })
+}
+
+/// Cached messages for `source`, or `None` when there is no valid cache for
+/// lines in `format`.
+pub fn load(source: &Path, format: &LineFormat) -> Result<Option<Vec<P2000Message>>> {
+    let path = cache_path(source);
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut bytes = Vec::new();
+    File::open(&path)?.read_to_end(&mut bytes)?;
+    let Some(body) = bytes.strip_prefix(MAGIC) else {
+        bail!("{} is not a p2k cache", path.display());
+    };
+    // Caches of other versions may not even deserialize as this one
+    let (version, _) = postcard::take_from_bytes::<u32>(body)?;
+    if version != VERSION {
+        return Ok(None);
+    }
+    let cache: CacheFile = postcard::from_bytes(body)?;
+    if cache.source != hash_file(source)? || cache.format != format_key(format) {
+        return Ok(None);
+    }
+
+    // Deserializing allocates every string separately; share them again
+    let interner = Interner::new();
+    let mut messages = cache.messages;
+    for msg in &mut messages {
+        msg.protocol = interner.intern(&msg.protocol);
+        msg.radio_address = interner.intern(&msg.radio_address);
+        msg.frequency = interner.intern(&msg.frequency);
+        if let MessageType::Other(code) = &mut msg.message_type {
+            *code = interner.intern(code);
+        }
+        for capcode in msg.capcodes.iter_mut().chain(msg.units.iter_mut()) {
+            *capcode = interner.intern(capcode);
+        }
+    }
+    Ok(Some(messages))
+}
+
+pub fn store(source: &Path, format: &LineFormat, messages: &[P2000Message]) -> Result<()> {
+    let cache = CacheFile {
+        version: VERSION,
+        source: hash_file(source)?,
+        format: format_key(format),
+        messages: messages.to_vec(),
+    };
+    let path = cache_path(source);
+    // Write to a temporary file first so a crash never leaves a truncated cache
+    let tmp = path.with_extension("p2k.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&postcard::to_stdvec(&cache)?)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp, &path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_cache_roundtrip_and_invalidation() {
+        let dir = std::env::temp_dir().join(format!("p2000_cache_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("feed.log");
+        let line = "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461";
+        std::fs::write(&source, format!("{}\n", line)).unwrap();
+
+        let format = LineFormat::default();
+        assert!(load(&source, &format).unwrap().is_none());
+        let messages = vec![Parser::new().parse_line(line).unwrap()];
+        store(&source, &format, &messages).unwrap();
+
+        let cached = load(&source, &format).unwrap().expect("Cache not used");
+        assert_eq!(cached.len(), 1);
+        assert_eq!(cached[0].to_line(), line);
+        assert_eq!(cached[0].timestamp, messages[0].timestamp);
+
+        // Parsed differently with another format
+        let local = LineFormat {
+            local_time: true,
+            ..LineFormat::default()
+        };
+        assert!(load(&source, &local).unwrap().is_none());
+
+        std::fs::write(&source, format!("{}\n{}\n", line, line)).unwrap();
+        assert!(load(&source, &format).unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
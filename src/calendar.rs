@@ -0,0 +1,405 @@
+//! Public holidays and local events. Messages on such a day are tagged with
+//! its name (`holiday:` in searches), and `p2000-rust days` compares each of
+//! them with an ordinary day, so New Year's Eve can be held against a normal
+//! night.
+//!
+//! A calendar day runs from 06:00 to 06:00, so the night belongs to the
+//! evening before it: New Year's Eve lasts until 06:00 on 1 January.
+
+use anyhow::{anyhow, Result};
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, Timelike, Weekday};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::Write;
+
+use crate::query::OutputFormat;
+
+/// When a calendar day starts
+const DAY_START_HOUR: i64 = 6;
+
+/// `[calendar]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct CalendarConfig {
+    /// The national holidays, New Year's Eve and King's Day
+    pub holidays: bool,
+    pub events: Vec<EventConfig>,
+}
+
+impl Default for CalendarConfig {
+    fn default() -> Self {
+        CalendarConfig {
+            holidays: true,
+            events: Vec::new(),
+        }
+    }
+}
+
+/// `[[calendar.events]]`: a festival, a fair, a football match.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct EventConfig {
+    pub name: String,
+    /// `2026-07-17`, or `07-17` for every year
+    pub date: String,
+    /// Last day, in the same form; the event lasts one day without it
+    pub until: Option<String>,
+    /// Only messages from these woonplaatsen or gemeenten; everywhere when empty
+    pub places: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum When {
+    Once(NaiveDate),
+    Yearly(u32, u32),
+}
+
+impl When {
+    fn parse(text: &str) -> Result<Self> {
+        let text = text.trim();
+        if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+            return Ok(When::Once(date));
+        }
+        // A leap year, so 02-29 parses
+        NaiveDate::parse_from_str(&format!("2024-{}", text), "%Y-%m-%d")
+            .map(|date| When::Yearly(date.month(), date.day()))
+            .map_err(|_| anyhow!("'{}' is no date; use YYYY-MM-DD or MM-DD", text))
+    }
+
+    fn in_year(self, year: i32) -> Option<NaiveDate> {
+        match self {
+            When::Once(date) => Some(date),
+            When::Yearly(month, day) => NaiveDate::from_ymd_opt(year, month, day),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Event {
+    name: String,
+    first: When,
+    last: When,
+    places: Vec<String>,
+}
+
+impl Event {
+    fn covers(&self, date: NaiveDate) -> bool {
+        // A yearly event over New Year starts in the year before
+        [date.year() - 1, date.year()].into_iter().any(|year| {
+            let Some(first) = self.first.in_year(year) else {
+                return false;
+            };
+            let last = match self.last.in_year(year) {
+                Some(last) if last < first && matches!(self.last, When::Yearly(..)) => self.last.in_year(year + 1),
+                last => last,
+            };
+            last.is_some_and(|last| first <= date && date <= last)
+        })
+    }
+
+    fn applies_to(&self, places: &[&str]) -> bool {
+        self.places.is_empty() || self.places.iter().any(|p| places.iter().any(|place| p.eq_ignore_ascii_case(place)))
+    }
+}
+
+/// Holidays and configured events.
+#[derive(Debug, Clone)]
+pub struct Calendar {
+    holidays: bool,
+    events: Vec<Event>,
+}
+
+impl Default for Calendar {
+    /// The holidays only.
+    fn default() -> Self {
+        Calendar {
+            holidays: true,
+            events: Vec::new(),
+        }
+    }
+}
+
+impl Calendar {
+    /// Fails on an event without a name or with a date that doesn't parse.
+    pub fn new(config: &CalendarConfig) -> Result<Self> {
+        let events = config
+            .events
+            .iter()
+            .map(|event| {
+                if event.name.is_empty() {
+                    return Err(anyhow!("[[calendar.events]] needs a name"));
+                }
+                let first = When::parse(&event.date).map_err(|e| anyhow!("{}: {}", event.name, e))?;
+                let last = match &event.until {
+                    Some(until) => When::parse(until).map_err(|e| anyhow!("{}: {}", event.name, e))?,
+                    None => first,
+                };
+                Ok(Event {
+                    name: event.name.clone(),
+                    first,
+                    last,
+                    places: event.places.clone(),
+                })
+            })
+            .collect::<Result<_>>()?;
+        Ok(Calendar {
+            holidays: config.holidays,
+            events,
+        })
+    }
+
+    /// Names of the holidays and events `time` falls on, for a message from
+    /// one of `places` (its woonplaats and gemeente).
+    pub fn days(&self, time: DateTime<Local>, places: &[&str]) -> Vec<&str> {
+        let date = day_of(time);
+        let mut days = if self.holidays { holidays(date) } else { Vec::new() };
+        days.extend(
+            self.events
+                .iter()
+                .filter(|event| event.covers(date) && event.applies_to(places))
+                .map(|event| event.name.as_str()),
+        );
+        days
+    }
+
+    /// Whether `date` is an ordinary day for a message from `places`.
+    fn is_ordinary(&self, date: NaiveDate, places: &[&str]) -> bool {
+        (!self.holidays || holidays(date).is_empty())
+            && !self.events.iter().any(|event| event.covers(date) && event.applies_to(places))
+    }
+}
+
+/// The calendar day `time` belongs to.
+pub fn day_of(time: DateTime<Local>) -> NaiveDate {
+    (time - Duration::hours(DAY_START_HOUR)).date_naive()
+}
+
+/// The holidays on `date`: the national ones, New Year's Eve and King's Day.
+pub fn holidays(date: NaiveDate) -> Vec<&'static str> {
+    let year = date.year();
+    let on = |month: u32, day: u32| NaiveDate::from_ymd_opt(year, month, day) == Some(date);
+    let easter = easter(year);
+    let after_easter = |days: i64| easter + Duration::days(days) == date;
+    // King's Day moves to Saturday when 27 April is a Sunday
+    let kings_day = match NaiveDate::from_ymd_opt(year, 4, 27) {
+        Some(day) if day.weekday() == Weekday::Sun => on(4, 26),
+        _ => on(4, 27),
+    };
+    [
+        (on(1, 1), "New Year's Day"),
+        (after_easter(-2), "Good Friday"),
+        (after_easter(0), "Easter Sunday"),
+        (after_easter(1), "Easter Monday"),
+        (kings_day, "King's Day"),
+        (on(5, 5), "Liberation Day"),
+        (after_easter(39), "Ascension Day"),
+        (after_easter(49), "Whit Sunday"),
+        (after_easter(50), "Whit Monday"),
+        (on(12, 25), "Christmas Day"),
+        (on(12, 26), "Second Christmas Day"),
+        (on(12, 31), "New Year's Eve"),
+    ]
+    .into_iter()
+    .filter(|(is, _)| *is)
+    .map(|(_, name)| name)
+    .collect()
+}
+
+/// Easter Sunday by the anonymous Gregorian algorithm.
+fn easter(year: i32) -> NaiveDate {
+    let (a, b, c) = (year % 19, year / 100, year % 100);
+    let (d, e) = (b / 4, b % 4);
+    let f = (b + 8) / 25;
+    let g = (b - f + 1) / 3;
+    let h = (19 * a + b - d - g + 15) % 30;
+    let (i, k) = (c / 4, c % 4);
+    let l = (32 + 2 * e + 2 * i - h - k) % 7;
+    let m = (a + 11 * h + 22 * l) / 451;
+    let month = (h + l - 7 * m + 114) / 31;
+    let day = (h + l - 7 * m + 114) % 31 + 1;
+    NaiveDate::from_ymd_opt(year, month as u32, day as u32).expect("Easter is a date")
+}
+
+/// One holiday or event in the archive against an ordinary day.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DayCount {
+    pub date: NaiveDate,
+    pub name: String,
+    pub messages: usize,
+    /// From 18:00 until the day ends at 06:00
+    pub night: usize,
+    /// Mean over the ordinary days seen, for the event's places
+    pub ordinary_messages: f64,
+    pub ordinary_night: f64,
+}
+
+/// Counts `messages`, given as time and place, on each holiday and event and
+/// on the ordinary days between them; oldest first. Days without messages
+/// don't count as ordinary, so an outage doesn't drag the mean down.
+pub fn count_days<'a>(
+    calendar: &Calendar,
+    messages: impl IntoIterator<Item = (DateTime<Local>, Option<&'a str>)>,
+) -> Vec<DayCount> {
+    // Per day and name, and per set of event places on ordinary days (none for everywhere)
+    let mut days: BTreeMap<(NaiveDate, String), (usize, usize)> = BTreeMap::new();
+    let mut ordinary: BTreeMap<Vec<String>, (usize, usize)> = BTreeMap::new();
+    let mut ordinary_dates: BTreeMap<Vec<String>, BTreeSet<NaiveDate>> = BTreeMap::new();
+    let scopes: BTreeSet<Vec<String>> = std::iter::once(Vec::new())
+        .chain(calendar.events.iter().map(|event| event.places.clone()))
+        .collect();
+    for (time, place) in messages {
+        let (date, places) = (day_of(time), place.as_slice());
+        let at_night = !(DAY_START_HOUR as u32..18).contains(&time.hour());
+        for name in calendar.days(time, places) {
+            let count = days.entry((date, name.to_string())).or_default();
+            count.0 += 1;
+            count.1 += usize::from(at_night);
+        }
+        for scope in &scopes {
+            let applies = scope.is_empty() || scope.iter().any(|p| places.iter().any(|place| p.eq_ignore_ascii_case(place)));
+            if applies && calendar.is_ordinary(date, places) {
+                let count = ordinary.entry(scope.clone()).or_default();
+                count.0 += 1;
+                count.1 += usize::from(at_night);
+                ordinary_dates.entry(scope.clone()).or_default().insert(date);
+            }
+        }
+    }
+
+    let scope_of = |name: &str| {
+        calendar.events.iter().find(|event| event.name == name).map(|event| event.places.clone()).unwrap_or_default()
+    };
+    days.into_iter()
+        .map(|((date, name), (messages, night))| {
+            let scope = scope_of(&name);
+            let (total, total_night) = ordinary.get(&scope).copied().unwrap_or_default();
+            let dates = ordinary_dates.get(&scope).map_or(0, BTreeSet::len).max(1) as f64;
+            DayCount {
+                date,
+                name,
+                messages,
+                night,
+                ordinary_messages: total as f64 / dates,
+                ordinary_night: total_night as f64 / dates,
+            }
+        })
+        .collect()
+}
+
+pub fn print(counts: &[DayCount], format: OutputFormat, out: &mut impl Write) -> Result<()> {
+    // "x2.4" times an ordinary day, or "-" when there were none
+    let ratio = |count: usize, ordinary: f64| {
+        if ordinary > 0.0 {
+            format!("x{:.1}", count as f64 / ordinary)
+        } else {
+            "-".to_string()
+        }
+    };
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, counts)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record(["date", "name", "messages", "night", "ordinary_messages", "ordinary_night"])?;
+            for c in counts {
+                writer.write_record([
+                    c.date.to_string(),
+                    c.name.clone(),
+                    c.messages.to_string(),
+                    c.night.to_string(),
+                    format!("{:.1}", c.ordinary_messages),
+                    format!("{:.1}", c.ordinary_night),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table => {
+            writeln!(out, "{:<10} | {:>8} {:>6} | {:>6} {:>6} | Day", "Date", "Messages", "", "Night", "")?;
+            for c in counts {
+                writeln!(
+                    out,
+                    "{:<10} | {:>8} {:>6} | {:>6} {:>6} | {}",
+                    c.date,
+                    c.messages,
+                    ratio(c.messages, c.ordinary_messages),
+                    c.night,
+                    ratio(c.night, c.ordinary_night),
+                    c.name
+                )?;
+            }
+            writeln!(out, "{} days", counts.len())?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_holidays_events_and_counts() {
+        let date = |text: &str| NaiveDate::parse_from_str(text, "%Y-%m-%d").unwrap();
+        assert_eq!(easter(2026), date("2026-04-05"));
+        assert_eq!(easter(2025), date("2025-04-20"));
+        assert_eq!(holidays(date("2026-05-14")), ["Ascension Day"]);
+        // 27 April 2025 was a Sunday
+        assert_eq!(holidays(date("2025-04-26")), ["King's Day"]);
+        assert!(holidays(date("2025-04-27")).is_empty());
+        assert!(holidays(date("2026-03-10")).is_empty());
+
+        let config: CalendarConfig = toml::from_str(
+            r#"
+            [[events]]
+            name = "Vierdaagse"
+            date = "2026-07-21"
+            until = "2026-07-24"
+            places = ["Nijmegen"]
+            [[events]]
+            name = "Jaarwisseling"
+            date = "12-30"
+            until = "01-01"
+            "#,
+        )
+        .unwrap();
+        let calendar = Calendar::new(&config).unwrap();
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        // Still the evening before until 06:00
+        assert_eq!(calendar.days(at("2027-01-01 03:00"), &[]), ["New Year's Eve", "Jaarwisseling"]);
+        assert_eq!(calendar.days(at("2027-01-01 12:00"), &[]), ["New Year's Day", "Jaarwisseling"]);
+        assert_eq!(calendar.days(at("2026-07-22 12:00"), &["Lent", "Nijmegen"]), ["Vierdaagse"]);
+        assert!(calendar.days(at("2026-07-22 12:00"), &["Arnhem"]).is_empty());
+        let bad = CalendarConfig {
+            events: vec![EventConfig { name: "Kermis".into(), date: "31-12".into(), ..Default::default() }],
+            ..Default::default()
+        };
+        assert!(Calendar::new(&bad).is_err());
+
+        let messages = [
+            (at("2025-12-29 14:00"), Some("Almelo")),
+            (at("2025-12-29 22:00"), Some("Almelo")),
+            (at("2026-01-05 23:00"), None),
+            (at("2025-12-31 20:00"), None),
+            (at("2026-01-01 00:30"), Some("Almelo")),
+            (at("2026-01-01 01:30"), None),
+            (at("2026-01-01 05:59"), None),
+            (at("2026-01-01 13:00"), None),
+        ];
+        let counts = count_days(&Calendar::default(), messages);
+        assert_eq!(counts.iter().map(|c| (c.name.as_str(), c.messages, c.night)).collect::<Vec<_>>(), [
+            ("New Year's Eve", 4, 4),
+            ("New Year's Day", 1, 0),
+        ]);
+        assert_eq!((counts[0].ordinary_messages, counts[0].ordinary_night), (1.5, 1.0));
+
+        let mut table = Vec::new();
+        print(&counts[..1], OutputFormat::Table, &mut table).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "Date       | Messages        |  Night        | Day\n\
+             2025-12-31 |        4   x2.7 |      4   x4.0 | New Year's Eve\n1 days\n"
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Readers for the capcode list layouts in circulation: the semicolon CSV in
+//! `data/`, comma or tab separated exports with a header row (scanner.nl,
+//! Tweakers), and JSON exports. The layout is detected from the content.
+
+use anyhow::{bail, Context, Result};
+use serde_json::Value;
+use std::path::Path;
+
+use crate::lookup::CapcodeInfo;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Column {
+    Code,
+    Service,
+    Region,
+    Place,
+    Description,
+    Short,
+}
+
+/// Column order of capcodelist.csv, which has no header
+const NATIVE_ORDER: [Column; 6] = [
+    Column::Code,
+    Column::Service,
+    Column::Region,
+    Column::Place,
+    Column::Description,
+    Column::Short,
+];
+
+/// Header names (lowercased) the exports use for each column
+fn column(name: &str) -> Option<Column> {
+    let column = match name.trim().trim_matches('"').to_lowercase().as_str() {
+        "capcode" | "code" | "ric" | "address" | "adres" => Column::Code,
+        "dienst" | "discipline" | "service" | "agency" | "organisatie" => Column::Service,
+        "regio" | "region" | "veiligheidsregio" => Column::Region,
+        "plaats" | "woonplaats" | "standplaats" | "place" | "city" => Column::Place,
+        "omschrijving" | "description" | "functie" | "naam" | "name" | "eenheid" | "label" => Column::Description,
+        "kort" | "short" | "afkorting" | "abbreviation" | "tag" => Column::Short,
+        _ => return None,
+    };
+    Some(column)
+}
+
+/// Reads a capcode list in any supported layout.
+pub fn read(path: &Path) -> Result<Vec<CapcodeInfo>> {
+    let bytes = std::fs::read(path)?;
+    let text = String::from_utf8_lossy(&bytes);
+    parse(&text).with_context(|| format!("Failed to read capcode list {}", path.display()))
+}
+
+/// Whether `text` is in the header-less semicolon layout of capcodelist.csv.
+pub(crate) fn is_native(text: &str) -> bool {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    let first = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    !first.trim_start().starts_with(['[', '{'])
+        && sniff_delimiter(first) == b';'
+        && !first.split(';').any(|name| column(name) == Some(Column::Code))
+}
+
+pub(crate) fn parse(text: &str) -> Result<Vec<CapcodeInfo>> {
+    let text = text.strip_prefix('\u{feff}').unwrap_or(text);
+    match text.trim_start().chars().next() {
+        Some('[' | '{') => parse_json(text),
+        _ => parse_csv(text),
+    }
+}
+
+fn parse_csv(text: &str) -> Result<Vec<CapcodeInfo>> {
+    let mut rdr = csv::ReaderBuilder::new()
+        .delimiter(sniff_delimiter(text))
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(text.as_bytes());
+    let mut records = rdr.records();
+
+    let Some(first) = records.next().transpose()? else {
+        return Ok(Vec::new());
+    };
+    let header: Vec<Option<Column>> = first.iter().map(column).collect();
+    let headered = header.contains(&Some(Column::Code));
+    let columns: Vec<Option<Column>> = if headered {
+        header
+    } else {
+        NATIVE_ORDER.iter().copied().map(Some).collect()
+    };
+
+    let mut infos = Vec::new();
+    let rows = (!headered).then_some(Ok(first)).into_iter().chain(records);
+    for record in rows {
+        let record = record?;
+        // Without a header only complete rows can be trusted to be in order
+        if !headered && record.len() < NATIVE_ORDER.len() {
+            continue;
+        }
+        let mut info = empty_info();
+        for (field, column) in record.iter().zip(&columns) {
+            if let Some(column) = column {
+                *slot(&mut info, *column) = field.trim().trim_matches('"').to_string();
+            }
+        }
+        if !info.code.is_empty() {
+            infos.push(info);
+        }
+    }
+    Ok(infos)
+}
+
+/// The most frequent of `;`, tab and `,` on the first line; `;` when none appear.
+fn sniff_delimiter(text: &str) -> u8 {
+    let first = text.lines().find(|line| !line.trim().is_empty()).unwrap_or("");
+    [b';', b'\t', b',']
+        .into_iter()
+        .map(|delimiter| (first.bytes().filter(|&b| b == delimiter).count(), delimiter))
+        .filter(|(count, _)| *count > 0)
+        .max_by_key(|(count, _)| *count)
+        .map_or(b';', |(_, delimiter)| delimiter)
+}
+
+/// An array of objects, an object of code -> object or description, or either
+/// wrapped in an object with a single array field.
+fn parse_json(text: &str) -> Result<Vec<CapcodeInfo>> {
+    let value: Value = serde_json::from_str(text)?;
+    let mut infos = Vec::new();
+    match value {
+        Value::Array(items) => infos.extend(items.iter().filter_map(|item| json_info(item, None))),
+        Value::Object(map) => {
+            let arrays: Vec<&Vec<Value>> = map.values().filter_map(Value::as_array).collect();
+            if let [items] = arrays.as_slice() {
+                infos.extend(items.iter().filter_map(|item| json_info(item, None)));
+            } else {
+                infos.extend(map.iter().filter_map(|(code, item)| json_info(item, Some(code))));
+            }
+        }
+        _ => bail!("expected an array or object of capcodes"),
+    }
+    Ok(infos)
+}
+
+fn json_info(item: &Value, code: Option<&str>) -> Option<CapcodeInfo> {
+    let mut info = empty_info();
+    if let Some(code) = code {
+        info.code = code.to_string();
+    }
+    match item {
+        Value::Object(fields) => {
+            for (name, value) in fields {
+                if let Some(column) = column(name) {
+                    *slot(&mut info, column) = json_text(value);
+                }
+            }
+        }
+        Value::String(description) => info.description = description.clone(),
+        _ => return None,
+    }
+    (!info.code.is_empty()).then_some(info)
+}
+
+fn json_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.trim().to_string(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+fn empty_info() -> CapcodeInfo {
+    CapcodeInfo {
+        code: String::new(),
+        service: String::new(),
+        region: String::new(),
+        place: String::new(),
+        description: String::new(),
+        short: String::new(),
+        source: "".into(),
+    }
+}
+
+fn slot(info: &mut CapcodeInfo, column: Column) -> &mut String {
+    match column {
+        Column::Code => &mut info.code,
+        Column::Service => &mut info.service,
+        Column::Region => &mut info.region,
+        Column::Place => &mut info.place,
+        Column::Description => &mut info.description,
+        Column::Short => &mut info.short,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detects_layouts() {
+        let layouts = [
+            "\"1420028\";\"Brandweer\";\"Rotterdam-Rijnmond\";\"Vlaardingen\";\"Bevelvoerders\";\"\"\n\"0100000\";\"Brandweer\"\n",
+            "\u{feff}Capcode,Omschrijving,Plaats,Regio,Discipline\n1420028,Bevelvoerders,Vlaardingen,Rotterdam-Rijnmond,Brandweer\n",
+            "ric\tdienst\tregio\twoonplaats\tfunctie\n001420028\tBrandweer\tRotterdam-Rijnmond\tVlaardingen\tBevelvoerders\n",
+            r#"[{"capcode": 1420028, "discipline": "Brandweer", "regio": "Rotterdam-Rijnmond", "plaats": "Vlaardingen", "omschrijving": "Bevelvoerders"}]"#,
+            r#"{"capcodes": [{"code": "1420028", "service": "Brandweer", "region": "Rotterdam-Rijnmond", "city": "Vlaardingen", "description": "Bevelvoerders"}]}"#,
+            r#"{"1420028": {"dienst": "Brandweer", "regio": "Rotterdam-Rijnmond", "plaats": "Vlaardingen", "omschrijving": "Bevelvoerders"}}"#,
+        ];
+        for layout in layouts {
+            let infos = parse(layout).unwrap();
+            assert_eq!(infos.len(), 1, "{}", layout);
+            let info = &infos[0];
+            assert_eq!(info.code.trim_start_matches('0'), "1420028", "{}", layout);
+            assert_eq!(
+                [&info.service, &info.region, &info.place, &info.description],
+                ["Brandweer", "Rotterdam-Rijnmond", "Vlaardingen", "Bevelvoerders"],
+                "{}",
+                layout
+            );
+        }
+
+        let descriptions = parse(r#"{"1420028": "Bevelvoerders"}"#).unwrap();
+        assert_eq!(descriptions[0].description, "Bevelvoerders");
+        assert!(parse("\"1\"").unwrap().is_empty());
+        assert!(parse("[1, 2").is_err());
+        assert!(is_native(layouts[0]) && !layouts[1..].iter().any(|layout| is_native(layout)));
+    }
+}
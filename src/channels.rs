@@ -0,0 +1,62 @@
+//! `[channels]`: names for values of the frequency field, such as
+//! `"169.650" = "P2000"`. A message its source gave no channel gets the name
+//! of its frequency, which then shows in the details and `channel:` searches.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::parser::P2000Message;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(transparent)]
+pub struct ChannelNames(BTreeMap<String, Arc<str>>);
+
+impl ChannelNames {
+    /// The name for `frequency`, written as in the table or as the same number
+    /// (`169.65` for `"169.650"`).
+    pub fn name(&self, frequency: &str) -> Option<&Arc<str>> {
+        let frequency = frequency.trim();
+        self.0.get(frequency).or_else(|| {
+            let number: f64 = frequency.parse().ok()?;
+            self.0
+                .iter()
+                .find(|(key, _)| key.trim().parse::<f64>().is_ok_and(|key| (key - number).abs() < 1e-9))
+                .map(|(_, name)| name)
+        })
+    }
+
+    /// Names `msg`'s channel after its frequency, unless its source did.
+    pub fn apply(&self, msg: &mut P2000Message) {
+        if msg.channel.is_none() {
+            msg.channel = self.name(&msg.frequency).cloned();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_channel_names() {
+        let names: ChannelNames =
+            toml::from_str("\"169.650\" = \"P2000\"\n\"172.450\" = \"Test\"\nregio = \"Regional\"").unwrap();
+        assert_eq!(names.name("169.650").map(|n| &**n), Some("P2000"));
+        assert_eq!(names.name(" 172.45").map(|n| &**n), Some("Test"));
+        assert_eq!(names.name("regio").map(|n| &**n), Some("Regional"));
+        assert_eq!(names.name("169.651"), None);
+
+        let parser = Parser::new();
+        let mut msg = parser
+            .parse_line("FLEX|2026-01-01 20:00:51|1600/2/K/A|169.650|002029568|ALN|A1 Vlaardingen")
+            .unwrap();
+        names.apply(&mut msg);
+        assert_eq!(msg.channel.as_deref(), Some("P2000"));
+        // The source's own channel wins
+        msg.channel = Some("SDR 1".into());
+        names.apply(&mut msg);
+        assert_eq!(msg.channel.as_deref(), Some("SDR 1"));
+    }
+}
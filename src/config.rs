@@ -0,0 +1,205 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::anomaly::AnomalyConfig;
+use crate::archive::ArchiveConfig;
+use crate::audio::AudioConfig;
+use crate::calendar::CalendarConfig;
+use crate::channels::ChannelNames;
+use crate::gap::GapConfig;
+use crate::geofence::Geofence;
+use crate::icons::IconStyle;
+use crate::lookup::CapcodeConfig;
+use crate::map::PaneLayout;
+use crate::pager::PagerConfig;
+use crate::parser::{Receiver, TimeSource};
+use crate::plugin::PipelineConfig;
+use crate::remote::ServerConfig;
+use crate::rules::Rule;
+use crate::sampler::SamplingConfig;
+use crate::script::ScriptConfig;
+use crate::scrub::ScrubRule;
+use crate::severity::SeverityConfig;
+use crate::sinks::{
+    gotify::GotifyConfig, influx::InfluxConfig, loki::LokiConfig, ntfy::NtfyConfig, syslog::SyslogConfig,
+    WebhookConfig,
+};
+use crate::theme::Theme;
+use crate::translate::TranslationConfig;
+use crate::units::UnitsConfig;
+use crate::view::FilterPreset;
+use crate::weather::WeatherConfig;
+
+pub const DEFAULT_CONFIG_PATH: &str = "p2000.toml";
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    pub rules: Vec<Rule>,
+    /// Named areas rules and searches can require; see `geofence`
+    pub geofences: Vec<Geofence>,
+    /// Clean-up of content from the live sources, before parsing
+    pub scrub: Vec<ScrubRule>,
+    pub ntfy: Option<NtfyConfig>,
+    pub gotify: Option<GotifyConfig>,
+    pub webhooks: Vec<WebhookConfig>,
+    pub loki: Option<LokiConfig>,
+    pub syslog: Option<SyslogConfig>,
+    pub influx: Option<InfluxConfig>,
+    pub script: Option<ScriptConfig>,
+    pub pipeline: Option<PipelineConfig>,
+    pub archive: Option<ArchiveConfig>,
+    /// Enables rule sounds in live mode and the daemon
+    pub audio: Option<AudioConfig>,
+    pub display: DisplayConfig,
+    /// Age thresholds for live rows
+    pub theme: Theme,
+    /// Feed outage detection
+    pub gaps: GapConfig,
+    /// Hourly volume spikes per region
+    pub anomaly: AnomalyConfig,
+    /// How long the unit board assumes a dispatched unit is out
+    pub units: UnitsConfig,
+    /// Holidays and local events messages are tagged with
+    pub calendar: CalendarConfig,
+    /// Thins out routine messages in live mode
+    pub sampling: Option<SamplingConfig>,
+    /// Own capcodes for the personal pager pane
+    pub pager: Option<PagerConfig>,
+    /// Enables the English translation enricher
+    pub translation: Option<TranslationConfig>,
+    /// Enables the weather enricher (feature `weather`)
+    pub weather: Option<WeatherConfig>,
+    /// Directory with capcodelist.csv and the location tables; see `data_dir()`
+    pub data_dir: Option<PathBuf>,
+    /// Which row wins for capcodes listed more than once
+    pub capcodes: CapcodeConfig,
+    /// Channel names by value of the frequency field
+    pub channels: ChannelNames,
+    /// Searches the quick-filter key cycles through
+    pub filters: Vec<FilterPreset>,
+    /// Weights of the severity score messages can be sorted and alerted by
+    pub severity: SeverityConfig,
+    /// Station live messages are tagged with
+    pub receiver: Option<Receiver>,
+    /// Streams messages to remote viewers
+    pub server: Option<ServerConfig>,
+    /// Options for registered components, by name
+    pub plugins: HashMap<String, toml::Table>,
+    /// File this was loaded from, if any
+    #[serde(skip)]
+    pub path: Option<PathBuf>,
+}
+
+/// `[display]` section: what the message list shows.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct DisplayConfig {
+    /// Show the derived veiligheidsregio as a list column
+    pub region_column: bool,
+    /// Service and urgency glyphs in list rows: "none", "ascii", "emoji" or "nerd"
+    pub icons: IconStyle,
+    /// Seconds new A1/P 1 messages flash in live mode; 0 disables
+    pub flash_secs: u64,
+    /// Ring the terminal bell for new A1/P 1 messages in live mode
+    pub bell: bool,
+    /// Ring the terminal bell for new messages at the place followed with 'F'
+    pub follow_bell: bool,
+    /// Clock behind the time sort: "decoded" (the decoder's timestamp) or
+    /// "received" (when the line was read, for decoders whose clock drifts)
+    pub sort_time: TimeSource,
+    /// Redraws per second while nothing changes; 0 redraws only on input, new
+    /// messages and flashing
+    pub idle_fps: f64,
+    /// Milliseconds to wait for input before checking the live feed again
+    pub poll_ms: u64,
+    /// Longer polling, no idle redraws or flashing, and capcode-only enrichment
+    /// of new messages, for boards like a Raspberry Pi Zero
+    pub low_power: bool,
+    /// Start with house numbers and postcodes hidden and coordinates rounded ('R')
+    pub redact: bool,
+    /// "stacked" (list above the details) or "split" (list beside a map and the
+    /// details, on wide terminals)
+    pub layout: PaneLayout,
+    /// Show consecutive repeats of a message as one row with a count ('z' expands)
+    pub collapse_repeats: bool,
+    /// A line above the list with today's totals
+    pub totals: bool,
+}
+
+impl Default for DisplayConfig {
+    fn default() -> Self {
+        DisplayConfig {
+            region_column: true,
+            icons: IconStyle::default(),
+            flash_secs: 5,
+            bell: false,
+            follow_bell: false,
+            sort_time: TimeSource::default(),
+            idle_fps: 1.0,
+            poll_ms: 100,
+            low_power: false,
+            redact: false,
+            layout: PaneLayout::default(),
+            collapse_repeats: true,
+            totals: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn load(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config {}", path.display()))?;
+        let config: Config =
+            toml::from_str(&content).with_context(|| format!("Invalid config {}", path.display()))?;
+        Ok(Config {
+            path: Some(path.to_path_buf()),
+            ..config
+        })
+    }
+
+    /// Directory of the config file, else the working directory; user
+    /// corrections are kept here.
+    pub fn config_dir(&self) -> PathBuf {
+        self.path
+            .as_deref()
+            .and_then(Path::parent)
+            .filter(|dir| !dir.as_os_str().is_empty())
+            .map_or_else(|| PathBuf::from("."), Path::to_path_buf)
+    }
+
+    /// The configured data directory, else `data/` in the working directory,
+    /// else `data/` next to the executable (for installs started from a shortcut).
+    pub fn data_dir(&self) -> PathBuf {
+        if let Some(dir) = &self.data_dir {
+            return dir.clone();
+        }
+        let local = PathBuf::from("data");
+        if local.is_dir() {
+            return local;
+        }
+        std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.parent().map(|dir| dir.join("data")))
+            .filter(|dir| dir.is_dir())
+            .unwrap_or(local)
+    }
+
+    /// Loads `path` if given, otherwise `p2000.toml` when present, otherwise an empty config.
+    pub fn load_or_default(path: Option<&Path>) -> Result<Self> {
+        match path {
+            Some(path) => Self::load(path),
+            None => {
+                let default_path = Path::new(DEFAULT_CONFIG_PATH);
+                if default_path.exists() {
+                    Self::load(default_path)
+                } else {
+                    Ok(Config::default())
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::anomaly::{Spike, VolumeDetector};
+use crate::archive::{Archive, RetentionScheduler};
+use crate::audio::AudioPlayer;
+use crate::config::Config;
+use crate::enrich::Enrichment;
+use crate::gap::{self, GapDetector};
+use crate::parser::{P2000Message, TimeSource};
+use crate::plugin::{self, BuildContext, Enricher, Pipeline, Registry};
+use crate::reader;
+use crate::rules::{AlertThrottle, Rule};
+use crate::script::ScriptHook;
+use crate::signal;
+use crate::sinks::{self, Sink};
+
+/// How often a silent feed is checked against the gap threshold
+const GAP_CHECK_INTERVAL: Duration = Duration::from_secs(10);
+
+/// Counters printed when the daemon stops.
+#[derive(Debug, Default)]
+struct Session {
+    received: usize,
+    unparsed: usize,
+    dropped_by_script: usize,
+    archived: usize,
+    suppressed: usize,
+}
+
+/// Headless mode: reads the configured source and dispatches rule matches to the
+/// configured sinks until the feed closes or a shutdown signal arrives.
+pub async fn run(registry: &Registry, ctx: &BuildContext<'_>) -> Result<()> {
+    let config: &Config = ctx.config;
+    let Pipeline {
+        source,
+        parser,
+        enrichers,
+        sinks,
+    } = registry.build(ctx)?;
+    if sinks.is_empty() {
+        eprintln!("Warning: no sinks configured, alerts will not be delivered");
+    }
+    let script = config.script.as_ref().map(ScriptHook::load).transpose()?;
+    let audio = match &config.audio {
+        Some(audio_config) => Some(AudioPlayer::start(audio_config, &config.rules)?),
+        None => None,
+    };
+    let mut archive = match &config.archive {
+        Some(archive_config) => {
+            let archive = Archive::open(&archive_config.path)?.with_segment_size(archive_config.segment_size);
+            let updated = archive.update_regions(&ctx.lookup, &ctx.location_lookup)?;
+            if updated > 0 {
+                eprintln!("Archive: derived the region of {} earlier messages again", updated);
+            }
+            Some((archive, RetentionScheduler::new(archive_config.retention.clone())))
+        }
+        None => None,
+    };
+    let receiver = config.receiver.clone().map(Arc::new);
+    let started = Instant::now();
+    let mut session = Session::default();
+    let mut throttle = AlertThrottle::default();
+
+    let mut gaps = GapDetector::new(&config.gaps);
+    if let Some(detector) = &mut gaps {
+        detector.observe(Local::now());
+    }
+    let mut gap_check = tokio::time::interval(GAP_CHECK_INTERVAL);
+    let mut volume = VolumeDetector::new(&config.anomaly);
+
+    let mut lines = reader::spawn_source(source);
+    let shutdown = signal::shutdown();
+    tokio::pin!(shutdown);
+
+    loop {
+        let line = tokio::select! {
+            line = lines.recv() => match line {
+                Some(line) => line?,
+                None => break,
+            },
+            _ = &mut shutdown => {
+                eprintln!("Shutting down...");
+                break;
+            }
+            _ = gap_check.tick() => {
+                let now = Local::now();
+                if let Some(silence) = gaps.as_mut().and_then(|d| d.check(now)) {
+                    report_gap(&sinks, config, now, silence);
+                }
+                continue;
+            }
+        };
+        session.received += 1;
+
+        let mut msg = match line.parse(&parser) {
+            Ok(msg) => msg,
+            Err(e) => {
+                session.unparsed += 1;
+                eprintln!("Warning: Failed to parse line: {}", e);
+                continue;
+            }
+        };
+        if msg.receiver.is_none() {
+            msg.receiver = receiver.clone();
+        }
+        config.channels.apply(&mut msg);
+        if let Some(gap) = gaps.as_mut().and_then(|d| d.observe(msg.time(TimeSource::Received))) {
+            eprintln!("Feed resumed after {} without messages", gap::format_duration(gap));
+        }
+        let mut enrichment = plugin::run_enrichers(&enrichers, &msg);
+        if let (Some(detector), Some(region)) = (&mut volume, &enrichment.region) {
+            if let Some(spike) = detector.observe(region, msg.time(TimeSource::Received)) {
+                report_spike(&sinks, config, &spike);
+            }
+        }
+
+        let mut script_alerts = Vec::new();
+        if let Some(script) = &script {
+            match run_script(script, &enrichers, &mut msg, &mut enrichment) {
+                Some(alerts) => script_alerts = alerts,
+                None => {
+                    session.dropped_by_script += 1;
+                    continue;
+                }
+            }
+        }
+
+        if let Some((archive, retention)) = &mut archive {
+            match archive.insert(&msg, &enrichment) {
+                Ok(_) => session.archived += 1,
+                Err(e) => eprintln!("Warning: failed to archive message: {}", e),
+            }
+            if let Err(e) = retention.tick(archive) {
+                eprintln!("Warning: archive retention failed: {}", e);
+            }
+        }
+
+        if let Some(audio) = &audio {
+            audio.alert(&msg, &enrichment);
+        }
+
+        // Sinks do blocking HTTP
+        tokio::task::block_in_place(|| {
+            sinks::record(&sinks, &msg, &enrichment);
+            let rules = ctx.rules.read().unwrap_or_else(|e| e.into_inner());
+            session.suppressed += sinks::dispatch(&sinks, &rules, &mut throttle, &msg, &enrichment);
+            for name in script_alerts {
+                let rule = Rule {
+                    name,
+                    ..Default::default()
+                };
+                sinks::send(&sinks, &rule, &msg, &enrichment);
+            }
+        });
+    }
+
+    tokio::task::block_in_place(|| sinks::flush(&sinks));
+    if let Some((archive, _)) = &archive {
+        if let Err(e) = archive.checkpoint() {
+            eprintln!("Warning: failed to checkpoint archive: {}", e);
+        }
+    }
+    eprintln!(
+        "Session: {} lines in {}s, {} unparsed, {} dropped by script, {} archived, {} alerts held back by cooldowns",
+        session.received,
+        started.elapsed().as_secs(),
+        session.unparsed,
+        session.dropped_by_script,
+        session.archived,
+        session.suppressed
+    );
+    Ok(())
+}
+
+/// Runs the script for `msg`: `None` when it drops the message, otherwise the
+/// alerts it raised. A message the script changed is enriched again, so the
+/// archive, rules and sinks see the message as rewritten.
+fn run_script(
+    script: &ScriptHook,
+    enrichers: &[Box<dyn Enricher>],
+    msg: &mut P2000Message,
+    enrichment: &mut Enrichment,
+) -> Option<Vec<String>> {
+    match script.run(msg, enrichment) {
+        Ok(outcome) if !outcome.keep => None,
+        Ok(outcome) => {
+            if outcome.modified {
+                *enrichment = plugin::run_enrichers(enrichers, msg);
+            }
+            Some(outcome.alerts)
+        }
+        Err(e) => {
+            eprintln!("Warning: script failed: {}", e);
+            Some(Vec::new())
+        }
+    }
+}
+
+/// Warns about a volume spike and, with `alert` set, sends it through the
+/// sinks with the region set for the sinks that tag by it.
+fn report_spike(sinks: &[Box<dyn Sink>], config: &Config, spike: &Spike) {
+    eprintln!("Warning: {}", spike);
+    if !config.anomaly.alert {
+        return;
+    }
+    let rule = Rule {
+        name: "Volume spike".to_string(),
+        ..Default::default()
+    };
+    let msg = P2000Message::notice(Local::now(), spike.to_string());
+    let enrichment = Enrichment {
+        region: Some(spike.region.clone()),
+        ..Default::default()
+    };
+    tokio::task::block_in_place(|| sinks::send(sinks, &rule, &msg, &enrichment));
+}
+
+/// Warns about a silent feed and, with `alert` set, sends it through the sinks.
+fn report_gap(sinks: &[Box<dyn Sink>], config: &Config, now: DateTime<Local>, silence: chrono::Duration) {
+    eprintln!("Warning: no messages for {}", gap::format_duration(silence));
+    if !config.gaps.alert {
+        return;
+    }
+    let rule = Rule {
+        name: "Receiver gap".to_string(),
+        ..Default::default()
+    };
+    let msg = gap::gap_message(now, silence);
+    tokio::task::block_in_place(|| sinks::send(sinks, &rule, &msg, &Enrichment::default()));
+}
+
+#[cfg(all(test, feature = "scripting"))]
+mod tests {
+    use super::*;
+    use crate::enrich::{CapcodeEnricher, RegionEnricher};
+    use crate::lookup::Lookup;
+    use crate::parser::Parser;
+    use crate::script::ScriptConfig;
+
+    #[test]
+    fn test_script_rewrite_is_enriched_again() {
+        let dir = std::env::temp_dir().join(format!("p2000_daemon_script_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = "\"0726119\";\"Ambulance\";\"Utrecht\";\"Utrecht\";\"Post Overvecht\";\"\"\n";
+        std::fs::write(dir.join("capcodes.csv"), list).unwrap();
+        std::fs::write(dir.join("abbreviations.txt"), "").unwrap();
+        // Pages for a retired capcode go to its successor
+        std::fs::write(
+            dir.join("rewrite.rhai"),
+            r#"
+            fn on_message(msg) {
+                if msg.capcodes.contains("001123117") { msg.capcodes = ["0726119"]; }
+                msg
+            }
+            "#,
+        )
+        .unwrap();
+        let lookup = Arc::new(Lookup::load(&dir.join("capcodes.csv"), &dir.join("abbreviations.txt")).unwrap());
+        let script = ScriptHook::load(&ScriptConfig {
+            path: dir.join("rewrite.rhai"),
+            max_operations: 100_000,
+        })
+        .unwrap();
+        std::fs::remove_dir_all(dir).ok();
+
+        let enrichers: Vec<Box<dyn Enricher>> =
+            vec![Box::new(CapcodeEnricher::new(lookup.clone())), Box::new(RegionEnricher::new(lookup))];
+        let rule = Rule {
+            name: "utrecht".to_string(),
+            capcodes: vec!["726119".to_string()],
+            query: Some("region:Utrecht".parse().unwrap()),
+            ..Default::default()
+        };
+        let mut msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461")
+            .unwrap();
+        let mut enrichment = plugin::run_enrichers(&enrichers, &msg);
+        assert!(!rule.matches(&msg, &enrichment));
+
+        assert_eq!(run_script(&script, &enrichers, &mut msg, &mut enrichment), Some(Vec::new()));
+        assert_eq!(&*msg.capcodes[0], "0726119");
+        assert_eq!(enrichment.region.as_deref(), Some("Utrecht"));
+        assert!(rule.matches(&msg, &enrichment));
+    }
+}
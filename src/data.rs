@@ -0,0 +1,366 @@
+//! The lookup tables in the data directory. Missing files are not fatal: the
+//! lookups start empty and can be loaded once the files are in place.
+
+use anyhow::Result;
+use std::collections::HashMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::capcodes;
+use crate::config::Config;
+use crate::feedback::{Feedback, CORRECTIONS_FILE};
+use crate::gazetteer::{self, GAZETTEER_FILE};
+use crate::location::{self, LocationLookup};
+use crate::lookup::{self, normalize_code, CapcodeConfig, Lookup};
+
+#[derive(Debug, Clone)]
+pub struct DataFiles {
+    pub capcodes: PathBuf,
+    pub abbreviations: PathBuf,
+    pub observations: PathBuf,
+    pub regions: PathBuf,
+    /// Optional place coordinates, next to the others
+    pub coordinates: PathBuf,
+    /// Optional prebuilt place lookup, used instead of the three files above
+    pub gazetteer: PathBuf,
+    pub capcode_config: CapcodeConfig,
+    /// "Wrong place" corrections to apply to the place lookup
+    pub corrections: Option<PathBuf>,
+}
+
+impl DataFiles {
+    pub fn in_dir(dir: &Path) -> Self {
+        DataFiles {
+            capcodes: dir.join("capcodelist.csv"),
+            abbreviations: dir.join("abbrevations.txt"),
+            observations: dir.join("Observations.csv"),
+            regions: dir.join("RegioSCodes.csv"),
+            coordinates: dir.join("4pp-final-2023.csv"),
+            gazetteer: dir.join(GAZETTEER_FILE),
+            capcode_config: CapcodeConfig::default(),
+            corrections: None,
+        }
+    }
+
+    /// The files in the configured data directory, with the `[capcodes]` options
+    /// and the corrections in the config directory.
+    pub fn from_config(config: &Config) -> Self {
+        let files = Self::in_dir(&config.data_dir());
+        DataFiles {
+            capcodes: config.capcodes.list.clone().unwrap_or(files.capcodes),
+            capcode_config: config.capcodes.clone(),
+            corrections: Some(config.config_dir().join(CORRECTIONS_FILE)),
+            ..files
+        }
+    }
+
+    /// Files that are not there, in the order above. The place files are not
+    /// needed next to a gazetteer.
+    pub fn missing(&self) -> Vec<&Path> {
+        let places: &[&PathBuf] = if self.gazetteer.exists() { &[] } else { &[&self.observations, &self.regions] };
+        [&self.capcodes, &self.abbreviations]
+            .into_iter()
+            .chain(&self.capcode_config.extra)
+            .chain(places.iter().copied())
+            .map(PathBuf::as_path)
+            .filter(|path| !path.exists())
+            .collect()
+    }
+
+    /// Names of the missing files for a warning, `None` when every file is there.
+    pub fn missing_summary(&self) -> Option<String> {
+        let missing = self.missing();
+        if missing.is_empty() {
+            return None;
+        }
+        let names: Vec<String> = missing
+            .iter()
+            .map(|path| path.file_name().unwrap_or(path.as_os_str()).to_string_lossy().into_owned())
+            .collect();
+        Some(names.join(", "))
+    }
+
+    pub fn dir(&self) -> &Path {
+        self.abbreviations.parent().unwrap_or(Path::new("."))
+    }
+
+    /// Loads whatever is present; missing files leave their lookup empty.
+    pub fn load(&self) -> Result<(Lookup, LocationLookup)> {
+        let lookup = Lookup::load_available(&self.capcodes, &self.abbreviations, &self.capcode_config)?;
+        let prebuilt = gazetteer::load(self).unwrap_or_else(|e| {
+            eprintln!("Warning: Ignoring {}: {}", self.gazetteer.display(), e);
+            None
+        });
+        let mut location_lookup = match prebuilt {
+            Some(location_lookup) => location_lookup,
+            None => LocationLookup::load_available(&self.observations, &self.regions)?,
+        };
+        if let Some(path) = &self.corrections {
+            location_lookup.apply_corrections(Feedback::load(path)?.places());
+        }
+        location_lookup.link_regions(&lookup);
+        Ok((lookup, location_lookup))
+    }
+
+    /// Checks every file for rows the loaders would skip or misread.
+    pub fn check(&self) -> Vec<FileReport> {
+        let mut reports = vec![check_file(&self.capcodes, true, check_capcodes)];
+        reports.extend(self.capcode_config.extra.iter().map(|path| check_file(path, true, check_capcodes)));
+        reports.extend([
+            check_file(&self.abbreviations, true, check_abbreviations),
+            check_file(&self.observations, true, |text, report| check_columns(text, report, 6)),
+            check_file(&self.regions, true, |text, report| check_columns(text, report, 5)),
+            check_file(&self.coordinates, false, check_coordinates),
+        ]);
+        reports
+    }
+}
+
+/// Issues shown per file before the rest are only counted
+const ISSUE_LIMIT: usize = 20;
+
+/// Result of checking one data file.
+#[derive(Debug)]
+pub struct FileReport {
+    pub path: PathBuf,
+    /// `None` when the file is missing
+    pub rows: Option<usize>,
+    /// Only for required files
+    pub required: bool,
+    /// "line N: ..." descriptions
+    pub issues: Vec<String>,
+}
+
+impl FileReport {
+    pub fn is_ok(&self) -> bool {
+        self.issues.is_empty() && (self.rows.is_some() || !self.required)
+    }
+
+    fn issue(&mut self, line: usize, issue: impl fmt::Display) {
+        self.issues.push(format!("line {}: {}", line, issue));
+    }
+}
+
+impl fmt::Display for FileReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = self.path.display();
+        match self.rows {
+            None if self.required => return writeln!(f, "{}: missing", name),
+            None => return writeln!(f, "{}: not present (optional)", name),
+            Some(rows) if self.issues.is_empty() => return writeln!(f, "{}: {}, ok", name, count(rows, "row")),
+            Some(rows) => writeln!(f, "{}: {}, {}", name, count(rows, "row"), count(self.issues.len(), "issue"))?,
+        }
+        for issue in self.issues.iter().take(ISSUE_LIMIT) {
+            writeln!(f, "  {}", issue)?;
+        }
+        if self.issues.len() > ISSUE_LIMIT {
+            writeln!(f, "  ... and {} more", self.issues.len() - ISSUE_LIMIT)?;
+        }
+        Ok(())
+    }
+}
+
+/// `1 issue`, `2 issues`
+fn count(n: usize, what: &str) -> String {
+    format!("{} {}{}", n, what, if n == 1 { "" } else { "s" })
+}
+
+/// Reads `path` and runs `check` over its text. Lines that are not UTF-8 are
+/// reported and replaced, since the loaders would fail or garble them.
+fn check_file(path: &Path, required: bool, check: impl Fn(&str, &mut FileReport)) -> FileReport {
+    let mut report = FileReport {
+        path: path.to_path_buf(),
+        rows: None,
+        required,
+        issues: Vec::new(),
+    };
+    let Ok(bytes) = std::fs::read(path) else {
+        return report;
+    };
+    for (i, line) in bytes.split(|&b| b == b'\n').enumerate() {
+        if std::str::from_utf8(line).is_err() {
+            report.issue(i + 1, "not valid UTF-8 (saved as Windows-1252? re-save as UTF-8)");
+        }
+    }
+    let text = String::from_utf8_lossy(&bytes);
+    let text = text.strip_prefix('\u{feff}').unwrap_or(&text);
+    check(text, &mut report);
+    report.rows = Some(text.lines().filter(|line| !line.trim().is_empty()).count());
+    report
+}
+
+/// Non-empty lines with their 1-based line numbers, split on `delimiter`.
+fn rows(text: &str, delimiter: char) -> impl Iterator<Item = (usize, Vec<&str>)> {
+    text.lines()
+        .enumerate()
+        .filter(|(_, line)| !line.trim().is_empty())
+        .map(move |(i, line)| (i + 1, line.split(delimiter).map(|f| f.trim().trim_matches('"')).collect()))
+}
+
+fn check_columns(text: &str, report: &mut FileReport, columns: usize) {
+    for (line, fields) in rows(text, ';') {
+        if fields.len() < columns {
+            report.issue(line, format!("expected {} columns, found {}", columns, fields.len()));
+        }
+    }
+}
+
+fn check_capcodes(text: &str, report: &mut FileReport) {
+    if !capcodes::is_native(text) {
+        return check_capcode_export(text, report);
+    }
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (line, fields) in rows(text, ';') {
+        if fields.len() < 6 {
+            report.issue(line, format!("expected 6 columns, found {}", fields.len()));
+            continue;
+        }
+        let code = fields[0];
+        if code.is_empty() || !code.chars().all(|c| c.is_ascii_digit()) {
+            report.issue(line, format!("'{}' is not a capcode", code));
+            continue;
+        }
+        let normalized = normalize_code(code);
+        if let Some(first) = seen.insert(normalized, line) {
+            report.issue(line, format!("capcode {} already listed on line {}", code, first));
+        }
+    }
+}
+
+/// Other layouts have no reliable line numbers, so only duplicates are reported, by entry.
+fn check_capcode_export(text: &str, report: &mut FileReport) {
+    let infos = match capcodes::parse(text) {
+        Ok(infos) => infos,
+        Err(e) => return report.issues.push(format!("unreadable: {:#}", e)),
+    };
+    let mut seen: HashMap<String, usize> = HashMap::new();
+    for (i, info) in infos.iter().enumerate() {
+        if let Some(first) = seen.insert(normalize_code(&info.code), i + 1) {
+            report.issues.push(format!("entry {}: capcode {} already listed as entry {}", i + 1, info.code, first));
+        }
+    }
+}
+
+fn check_abbreviations(text: &str, report: &mut FileReport) {
+    let parsed = lookup::parse_abbreviations(text);
+    let mut issues: Vec<(usize, String)> = parsed
+        .malformed
+        .into_iter()
+        .map(|line| (line, "expected 'ABBREVIATION: expansion', '[category]' or '# comment'".to_string()))
+        .collect();
+    let mut seen: HashMap<&str, usize> = HashMap::new();
+    for entry in &parsed.entries {
+        if let Some(first) = seen.insert(&entry.abbreviation, entry.line) {
+            issues.push((entry.line, format!("'{}' already defined on line {}", entry.abbreviation, first)));
+        }
+    }
+    issues.sort_by_key(|(line, _)| *line);
+    for (line, issue) in issues {
+        report.issue(line, issue);
+    }
+}
+
+fn check_coordinates(text: &str, report: &mut FileReport) {
+    // Place names may contain commas, so this one needs real CSV parsing, as
+    // strict as the loader's: rows it can't read are skipped there
+    let mut reader = csv::ReaderBuilder::new().from_reader(text.as_bytes());
+    for record in reader.records() {
+        let record = match record {
+            Ok(record) => record,
+            Err(e) => {
+                let line = e.position().map_or(0, |position| position.line() as usize);
+                match e.kind() {
+                    csv::ErrorKind::UnequalLengths { expected_len, len, .. } => {
+                        report.issue(line, format!("expected {} columns, found {}", expected_len, len))
+                    }
+                    _ => report.issue(line, format!("unreadable row: {}", e)),
+                }
+                continue;
+            }
+        };
+        let line = record.position().map_or(0, |position| position.line() as usize);
+        if record.len() < 6 {
+            report.issue(line, format!("expected 6 columns, found {}", record.len()));
+            continue;
+        }
+        match (record[4].trim().parse::<f64>(), record[5].trim().parse::<f64>()) {
+            (Ok(lat), Ok(lon)) if location::to_wgs84(lat, lon).is_some() => {}
+            (Ok(lat), Ok(lon)) => report.issue(line, format!("{}, {} is outside the Netherlands", lat, lon)),
+            _ => report.issue(line, format!("unparsable coordinates '{}', '{}'", &record[4], &record[5])),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_missing_files_load_empty() {
+        let dir = std::env::temp_dir().join(format!("p2000_data_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join("abbrevations.txt"), "BR: Brand\n").unwrap();
+        let files = DataFiles::in_dir(&dir);
+
+        assert_eq!(files.missing().len(), 3);
+        let summary = files.missing_summary().unwrap();
+        assert_eq!(summary, "capcodelist.csv, Observations.csv, RegioSCodes.csv");
+        let (lookup, location_lookup) = files.load().unwrap();
+        assert!(lookup.resolve_capcode("1420028").is_none());
+        assert_eq!(lookup.expand_abbreviation("BR").map(String::as_str), Some("Brand"));
+        assert!(location_lookup.find_location_by_text("Brand Vlaardingen").is_none());
+
+        let complete = DataFiles::in_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("data").as_path());
+        assert_eq!(complete.missing_summary(), None);
+        std::fs::remove_dir_all(dir).ok();
+    }
+
+    #[test]
+    fn test_check_reports_bad_rows() {
+        let dir = std::env::temp_dir().join(format!("p2000_check_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(
+            dir.join("capcodelist.csv"),
+            b"\"0100000\";\"Brandweer\";\"A\";\"\";\"Proefalarm\";\"\"\n\
+              \"100000\";\"Brandweer\";\"A\";\"\";\"Dubbel\";\"\"\n\
+              \"0100001\";\"Brandweer\"\n\
+              \"0100002\";\"Brandw\xe9er\";\"A\";\"\";\"Bevelvoerders\";\"\"\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("abbrevations.txt"), "BR: Brand\nzonder uitleg\nBR: Brand\n").unwrap();
+        std::fs::write(
+            dir.join("4pp-final-2023.csv"),
+            "id,woonplaats,gemeente,provincie,latitude,longitude\n1,\"A, B\",A,P,52.1,5.1\n2,A,A,P,52.1,x\n3,C,C\n",
+        )
+        .unwrap();
+        let reports = DataFiles::in_dir(&dir).check();
+
+        assert_eq!(
+            reports[0].issues,
+            [
+                "line 4: not valid UTF-8 (saved as Windows-1252? re-save as UTF-8)",
+                "line 2: capcode 100000 already listed on line 1",
+                "line 3: expected 6 columns, found 2",
+            ]
+        );
+        assert_eq!(
+            reports[1].issues,
+            [
+                "line 2: expected 'ABBREVIATION: expansion', '[category]' or '# comment'",
+                "line 3: 'BR' already defined on line 1"
+            ]
+        );
+        assert!(!reports[2].is_ok() && reports[2].rows.is_none());
+        assert_eq!(
+            reports[4].issues,
+            ["line 3: unparsable coordinates '52.1', 'x'", "line 4: expected 6 columns, found 3"]
+        );
+        assert!(reports[4].to_string().contains("4 rows, 2 issues"));
+        assert!(reports[1].to_string().contains("3 rows, 2 issues"));
+        assert_eq!(count(1, "issue"), "1 issue");
+
+        let complete = DataFiles::in_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join("data").as_path());
+        assert!(complete.check().iter().all(|report| report.rows.is_some()));
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
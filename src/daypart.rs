@@ -0,0 +1,150 @@
+//! When in the day a message came: the part of the day by the clock, and
+//! whether it was dark at the matched place by its sunrise and sunset. For
+//! filtering (`daypart:night`, `daypart:dark`) and the stats screen.
+
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDate, TimeZone, Timelike, Utc};
+use serde::Serialize;
+use std::fmt;
+
+/// De Bilt, the middle of the country, for messages without a known place
+pub const DEFAULT_POINT: (f64, f64) = (52.10, 5.18);
+
+/// The sun's center this far below the horizon counts as rise and set,
+/// allowing for refraction and its radius
+const ZENITH: f64 = 90.833;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum DayPart {
+    /// 00:00 to 06:00
+    Night,
+    Morning,
+    Afternoon,
+    /// 18:00 to midnight
+    Evening,
+}
+
+impl DayPart {
+    pub const ALL: [DayPart; 4] = [DayPart::Night, DayPart::Morning, DayPart::Afternoon, DayPart::Evening];
+
+    /// By the local clock, in blocks of six hours.
+    pub fn of(time: DateTime<Local>) -> Self {
+        DayPart::ALL[time.hour() as usize / 6]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            DayPart::Night => "night",
+            DayPart::Morning => "morning",
+            DayPart::Afternoon => "afternoon",
+            DayPart::Evening => "evening",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Daylight {
+    Light,
+    Dark,
+}
+
+impl Daylight {
+    pub fn name(self) -> &'static str {
+        match self {
+            Daylight::Light => "light",
+            Daylight::Dark => "dark",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct Timing {
+    pub day_part: DayPart,
+    pub daylight: Daylight,
+}
+
+impl Timing {
+    /// At `point` (latitude, longitude), or at [`DEFAULT_POINT`].
+    pub fn of(time: DateTime<Local>, point: Option<(f64, f64)>) -> Self {
+        let (lat, lon) = point.unwrap_or(DEFAULT_POINT);
+        let light = match sun_times(time.with_timezone(&Utc).date_naive(), lat, lon) {
+            Some((sunrise, sunset)) => time >= sunrise && time < sunset,
+            // No sunrise or sunset that day: polar day in summer, night in winter
+            None => (4..=9).contains(&time.month()) == (lat > 0.0),
+        };
+        Timing {
+            day_part: DayPart::of(time),
+            daylight: if light { Daylight::Light } else { Daylight::Dark },
+        }
+    }
+}
+
+/// `night, dark`
+impl fmt::Display for Timing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}, {}", self.day_part.name(), self.daylight.name())
+    }
+}
+
+/// Sunrise and sunset on `date` (UTC) at `lat`, `lon`, by the almanac
+/// algorithm of the US Naval Observatory, good to a minute or two. `None`
+/// when the sun doesn't rise or set that day.
+pub fn sun_times(date: NaiveDate, lat: f64, lon: f64) -> Option<(DateTime<Local>, DateTime<Local>)> {
+    let event = |rising: bool| -> Option<DateTime<Local>> {
+        let (sin, cos) = (|deg: f64| deg.to_radians().sin(), |deg: f64| deg.to_radians().cos());
+        let lon_hours = lon / 15.0;
+        let t = date.ordinal() as f64 + ((if rising { 6.0 } else { 18.0 }) - lon_hours) / 24.0;
+        // The sun's mean anomaly, true longitude and right ascension
+        let m = 0.9856 * t - 3.289;
+        let l = (m + 1.916 * sin(m) + 0.020 * sin(2.0 * m) + 282.634).rem_euclid(360.0);
+        let mut ra = (0.91764 * l.to_radians().tan()).atan().to_degrees().rem_euclid(360.0);
+        ra += (l / 90.0).floor() * 90.0 - (ra / 90.0).floor() * 90.0;
+        let sin_dec = 0.39782 * sin(l);
+        let cos_dec = sin_dec.asin().cos();
+        let cos_h = (cos(ZENITH) - sin_dec * sin(lat)) / (cos_dec * cos(lat));
+        if !(-1.0..=1.0).contains(&cos_h) {
+            return None;
+        }
+        let h = cos_h.acos().to_degrees();
+        let h = (if rising { 360.0 - h } else { h }) / 15.0;
+        let ut = (h + ra / 15.0 - 0.06571 * t - 6.622 - lon_hours).rem_euclid(24.0);
+        let midnight = Utc.from_utc_datetime(&date.and_hms_opt(0, 0, 0)?);
+        Some((midnight + Duration::seconds((ut * 3600.0) as i64)).with_timezone(&Local))
+    };
+    Some((event(true)?, event(false)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sun_times_and_timing() {
+        let utc = |time: DateTime<Local>| time.with_timezone(&Utc).format("%H:%M").to_string();
+        let minutes = |time: DateTime<Local>| {
+            let time = time.with_timezone(&Utc);
+            time.hour() * 60 + time.minute()
+        };
+        // De Bilt: 05:19 to 22:02 CEST at midsummer, 08:45 to 16:29 CET at midwinter
+        let (rise, set) = sun_times(NaiveDate::from_ymd_opt(2026, 6, 21).unwrap(), 52.10, 5.18).unwrap();
+        assert!(minutes(rise).abs_diff(3 * 60 + 19) <= 3, "{}", utc(rise));
+        assert!(minutes(set).abs_diff(20 * 60 + 2) <= 3, "{}", utc(set));
+        let (rise, set) = sun_times(NaiveDate::from_ymd_opt(2026, 12, 21).unwrap(), 52.10, 5.18).unwrap();
+        assert!(minutes(rise).abs_diff(7 * 60 + 45) <= 3, "{}", utc(rise));
+        assert!(minutes(set).abs_diff(15 * 60 + 29) <= 3, "{}", utc(set));
+        // Svalbard in June
+        assert_eq!(sun_times(NaiveDate::from_ymd_opt(2026, 6, 21).unwrap(), 78.2, 15.6), None);
+
+        let at = |text: &str| Utc.from_utc_datetime(&text.parse().unwrap()).with_timezone(&Local);
+        let summer_evening = Timing::of(at("2026-06-21T19:30:00"), None);
+        assert_eq!(summer_evening.daylight, Daylight::Light);
+        let winter_evening = Timing::of(at("2026-12-21T17:30:00"), Some((51.44, 5.47)));
+        assert_eq!(winter_evening.daylight, Daylight::Dark);
+        let local = |hour: u32, minute: u32| Local.with_ymd_and_hms(2026, 1, 8, hour, minute, 0).unwrap();
+        assert_eq!(DayPart::of(local(5, 59)), DayPart::Night);
+        assert_eq!(DayPart::of(local(18, 0)), DayPart::Evening);
+        // Polar night, so dark at any hour whatever the zone the tests run in
+        assert_eq!(Timing::of(local(3, 0), Some((78.2, 15.6))).to_string(), "night, dark");
+    }
+}
@@ -0,0 +1,546 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::calendar::Calendar;
+use crate::daypart::Timing;
+use crate::geofence::{self, Geofence};
+use crate::location::{FoundLocation, LocationLookup};
+use crate::lookup::{CapcodeInfo, Lookup, NATIONWIDE_REGION};
+use crate::parser::P2000Message;
+use crate::plugin::Enricher;
+use crate::severity::SeverityConfig;
+use crate::translate::Translator;
+use crate::units;
+use crate::weather::{Weather, WeatherClient};
+
+/// Everything the lookup tables add to a parsed message.
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Enrichment {
+    /// Shared with the lookup table rather than copied per message
+    pub capcodes: Vec<Arc<CapcodeInfo>>,
+    pub location: Option<FoundLocation>,
+    pub abbreviations: Vec<Expansion>,
+    /// Veiligheidsregio the message belongs to; see [`derive_region`]
+    pub region: Option<String>,
+    /// Unit callsigns in the content, see [`units::callsigns`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub units: Vec<String>,
+    /// Names of the `[[geofences]]` the message is inside
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub geofences: Vec<String>,
+    /// English rendering of the content, when translation is enabled
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub translation: Option<String>,
+    /// Part of the day and whether it was dark at the matched place
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub timing: Option<Timing>,
+    /// Holidays and `[[calendar.events]]` the message falls on
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub calendar: Vec<String>,
+    /// Current weather at the matched place, when `[weather]` is configured
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weather: Option<Weather>,
+    /// Severity score, see [`SeverityConfig`]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub severity: Option<u32>,
+    /// Fields added by third-party enrichers, keyed by enricher name.
+    pub extra: BTreeMap<String, serde_json::Value>,
+    /// Where the derived fields came from; see [`provenance`]
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub provenance: Vec<Provenance>,
+}
+
+impl Enrichment {
+    /// First resolved unit, skipping nationwide group codes that every page carries.
+    pub fn primary_capcode(&self) -> Option<&Arc<CapcodeInfo>> {
+        self.capcodes
+            .iter()
+            .find(|c| !c.description.starts_with("Groepscode"))
+            .or_else(|| self.capcodes.first())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Expansion {
+    pub abbreviation: String,
+    pub expansion: String,
+    /// Section of the abbreviations file, e.g. "medical"
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Arc<str>>,
+}
+
+/// Which table or rule produced an enriched field, and how sure it is.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Provenance {
+    /// "priority", "service", "location" or "region"
+    pub field: &'static str,
+    pub value: String,
+    pub source: String,
+    /// From 0 (a guess) to 1 (read from the message or looked up exactly)
+    pub confidence: f32,
+}
+
+/// Runs the built-in enrichers.
+pub fn enrich(msg: &P2000Message, lookup: &Lookup, location_lookup: &LocationLookup) -> Enrichment {
+    let mut enrichment = Enrichment {
+        capcodes: resolve_capcodes(msg, lookup),
+        location: find_location(msg, location_lookup),
+        abbreviations: find_abbreviations(msg, lookup),
+        region: None,
+        units: units::callsigns(&msg.content),
+        geofences: Vec::new(),
+        translation: None,
+        timing: None,
+        calendar: Vec::new(),
+        weather: None,
+        severity: None,
+        extra: BTreeMap::new(),
+        provenance: Vec::new(),
+    };
+    enrichment.region = derive_region(&enrichment, lookup);
+    enrichment.timing = Some(timing(msg, &enrichment));
+    enrichment.provenance = provenance(msg, &enrichment, lookup);
+    enrichment
+}
+
+/// Capcodes and the region derived from them only, skipping the place and
+/// abbreviation scans over the text; for low-power setups.
+pub fn enrich_capcodes(msg: &P2000Message, lookup: &Lookup) -> Enrichment {
+    let mut enrichment = Enrichment {
+        capcodes: resolve_capcodes(msg, lookup),
+        units: units::callsigns(&msg.content),
+        ..Default::default()
+    };
+    enrichment.region = derive_region(&enrichment, lookup);
+    enrichment.timing = Some(timing(msg, &enrichment));
+    enrichment.provenance = provenance(msg, &enrichment, lookup);
+    enrichment
+}
+
+/// Daylight at the matched place when it has coordinates, else in the middle of the country.
+fn timing(msg: &P2000Message, enrichment: &Enrichment) -> Timing {
+    let point = enrichment.location.as_ref().and_then(|found| found.info.latitude.zip(found.info.longitude));
+    Timing::of(msg.timestamp, point)
+}
+
+/// The region most resolved capcodes belong to (nationwide group codes don't
+/// vote; ties go to the first listed), else the region serving the matched place.
+pub fn derive_region(enrichment: &Enrichment, lookup: &Lookup) -> Option<String> {
+    region_provenance(enrichment, lookup).map(|p| p.value)
+}
+
+/// `derive_region` with its source: the share of votes, or the place.
+fn region_provenance(enrichment: &Enrichment, lookup: &Lookup) -> Option<Provenance> {
+    let mut votes: Vec<(&str, usize)> = Vec::new();
+    for info in &enrichment.capcodes {
+        if info.region.is_empty() || info.region == NATIONWIDE_REGION {
+            continue;
+        }
+        match votes.iter_mut().find(|(region, _)| *region == info.region) {
+            Some((_, count)) => *count += 1,
+            None => votes.push((&info.region, 1)),
+        }
+    }
+    let voters: usize = votes.iter().map(|(_, count)| count).sum();
+    let mut winner: Option<(&str, usize)> = None;
+    for (region, count) in votes {
+        if winner.is_none_or(|(_, best)| count > best) {
+            winner = Some((region, count));
+        }
+    }
+    if let Some((region, count)) = winner {
+        return Some(Provenance {
+            field: "region",
+            value: region.to_string(),
+            source: format!("capcode list, {} of {} regional capcodes", count, voters),
+            confidence: count as f32 / voters as f32,
+        });
+    }
+
+    let found = enrichment.location.as_ref()?;
+    if let Some(region) = &found.info.veiligheidsregio {
+        return Some(Provenance {
+            field: "region",
+            value: region.clone(),
+            source: format!("veiligheidsregio of {}", found.info),
+            confidence: 0.5,
+        });
+    }
+    let region = lookup
+        .region_for_place(found.info.place())
+        .or_else(|| lookup.region_for_place(&found.info.gemeente.name))
+        .or_else(|| lookup.region_for_place(&found.found_place))?;
+    Some(Provenance {
+        field: "region",
+        value: region.to_string(),
+        source: format!("region most capcodes of {} belong to", found.info.place()),
+        confidence: 0.5,
+    })
+}
+
+/// Sources of the priority, service, location and region of an enriched message.
+pub fn provenance(msg: &P2000Message, enrichment: &Enrichment, lookup: &Lookup) -> Vec<Provenance> {
+    let mut sources = Vec::new();
+    if let Some(priority) = &msg.priority {
+        sources.push(Provenance {
+            field: "priority",
+            value: priority.clone(),
+            source: "start of the message".to_string(),
+            confidence: 1.0,
+        });
+    }
+
+    let service = match (enrichment.primary_capcode(), msg.priority.as_deref().and_then(|p| p.chars().next())) {
+        (Some(info), _) => Some((info.service.clone(), format!("capcode {}", info.code), 0.95)),
+        (None, Some('A' | 'B')) => Some(("Ambulance".to_string(), "A/B priority".to_string(), 0.6)),
+        (None, Some('P')) => Some(("Brandweer".to_string(), "P priority".to_string(), 0.6)),
+        _ => None,
+    };
+    if let Some((value, source, confidence)) = service {
+        sources.push(Provenance {
+            field: "service",
+            value,
+            source,
+            confidence,
+        });
+    }
+
+    if let Some(found) = &enrichment.location {
+        let place = found.found_place.trim();
+        // The parser's location is the tail after the incident details, where addresses go
+        let (source, mut confidence) = if msg.location.to_lowercase().contains(&place.to_lowercase()) {
+            ("place name in the address", 0.9)
+        } else {
+            ("place name elsewhere in the text", 0.6)
+        };
+        // Short names like "Ee" or "Epe" also occur inside ordinary words
+        if place.chars().count() <= 4 {
+            confidence = 0.4_f32.min(confidence);
+        }
+        sources.push(Provenance {
+            field: "location",
+            value: place.to_string(),
+            source: source.to_string(),
+            confidence,
+        });
+    }
+
+    sources.extend(region_provenance(enrichment, lookup));
+    sources
+}
+
+fn resolve_capcodes(msg: &P2000Message, lookup: &Lookup) -> Vec<Arc<CapcodeInfo>> {
+    msg.capcodes
+        .iter()
+        .filter_map(|code| lookup.resolve_capcode(code).cloned())
+        .collect()
+}
+
+fn find_location(msg: &P2000Message, location_lookup: &LocationLookup) -> Option<FoundLocation> {
+    // Search for place names in the full message (content + location)
+    let full_text = format!("{} {}", msg.location, msg.content);
+    location_lookup.find_location_by_text(&full_text)
+}
+
+fn find_abbreviations(msg: &P2000Message, lookup: &Lookup) -> Vec<Expansion> {
+    lookup
+        .find_abbreviations(&msg.content)
+        .into_iter()
+        .map(|(abbreviation, entry)| Expansion {
+            abbreviation,
+            expansion: entry.expansion.clone(),
+            category: entry.category.clone(),
+        })
+        .collect()
+}
+
+pub struct CapcodeEnricher {
+    lookup: Arc<Lookup>,
+}
+
+impl CapcodeEnricher {
+    pub fn new(lookup: Arc<Lookup>) -> Self {
+        CapcodeEnricher { lookup }
+    }
+}
+
+impl Enricher for CapcodeEnricher {
+    fn name(&self) -> &str {
+        "capcodes"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.capcodes = resolve_capcodes(msg, &self.lookup);
+    }
+}
+
+pub struct LocationEnricher {
+    location_lookup: Arc<LocationLookup>,
+}
+
+impl LocationEnricher {
+    pub fn new(location_lookup: Arc<LocationLookup>) -> Self {
+        LocationEnricher { location_lookup }
+    }
+}
+
+impl Enricher for LocationEnricher {
+    fn name(&self) -> &str {
+        "locations"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.location = find_location(msg, &self.location_lookup);
+    }
+}
+
+pub struct AbbreviationEnricher {
+    lookup: Arc<Lookup>,
+}
+
+impl AbbreviationEnricher {
+    pub fn new(lookup: Arc<Lookup>) -> Self {
+        AbbreviationEnricher { lookup }
+    }
+}
+
+impl Enricher for AbbreviationEnricher {
+    fn name(&self) -> &str {
+        "abbreviations"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.abbreviations = find_abbreviations(msg, &self.lookup);
+    }
+}
+
+/// Needs the capcodes and locations enrichers to run first. Also records the provenance.
+pub struct RegionEnricher {
+    lookup: Arc<Lookup>,
+}
+
+impl RegionEnricher {
+    pub fn new(lookup: Arc<Lookup>) -> Self {
+        RegionEnricher { lookup }
+    }
+}
+
+impl Enricher for RegionEnricher {
+    fn name(&self) -> &str {
+        "region"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.region = derive_region(enrichment, &self.lookup);
+        enrichment.provenance = provenance(msg, enrichment, &self.lookup);
+    }
+}
+
+/// Needs the abbreviations enricher to run first for the category weights.
+pub struct SeverityEnricher {
+    config: SeverityConfig,
+}
+
+impl SeverityEnricher {
+    pub fn new(config: SeverityConfig) -> Self {
+        SeverityEnricher { config }
+    }
+}
+
+impl Enricher for SeverityEnricher {
+    fn name(&self) -> &str {
+        "severity"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        let categories = enrichment.abbreviations.iter().filter_map(|e| e.category.as_deref());
+        enrichment.severity = Some(self.config.assess(msg, categories).total());
+    }
+}
+
+pub struct UnitEnricher;
+
+impl Enricher for UnitEnricher {
+    fn name(&self) -> &str {
+        "units"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.units = units::callsigns(&msg.content);
+    }
+}
+
+/// Needs the locations enricher to run first for circles, polygons and places.
+pub struct GeofenceEnricher {
+    fences: Arc<Vec<Geofence>>,
+}
+
+impl GeofenceEnricher {
+    pub fn new(fences: Arc<Vec<Geofence>>) -> Self {
+        GeofenceEnricher { fences }
+    }
+}
+
+impl Enricher for GeofenceEnricher {
+    fn name(&self) -> &str {
+        "geofences"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.geofences = geofence::containing(&self.fences, msg, enrichment);
+    }
+}
+
+pub struct TranslationEnricher {
+    translator: Arc<Translator>,
+}
+
+impl TranslationEnricher {
+    pub fn new(translator: Arc<Translator>) -> Self {
+        TranslationEnricher { translator }
+    }
+}
+
+impl Enricher for TranslationEnricher {
+    fn name(&self) -> &str {
+        "translate"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.translation = self.translator.translate(&msg.content);
+    }
+}
+
+/// Daylight at the place the `locations` enricher found, when it ran first.
+pub struct DayPartEnricher;
+
+impl Enricher for DayPartEnricher {
+    fn name(&self) -> &str {
+        "daypart"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        enrichment.timing = Some(timing(msg, enrichment));
+    }
+}
+
+/// Runs after `locations` for events limited to some places.
+pub struct CalendarEnricher {
+    calendar: Arc<Calendar>,
+}
+
+impl CalendarEnricher {
+    pub fn new(calendar: Arc<Calendar>) -> Self {
+        CalendarEnricher { calendar }
+    }
+}
+
+impl Enricher for CalendarEnricher {
+    fn name(&self) -> &str {
+        "calendar"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        let info = enrichment.location.as_ref().map(|found| &found.info);
+        let places: Vec<&str> = info.map(|info| vec![info.place(), info.gemeente.name.as_str()]).unwrap_or_default();
+        enrichment.calendar = self.calendar.days(msg.timestamp, &places).into_iter().map(String::from).collect();
+    }
+}
+
+/// Runs after `locations`, which finds the coordinates.
+pub struct WeatherEnricher {
+    client: Arc<WeatherClient>,
+}
+
+impl WeatherEnricher {
+    pub fn new(client: Arc<WeatherClient>) -> Self {
+        WeatherEnricher { client }
+    }
+}
+
+impl Enricher for WeatherEnricher {
+    fn name(&self) -> &str {
+        "weather"
+    }
+
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment) {
+        let Some(info) = enrichment.location.as_ref().map(|found| &found.info) else {
+            return;
+        };
+        let (Some(lat), Some(lon)) = (info.latitude, info.longitude) else {
+            return;
+        };
+        if !self.client.applies_to(msg.timestamp) {
+            return;
+        }
+        match self.client.current(lat, lon) {
+            Ok(weather) => enrichment.weather = weather,
+            Err(e) => eprintln!("Warning: weather lookup for {} failed: {}", info.place(), e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn capcode(region: &str) -> Arc<CapcodeInfo> {
+        Arc::new(CapcodeInfo {
+            code: "0100000".to_string(),
+            service: "Brandweer".to_string(),
+            region: region.to_string(),
+            place: String::new(),
+            description: String::new(),
+            short: String::new(),
+            source: "capcodelist".into(),
+        })
+    }
+
+    #[test]
+    fn test_region_majority_vote() {
+        let lookup = Lookup::default();
+        let mut enrichment = Enrichment {
+            capcodes: vec![
+                capcode("Landelijk"),
+                capcode("Utrecht"),
+                capcode("Gooi en Vechtstreek"),
+                capcode("Gooi en Vechtstreek"),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(derive_region(&enrichment, &lookup).as_deref(), Some("Gooi en Vechtstreek"));
+
+        enrichment.capcodes.truncate(3);
+        assert_eq!(derive_region(&enrichment, &lookup).as_deref(), Some("Utrecht"));
+
+        enrichment.capcodes.truncate(1);
+        assert_eq!(derive_region(&enrichment, &lookup), None);
+    }
+
+    #[test]
+    fn test_provenance() {
+        let lookup = Lookup::default();
+        let msg = crate::parser::Parser::new()
+            .parse_line("FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|001420028|ALN|P 1 Brand woning Vlaardingen")
+            .unwrap();
+        let enrichment = Enrichment {
+            capcodes: vec![capcode("Utrecht"), capcode("Rotterdam-Rijnmond"), capcode("Utrecht")],
+            ..Default::default()
+        };
+        let sources = provenance(&msg, &enrichment, &lookup);
+        let summary: Vec<(&str, &str, f32)> = sources
+            .iter()
+            .map(|p| (p.field, p.value.as_str(), p.confidence))
+            .collect();
+        let region_share = 2.0 / 3.0;
+        assert_eq!(
+            summary,
+            [("priority", "P 1", 1.0), ("service", "Brandweer", 0.95), ("region", "Utrecht", region_share)]
+        );
+        assert_eq!(sources[2].source, "capcode list, 2 of 3 regional capcodes");
+
+        let guessed = provenance(&msg, &Enrichment::default(), &lookup);
+        assert_eq!((guessed[1].value.as_str(), guessed[1].confidence), ("Brandweer", 0.6));
+    }
+}
@@ -0,0 +1,151 @@
+//! Common Alerting Protocol 1.2 (OASIS) export.
+
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::rules;
+
+const CAP_NAMESPACE: &str = "urn:oasis:names:tc:emergency:cap:1.2";
+/// Radius of the area circle around a matched place, in km
+const AREA_RADIUS_KM: f64 = 2.0;
+
+/// Time and first capcode, and the start of the content hash so a page re-sent
+/// in the same second with other text gets its own identifier.
+pub fn identifier(sender: &str, msg: &P2000Message) -> String {
+    format!(
+        "{}-{}-{}-{}",
+        sender,
+        msg.timestamp.format("%Y%m%d%H%M%S"),
+        msg.capcodes.first().map(|c| &**c).unwrap_or("0"),
+        &msg.content_hash()[..12]
+    )
+}
+
+/// Renders one message as a CAP `<alert>` document with the given identifier.
+pub fn to_xml(msg: &P2000Message, enrichment: &Enrichment, sender: &str, identifier: &str) -> String {
+    let (urgency, severity) = urgency_severity(msg);
+    let category = category(enrichment);
+    let event = enrichment
+        .primary_capcode()
+        .map(|c| c.service.as_str())
+        .filter(|s| !s.is_empty())
+        .unwrap_or("P2000 alert");
+    let headline = match (&msg.priority, &enrichment.location) {
+        (Some(priority), Some(found)) => format!("{} {}", priority, found.found_place.trim()),
+        (Some(priority), None) => priority.clone(),
+        (None, Some(found)) => found.found_place.trim().to_string(),
+        (None, None) => event.to_string(),
+    };
+
+    let mut xml = String::new();
+    xml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!("<alert xmlns=\"{}\">\n", CAP_NAMESPACE));
+    element(&mut xml, 1, "identifier", identifier);
+    element(&mut xml, 1, "sender", sender);
+    // CAP forbids the "Z" suffix, so always use a numeric offset
+    element(&mut xml, 1, "sent", &msg.timestamp.format("%Y-%m-%dT%H:%M:%S%:z").to_string());
+    element(&mut xml, 1, "status", "Actual");
+    element(&mut xml, 1, "msgType", "Alert");
+    if let Some(receiver) = &msg.receiver {
+        element(&mut xml, 1, "source", &receiver.to_string());
+    }
+    element(&mut xml, 1, "scope", "Public");
+    xml.push_str("  <info>\n");
+    element(&mut xml, 2, "language", "nl-NL");
+    element(&mut xml, 2, "category", category);
+    element(&mut xml, 2, "event", event);
+    element(&mut xml, 2, "urgency", urgency);
+    element(&mut xml, 2, "severity", severity);
+    element(&mut xml, 2, "certainty", "Observed");
+    element(&mut xml, 2, "senderName", "P2000");
+    element(&mut xml, 2, "headline", &headline);
+    element(&mut xml, 2, "description", &msg.content);
+    for capcode in &msg.capcodes {
+        parameter(&mut xml, "capcode", capcode);
+    }
+    if let Some(code) = &msg.incident_code {
+        parameter(&mut xml, "incidentCode", code);
+    }
+    for info in &enrichment.capcodes {
+        let unit = if info.place.is_empty() {
+            info.description.clone()
+        } else {
+            format!("{} - {}", info.description, info.place)
+        };
+        parameter(&mut xml, "unit", &unit);
+    }
+    if let Some(weather) = &enrichment.weather {
+        parameter(&mut xml, "weather", &weather.to_string());
+    }
+
+    xml.push_str("    <area>\n");
+    let area_desc = match &enrichment.location {
+        Some(found) => {
+            let mut parts = vec![found.found_place.trim()];
+            if !found.info.province.name.is_empty() {
+                parts.push(&found.info.province.name);
+            }
+            parts.join(", ")
+        }
+        None if !msg.location.is_empty() => msg.location.clone(),
+        None => "Nederland".to_string(),
+    };
+    element(&mut xml, 3, "areaDesc", &area_desc);
+    if let Some(found) = &enrichment.location {
+        if let (Some(lat), Some(lon)) = (found.info.latitude, found.info.longitude) {
+            element(&mut xml, 3, "circle", &format!("{},{} {}", lat, lon, AREA_RADIUS_KM));
+        }
+    }
+    xml.push_str("    </area>\n");
+    xml.push_str("  </info>\n");
+    xml.push_str("</alert>\n");
+    xml
+}
+
+fn urgency_severity(msg: &P2000Message) -> (&'static str, &'static str) {
+    match msg.priority.as_deref().map(rules::normalize_priority).as_deref() {
+        Some("A0" | "A1" | "P1") => ("Immediate", "Severe"),
+        Some("A2" | "P2") => ("Expected", "Moderate"),
+        Some("B" | "P3") => ("Future", "Minor"),
+        _ => ("Unknown", "Unknown"),
+    }
+}
+
+/// CAP category from the service of the primary resolved capcode.
+fn category(enrichment: &Enrichment) -> &'static str {
+    let service = enrichment
+        .primary_capcode()
+        .map(|c| c.service.to_lowercase())
+        .unwrap_or_default();
+    if service.contains("brandweer") {
+        "Fire"
+    } else if service.contains("ambulance") || service.contains("traumaheli") {
+        "Health"
+    } else if service.contains("politie") {
+        "Security"
+    } else if service.contains("knrm") || service.contains("reddingsbrigade") {
+        "Rescue"
+    } else {
+        "Other"
+    }
+}
+
+fn element(xml: &mut String, depth: usize, name: &str, value: &str) {
+    xml.push_str(&"  ".repeat(depth));
+    xml.push_str(&format!("<{}>{}</{}>\n", name, escape(value), name));
+}
+
+fn parameter(xml: &mut String, name: &str, value: &str) {
+    xml.push_str("    <parameter>\n");
+    element(xml, 3, "valueName", name);
+    element(xml, 3, "value", value);
+    xml.push_str("    </parameter>\n");
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&apos;")
+}
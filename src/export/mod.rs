@@ -0,0 +1,88 @@
+pub mod cap;
+pub mod redact;
+pub mod timeline;
+
+use anyhow::{Context, Result};
+use std::collections::HashSet;
+use std::path::Path;
+
+use crate::enrich;
+use crate::location::LocationLookup;
+use crate::lookup::Lookup;
+use crate::parser::P2000Message;
+
+pub const DEFAULT_CAP_SENDER: &str = "p2000-rust";
+
+/// Writes one CAP document per message into `dir`, named after the alert identifier,
+/// and returns how many were written: copies of a message are written once.
+/// With `redact` addresses are left out, see [`redact`].
+pub fn write_cap(
+    messages: &[P2000Message],
+    lookup: &Lookup,
+    location_lookup: &LocationLookup,
+    dir: &Path,
+    sender: &str,
+    redact: bool,
+) -> Result<usize> {
+    std::fs::create_dir_all(dir)
+        .with_context(|| format!("Failed to create {}", dir.display()))?;
+    let mut copies = HashSet::new();
+    let mut identifiers = HashSet::new();
+    for msg in messages {
+        if !copies.insert(msg.content_hash()) {
+            continue;
+        }
+        let mut enrichment = enrich::enrich(msg, lookup, location_lookup);
+        let redacted;
+        let msg = if redact {
+            redact::redact_enrichment(&mut enrichment);
+            redacted = redact::redact(msg);
+            &redacted
+        } else {
+            msg
+        };
+        // Redacting can make different messages alike, number those
+        let base = cap::identifier(sender, msg);
+        let mut identifier = base.clone();
+        for n in 2.. {
+            if identifiers.insert(identifier.clone()) {
+                break;
+            }
+            identifier = format!("{}-{}", base, n);
+        }
+        let path = dir.join(format!("{}.xml", identifier));
+        std::fs::write(&path, cap::to_xml(msg, &enrichment, sender, &identifier))
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+    }
+    Ok(identifiers.len())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_write_cap_identifiers() {
+        let parser = Parser::new();
+        let messages: Vec<P2000Message> = [
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|001420028|ALN|P 1 Brand woning Kerkstraat 4 Vlaardingen",
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|001420028|ALN|P 1 Brand woning Kerkstraat 4 Vlaardingen",
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|001420028|ALN|P 1 Brand woning Kerkstraat 6 Vlaardingen",
+        ]
+        .iter()
+        .map(|line| parser.parse_line(line).unwrap())
+        .collect();
+        assert_ne!(cap::identifier("test", &messages[0]), cap::identifier("test", &messages[2]));
+
+        let dir = std::env::temp_dir().join(format!("p2000_cap_test_{}", std::process::id()));
+        let (lookup, location_lookup) = (Lookup::default(), LocationLookup::default());
+        let written = write_cap(&messages, &lookup, &location_lookup, &dir.join("plain"), "test", false).unwrap();
+        assert_eq!(written, 2);
+        let written = write_cap(&messages, &lookup, &location_lookup, &dir.join("redacted"), "test", true).unwrap();
+        assert_eq!(written, 2);
+        let numbered = cap::identifier("test", &redact::redact(&messages[2])) + "-2.xml";
+        assert!(dir.join("redacted").join(numbered).exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,135 @@
+//! Redaction for sharing datasets and screenshots: house numbers and postcodes
+//! are replaced by placeholders and coordinates are rounded, so what remains
+//! locates an incident to the street and municipality but not the address.
+
+use crate::enrich::Enrichment;
+use crate::location::FoundLocation;
+use crate::parser::P2000Message;
+use crate::tokenize::{tokenize, TokenKind};
+
+pub const POSTCODE: &str = "[postcode]";
+pub const HOUSE_NUMBER: &str = "[nr]";
+/// One decimal is about 10 km, roughly the size of a municipality
+const COORDINATE_DECIMALS: i32 = 1;
+
+/// Endings of Dutch street names; a number right after one is a house number.
+/// Endings common in place names too (`-dam`, `-hoek`) are left out.
+const STREET_SUFFIXES: &[&str] = &[
+    "straat", "laan", "weg", "plein", "dijk", "kade", "gracht", "singel", "pad", "hof", "dreef",
+    "steeg", "markt", "park", "plantsoen", "veld", "baan", "ring", "erf", "wal", "haven",
+    "boulevard", "allee", "zijde",
+];
+
+/// `msg` with house numbers and postcodes removed from its content and location.
+pub fn redact(msg: &P2000Message) -> P2000Message {
+    P2000Message {
+        content: redact_text(&msg.content),
+        location: redact_text(&msg.location),
+        ..msg.clone()
+    }
+}
+
+/// Rounds the matched place's coordinates and redacts the translation.
+pub fn redact_enrichment(enrichment: &mut Enrichment) {
+    if let Some(found) = &mut enrichment.location {
+        redact_location(found);
+    }
+    if let Some(translation) = &mut enrichment.translation {
+        *translation = redact_text(translation);
+    }
+}
+
+pub fn redact_location(found: &mut FoundLocation) {
+    let scale = 10f64.powi(COORDINATE_DECIMALS);
+    for coordinate in [&mut found.info.latitude, &mut found.info.longitude].into_iter().flatten() {
+        *coordinate = (*coordinate * scale).round() / scale;
+    }
+}
+
+/// `text` with postcodes (`3135ZH`, `3135 ZH`) and numbers following a street
+/// name replaced by placeholders; everything else, spacing included, is kept.
+pub fn redact_text(text: &str) -> String {
+    let tokens = tokenize(text);
+    let mut out = String::with_capacity(text.len());
+    let mut copied = 0;
+    let mut i = 0;
+    while i < tokens.len() {
+        let token = &tokens[i];
+        let next = tokens.get(i + 1);
+        let (placeholder, end) = if token.kind == TokenKind::Postcode {
+            (POSTCODE, i)
+        } else if is_postcode_digits(token.text)
+            && next.is_some_and(|next| next.spaced && next.text.len() == 2 && next.text.bytes().all(|b| b.is_ascii_uppercase()))
+        {
+            (POSTCODE, i + 1)
+        } else if i > 0 && tokens[i - 1].kind == TokenKind::Word && is_street(tokens[i - 1].text) && token.spaced && is_house_number(token.text) {
+            (HOUSE_NUMBER, i)
+        } else {
+            i += 1;
+            continue;
+        };
+        out.push_str(&text[copied..token.span.start]);
+        out.push_str(placeholder);
+        copied = tokens[end].span.end;
+        i = end + 1;
+    }
+    out.push_str(&text[copied..]);
+    out
+}
+
+fn is_postcode_digits(text: &str) -> bool {
+    text.len() == 4 && !text.starts_with('0') && text.bytes().all(|b| b.is_ascii_digit())
+}
+
+fn is_street(word: &str) -> bool {
+    let word = word.to_lowercase();
+    STREET_SUFFIXES.iter().any(|suffix| word.ends_with(suffix))
+}
+
+/// `12`, `12a`, `12-14`, `1024bis`: starts with at most four digits
+fn is_house_number(text: &str) -> bool {
+    let digits = text.bytes().take_while(u8::is_ascii_digit).count();
+    (1..=4).contains(&digits) && text.bytes().all(|b| b.is_ascii_alphanumeric() || b == b'-')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::{Area, LocationInfo};
+
+    #[test]
+    fn test_redacts_addresses() {
+        assert_eq!(
+            redact_text("A1 Nassaulaan 12a 3135ZH Vlaardingen 17128"),
+            "A1 Nassaulaan [nr] [postcode] Vlaardingen 17128"
+        );
+        assert_eq!(
+            redact_text("P 2 BR woning (Kerkstraat 3-5, 1234 AB Ee) rit 2"),
+            "P 2 BR woning (Kerkstraat [nr], [postcode] Ee) rit 2"
+        );
+        assert_eq!(redact_text("A2 Dorpsweg Utrecht 630 17-128"), "A2 Dorpsweg Utrecht 630 17-128");
+
+        let mut found = FoundLocation {
+            found_place: "Vlaardingen".to_string(),
+            info: LocationInfo {
+                woonplaats: Some(Area {
+                    code: "WP1015".to_string(),
+                    name: "Vlaardingen".to_string(),
+                }),
+                gemeente: Area {
+                    code: "GM0622".to_string(),
+                    name: "Vlaardingen".to_string(),
+                },
+                veiligheidsregio: Some("Rotterdam-Rijnmond".to_string()),
+                province: Area {
+                    code: "PV28".to_string(),
+                    name: "Zuid-Holland".to_string(),
+                },
+                latitude: Some(51.9123),
+                longitude: Some(4.3456),
+            },
+        };
+        redact_location(&mut found);
+        assert_eq!((found.info.latitude, found.info.longitude), (Some(51.9), Some(4.3)));
+    }
+}
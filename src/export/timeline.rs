@@ -0,0 +1,234 @@
+//! Chronological Markdown timeline of one incident, for after-action reports.
+
+use chrono::Duration;
+use std::collections::BTreeSet;
+
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::rules;
+
+/// Pages for the same place further apart than this start a new incident
+const THREAD_GAP_MINUTES: i64 = 60;
+
+/// Content words that mark an upscaled incident
+const ESCALATION_WORDS: [&str; 5] = ["grip", "opschaling", "middelbrand", "grote brand", "ramp"];
+
+/// What groups pages into one incident: the matched place, else the text itself
+/// (the same page repeated for several capcodes).
+pub fn thread_key(msg: &P2000Message, enrichment: &Enrichment) -> String {
+    match &enrichment.location {
+        Some(found) => found.found_place.trim().to_lowercase(),
+        None => msg.content.trim().to_lowercase(),
+    }
+}
+
+/// Indices of the messages in the same incident as `messages[anchor]`, oldest
+/// first: same key, each within the gap of its neighbour in the thread.
+pub fn thread(messages: &[P2000Message], anchor: usize, key: impl Fn(&P2000Message) -> String) -> Vec<usize> {
+    let anchor_key = key(&messages[anchor]);
+    let mut candidates: Vec<usize> = (0..messages.len())
+        .filter(|&i| i == anchor || key(&messages[i]) == anchor_key)
+        .collect();
+    candidates.sort_by_key(|&i| (messages[i].timestamp, i));
+
+    let gap = Duration::minutes(THREAD_GAP_MINUTES);
+    let position = candidates.iter().position(|&i| i == anchor).unwrap_or(0);
+    let mut start = position;
+    while start > 0 && messages[candidates[start]].timestamp - messages[candidates[start - 1]].timestamp <= gap {
+        start -= 1;
+    }
+    let mut end = position;
+    while end + 1 < candidates.len()
+        && messages[candidates[end + 1]].timestamp - messages[candidates[end]].timestamp <= gap
+    {
+        end += 1;
+    }
+    candidates[start..=end].to_vec()
+}
+
+/// Renders a thread (oldest first) as a Markdown section with one table row per
+/// page, classifying each as dispatch, escalation, added units or repeat.
+pub fn to_markdown(thread: &[(&P2000Message, &Enrichment)]) -> String {
+    let Some((first, first_enrichment)) = thread.first() else {
+        return String::new();
+    };
+    let last = thread.last().map(|(msg, _)| msg).unwrap_or(first);
+    let place = first_enrichment
+        .location
+        .as_ref()
+        .map(|found| found.found_place.trim().to_string())
+        .unwrap_or_else(|| first.location.clone());
+
+    let mut out = format!("# Incident timeline: {}\n\n", if place.is_empty() { "unknown place" } else { &place });
+    out.push_str(&format!(
+        "{} to {}, {} messages\n\n",
+        first.timestamp.format("%Y-%m-%d %H:%M:%S"),
+        last.timestamp.format("%H:%M:%S"),
+        thread.len()
+    ));
+    let receivers: BTreeSet<String> = thread
+        .iter()
+        .filter_map(|(msg, _)| msg.receiver.as_ref().map(|r| r.to_string()))
+        .collect();
+    if !receivers.is_empty() {
+        out.push_str(&format!("Received by {}\n\n", receivers.into_iter().collect::<Vec<_>>().join("; ")));
+    }
+    out.push_str("| Time | Priority | Event | New units | Message |\n");
+    out.push_str("|------|----------|-------|-----------|---------|\n");
+
+    let mut seen_units = BTreeSet::new();
+    let mut announced = BTreeSet::new();
+    let mut previous_priority: Option<String> = None;
+    for (i, (msg, enrichment)) in thread.iter().enumerate() {
+        let units = units(msg, enrichment);
+        let new_units: Vec<&str> = units
+            .iter()
+            .filter(|u| !seen_units.contains(*u))
+            .map(String::as_str)
+            .collect();
+        let priority = msg.priority.as_deref().map(rules::normalize_priority);
+        let upscaled = announces_upscaling(&msg.content, &mut announced);
+
+        let mut events = Vec::new();
+        if i == 0 {
+            events.push("Dispatch".to_string());
+        } else {
+            if let Some(change) = escalation(previous_priority.as_deref(), priority.as_deref(), upscaled) {
+                events.push(change);
+            }
+            if !new_units.is_empty() {
+                events.push("Units added".to_string());
+            }
+            if events.is_empty() {
+                events.push("Repeat".to_string());
+            }
+        }
+
+        out.push_str(&format!(
+            "| {} | {} | {} | {} | {} |\n",
+            msg.timestamp.format("%H:%M:%S"),
+            cell(msg.priority.as_deref().unwrap_or("")),
+            events.join(", "),
+            cell(&new_units.join(", ")),
+            cell(&msg.content)
+        ));
+        seen_units.extend(units);
+        if priority.is_some() {
+            previous_priority = priority;
+        }
+    }
+    out
+}
+
+/// Dispatched units by description, skipping nationwide group codes; raw capcodes when unknown.
+fn units(msg: &P2000Message, enrichment: &Enrichment) -> Vec<String> {
+    let described: Vec<String> = enrichment
+        .capcodes
+        .iter()
+        .filter(|c| !c.description.starts_with("Groepscode"))
+        .map(|c| c.description.clone())
+        .collect();
+    if enrichment.capcodes.is_empty() {
+        msg.capcodes.iter().map(|c| c.to_string()).collect()
+    } else {
+        described
+    }
+}
+
+/// "Escalation (P2 → P1)" when the priority got more urgent within the same
+/// service, or "Escalation" when the text announces upscaling.
+fn escalation(previous: Option<&str>, current: Option<&str>, upscaled: bool) -> Option<String> {
+    if let (Some(previous), Some(current)) = (previous, current) {
+        let same_service = previous.chars().next() == current.chars().next();
+        if same_service && current < previous {
+            return Some(format!("Escalation ({} → {})", previous, current));
+        }
+    }
+    upscaled.then(|| "Escalation".to_string())
+}
+
+/// Whether `content` has an escalation word not yet in `announced`, the words
+/// of earlier pages in the thread; a page repeated for more capcodes has the
+/// same words without being a new escalation.
+fn announces_upscaling(content: &str, announced: &mut BTreeSet<&'static str>) -> bool {
+    let content = content.to_lowercase();
+    let mut new = false;
+    for word in ESCALATION_WORDS {
+        if content.contains(word) {
+            new |= announced.insert(word);
+        }
+    }
+    new
+}
+
+fn cell(text: &str) -> String {
+    text.replace('|', "\\|")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::lookup::CapcodeInfo;
+    use crate::parser::Parser;
+    use std::sync::Arc;
+
+    fn unit(description: &str) -> Arc<CapcodeInfo> {
+        Arc::new(CapcodeInfo {
+            code: String::new(),
+            service: "Brandweer".to_string(),
+            region: String::new(),
+            place: String::new(),
+            description: description.to_string(),
+            short: String::new(),
+            source: "capcodelist".into(),
+        })
+    }
+
+    #[test]
+    fn test_thread_and_timeline() {
+        let parser = Parser::new();
+        let messages: Vec<P2000Message> = [
+            "FLEX|2026-01-01 20:01:50|1600/2/K/A|00.053|002029568|ALN|P 2 BLB-02 BR woning Hertogstraat Weert 234431",
+            "FLEX|2026-01-01 20:02:10|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:09:12|1600/2/K/A|00.054|002029569|ALN|P 1 BLB-02 Middelbrand Hertogstraat Weert 234431",
+            "FLEX|2026-01-01 20:09:12|1600/2/K/A|00.054|002029569|ALN|P 1 BLB-02 Middelbrand Hertogstraat Weert 234431",
+            "FLEX|2026-01-01 23:30:00|1600/2/K/A|00.055|002029568|ALN|P 2 Nacontrole Hertogstraat Weert",
+        ]
+        .iter()
+        .map(|line| parser.parse_line(line).unwrap())
+        .collect();
+        let key = |msg: &P2000Message| {
+            if msg.content.contains("Weert") { "weert".to_string() } else { msg.content.to_lowercase() }
+        };
+        assert_eq!(thread(&messages, 2, key), [0, 2, 3]);
+
+        let enrichments = [
+            Enrichment { capcodes: vec![unit("TS Weert")], ..Default::default() },
+            Enrichment { capcodes: vec![unit("TS Weert"), unit("AL Weert")], ..Default::default() },
+            Enrichment { capcodes: vec![unit("TS Weert"), unit("AL Weert")], ..Default::default() },
+        ];
+        let timeline: Vec<(&P2000Message, &Enrichment)> =
+            [0, 2, 3].iter().zip(&enrichments).map(|(&i, e)| (&messages[i], e)).collect();
+        let markdown = to_markdown(&timeline);
+        let rows: Vec<&str> = markdown.lines().skip(6).collect();
+        // Feed times are UTC and shown in local time
+        let time = |i: usize| messages[i].timestamp.format("%H:%M:%S").to_string();
+        assert_eq!(
+            rows,
+            [
+                format!("| {} | P 2 | Dispatch | TS Weert | P 2 BLB-02 BR woning Hertogstraat Weert 234431 |", time(0)),
+                format!(
+                    "| {} | P 1 | Escalation (P2 → P1), Units added | AL Weert | P 1 BLB-02 Middelbrand Hertogstraat Weert 234431 |",
+                    time(2)
+                ),
+                format!("| {} | P 1 | Repeat |  | P 1 BLB-02 Middelbrand Hertogstraat Weert 234431 |", time(3)),
+            ]
+        );
+
+        // A word escalates once, on the first page that has it
+        let mut announced = BTreeSet::new();
+        assert!(announces_upscaling("P 1 Middelbrand Weert", &mut announced));
+        assert!(!announces_upscaling("P 1 Middelbrand Weert", &mut announced));
+        assert!(announces_upscaling("P 1 Middelbrand GRIP 1 Weert", &mut announced));
+    }
+}
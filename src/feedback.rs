@@ -0,0 +1,108 @@
+//! Corrections made in the TUI ("wrong place"). They are appended to
+//! `corrections.tsv` in the config directory and applied to the place lookup
+//! the next time the data files are loaded.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+pub const CORRECTIONS_FILE: &str = "corrections.tsv";
+
+/// A place the lookup matched in a message, and the one the user says it was.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlaceCorrection {
+    pub wrong: String,
+    /// `None` when the text named no place at all
+    pub right: Option<String>,
+}
+
+#[derive(Debug)]
+pub struct Feedback {
+    path: PathBuf,
+    places: Vec<PlaceCorrection>,
+}
+
+impl Feedback {
+    /// Reads the corrections in `path`; a missing file has none yet.
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut feedback = Feedback {
+            path: path.to_path_buf(),
+            places: Vec::new(),
+        };
+        if !path.exists() {
+            return Ok(feedback);
+        }
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read corrections {}", path.display()))?;
+        // kind, wrong, right, then the message for reference
+        for line in content.lines().filter(|line| !line.starts_with('#')) {
+            let mut fields = line.split('\t');
+            if let (Some("place"), Some(wrong)) = (fields.next(), fields.next()) {
+                let right = fields.next().map(str::trim).filter(|right| !right.is_empty());
+                feedback.places.push(PlaceCorrection {
+                    wrong: wrong.trim().to_string(),
+                    right: right.map(str::to_string),
+                });
+            }
+        }
+        Ok(feedback)
+    }
+
+    pub fn places(&self) -> &[PlaceCorrection] {
+        &self.places
+    }
+
+    /// Records that `wrong` was matched in `content` where `right` (or no place) was meant.
+    pub fn record_place(&mut self, wrong: &str, right: Option<&str>, content: &str) -> Result<()> {
+        let clean = |text: &str| text.replace(['\t', '\n'], " ").trim().to_string();
+        let correction = PlaceCorrection {
+            wrong: clean(wrong),
+            right: right.map(clean).filter(|right| !right.is_empty()),
+        };
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        writeln!(
+            file,
+            "place\t{}\t{}\t{}",
+            correction.wrong,
+            correction.right.as_deref().unwrap_or(""),
+            clean(content)
+        )?;
+        self.places.push(correction);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::location::LocationLookup;
+
+    #[test]
+    fn test_corrections_round_trip() {
+        let path = std::env::temp_dir().join(format!("p2000_corrections_{}.tsv", std::process::id()));
+        std::fs::remove_file(&path).ok();
+        let mut feedback = Feedback::load(&path).unwrap();
+        assert!(feedback.places().is_empty());
+        feedback.record_place("Ee", None, "P 2 Brand\tEerste Ekelstraat").unwrap();
+        feedback.record_place("Vlaardingen", Some(" Schiedam "), "A1 Vlaardingerdijk Schiedam").unwrap();
+
+        let loaded = Feedback::load(&path).unwrap();
+        assert_eq!(loaded.places(), feedback.places());
+        assert_eq!(loaded.places()[1].right.as_deref(), Some("Schiedam"));
+        std::fs::remove_file(path).ok();
+
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let mut locations =
+            LocationLookup::load_available(&data.join("Observations.csv"), &data.join("RegioSCodes.csv")).unwrap();
+        let place = |locations: &LocationLookup| {
+            locations.find_location_by_text("Vlaardingerdijk Schiedam Vlaardingen").map(|found| found.found_place)
+        };
+        assert_eq!(place(&locations).as_deref(), Some("Vlaardingen"));
+        locations.apply_corrections(loaded.places());
+        assert_eq!(place(&locations).as_deref(), Some("Schiedam"));
+    }
+}
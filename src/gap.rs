@@ -0,0 +1,114 @@
+//! Feed outages: stretches without any message, from signal loss or a crashed
+//! decoder, so an unattended receiver going quiet gets noticed.
+
+use chrono::{DateTime, Duration, Local};
+use serde::Deserialize;
+
+use crate::parser::{P2000Message, TimeSource};
+
+/// `[gaps]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct GapConfig {
+    /// Silence longer than this counts as an outage; 0 disables gap detection
+    pub threshold_secs: u64,
+    /// Alert while the feed is silent: the terminal bell in live mode, the sinks in the daemon
+    pub alert: bool,
+}
+
+impl Default for GapConfig {
+    fn default() -> Self {
+        GapConfig {
+            threshold_secs: 600,
+            alert: false,
+        }
+    }
+}
+
+pub struct GapDetector {
+    threshold: Duration,
+    last: Option<DateTime<Local>>,
+    // The current silence was already reported by `check`
+    reported: bool,
+}
+
+impl GapDetector {
+    /// `None` when gap detection is disabled.
+    pub fn new(config: &GapConfig) -> Option<Self> {
+        (config.threshold_secs > 0).then(|| GapDetector {
+            threshold: Duration::seconds(config.threshold_secs as i64),
+            last: None,
+            reported: false,
+        })
+    }
+
+    /// Records a message time; returns the silence it ended when that was a gap.
+    pub fn observe(&mut self, time: DateTime<Local>) -> Option<Duration> {
+        let gap = self.last.map(|last| time - last).filter(|gap| *gap > self.threshold);
+        self.last = Some(self.last.map_or(time, |last| last.max(time)));
+        self.reported = false;
+        gap
+    }
+
+    /// The ongoing silence, once per gap, as soon as it passes the threshold.
+    pub fn check(&mut self, now: DateTime<Local>) -> Option<Duration> {
+        let silence = now - self.last?;
+        if self.reported || silence <= self.threshold {
+            return None;
+        }
+        self.reported = true;
+        Some(silence)
+    }
+
+    /// Gaps in an existing list, keyed by the index of the message that ended them.
+    pub fn scan(&mut self, messages: &[P2000Message], source: TimeSource) -> Vec<(usize, Duration)> {
+        messages
+            .iter()
+            .enumerate()
+            .filter_map(|(i, msg)| self.observe(msg.time(source)).map(|gap| (i, gap)))
+            .collect()
+    }
+}
+
+/// "45s", "14m", "2h05m" or "3d04h".
+pub fn format_duration(duration: Duration) -> String {
+    let secs = duration.num_seconds().max(0);
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h{:02}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d{:02}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// A pseudo-message for reporting an ongoing gap through the alert sinks.
+pub fn gap_message(now: DateTime<Local>, silence: Duration) -> P2000Message {
+    P2000Message::notice(
+        now,
+        format!("No messages received for {}; check the receiver", format_duration(silence)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gaps_and_silence() {
+        let mut detector = GapDetector::new(&GapConfig::default()).unwrap();
+        let start = Local::now();
+        assert_eq!(detector.check(start), None);
+        assert_eq!(detector.observe(start), None);
+        assert_eq!(detector.observe(start + Duration::minutes(5)), None);
+        assert_eq!(detector.check(start + Duration::minutes(14)), None);
+
+        // Reported once while silent, then the gap is returned when the feed resumes
+        let silence = detector.check(start + Duration::minutes(16)).unwrap();
+        assert_eq!(format_duration(silence), "11m");
+        assert_eq!(detector.check(start + Duration::minutes(20)), None);
+        let gap = detector.observe(start + Duration::minutes(65)).unwrap();
+        assert_eq!(format_duration(gap), "1h00m");
+
+        assert!(GapDetector::new(&GapConfig { threshold_secs: 0, ..Default::default() }).is_none());
+    }
+}
@@ -0,0 +1,135 @@
+//! Prebuilt place lookup in `data/gazetteer.bin`.
+//!
+//! Reading Observations.csv, RegioSCodes.csv and 4pp-final-2023.csv takes a
+//! while at every start. `p2000-rust build-gazetteer` compiles them into one
+//! file: the places themselves and an FST from place name to place. The file
+//! keeps the length and CRC32 of the CSV files it was built from; when one of
+//! them has changed since, the CSV files are read as before.
+
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Read, Write};
+use std::path::Path;
+
+use crate::cache::{hash_file, SourceHash};
+use crate::data::DataFiles;
+use crate::location::{LocationInfo, LocationLookup};
+
+pub const GAZETTEER_FILE: &str = "gazetteer.bin";
+
+const MAGIC: &[u8; 4] = b"P2KG";
+/// Bump whenever `LocationInfo` or the way places are matched changes
+const VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct GazetteerFile {
+    version: u32,
+    /// Per source file, `None` when it was not there
+    sources: Vec<Option<SourceHash>>,
+    places: Vec<LocationInfo>,
+    /// FST from place name to index in `places`
+    names: Vec<u8>,
+}
+
+/// The CSV files a gazetteer is built from, in a fixed order.
+fn sources(files: &DataFiles) -> [&Path; 3] {
+    [&files.observations, &files.regions, &files.coordinates]
+}
+
+/// Reads the CSV files in `files` and writes their gazetteer; returns the
+/// number of place names.
+pub fn build(files: &DataFiles) -> Result<usize> {
+    let lookup = LocationLookup::load(&files.observations, &files.regions)?;
+    let (places, mut names) = lookup.records();
+    names.sort();
+    let count = names.len();
+    let names = fst::Map::from_iter(names.into_iter().map(|(name, index)| (name, index as u64)))?;
+    let gazetteer = GazetteerFile {
+        version: VERSION,
+        sources: sources(files)
+            .into_iter()
+            .map(|path| path.exists().then(|| hash_file(path)).transpose())
+            .collect::<Result<_>>()?,
+        places,
+        names: names.into_fst().into_inner(),
+    };
+
+    // Write to a temporary file first so a crash never leaves a truncated gazetteer
+    let tmp = files.gazetteer.with_extension("bin.tmp");
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&postcard::to_stdvec(&gazetteer)?)?;
+    writer.into_inner().map_err(|e| e.into_error())?.sync_all()?;
+    std::fs::rename(&tmp, &files.gazetteer)?;
+    Ok(count)
+}
+
+/// The gazetteer in `files`, or `None` when there is none, it was built by
+/// another version, or a CSV file it was built from has changed since. A CSV
+/// file that is no longer there doesn't count as changed.
+pub fn load(files: &DataFiles) -> Result<Option<LocationLookup>> {
+    if !files.gazetteer.exists() {
+        return Ok(None);
+    }
+    let mut bytes = Vec::new();
+    File::open(&files.gazetteer)?.read_to_end(&mut bytes)?;
+    let Some(body) = bytes.strip_prefix(MAGIC) else {
+        bail!("{} is not a gazetteer", files.gazetteer.display());
+    };
+    let gazetteer: GazetteerFile = postcard::from_bytes(body)?;
+    if gazetteer.version != VERSION {
+        return Ok(None);
+    }
+    for (path, built_from) in sources(files).into_iter().zip(&gazetteer.sources) {
+        if path.exists() && Some(hash_file(path)?) != *built_from {
+            return Ok(None);
+        }
+    }
+
+    let names = fst::Map::new(gazetteer.names)?;
+    let names = names
+        .stream()
+        .into_str_vec()?
+        .into_iter()
+        .map(|(name, index)| (name, index as usize))
+        .collect();
+    Ok(Some(LocationLookup::from_records(gazetteer.places, names)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gazetteer_matches_csv_until_sources_change() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let dir = std::env::temp_dir().join(format!("p2000_gazetteer_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for name in ["Observations.csv", "RegioSCodes.csv", "4pp-final-2023.csv"] {
+            std::fs::copy(data.join(name), dir.join(name)).unwrap();
+        }
+        let files = DataFiles::in_dir(&dir);
+        assert!(load(&files).unwrap().is_none());
+
+        assert!(build(&files).unwrap() > 1000);
+        let csv = LocationLookup::load(&files.observations, &files.regions).unwrap();
+        let prebuilt = load(&files).unwrap().expect("Gazetteer not used");
+        for text in ["A1 Dorpsstraat Aadorp", "Brand Almelo", "Montferland", "A2 Hapert Rit: 463", "Nergens"] {
+            let format = |lookup: &LocationLookup| {
+                lookup.find_location_by_text(text).map(|found| lookup.format_found_location(&found))
+            };
+            assert_eq!(format(&prebuilt), format(&csv), "{}", text);
+        }
+        assert_eq!(prebuilt.format("WP1925"), csv.format("WP1925"));
+
+        // Shipped without the CSV files
+        std::fs::remove_file(&files.coordinates).unwrap();
+        assert!(load(&files).unwrap().is_some());
+        // Custom data
+        std::fs::write(&files.regions, "WP1925;1;RegioS;1;Aadorp;\n").unwrap();
+        assert!(load(&files).unwrap().is_none());
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
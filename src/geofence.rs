@@ -0,0 +1,133 @@
+//! `[[geofences]]`: named areas given as a circle, a polygon, postcodes or
+//! place names. The `geofences` enricher lists the fences a message falls in;
+//! rules (`geofences = [...]`) and searches (`fence:`) can then require one.
+
+use serde::Deserialize;
+
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::tokenize::{tokenize, TokenKind};
+
+/// Mean radius of the earth
+const EARTH_RADIUS_KM: f64 = 6371.0;
+
+/// A message is inside when it matches any of the given areas.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Geofence {
+    pub name: String,
+    /// Circle center as `[latitude, longitude]`, with `radius_km`
+    pub center: Option<[f64; 2]>,
+    pub radius_km: f64,
+    /// Corners as `[latitude, longitude]`, in order around the area
+    pub polygon: Vec<[f64; 2]>,
+    /// Full postcodes or 4-digit areas: `"3135"` covers `3135ZH`
+    pub postcodes: Vec<String>,
+    /// Place names, compared with the woonplaats and gemeente found in the message
+    pub places: Vec<String>,
+}
+
+/// Where a message is, as far as the enrichment knows.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Spot<'a> {
+    /// Latitude and longitude of the matched place
+    pub point: Option<(f64, f64)>,
+    pub postcode: Option<&'a str>,
+    /// Woonplaats, or the gemeente when the message named a whole gemeente
+    pub place: Option<&'a str>,
+    pub gemeente: Option<&'a str>,
+}
+
+impl<'a> Spot<'a> {
+    /// The first postcode in the content and the place the enrichment matched.
+    pub fn of(msg: &'a P2000Message, enrichment: &'a Enrichment) -> Self {
+        let info = enrichment.location.as_ref().map(|found| &found.info);
+        Spot {
+            point: info.and_then(|info| info.latitude.zip(info.longitude)),
+            postcode: tokenize(&msg.content)
+                .into_iter()
+                .find(|token| token.kind == TokenKind::Postcode)
+                .map(|token| token.text),
+            place: info.map(|info| info.place()),
+            gemeente: info.map(|info| info.gemeente.name.as_str()),
+        }
+    }
+}
+
+impl Geofence {
+    pub fn contains(&self, spot: &Spot) -> bool {
+        let in_circle = self
+            .center
+            .zip(spot.point)
+            .is_some_and(|([lat, lon], point)| distance_km((lat, lon), point) <= self.radius_km);
+        let in_polygon = self.polygon.len() >= 3 && spot.point.is_some_and(|point| in_polygon(&self.polygon, point));
+        let in_postcodes = spot.postcode.is_some_and(|postcode| {
+            let postcode = postcode.to_uppercase();
+            self.postcodes.iter().any(|p| postcode.starts_with(&p.replace(' ', "").to_uppercase()))
+        });
+        let in_places = [spot.place, spot.gemeente]
+            .into_iter()
+            .flatten()
+            .any(|place| self.places.iter().any(|p| p.eq_ignore_ascii_case(place)));
+        in_circle || in_polygon || in_postcodes || in_places
+    }
+}
+
+/// Names of the fences `msg` is inside, in config order.
+pub fn containing(fences: &[Geofence], msg: &P2000Message, enrichment: &Enrichment) -> Vec<String> {
+    let spot = Spot::of(msg, enrichment);
+    fences.iter().filter(|fence| fence.contains(&spot)).map(|fence| fence.name.clone()).collect()
+}
+
+/// Great-circle distance between two latitude/longitude points.
+pub fn distance_km((lat1, lon1): (f64, f64), (lat2, lon2): (f64, f64)) -> f64 {
+    let (lat1, lat2) = (lat1.to_radians(), lat2.to_radians());
+    let (dlat, dlon) = (lat2 - lat1, (lon2 - lon1).to_radians());
+    let a = (dlat / 2.0).sin().powi(2) + lat1.cos() * lat2.cos() * (dlon / 2.0).sin().powi(2);
+    2.0 * EARTH_RADIUS_KM * a.sqrt().asin()
+}
+
+/// Even-odd rule: a ray from `point` crosses the edges an odd number of times.
+fn in_polygon(corners: &[[f64; 2]], (lat, lon): (f64, f64)) -> bool {
+    let mut inside = false;
+    for (i, &[lat1, lon1]) in corners.iter().enumerate() {
+        let [lat2, lon2] = corners[(i + 1) % corners.len()];
+        if (lat1 > lat) != (lat2 > lat) && lon < lon1 + (lat - lat1) / (lat2 - lat1) * (lon2 - lon1) {
+            inside = !inside;
+        }
+    }
+    inside
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fence_shapes() {
+        let vlaardingen = (51.91, 4.34);
+        let spot = Spot {
+            point: Some(vlaardingen),
+            postcode: Some("3135ZH"),
+            place: Some("Vlaardingen"),
+            gemeente: Some("Vlaardingen"),
+        };
+        let fence = |config: &str| -> Geofence { toml::from_str(config).unwrap() };
+
+        // Rotterdam centre is about 9 km away
+        assert!(fence("center = [51.92, 4.48]\nradius_km = 10").contains(&spot));
+        assert!(!fence("center = [51.92, 4.48]\nradius_km = 5").contains(&spot));
+        let square = "polygon = [[51.8, 4.2], [52.0, 4.2], [52.0, 4.4], [51.8, 4.4]]";
+        assert!(fence(square).contains(&spot));
+        assert!(!fence(square).contains(&Spot { point: Some((51.91, 4.5)), ..Default::default() }));
+        assert!(fence("postcodes = [\"3135\"]").contains(&spot));
+        assert!(fence("postcodes = [\"3135 zh\"]").contains(&spot));
+        assert!(!fence("postcodes = [\"3136\"]").contains(&spot));
+        assert!(fence("places = [\"vlaardingen\"]").contains(&spot));
+        let village = Spot { place: Some("Aadorp"), gemeente: Some("Almelo"), ..Default::default() };
+        assert!(fence("places = [\"Almelo\"]").contains(&village));
+        // No coordinates, no circle
+        assert!(!fence("center = [51.92, 4.48]\nradius_km = 10").contains(&Spot::default()));
+        assert!((distance_km((52.37, 4.90), (51.92, 4.48)) - 57.0).abs() < 1.0);
+    }
+}
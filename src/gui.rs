@@ -0,0 +1,274 @@
+//! Desktop window front-end: message table, detail pane and a map of matched places.
+
+use anyhow::{anyhow, Result};
+use eframe::egui;
+use std::sync::Arc;
+
+use crate::enrich::{self, Enrichment};
+use crate::location::LocationLookup;
+use crate::lookup::Lookup;
+use crate::parser::P2000Message;
+use crate::severity::SeverityConfig;
+use crate::state::{AppState, SearchScope, SortOrder};
+use crate::translate::Translator;
+
+/// Bounding box of the Netherlands as (south, west, north, east)
+const MAP_BOUNDS: (f64, f64, f64, f64) = (50.7, 3.3, 53.6, 7.3);
+
+struct GuiApp {
+    state: AppState,
+    // Parallel to `state.messages()`; computed once so the map can draw every message
+    enrichments: Vec<Enrichment>,
+    search: String,
+}
+
+pub fn run(
+    messages: Vec<P2000Message>,
+    lookup: Lookup,
+    location_lookup: LocationLookup,
+    translator: Option<Translator>,
+    severity: SeverityConfig,
+) -> Result<()> {
+    let enrichments: Vec<Enrichment> = messages
+        .iter()
+        .map(|msg| {
+            let mut enrichment = enrich::enrich(msg, &lookup, &location_lookup);
+            enrichment.translation = translator.as_ref().and_then(|t| t.translate(&msg.content));
+            enrichment
+        })
+        .collect();
+    let regions = enrichments.iter().map(|e: &Enrichment| e.region.clone()).collect();
+    let app = GuiApp {
+        state: AppState::new(messages)
+            .with_lookup(Arc::new(lookup))
+            .with_severity(severity)
+            .with_regions(regions),
+        enrichments,
+        search: String::new(),
+    };
+    let options = eframe::NativeOptions {
+        viewport: egui::ViewportBuilder::default()
+            .with_title("P2000")
+            .with_inner_size([1200.0, 800.0]),
+        ..Default::default()
+    };
+    eframe::run_native("P2000", options, Box::new(|_cc| Ok(Box::new(app))))
+        .map_err(|e| anyhow!("GUI failed: {}", e))
+}
+
+impl eframe::App for GuiApp {
+    fn ui(&mut self, ui: &mut egui::Ui, _frame: &mut eframe::Frame) {
+        egui::Panel::top("search").show(ui, |ui| {
+            ui.horizontal(|ui| {
+                ui.label("Search:");
+                let search = ui.text_edit_singleline(&mut self.search);
+                if search.changed() {
+                    self.state.set_search_query(&self.search);
+                }
+                if search.lost_focus() {
+                    self.state.commit_filter();
+                }
+                if ui.button("Undo").clicked() && self.state.undo_filter() {
+                    self.search = self.state.search_query().to_string();
+                }
+                if ui.button("Redo").clicked() && self.state.redo_filter() {
+                    self.search = self.state.search_query().to_string();
+                }
+                let scope = self.state.filter().scope;
+                egui::ComboBox::from_id_salt("scope")
+                    .selected_text(format!("Search {}", scope.name()))
+                    .show_ui(ui, |ui| {
+                        for choice in SearchScope::ALL {
+                            if ui.selectable_label(choice == scope, choice.name()).clicked() {
+                                self.state.set_search_scope(choice);
+                                self.state.commit_filter();
+                            }
+                        }
+                    });
+                let current = self.state.filter().region.clone();
+                let mut selected = current.clone();
+                egui::ComboBox::from_id_salt("region")
+                    .selected_text(selected.as_deref().unwrap_or("All regions"))
+                    .show_ui(ui, |ui| {
+                        ui.selectable_value(&mut selected, None, "All regions");
+                        for name in self.state.region_names() {
+                            ui.selectable_value(&mut selected, Some(name.to_string()), name);
+                        }
+                    });
+                if selected != current {
+                    self.state.set_region_filter(selected);
+                    self.state.commit_filter();
+                }
+                let mut hide_minor = self.state.filter().hide_minor_pages;
+                if ui.checkbox(&mut hide_minor, "Hide numeric and group pages").changed() {
+                    self.state.toggle_minor_pages();
+                    self.state.commit_filter();
+                }
+                let mut by_region = self.state.filter().sort == SortOrder::Region;
+                if ui.checkbox(&mut by_region, "Sort by region").changed() {
+                    self.state.toggle_region_sort();
+                    self.state.commit_filter();
+                }
+                let mut by_severity = self.state.filter().sort == SortOrder::Severity;
+                if ui.checkbox(&mut by_severity, "Sort by severity").changed() {
+                    self.state.set_sort(if by_severity { SortOrder::Severity } else { SortOrder::Arrival });
+                    self.state.commit_filter();
+                }
+                ui.label(format!(
+                    "{} of {} messages",
+                    self.state.filtered_len(),
+                    self.state.messages().len()
+                ));
+            });
+        });
+
+        egui::Panel::right("map").default_size(360.0).show(ui, |ui| {
+            ui.heading("Map");
+            self.map(ui);
+        });
+
+        egui::Panel::bottom("details")
+            .resizable(true)
+            .default_size(200.0)
+            .show(ui, |ui| self.details(ui));
+
+        egui::CentralPanel::default().show(ui, |ui| self.table(ui));
+    }
+}
+
+impl GuiApp {
+    fn enrichment(&self, msg_index: usize) -> &Enrichment {
+        &self.enrichments[msg_index]
+    }
+
+    fn table(&mut self, ui: &mut egui::Ui) {
+        let row_height = ui.text_style_height(&egui::TextStyle::Monospace);
+        let mut clicked = None;
+        egui::ScrollArea::vertical().auto_shrink(false).show_rows(
+            ui,
+            row_height,
+            self.state.filtered_len(),
+            |ui, rows| {
+                for (i, msg) in self.state.filtered().enumerate().skip(rows.start).take(rows.len()) {
+                    let region = self.state.message_index(i).and_then(|m| self.state.region(m));
+                    let text = format!(
+                        "{:>3} | {} | {:<25} | {}",
+                        msg.priority.as_deref().unwrap_or(""),
+                        msg.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                        region.unwrap_or("-"),
+                        msg.content
+                    );
+                    let label = egui::RichText::new(text).monospace().color(priority_color(msg));
+                    if ui
+                        .selectable_label(i == self.state.selected_index(), label)
+                        .clicked()
+                    {
+                        clicked = Some(i);
+                    }
+                }
+            },
+        );
+        if let Some(i) = clicked {
+            self.state.select(i);
+        }
+
+        if ui.input(|input| input.key_pressed(egui::Key::ArrowDown)) {
+            self.state.move_down();
+        }
+        if ui.input(|input| input.key_pressed(egui::Key::ArrowUp)) {
+            self.state.move_up();
+        }
+    }
+
+    fn details(&self, ui: &mut egui::Ui) {
+        let Some(&msg_index) = self.state.filtered_indices().get(self.state.selected_index()) else {
+            ui.label("No message selected");
+            return;
+        };
+        let msg = &self.state.messages()[msg_index];
+        let enrichment = self.enrichment(msg_index);
+
+        egui::Grid::new("detail_grid").num_columns(2).striped(true).show(ui, |ui| {
+            let mut row = |name: &str, value: String| {
+                ui.label(name);
+                ui.label(value);
+                ui.end_row();
+            };
+            row("Priority", msg.priority.clone().unwrap_or_else(|| "-".to_string()));
+            row("Incident code", msg.incident_code.clone().unwrap_or_else(|| "-".to_string()));
+            row("Timestamp", msg.timestamp.format("%Y-%m-%d %H:%M:%S").to_string());
+            row("Region", enrichment.region.clone().unwrap_or_else(|| "-".to_string()));
+            row(
+                "Location",
+                enrichment
+                    .location
+                    .as_ref()
+                    .map(|found| found.info.to_string())
+                    .unwrap_or_else(|| msg.location.clone()),
+            );
+            row(
+                "Units",
+                enrichment
+                    .capcodes
+                    .iter()
+                    .map(|info| info.description.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            );
+            row(
+                "Abbreviations",
+                enrichment
+                    .abbreviations
+                    .iter()
+                    .map(|a| format!("{}: {}", a.abbreviation, a.expansion))
+                    .collect::<Vec<_>>()
+                    .join("; "),
+            );
+            row("Capcodes", msg.capcodes.join(", "));
+            row("Content", msg.content.clone());
+            if let Some(english) = &enrichment.translation {
+                row("English", english.clone());
+            }
+        });
+    }
+
+    fn map(&self, ui: &mut egui::Ui) {
+        let size = egui::vec2(ui.available_width(), ui.available_width() * 1.2);
+        let (response, painter) = ui.allocate_painter(size, egui::Sense::hover());
+        let rect = response.rect;
+        painter.rect_filled(rect, 4.0, ui.visuals().extreme_bg_color);
+
+        let (south, west, north, east) = MAP_BOUNDS;
+        let project = |lat: f64, lon: f64| {
+            egui::pos2(
+                rect.left() + ((lon - west) / (east - west)) as f32 * rect.width(),
+                rect.top() + ((north - lat) / (north - south)) as f32 * rect.height(),
+            )
+        };
+
+        let selected = self.state.filtered_indices().get(self.state.selected_index()).copied();
+        for &i in self.state.filtered_indices() {
+            let Some(found) = &self.enrichments[i].location else {
+                continue;
+            };
+            let (Some(lat), Some(lon)) = (found.info.latitude, found.info.longitude) else {
+                continue;
+            };
+            let color = priority_color(&self.state.messages()[i]);
+            if Some(i) == selected {
+                painter.circle_filled(project(lat, lon), 6.0, color);
+                painter.circle_stroke(project(lat, lon), 8.0, egui::Stroke::new(2.0, egui::Color32::WHITE));
+            } else {
+                painter.circle_filled(project(lat, lon), 2.5, color.gamma_multiply(0.6));
+            }
+        }
+    }
+}
+
+fn priority_color(msg: &P2000Message) -> egui::Color32 {
+    match msg.priority.as_deref() {
+        Some(p) if p.starts_with('A') => egui::Color32::from_rgb(230, 70, 70),
+        Some(p) if p.starts_with('P') => egui::Color32::from_rgb(230, 200, 60),
+        _ => egui::Color32::GRAY,
+    }
+}
@@ -0,0 +1,112 @@
+//! Service and priority glyphs for the message list.
+
+use serde::Deserialize;
+
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::rules;
+
+/// `icons` under `[display]`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum IconStyle {
+    #[default]
+    None,
+    /// Three-letter service codes, for terminals without Unicode
+    Ascii,
+    Emoji,
+    /// Font Awesome glyphs from a Nerd Font
+    Nerd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Service {
+    Ambulance,
+    Fire,
+    Police,
+    /// KNRM and lifeguards
+    Rescue,
+    Other,
+}
+
+impl Service {
+    /// Maps the service column of the capcode list.
+    pub fn from_name(name: &str) -> Self {
+        match name {
+            "Ambulance" => Service::Ambulance,
+            "Brandweer" => Service::Fire,
+            "Politie" => Service::Police,
+            "KNRM" | "Reddingsbrigade" => Service::Rescue,
+            _ => Service::Other,
+        }
+    }
+
+    /// The primary capcode's service, else a guess from the priority: A and B
+    /// priorities are ambulance rides, P priorities fire brigade calls.
+    pub fn of(msg: &P2000Message, enrichment: &Enrichment) -> Option<Self> {
+        if let Some(info) = enrichment.primary_capcode() {
+            return Some(Self::from_name(&info.service));
+        }
+        match msg.priority.as_deref()?.chars().next()? {
+            'A' | 'B' => Some(Service::Ambulance),
+            'P' => Some(Service::Fire),
+            _ => None,
+        }
+    }
+}
+
+impl IconStyle {
+    /// Glyph for a service, padded so every glyph of a style takes the same width.
+    pub fn service(self, service: Option<Service>) -> &'static str {
+        match (self, service) {
+            (IconStyle::None, _) => "",
+            (IconStyle::Ascii, Some(Service::Ambulance)) => "AMB",
+            (IconStyle::Ascii, Some(Service::Fire)) => "BRW",
+            (IconStyle::Ascii, Some(Service::Police)) => "POL",
+            (IconStyle::Ascii, Some(Service::Rescue)) => "RED",
+            (IconStyle::Ascii, _) => "---",
+            (IconStyle::Emoji, Some(Service::Ambulance)) => "🚑",
+            (IconStyle::Emoji, Some(Service::Fire)) => "🚒",
+            (IconStyle::Emoji, Some(Service::Police)) => "🚓",
+            (IconStyle::Emoji, Some(Service::Rescue)) => "⛑ ",
+            (IconStyle::Emoji, _) => "📟",
+            (IconStyle::Nerd, Some(Service::Ambulance)) => "\u{f0f9} ",
+            (IconStyle::Nerd, Some(Service::Fire)) => "\u{f06d} ",
+            (IconStyle::Nerd, Some(Service::Police)) => "\u{f132} ",
+            (IconStyle::Nerd, Some(Service::Rescue)) => "\u{f1cd} ",
+            (IconStyle::Nerd, _) => "\u{f0f3} ",
+        }
+    }
+
+    /// Marker for the most urgent priorities (A1, P 1), blank otherwise.
+    pub fn urgency(self, priority: Option<&str>) -> &'static str {
+        let urgent = priority.is_some_and(rules::is_urgent);
+        match (self, urgent) {
+            (IconStyle::None, _) => "",
+            (IconStyle::Ascii, true) => "!",
+            (IconStyle::Ascii, false) => " ",
+            (IconStyle::Emoji, true) => "🚨",
+            (IconStyle::Nerd, true) => "\u{f071} ",
+            (IconStyle::Emoji | IconStyle::Nerd, false) => "  ",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_service_from_priority_fallback() {
+        let parser = Parser::new();
+        let msg = parser
+            .parse_line("FLEX|2026-01-01 20:01:50|1600/2/K/A|00.053|002029568|ALN|P 1 BLB-02 BR woning Hertogstraat Weert 234431")
+            .unwrap();
+        let enrichment = Enrichment::default();
+        assert_eq!(Service::of(&msg, &enrichment), Some(Service::Fire));
+        assert_eq!(IconStyle::Ascii.urgency(msg.priority.as_deref()), "!");
+        assert_eq!(IconStyle::Emoji.service(Some(Service::Fire)), "🚒");
+        assert_eq!(IconStyle::None.service(Some(Service::Fire)), "");
+    }
+}
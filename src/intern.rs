@@ -0,0 +1,34 @@
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+
+/// Shares one allocation per distinct string. Feeds repeat the same protocol,
+/// frequency, message type and capcodes on nearly every line, so large archives
+/// keep only a few thousand copies instead of one per message.
+#[derive(Debug, Default)]
+pub struct Interner {
+    strings: Mutex<HashSet<Arc<str>>>,
+}
+
+impl Interner {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn intern(&self, value: &str) -> Arc<str> {
+        let mut strings = self.strings.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(existing) = strings.get(value) {
+            return existing.clone();
+        }
+        let shared: Arc<str> = Arc::from(value);
+        strings.insert(shared.clone());
+        shared
+    }
+
+    pub fn len(&self) -> usize {
+        self.strings.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
@@ -0,0 +1,52 @@
+pub mod address;
+pub mod anomaly;
+pub mod archive;
+pub mod audio;
+pub mod cache;
+pub mod calendar;
+pub mod capcodes;
+pub mod channels;
+pub mod config;
+pub mod daemon;
+pub mod data;
+pub mod daypart;
+pub mod enrich;
+pub mod export;
+pub mod feedback;
+pub mod gap;
+pub mod gazetteer;
+pub mod geofence;
+#[cfg(feature = "gui")]
+pub mod gui;
+pub mod icons;
+pub mod intern;
+pub mod location;
+pub mod lookup;
+pub mod map;
+pub mod pager;
+pub mod parser;
+pub mod plugin;
+pub mod query;
+pub mod reader;
+pub mod recording;
+pub mod remote;
+pub mod rollup;
+pub mod rules;
+pub mod sampler;
+pub mod script;
+pub mod scrub;
+pub mod search;
+pub mod sdr;
+pub mod severity;
+pub mod signal;
+pub mod sinks;
+pub mod state;
+pub mod stats;
+pub mod suggest;
+pub mod theme;
+pub mod tokenize;
+pub mod translate;
+pub mod tui;
+pub mod units;
+pub mod view;
+pub mod weather;
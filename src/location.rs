@@ -1,28 +1,109 @@
+//! Place names in message text, resolved through the CBS hierarchy:
+//! woonplaats (town or village) → gemeente (municipality) → veiligheidsregio
+//! (safety region) → provincie.
+
 use anyhow::Result;
-use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs::File;
 use std::path::Path;
 
-#[derive(Debug, Clone, Default)]
+use crate::feedback::PlaceCorrection;
+use crate::geofence;
+use crate::lookup::Lookup;
+
+/// A named area and its CBS code: `WP1925` Aadorp, `GM0141` Almelo, `PV23` Overijssel.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Area {
+    pub code: String,
+    pub name: String,
+}
+
+/// Which level of the hierarchy a place name in a message named.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PlaceLevel {
+    Woonplaats,
+    /// A gemeente named by a name that is no woonplaats, e.g. `Montferland`
+    Gemeente,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct LocationInfo {
-    pub place: String,
-    pub province: String,
-    pub region: String,
+    /// `None` when the place is a gemeente as a whole
+    pub woonplaats: Option<Area>,
+    pub gemeente: Area,
+    /// From the capcode list, once linked with `link_regions`
+    pub veiligheidsregio: Option<String>,
+    pub province: Area,
     pub latitude: Option<f64>,
     pub longitude: Option<f64>,
 }
 
-#[derive(Debug, Clone)]
+impl LocationInfo {
+    pub fn level(&self) -> PlaceLevel {
+        match self.woonplaats {
+            Some(_) => PlaceLevel::Woonplaats,
+            None => PlaceLevel::Gemeente,
+        }
+    }
+
+    /// The most specific name: the woonplaats, else the gemeente.
+    pub fn place(&self) -> &str {
+        self.woonplaats.as_ref().map_or(&self.gemeente.name, |woonplaats| &woonplaats.name)
+    }
+}
+
+/// `Aadorp, gemeente Almelo | Twente | Overijssel`; a woonplaats named like its
+/// gemeente is shown once, a whole gemeente as `gemeente Montferland`.
+impl fmt::Display for LocationInfo {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.woonplaats {
+            Some(woonplaats) if self.gemeente.name.is_empty() || woonplaats.name == self.gemeente.name => {
+                write!(f, "{}", woonplaats.name)?
+            }
+            Some(woonplaats) => write!(f, "{}, gemeente {}", woonplaats.name, self.gemeente.name)?,
+            None => write!(f, "gemeente {}", self.gemeente.name)?,
+        }
+        if let Some(region) = &self.veiligheidsregio {
+            write!(f, " | {}", region)?;
+        }
+        if !self.province.name.is_empty() {
+            write!(f, " | {}", self.province.name)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
 pub struct FoundLocation {
-    pub found_place: String,  // Place name found in message text
-    pub info: LocationInfo,   // Municipality, province, region from data
+    /// Place name as matched in the message text
+    pub found_place: String,
+    pub info: LocationInfo,
+}
+
+// What a searchable place name stands for
+#[derive(Debug, Clone)]
+enum Target {
+    /// By woonplaats code
+    Woonplaats(String),
+    /// By gemeente name
+    Gemeente(String),
 }
 
 #[derive(Debug, Default)]
 pub struct LocationLookup {
-    locations: HashMap<String, LocationInfo>,
+    // By woonplaats code
+    woonplaatsen: HashMap<String, LocationInfo>,
+    // By gemeente name
+    gemeenten: HashMap<String, LocationInfo>,
     place_names: Vec<String>,
-    place_to_wp: HashMap<String, String>,
+    // Lowercased `place_names`, same order, so matching doesn't re-lowercase per message
+    place_names_lower: Vec<String>,
+    targets: HashMap<String, Target>,
+    // Woonplaatsen with coordinates, for `nearest`
+    nearby: KdTree,
 }
 
 impl LocationLookup {
@@ -30,197 +111,471 @@ impl LocationLookup {
         observations_path: &Path,
         regios_codes_path: &Path,
     ) -> Result<Self> {
-        let mut locations: HashMap<String, LocationInfo> = HashMap::new();
-        let mut place_names: Vec<String> = Vec::new();
-        let mut place_to_wp: HashMap<String, String> = HashMap::new();
-        let mut seen_places: HashSet<String> = HashSet::new();
+        Self::load_from(observations_path, Some(regios_codes_path))
+    }
 
-        // Load 4pp-final-2023.csv for coordinates (if it exists)
-        let coords_path = Path::new("data/4pp-final-2023.csv");
-        let mut woonplaats_coords: HashMap<String, (f64, f64)> = HashMap::new();
-        if coords_path.exists() {
-            if let Ok(file) = File::open(coords_path) {
-                let mut rdr = csv::ReaderBuilder::new().from_reader(file);
-                for result in rdr.records() {
-                    if let Ok(record) = result {
-                        if record.len() >= 6 {
-                            if let Ok(lat) = record[4].parse::<f64>() {
-                                if let Ok(lon) = record[5].parse::<f64>() {
-                                    // Trim quotes and whitespace from place name
-                                    let woonplaats = record[1].trim_matches('"').trim().to_string();
-                                    woonplaats_coords.insert(woonplaats, (lat, lon));
-                                }
-                            }
-                        }
-                    }
-                }
-            }
+    /// Like `load`, but missing files leave the lookup empty: without
+    /// Observations.csv nothing resolves, without RegioSCodes.csv only the
+    /// gemeente names are matched.
+    pub fn load_available(observations_path: &Path, regios_codes_path: &Path) -> Result<Self> {
+        if !observations_path.exists() {
+            return Ok(LocationLookup::default());
         }
+        Self::load_from(observations_path, Some(regios_codes_path).filter(|path| path.exists()))
+    }
 
-        // Load Observations.csv for province and region
+    fn load_from(observations_path: &Path, regios_codes_path: Option<&Path>) -> Result<Self> {
+        // 4pp-final-2023.csv next to the other data files: coordinates by woonplaats name
+        let coordinates = load_coordinates(&observations_path.with_file_name("4pp-final-2023.csv"));
+        let with_coordinates = |info: &mut LocationInfo, name: &str| {
+            if let Some(&(lat, lon)) = coordinates.get(name) {
+                info.latitude = Some(lat);
+                info.longitude = Some(lon);
+            }
+        };
+
+        // Observations.csv: the gemeente and provincie of each woonplaats code
+        let mut woonplaatsen: HashMap<String, LocationInfo> = HashMap::new();
         let file = File::open(observations_path)?;
         let mut rdr = csv::ReaderBuilder::new()
             .delimiter(b';')
             .from_reader(file);
-
         for result in rdr.records() {
             let record = result?;
             if record.len() < 6 {
                 continue;
             }
-
             let measure = record[1].trim();
             let wp_code = record[2].trim();
-            let value = record[4].trim();
-
-            let loc = locations.entry(wp_code.to_string()).or_default();
-
+            let value = record[4].trim().to_string();
+            let info = woonplaatsen.entry(wp_code.to_string()).or_default();
             match measure {
-                "GM000C" => {
-                    loc.place = value.to_string();
-                    // Look up coordinates from woonplaats mapping
-                    if let Some((lat, lon)) = woonplaats_coords.get(value.trim()) {
-                        loc.latitude = Some(*lat);
-                        loc.longitude = Some(*lon);
-                    }
-                    // Also add place name from Observations as a searchable name
-                    let place_str = value.trim().to_string();  // Extra trim to handle spaces in CSV fields
-                    if !place_str.is_empty() && !seen_places.contains(&place_str) && place_str.len() >= 3 {
-                        place_to_wp.insert(place_str.clone(), wp_code.to_string());
-                        place_names.push(place_str.clone());
-                        seen_places.insert(place_str);
-                    }
-                }
-                "PV0002" => loc.province = value.to_string(),
-                "LD0002" => loc.region = value.to_string(),
+                "GM000C" => info.gemeente.name = value,
+                "GM000B" => info.gemeente.code = value,
+                "PV0002" => info.province.name = value,
+                "PV0001" => info.province.code = value,
                 _ => {}
             }
         }
 
-        // Load RegioSCodes.csv for place names and WP code mapping
-        let file = File::open(regios_codes_path)?;
-        let mut rdr = csv::ReaderBuilder::new()
-            .delimiter(b';')
-            .from_reader(file);
-
-        for result in rdr.records() {
-            let record = result?;
-            if record.len() < 5 {
+        // Each gemeente on its own, at the coordinates of the woonplaats of the same name
+        let mut gemeenten: BTreeMap<String, LocationInfo> = BTreeMap::new();
+        for info in woonplaatsen.values() {
+            if info.gemeente.name.is_empty() || gemeenten.contains_key(&info.gemeente.name) {
                 continue;
             }
+            let mut gemeente = LocationInfo {
+                gemeente: info.gemeente.clone(),
+                province: info.province.clone(),
+                ..Default::default()
+            };
+            with_coordinates(&mut gemeente, &info.gemeente.name);
+            gemeenten.insert(info.gemeente.name.clone(), gemeente);
+        }
 
-            let wp_code = record[0].trim_matches('"').trim();
-            let title = record[4].trim_matches('"').trim();  // Title is field 4 (0-indexed)
-
-            if !wp_code.is_empty() && !title.is_empty() {
-                let title_str = title.to_string();
-                // Only add if we haven't seen this place name before AND it's at least 3 characters
-                if !seen_places.contains(&title_str) && title_str.len() >= 3 {
-                    place_to_wp.insert(title_str.clone(), wp_code.to_string());
-                    place_names.push(title_str.clone());
-                    seen_places.insert(title_str.clone());
-                    
-                    // Also try to load coordinates for this place name from 4pp
-                    if let Some((lat, lon)) = woonplaats_coords.get(&title_str) {
-                        if let Some(loc) = locations.get_mut(wp_code) {
-                            loc.latitude = Some(*lat);
-                            loc.longitude = Some(*lon);
-                        }
-                    }
+        let mut place_names: Vec<String> = Vec::new();
+        let mut targets: HashMap<String, Target> = HashMap::new();
+        let mut seen_places: HashSet<String> = HashSet::new();
+        // Names shorter than 3 letters match inside too many words
+        let mut add_name = |name: &str, target: Target| {
+            if name.len() >= 3 && seen_places.insert(name.to_string()) {
+                targets.insert(name.to_string(), target);
+                place_names.push(name.to_string());
+            }
+        };
+
+        // RegioSCodes.csv: woonplaats names, which win over gemeente names
+        if let Some(regios_codes_path) = regios_codes_path {
+            let file = File::open(regios_codes_path)?;
+            let mut rdr = csv::ReaderBuilder::new()
+                .delimiter(b';')
+                .from_reader(file);
+
+            for result in rdr.records() {
+                let record = result?;
+                if record.len() < 5 {
+                    continue;
                 }
+                let wp_code = record[0].trim_matches('"').trim();
+                let title = record[4].trim_matches('"').trim(); // Title is field 4 (0-indexed)
+                let Some(info) = woonplaatsen.get_mut(wp_code).filter(|_| !title.is_empty()) else {
+                    continue;
+                };
+                info.woonplaats = Some(Area {
+                    code: wp_code.to_string(),
+                    name: title.to_string(),
+                });
+                with_coordinates(info, title);
+                add_name(title, Target::Woonplaats(wp_code.to_string()));
             }
         }
+        if regios_codes_path.is_some() {
+            // Codes without a name are no longer in use
+            woonplaatsen.retain(|_, info| info.woonplaats.is_some());
+        }
+        for name in gemeenten.keys() {
+            add_name(name, Target::Gemeente(name.clone()));
+        }
 
-        // Sort place names by length (longest first) for matching priority
-        place_names.sort_by(|a, b| b.len().cmp(&a.len()));
+        Ok(Self::with_names(woonplaatsen, gemeenten.into_iter().collect(), place_names, targets))
+    }
 
-        Ok(LocationLookup {
-            locations,
+    fn with_names(
+        woonplaatsen: HashMap<String, LocationInfo>,
+        gemeenten: HashMap<String, LocationInfo>,
+        mut place_names: Vec<String>,
+        targets: HashMap<String, Target>,
+    ) -> Self {
+        // Longest first for matching priority, then alphabetical so the order
+        // doesn't depend on where the names came from
+        place_names.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a.cmp(b)));
+        let place_names_lower = place_names.iter().map(|name| name.to_lowercase()).collect();
+        let nearby = KdTree::new(
+            woonplaatsen
+                .iter()
+                .filter(|(_, info)| info.woonplaats.is_some())
+                .filter_map(|(code, info)| Some((project(info.latitude?, info.longitude?), code.clone())))
+                .collect(),
+        );
+        LocationLookup {
+            woonplaatsen,
+            gemeenten,
             place_names,
-            place_to_wp,
-        })
+            place_names_lower,
+            targets,
+            nearby,
+        }
+    }
+
+    /// Every woonplaats (by code) and gemeente (by name), and for each place
+    /// name the index of the place it matches: what the gazetteer stores.
+    pub(crate) fn records(&self) -> (Vec<LocationInfo>, Vec<(String, usize)>) {
+        let mut codes: Vec<&String> = self.woonplaatsen.keys().collect();
+        codes.sort();
+        let mut gemeenten: Vec<&String> = self.gemeenten.keys().collect();
+        gemeenten.sort();
+        let records: Vec<LocationInfo> = codes
+            .iter()
+            .map(|code| self.woonplaatsen[*code].clone())
+            .chain(gemeenten.iter().map(|name| self.gemeenten[*name].clone()))
+            .collect();
+        let names = self
+            .place_names
+            .iter()
+            .filter_map(|name| {
+                let index = match self.targets.get(name)? {
+                    Target::Woonplaats(code) => codes.binary_search(&code).ok()?,
+                    Target::Gemeente(gemeente) => codes.len() + gemeenten.binary_search(&gemeente).ok()?,
+                };
+                Some((name.clone(), index))
+            })
+            .collect();
+        (records, names)
+    }
+
+    /// The lookup stored by `records`.
+    pub(crate) fn from_records(records: Vec<LocationInfo>, names: Vec<(String, usize)>) -> Self {
+        let targets: HashMap<String, Target> = names
+            .iter()
+            .filter_map(|(name, index)| {
+                let info = records.get(*index)?;
+                let target = match &info.woonplaats {
+                    Some(woonplaats) => Target::Woonplaats(woonplaats.code.clone()),
+                    None => Target::Gemeente(info.gemeente.name.clone()),
+                };
+                Some((name.clone(), target))
+            })
+            .collect();
+        let (mut woonplaatsen, mut gemeenten) = (HashMap::new(), HashMap::new());
+        for info in records {
+            match &info.woonplaats {
+                Some(woonplaats) => woonplaatsen.insert(woonplaats.code.clone(), info),
+                None => gemeenten.insert(info.gemeente.name.clone(), info),
+            };
+        }
+        let place_names = names.into_iter().map(|(name, _)| name).filter(|name| targets.contains_key(name)).collect();
+        Self::with_names(woonplaatsen, gemeenten, place_names, targets)
+    }
+
+    /// Fills in the veiligheidsregio of every place: the region most capcodes
+    /// of the woonplaats belong to, else those of its gemeente.
+    pub fn link_regions(&mut self, lookup: &Lookup) {
+        for info in self.woonplaatsen.values_mut().chain(self.gemeenten.values_mut()) {
+            info.veiligheidsregio = info
+                .woonplaats
+                .as_ref()
+                .and_then(|woonplaats| lookup.region_for_place(&woonplaats.name))
+                .or_else(|| lookup.region_for_place(&info.gemeente.name))
+                .map(str::to_string);
+        }
     }
 
+    /// The names matched in message text, longest first.
+    pub fn place_names(&self) -> &[String] {
+        &self.place_names
+    }
+
+    /// The woonplaats with CBS code `wp_code`.
     pub fn resolve(&self, wp_code: &str) -> Option<&LocationInfo> {
-        self.locations.get(wp_code)
+        self.woonplaatsen.get(wp_code)
+    }
+
+    /// The woonplaats closest to a point, for a message with coordinates or a
+    /// postcode but no place name, and how far away it is in km. The region
+    /// is its `veiligheidsregio`.
+    pub fn nearest(&self, lat: f64, lon: f64) -> Option<(&LocationInfo, f64)> {
+        let code = self.nearby.nearest(project(lat, lon))?;
+        let info = self.woonplaatsen.get(code)?;
+        let distance = geofence::distance_km((lat, lon), (info.latitude?, info.longitude?));
+        Some((info, distance))
     }
 
     pub fn find_location_by_text(&self, text: &str) -> Option<FoundLocation> {
         let text_lower = text.to_lowercase();
 
         // Search for place names in order (longest first)
-        for place in &self.place_names {
-            let place_lower = place.to_lowercase();
-            if text_lower.contains(&place_lower) {
-                // Get WP code from RegioSCodes mapping
-                if let Some(wp_code) = self.place_to_wp.get(place) {
-                    if let Some(info) = self.locations.get(wp_code) {
-                        return Some(FoundLocation {
-                            found_place: place.clone(),
-                            info: info.clone(),
-                        });
-                    }
-                }
+        for (place, place_lower) in self.place_names.iter().zip(&self.place_names_lower) {
+            if !text_lower.contains(place_lower.as_str()) {
+                continue;
+            }
+            let info = match self.targets.get(place) {
+                Some(Target::Woonplaats(code)) => self.woonplaatsen.get(code),
+                Some(Target::Gemeente(name)) => self.gemeenten.get(name),
+                None => None,
+            };
+            if let Some(info) = info {
+                return Some(FoundLocation {
+                    found_place: place.clone(),
+                    info: info.clone(),
+                });
             }
         }
         None
     }
 
-    pub fn format(&self, wp_code: &str) -> String {
-        if let Some(info) = self.resolve(wp_code) {
-            let mut parts = vec![];
-            if !info.place.is_empty() {
-                parts.push(info.place.trim().to_string());
+    /// Reorders matching after "wrong place" corrections: places users named as
+    /// the right one are tried first, places matched wrongly last. Length order
+    /// is kept within each group.
+    pub fn apply_corrections(&mut self, corrections: &[PlaceCorrection]) {
+        let mut score: HashMap<String, i32> = HashMap::new();
+        for correction in corrections {
+            *score.entry(correction.wrong.to_lowercase()).or_default() -= 1;
+            if let Some(right) = &correction.right {
+                *score.entry(right.to_lowercase()).or_default() += 1;
             }
-            if !info.province.is_empty() {
-                parts.push(info.province.trim().to_string());
-            }
-            if !info.region.is_empty() {
-                parts.push(info.region.trim().to_string());
-            }
-            parts.join(", ")
-        } else {
-            wp_code.to_string()
         }
+        let mut places: Vec<(String, String)> =
+            self.place_names.drain(..).zip(self.place_names_lower.drain(..)).collect();
+        places.sort_by_key(|(_, lower)| std::cmp::Reverse(score.get(lower).copied().unwrap_or(0).signum()));
+        (self.place_names, self.place_names_lower) = places.into_iter().unzip();
     }
 
-    pub fn format_info(&self, info: &LocationInfo) -> String {
-        let mut parts = vec![];
-        if !info.place.is_empty() {
-            parts.push(info.place.trim().to_string());
+    pub fn format(&self, wp_code: &str) -> String {
+        match self.resolve(wp_code) {
+            Some(info) => info.to_string(),
+            None => wp_code.to_string(),
         }
-        if !info.province.is_empty() {
-            parts.push(info.province.trim().to_string());
+    }
+
+    /// The hierarchy of a matched place, with its coordinates when known.
+    pub fn format_found_location(&self, found: &FoundLocation) -> String {
+        match (found.info.latitude, found.info.longitude) {
+            // Full precision
+            (Some(lat), Some(lon)) => format!("{} | [{}, {}]", found.info, lat, lon),
+            _ => found.info.to_string(),
         }
-        if !info.region.is_empty() {
-            parts.push(info.region.trim().to_string());
+    }
+}
+
+/// Latitude and longitude as km on a plane; close enough around the
+/// Netherlands to tell which place is nearest.
+fn project(lat: f64, lon: f64) -> [f64; 2] {
+    [lat * 111.2, lon * 111.2 * WGS84_ORIGIN.0.to_radians().cos()]
+}
+
+/// Points in an implicit k-d tree: every slice is split at its median, on
+/// alternating axes, with the median in the middle.
+#[derive(Debug, Default)]
+struct KdTree {
+    points: Vec<([f64; 2], String)>,
+}
+
+impl KdTree {
+    fn new(mut points: Vec<([f64; 2], String)>) -> Self {
+        fn build(points: &mut [([f64; 2], String)], axis: usize) {
+            if points.len() <= 1 {
+                return;
+            }
+            let mid = points.len() / 2;
+            points.select_nth_unstable_by(mid, |a, b| a.0[axis].total_cmp(&b.0[axis]));
+            let (left, right) = points.split_at_mut(mid);
+            build(left, 1 - axis);
+            build(&mut right[1..], 1 - axis);
         }
-        parts.join(", ")
+        build(&mut points, 0);
+        KdTree { points }
     }
 
-    pub fn format_found_location(&self, found: &FoundLocation) -> String {
-        let mut parts = vec![];
-        
-        // Format place: show "Found (Municipality)" if they differ, otherwise just the place
-        let place_str = if found.found_place.trim() != found.info.place.trim() {
-            format!("{} ({})", found.found_place.trim(), found.info.place.trim())
-        } else {
-            found.found_place.trim().to_string()
-        };
-        parts.push(place_str);
-        
-        if !found.info.province.is_empty() {
-            parts.push(found.info.province.trim().to_string());
+    fn nearest(&self, target: [f64; 2]) -> Option<&str> {
+        fn search<'a>(points: &'a [([f64; 2], String)], target: [f64; 2], axis: usize, best: &mut Option<(&'a str, f64)>) {
+            if points.is_empty() {
+                return;
+            }
+            let mid = points.len() / 2;
+            let (point, code) = &points[mid];
+            let distance = (point[0] - target[0]).powi(2) + (point[1] - target[1]).powi(2);
+            if best.is_none_or(|(_, closest)| distance < closest) {
+                *best = Some((code, distance));
+            }
+            let offset = target[axis] - point[axis];
+            let (near, far) = if offset < 0.0 { (&points[..mid], &points[mid + 1..]) } else { (&points[mid + 1..], &points[..mid]) };
+            search(near, target, 1 - axis, best);
+            // The other side can only be closer when the splitting line is
+            if best.is_none_or(|(_, closest)| offset * offset < closest) {
+                search(far, target, 1 - axis, best);
+            }
         }
-        if !found.info.region.is_empty() {
-            parts.push(found.info.region.trim().to_string());
+        let mut best = None;
+        search(&self.points, target, 0, &mut best);
+        best.map(|(code, _)| code)
+    }
+}
+
+/// Amersfoort, the origin of RD New: x and y in metres, latitude and longitude in degrees
+const RD_ORIGIN: (f64, f64) = (155_000.0, 463_000.0);
+const WGS84_ORIGIN: (f64, f64) = (52.155_174_40, 5.387_206_21);
+
+// Schreutelkamp and Strang van Hees' approximation, accurate to about a metre
+// on land: (power of dx, power of dy, arc seconds)
+const RD_TO_LAT: &[(i32, i32, f64)] = &[
+    (0, 1, 3235.65389), (2, 0, -32.58297), (0, 2, -0.24750), (2, 1, -0.84978), (0, 3, -0.06550), (2, 2, -0.01709),
+    (1, 0, -0.00738), (4, 0, 0.00530), (2, 3, -0.00039), (4, 1, 0.00033), (1, 1, -0.00012),
+];
+const RD_TO_LON: &[(i32, i32, f64)] = &[
+    (1, 0, 5260.52916), (1, 1, 105.94684), (1, 2, 2.45656), (3, 0, -0.81885), (1, 3, 0.05594), (3, 1, -0.05607),
+    (0, 1, 0.01199), (3, 2, -0.00256), (1, 4, 0.00128), (0, 2, 0.00022), (2, 0, -0.00022), (5, 0, 0.00026),
+];
+// (power of dlat, power of dlon, metres)
+const WGS84_TO_X: &[(i32, i32, f64)] = &[
+    (0, 1, 190094.945), (1, 1, -11832.228), (2, 1, -114.221), (0, 3, -32.391), (1, 0, -0.705), (3, 1, -2.340),
+    (1, 3, -0.608), (0, 2, -0.008), (2, 3, 0.148),
+];
+const WGS84_TO_Y: &[(i32, i32, f64)] = &[
+    (1, 0, 309056.544), (0, 2, 3638.893), (2, 0, 73.077), (1, 2, -157.984), (3, 0, 59.788), (0, 1, 0.433),
+    (2, 2, -6.439), (1, 1, -0.032), (0, 4, 0.092), (1, 4, -0.054),
+];
+
+fn polynomial(terms: &[(i32, i32, f64)], a: f64, b: f64) -> f64 {
+    terms.iter().map(|&(p, q, coefficient)| coefficient * a.powi(p) * b.powi(q)).sum()
+}
+
+/// RD New (EPSG:28992) `x` and `y` in metres, as used by BAG and the CBS
+/// squares, to WGS84 latitude and longitude.
+pub fn rd_to_wgs84(x: f64, y: f64) -> (f64, f64) {
+    let (dx, dy) = ((x - RD_ORIGIN.0) * 1e-5, (y - RD_ORIGIN.1) * 1e-5);
+    (
+        WGS84_ORIGIN.0 + polynomial(RD_TO_LAT, dx, dy) / 3600.0,
+        WGS84_ORIGIN.1 + polynomial(RD_TO_LON, dx, dy) / 3600.0,
+    )
+}
+
+/// WGS84 latitude and longitude to RD New `x` and `y` in metres.
+pub fn wgs84_to_rd(lat: f64, lon: f64) -> (f64, f64) {
+    let (dlat, dlon) = (0.36 * (lat - WGS84_ORIGIN.0), 0.36 * (lon - WGS84_ORIGIN.1));
+    (
+        RD_ORIGIN.0 + polynomial(WGS84_TO_X, dlat, dlon),
+        RD_ORIGIN.1 + polynomial(WGS84_TO_Y, dlat, dlon),
+    )
+}
+
+/// A coordinate pair from a data file as latitude and longitude: either
+/// already in the Netherlands in degrees, or RD `x` and `y` inside its grid.
+/// `None` for anything else.
+pub fn to_wgs84(a: f64, b: f64) -> Option<(f64, f64)> {
+    if (50.0..54.0).contains(&a) && (3.0..8.0).contains(&b) {
+        Some((a, b))
+    } else if (0.0..300_000.0).contains(&a) && (300_000.0..625_000.0).contains(&b) {
+        Some(rd_to_wgs84(a, b))
+    } else {
+        None
+    }
+}
+
+/// Coordinates by woonplaats name, in latitude and longitude or RD; empty
+/// when the file is missing.
+fn load_coordinates(path: &Path) -> HashMap<String, (f64, f64)> {
+    let mut coordinates = HashMap::new();
+    let Ok(file) = File::open(path) else {
+        return coordinates;
+    };
+    let mut rdr = csv::ReaderBuilder::new().from_reader(file);
+    for record in rdr.records().flatten() {
+        if record.len() < 6 {
+            continue;
+        }
+        let parsed = (record[4].trim().parse::<f64>(), record[5].trim().parse::<f64>());
+        if let Some((lat, lon)) = parsed.0.ok().zip(parsed.1.ok()).and_then(|(a, b)| to_wgs84(a, b)) {
+            // Trim quotes and whitespace from place name
+            let woonplaats = record[1].trim_matches('"').trim().to_string();
+            coordinates.insert(woonplaats, (lat, lon));
         }
-        
-        // Add coordinates if available (keep full precision)
-        if let (Some(lat), Some(lon)) = (found.info.latitude, found.info.longitude) {
-            parts.push(format!("[{}, {}]", lat, lon));
+    }
+    coordinates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_woonplaats_and_gemeente_levels() {
+        let data = Path::new(env!("CARGO_MANIFEST_DIR")).join("data");
+        let locations = LocationLookup::load(&data.join("Observations.csv"), &data.join("RegioSCodes.csv")).unwrap();
+
+        let aadorp = locations.find_location_by_text("A1 Dorpsstraat Aadorp").unwrap().info;
+        assert_eq!(aadorp.level(), PlaceLevel::Woonplaats);
+        assert_eq!(aadorp.woonplaats.as_ref().map(|w| w.code.as_str()), Some("WP1925"));
+        assert_eq!((aadorp.gemeente.code.as_str(), aadorp.province.code.as_str()), ("GM0141", "PV23"));
+        assert_eq!(aadorp.to_string(), "Aadorp, gemeente Almelo | Overijssel");
+        assert_eq!(locations.format("WP1925"), aadorp.to_string());
+
+        // A woonplaats named like its gemeente, not the gemeente's first woonplaats
+        let almelo = locations.find_location_by_text("Brand Almelo").unwrap().info;
+        assert_eq!(almelo.woonplaats.as_ref().map(|w| w.code.as_str()), Some("WP1924"));
+        assert_eq!(almelo.to_string(), "Almelo | Overijssel");
+
+        let montferland = locations.find_location_by_text("Montferland").unwrap();
+        assert_eq!(montferland.info.level(), PlaceLevel::Gemeente);
+        assert_eq!(montferland.info.place(), "Montferland");
+        assert_eq!(montferland.info.to_string(), "gemeente Montferland | Gelderland");
+
+        // A bit west of the centre of Aadorp
+        let (nearest, distance) = locations.nearest(52.3795, 6.6130).unwrap();
+        assert_eq!(nearest.place(), "Aadorp");
+        assert!(distance < 2.0);
+        // Brute force agrees, also far from any place
+        for (lat, lon) in [(52.09, 5.12), (53.45, 6.8), (51.3, 3.5), (50.0, 8.0)] {
+            let (nearest, distance) = locations.nearest(lat, lon).unwrap();
+            let closest = locations
+                .woonplaatsen
+                .values()
+                .filter_map(|info| Some(geofence::distance_km((lat, lon), (info.latitude?, info.longitude?))))
+                .fold(f64::MAX, f64::min);
+            assert!(distance - closest < 0.5, "{} at {} km, closest {} km", nearest, distance, closest);
         }
-        
-        parts.join(" | ")
+        assert!(LocationLookup::default().nearest(52.0, 5.0).is_none());
+    }
+
+    #[test]
+    fn test_rd_conversion() {
+        let close = |(a, b): (f64, f64), (c, d): (f64, f64), tolerance: f64| (a - c).abs() < tolerance && (b - d).abs() < tolerance;
+        assert_eq!(rd_to_wgs84(155_000.0, 463_000.0), WGS84_ORIGIN);
+        // Westertoren, Amsterdam
+        let westertoren = (120_700.723, 487_525.501);
+        assert!(close(rd_to_wgs84(westertoren.0, westertoren.1), (52.374_532_53, 4.883_525_59), 1e-5));
+        assert!(close(wgs84_to_rd(52.374_532_53, 4.883_525_59), westertoren, 1.0));
+        assert_eq!(to_wgs84(52.37, 4.88), Some((52.37, 4.88)));
+        assert!(close(to_wgs84(westertoren.0, westertoren.1).unwrap(), (52.3745, 4.8835), 1e-4));
+        assert_eq!(to_wgs84(48.85, 2.35), None);
     }
 }
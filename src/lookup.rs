@@ -1,26 +1,16 @@
 use anyhow::Result;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::fs::File;
-use std::path::Path;
-
-#[derive(Debug, Clone, Deserialize)]
-struct CapcodeCsv {
-    #[serde(rename = "capcode")]
-    capcode: String,
-    #[serde(rename = "dienst")]
-    service: String,
-    #[serde(rename = "regio")]
-    region: String,
-    #[serde(rename = "plaats")]
-    place: String,
-    #[serde(rename = "omschrijving")]
-    description: String,
-    #[serde(rename = "afkorting")]
-    short: String,
-}
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 
-#[derive(Debug, Clone)]
+use crate::capcodes;
+use crate::tokenize::tokenize;
+
+/// Region column value for capcodes that page across the whole country
+pub const NATIONWIDE_REGION: &str = "Landelijk";
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
 pub struct CapcodeInfo {
     pub code: String,
     pub service: String,
@@ -28,32 +18,144 @@ pub struct CapcodeInfo {
     pub place: String,
     pub description: String,
     pub short: String,
+    /// Name of the list the row came from: its file name without extension
+    pub source: Arc<str>,
+}
+
+/// Which row wins when capcodelist.csv, or the lists together, list a capcode
+/// more than once. The lists count in config order: `list`, then `extra`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CapcodePrecedence {
+    /// The row furthest down the file
+    #[default]
+    Last,
+    First,
+    /// The row with a description, then the one with the most filled-in fields; ties go to the last
+    Described,
+}
+
+/// `[capcodes]` section.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct CapcodeConfig {
+    /// Capcode list to use instead of capcodelist.csv in the data directory, in any supported layout
+    pub list: Option<PathBuf>,
+    /// More lists loaded next to it, such as a regional enthusiast list
+    pub extra: Vec<PathBuf>,
+    pub precedence: CapcodePrecedence,
+    /// File in the capcodelist.csv layout whose rows always win
+    pub overrides: Option<PathBuf>,
+}
+
+/// A capcode listed more than once with different details.
+#[derive(Debug, Clone)]
+pub struct CapcodeConflict {
+    /// Normalized code
+    pub code: String,
+    /// The differing rows, in file order
+    pub rows: Vec<Arc<CapcodeInfo>>,
+    /// The row lookups use; an override when one exists
+    pub kept: Arc<CapcodeInfo>,
+}
+
+/// An entry of the abbreviations file.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Abbreviation {
+    pub expansion: String,
+    /// Lowercased name of the `[section]` it is listed under
+    pub category: Option<Arc<str>>,
 }
 
 #[derive(Debug, Default)]
 pub struct Lookup {
-    capcodes: HashMap<String, CapcodeInfo>,
-    abbreviations: HashMap<String, String>,
-    abbreviations_no_space: HashMap<String, String>,
+    capcodes: HashMap<String, Arc<CapcodeInfo>>,
+    // Names of the lists loaded, overrides last
+    capcode_sources: Vec<Arc<str>>,
+    conflicts: Vec<CapcodeConflict>,
+    abbreviations: HashMap<String, Abbreviation>,
+    abbreviations_no_space: HashMap<String, Abbreviation>,
+    // Lowercased place -> the veiligheidsregio most of its capcodes belong to
+    place_regions: HashMap<String, String>,
 }
 
 impl Lookup {
     pub fn load(capcode_path: &Path, abbreviations_path: &Path) -> Result<Self> {
-        let capcodes = load_capcodes(capcode_path)?;
+        Self::load_any(capcode_path, abbreviations_path, &CapcodeConfig::default())
+    }
+
+    /// Loads a capcode list in any layout `capcodes::read` recognizes, resolving
+    /// duplicate capcodes as `config` says.
+    pub fn load_any(capcode_path: &Path, abbreviations_path: &Path, config: &CapcodeConfig) -> Result<Self> {
+        let lists: Vec<&Path> = std::iter::once(capcode_path).chain(config.extra.iter().map(PathBuf::as_path)).collect();
+        let (capcodes, capcode_sources, conflicts) = load_capcodes(&lists, config)?;
         let (abbreviations, abbreviations_no_space) = load_abbreviations(abbreviations_path)?;
+        let place_regions = place_regions(&capcodes);
         Ok(Lookup {
             capcodes,
+            capcode_sources,
+            conflicts,
             abbreviations,
             abbreviations_no_space,
+            place_regions,
         })
     }
 
-    pub fn resolve_capcode(&self, code: &str) -> Option<&CapcodeInfo> {
+    /// Like `load`, but a missing file leaves its part of the lookup empty.
+    pub fn load_available(capcode_path: &Path, abbreviations_path: &Path, config: &CapcodeConfig) -> Result<Self> {
+        let lists: Vec<&Path> = std::iter::once(capcode_path)
+            .chain(config.extra.iter().map(PathBuf::as_path))
+            .filter(|path| path.exists())
+            .collect();
+        let (capcodes, capcode_sources, conflicts) = load_capcodes(&lists, config)?;
+        let (abbreviations, abbreviations_no_space) = if abbreviations_path.exists() {
+            load_abbreviations(abbreviations_path)?
+        } else {
+            Default::default()
+        };
+        let place_regions = place_regions(&capcodes);
+        Ok(Lookup {
+            capcodes,
+            capcode_sources,
+            conflicts,
+            abbreviations,
+            abbreviations_no_space,
+            place_regions,
+        })
+    }
+
+    /// Names of the capcode lists loaded, in precedence order with the
+    /// overrides last.
+    pub fn capcode_sources(&self) -> &[Arc<str>] {
+        &self.capcode_sources
+    }
+
+    /// Capcodes listed more than once with different details, by code.
+    pub fn capcode_conflicts(&self) -> &[CapcodeConflict] {
+        &self.conflicts
+    }
+
+    /// Every capcode in the lists, in no particular order.
+    pub fn capcode_infos(&self) -> impl Iterator<Item = &CapcodeInfo> {
+        self.capcodes.values().map(|info| &**info)
+    }
+
+    pub fn resolve_capcode(&self, code: &str) -> Option<&Arc<CapcodeInfo>> {
         let key = normalize_code(code);
         self.capcodes.get(&key)
     }
 
+    /// Veiligheidsregio serving a place, judged by the capcodes listed for it.
+    pub fn region_for_place(&self, place: &str) -> Option<&str> {
+        self.place_regions.get(&place.trim().to_lowercase()).map(String::as_str)
+    }
+
     pub fn expand_abbreviation(&self, token: &str) -> Option<&String> {
+        self.abbreviation(token).map(|abbreviation| &abbreviation.expansion)
+    }
+
+    /// The entry for `token`, also when written without its spaces.
+    pub fn abbreviation(&self, token: &str) -> Option<&Abbreviation> {
         if let Some(hit) = self.abbreviations.get(token) {
             return Some(hit);
         }
@@ -63,37 +165,226 @@ impl Lookup {
         }
         self.abbreviations_no_space.get(&normalized)
     }
+
+    /// Abbreviations matching `query`, case-insensitively: exact matches first,
+    /// then abbreviations starting with it, then expansions containing it.
+    pub fn search_abbreviations(&self, query: &str) -> Vec<(&str, &Abbreviation)> {
+        let query = query.trim().to_lowercase();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits: Vec<(u8, &str, &Abbreviation)> = self
+            .abbreviations
+            .iter()
+            .filter_map(|(abbreviation, entry)| {
+                let lower = abbreviation.to_lowercase();
+                let rank = if lower == query {
+                    0
+                } else if lower.starts_with(&query) {
+                    1
+                } else if entry.expansion.to_lowercase().contains(&query) {
+                    2
+                } else {
+                    return None;
+                };
+                Some((rank, abbreviation.as_str(), entry))
+            })
+            .collect();
+        hits.sort_by(|a, b| (a.0, a.1).cmp(&(b.0, b.1)));
+        hits.into_iter().map(|(_, abbreviation, entry)| (abbreviation, entry)).collect()
+    }
+
+    /// Every abbreviation, in no particular order.
+    pub fn abbreviation_names(&self) -> impl Iterator<Item = &str> {
+        self.abbreviations.keys().map(String::as_str)
+    }
+
+    /// Categories of the abbreviations file, sorted.
+    pub fn abbreviation_categories(&self) -> Vec<&str> {
+        let categories: std::collections::BTreeSet<&str> =
+            self.abbreviations.values().filter_map(|a| a.category.as_deref()).collect();
+        categories.into_iter().collect()
+    }
+
+    /// Finds all known abbreviations in `content`, in order of appearance, without duplicates.
+    pub fn find_abbreviations(&self, content: &str) -> Vec<(String, &Abbreviation)> {
+        let mut seen = std::collections::HashSet::new();
+        let mut expansions = Vec::new();
+
+        let tokens: Vec<&str> = tokenize(content)
+            .into_iter()
+            .filter(|token| !token.is_punctuation())
+            .map(|token| token.text)
+            .collect();
+
+        for (i, &token) in tokens.iter().enumerate() {
+            // Direct match
+            if let Some(exp) = self.abbreviation(token) {
+                if seen.insert(token.to_string()) {
+                    expansions.push((token.to_string(), exp));
+                }
+            }
+
+            // Combined match with next token (e.g., "P" + "1" => "P1")
+            if let Some(next) = tokens.get(i + 1) {
+                let combined = format!("{}{}", token, next);
+                if let Some(exp) = self.abbreviation(&combined) {
+                    if seen.insert(combined.clone()) {
+                        expansions.push((combined, exp));
+                    }
+                }
+            }
+        }
+
+        expansions
+    }
 }
 
-fn load_capcodes(path: &Path) -> Result<HashMap<String, CapcodeInfo>> {
-    let file = File::open(path)?;
-    let mut rdr = csv::ReaderBuilder::new()
-        .delimiter(b';')
-        .has_headers(false)
-        .from_reader(file);
+type Capcodes = HashMap<String, Arc<CapcodeInfo>>;
 
-    let mut map = HashMap::new();
-    for result in rdr.records() {
-        let record = result?;
-        if record.len() < 6 {
-            continue;
+fn load_capcodes(lists: &[&Path], config: &CapcodeConfig) -> Result<(Capcodes, Vec<Arc<str>>, Vec<CapcodeConflict>)> {
+    let mut rows: HashMap<String, Vec<Arc<CapcodeInfo>>> = HashMap::new();
+    let mut sources = Vec::new();
+    for path in lists {
+        let source = list_name(path);
+        for info in capcodes::read(path)? {
+            let key = normalize_code(&info.code);
+            rows.entry(key).or_default().push(Arc::new(CapcodeInfo {
+                source: source.clone(),
+                ..info
+            }));
         }
-        let info = CapcodeInfo {
-            code: record[0].trim_matches('"').to_string(),
-            service: record[1].trim_matches('"').to_string(),
-            region: record[2].trim_matches('"').to_string(),
-            place: record[3].trim_matches('"').to_string(),
-            description: record[4].trim_matches('"').to_string(),
-            short: record[5].trim_matches('"').to_string(),
+        sources.push(source);
+    }
+    let overrides = match &config.overrides {
+        Some(path) => {
+            let source = list_name(path);
+            sources.push(source.clone());
+            capcodes::read(path)?
+                .into_iter()
+                .map(|info| {
+                    let key = normalize_code(&info.code);
+                    (key, Arc::new(CapcodeInfo { source: source.clone(), ..info }))
+                })
+                .collect()
+        }
+        None => HashMap::new(),
+    };
+
+    let mut map = HashMap::with_capacity(rows.len());
+    let mut conflicts = Vec::new();
+    for (key, rows) in rows {
+        let kept = match overrides.get(&key) {
+            Some(info) => info.clone(),
+            None => pick(&rows, config.precedence).clone(),
         };
-        let key = normalize_code(&info.code);
-        map.insert(key, info);
+        let mut distinct: Vec<Arc<CapcodeInfo>> = Vec::new();
+        for row in rows {
+            if !distinct.iter().any(|seen| seen.same_details(&row)) {
+                distinct.push(row);
+            }
+        }
+        if distinct.len() > 1 {
+            conflicts.push(CapcodeConflict {
+                code: key.clone(),
+                rows: distinct,
+                kept: kept.clone(),
+            });
+        }
+        map.insert(key, kept);
+    }
+    // Overrides may also add capcodes the list lacks
+    for (key, info) in overrides {
+        map.entry(key).or_insert(info);
+    }
+    conflicts.sort_by(|a, b| (a.code.len(), &a.code).cmp(&(b.code.len(), &b.code)));
+    Ok((map, sources, conflicts))
+}
+
+/// `regional` for `lists/regional.csv`.
+fn list_name(path: &Path) -> Arc<str> {
+    path.file_stem().unwrap_or(path.as_os_str()).to_string_lossy().into()
+}
+
+fn pick(rows: &[Arc<CapcodeInfo>], precedence: CapcodePrecedence) -> &Arc<CapcodeInfo> {
+    match precedence {
+        CapcodePrecedence::Last => &rows[rows.len() - 1],
+        CapcodePrecedence::First => &rows[0],
+        CapcodePrecedence::Described => rows
+            .iter()
+            .max_by_key(|info| (!info.description.is_empty(), info.filled_fields()))
+            .expect("at least one row per capcode"),
     }
-    Ok(map)
 }
 
-fn normalize_code(code: &str) -> String {
-    let trimmed = code.trim_start_matches('0');
+impl CapcodeInfo {
+    fn filled_fields(&self) -> usize {
+        [&self.service, &self.region, &self.place, &self.description, &self.short]
+            .iter()
+            .filter(|field| !field.is_empty())
+            .count()
+    }
+
+    /// Equal apart from how the code is padded and which list it is in.
+    fn same_details(&self, other: &CapcodeInfo) -> bool {
+        CapcodeInfo {
+            code: other.code.clone(),
+            source: other.source.clone(),
+            ..self.clone()
+        } == *other
+    }
+}
+
+fn place_regions(capcodes: &HashMap<String, Arc<CapcodeInfo>>) -> HashMap<String, String> {
+    let mut counts: HashMap<String, HashMap<&str, usize>> = HashMap::new();
+    for info in capcodes.values() {
+        if info.place.is_empty() || info.region.is_empty() || info.region == NATIONWIDE_REGION {
+            continue;
+        }
+        *counts
+            .entry(info.place.to_lowercase())
+            .or_default()
+            .entry(info.region.as_str())
+            .or_default() += 1;
+    }
+    counts
+        .into_iter()
+        .filter_map(|(place, regions)| {
+            // Ties go to the alphabetically first region so the result doesn't depend on hash order
+            let (region, _) = regions
+                .into_iter()
+                .max_by(|(a, x), (b, y)| x.cmp(y).then(b.cmp(a)))?;
+            Some((place, region.to_string()))
+        })
+        .collect()
+}
+
+/// Canonical form of a capcode for comparisons: decimal without leading zeros.
+///
+/// Feeds and lists write the same code in several ways: 7 or 9 digits
+/// (`1420028`, `001420028`), 10-digit FLEX long addresses, hex (`0x15AAFC`),
+/// and POCSAG RICs with a function suffix (`RIC 1420028`, `1420028A`,
+/// `1420028/0`). Anything else is only trimmed.
+pub fn normalize_code(code: &str) -> String {
+    let mut code = code.trim();
+    if code.len() > 3 && code.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("RIC")) {
+        code = code[3..].trim_start_matches([' ', ':']);
+    }
+    if let Some(hex) = code.strip_prefix("0x").or_else(|| code.strip_prefix("0X")) {
+        return match u64::from_str_radix(hex, 16) {
+            Ok(value) => value.to_string(),
+            Err(_) => code.to_string(),
+        };
+    }
+    // POCSAG function bits: A-D or /0-/3
+    let digits = match code.as_bytes() {
+        [rest @ .., b'/', b'0'..=b'3'] | [rest @ .., b'A'..=b'D' | b'a'..=b'd'] => &code[..rest.len()],
+        _ => code,
+    };
+    if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return code.to_string();
+    }
+    let trimmed = digits.trim_start_matches('0');
     if trimmed.is_empty() {
         "0".to_string()
     } else {
@@ -101,25 +392,254 @@ fn normalize_code(code: &str) -> String {
     }
 }
 
-fn load_abbreviations(path: &Path) -> Result<(HashMap<String, String>, HashMap<String, String>)> {
+/// FLEX temporary addresses, which the network uses for group calls
+const GROUP_ADDRESSES: std::ops::RangeInclusive<u64> = 2_029_568..=2_029_583;
+
+/// First capcode of the FLEX long (two-word) addresses
+const FIRST_LONG_ADDRESS: u64 = 2_101_249;
+
+/// What kind of FLEX address a capcode is.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    /// One pager or a fixed group of pagers; also anything that is not a number
+    Short,
+    /// A temporary address for a group call: the network first tells each
+    /// member capcode to listen to it, then sends the message once. The other
+    /// capcodes of the message are the members.
+    Group,
+    /// A long address, as some networks give to their own pagers
+    Long,
+}
+
+impl AddressKind {
+    pub fn of(code: &str) -> Self {
+        match normalize_code(code).parse::<u64>() {
+            Ok(number) if GROUP_ADDRESSES.contains(&number) => AddressKind::Group,
+            Ok(number) if number >= FIRST_LONG_ADDRESS => AddressKind::Long,
+            _ => AddressKind::Short,
+        }
+    }
+}
+
+type Abbreviations = HashMap<String, Abbreviation>;
+
+fn load_abbreviations(path: &Path) -> Result<(Abbreviations, Abbreviations)> {
+    let content = std::fs::read_to_string(path)?;
     let mut map = HashMap::new();
     let mut map_no_space = HashMap::new();
-    let content = std::fs::read_to_string(path)?;
-    for line in content.lines() {
-        if line.trim().is_empty() {
+    for entry in parse_abbreviations(&content).entries {
+        let normalized = entry.abbreviation.replace(' ', "");
+        if !normalized.is_empty() {
+            map_no_space.insert(normalized, entry.value.clone());
+        }
+        map.insert(entry.abbreviation, entry.value);
+    }
+    Ok((map, map_no_space))
+}
+
+/// One `ABBR: expansion` entry with the line it starts on.
+pub(crate) struct AbbreviationEntry {
+    pub line: usize,
+    pub abbreviation: String,
+    pub value: Abbreviation,
+}
+
+pub(crate) struct ParsedAbbreviations {
+    pub entries: Vec<AbbreviationEntry>,
+    /// Lines that are neither an entry, a continuation, a section nor a comment
+    pub malformed: Vec<usize>,
+}
+
+/// Parses the abbreviations file. Besides `ABBR: expansion` lines it has
+/// `# comments`, `[category]` sections that apply to the entries below them,
+/// and indented lines continuing the previous expansion.
+pub(crate) fn parse_abbreviations(content: &str) -> ParsedAbbreviations {
+    let mut parsed = ParsedAbbreviations {
+        entries: Vec::new(),
+        malformed: Vec::new(),
+    };
+    let mut category: Option<Arc<str>> = None;
+    // Continuations only follow an entry, not a blank line or a section
+    let mut open = false;
+    for (i, line) in content.lines().enumerate() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            open = false;
             continue;
         }
-        if let Some((abbr, rest)) = line.split_once(':') {
-            let key = abbr.trim();
-            let value = rest.trim();
-            if !key.is_empty() && !value.is_empty() {
-                map.insert(key.to_string(), value.to_string());
-                let normalized = key.replace(' ', "");
-                if !normalized.is_empty() {
-                    map_no_space.insert(normalized, value.to_string());
-                }
+        if trimmed.starts_with('#') {
+            continue;
+        }
+        if line.starts_with([' ', '\t']) && open {
+            if let Some(entry) = parsed.entries.last_mut() {
+                entry.value.expansion.push(' ');
+                entry.value.expansion.push_str(trimmed);
+            }
+            continue;
+        }
+        if let Some(name) = trimmed.strip_prefix('[').and_then(|rest| rest.strip_suffix(']')) {
+            let name = name.trim().to_lowercase();
+            category = (!name.is_empty()).then(|| name.into());
+            open = false;
+            continue;
+        }
+        match line.split_once(':') {
+            Some((abbr, rest)) if !abbr.trim().is_empty() && !rest.trim().is_empty() => {
+                parsed.entries.push(AbbreviationEntry {
+                    line: i + 1,
+                    abbreviation: abbr.trim().to_string(),
+                    value: Abbreviation {
+                        expansion: rest.trim().to_string(),
+                        category: category.clone(),
+                    },
+                });
+                open = true;
+            }
+            _ => {
+                parsed.malformed.push(i + 1);
+                open = false;
             }
         }
     }
-    Ok((map, map_no_space))
+    parsed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_code() {
+        for code in ["1420028", "001420028", "0001420028", " 1420028 ", "0x15AAFC", "0X15aafc", "RIC 1420028", "ric:1420028", "1420028A", "1420028d", "1420028/2"] {
+            assert_eq!(normalize_code(code), "1420028", "{}", code);
+        }
+        assert_eq!(normalize_code("2101249000"), "2101249000");
+        assert_eq!(normalize_code("000000000"), "0");
+        assert_eq!(normalize_code("0xZZ"), "0xZZ");
+        assert_eq!(normalize_code("ABCD"), "ABCD");
+        assert_eq!(normalize_code("1420028/7"), "1420028/7");
+        // Garbled decodes are no capcode but must not panic
+        assert_eq!(normalize_code("abé1"), "abé1");
+        assert_eq!(normalize_code("Ré 1420028"), "Ré 1420028");
+    }
+
+    #[test]
+    fn test_address_kind() {
+        assert_eq!(AddressKind::of("001420028"), AddressKind::Short);
+        assert_eq!(AddressKind::of("002029568"), AddressKind::Group);
+        assert_eq!(AddressKind::of("2029583"), AddressKind::Group);
+        assert_eq!(AddressKind::of("002029584"), AddressKind::Short);
+        assert_eq!(AddressKind::of("2101249000"), AddressKind::Long);
+        assert_eq!(AddressKind::of("ABCD"), AddressKind::Short);
+    }
+
+    #[test]
+    fn test_abbreviation_sections() {
+        let parsed = parse_abbreviations(
+            "BR: Brand\n\
+             # Ambulance\n\
+             [Medical]\n\
+             REA: Reanimatie\n\
+             MMT: Mobiel Medisch Team,\n  \tmet traumaheli\n\
+             zonder uitleg\n\
+             \n  losse regel\n\
+             [politie]\n\
+             AT: Arrestatieteam\n",
+        );
+        let entries: Vec<(usize, &str, &str, Option<&str>)> = parsed
+            .entries
+            .iter()
+            .map(|e| (e.line, e.abbreviation.as_str(), e.value.expansion.as_str(), e.value.category.as_deref()))
+            .collect();
+        assert_eq!(
+            entries,
+            [
+                (1, "BR", "Brand", None),
+                (4, "REA", "Reanimatie", Some("medical")),
+                (5, "MMT", "Mobiel Medisch Team, met traumaheli", Some("medical")),
+                (11, "AT", "Arrestatieteam", Some("politie")),
+            ]
+        );
+        assert_eq!(parsed.malformed, [7, 9]);
+
+        let (abbreviations, abbreviations_no_space) = parsed
+            .entries
+            .into_iter()
+            .map(|e| ((e.abbreviation.clone(), e.value.clone()), (e.abbreviation, e.value)))
+            .unzip();
+        let lookup = Lookup {
+            abbreviations,
+            abbreviations_no_space,
+            ..Default::default()
+        };
+        assert_eq!(lookup.abbreviation_categories(), ["medical", "politie"]);
+        let found: Vec<&str> = lookup.search_abbreviations("a").iter().map(|(a, _)| *a).collect();
+        assert_eq!(found, ["AT", "BR", "MMT", "REA"]);
+        let found: Vec<&str> = lookup.search_abbreviations("TRAUMA").iter().map(|(a, _)| *a).collect();
+        assert_eq!(found, ["MMT"]);
+    }
+
+    #[test]
+    fn test_capcode_conflicts() {
+        let dir = std::env::temp_dir().join(format!("p2000_lookup_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = dir.join("capcodelist.csv");
+        std::fs::write(
+            &list,
+            "\"1420016\";\"Brandweer\";\"Rotterdam-Rijnmond\";\"Rotterdam\";\"Bevelvoerders\";\"\"\n\
+             \"0100000\";\"Brandweer\";\"Amsterdam-Amstelland\";\"\";\"Proefalarm\";\"\"\n\
+             \"100000\";\"Brandweer\";\"Amsterdam-Amstelland\";\"\";\"Proefalarm\";\"\"\n\
+             \"01420016\";\"Brandweer\";\"Rotterdam-Rijnmond\";\"\";\"\";\"\"\n",
+        )
+        .unwrap();
+        let abbreviations = dir.join("abbrevations.txt");
+        std::fs::write(&abbreviations, "").unwrap();
+        let load = |config: CapcodeConfig| Lookup::load_any(&list, &abbreviations, &config).unwrap();
+
+        let last = load(CapcodeConfig::default());
+        let conflicts = last.capcode_conflicts();
+        assert_eq!(conflicts.len(), 1, "identical rows are not a conflict");
+        assert_eq!(conflicts[0].code, "1420016");
+        assert_eq!(conflicts[0].rows.len(), 2);
+        assert_eq!(last.resolve_capcode("1420016").unwrap().description, "");
+
+        let described = load(CapcodeConfig {
+            precedence: CapcodePrecedence::Described,
+            ..Default::default()
+        });
+        assert_eq!(described.resolve_capcode("1420016").unwrap().description, "Bevelvoerders");
+
+        let overrides = dir.join("overrides.csv");
+        std::fs::write(&overrides, "\"1420016\";\"Brandweer\";\"Rotterdam-Rijnmond\";\"Rotterdam\";\"Eigen\";\"\"\n").unwrap();
+        let overridden = load(CapcodeConfig {
+            overrides: Some(overrides),
+            ..Default::default()
+        });
+        assert_eq!(overridden.resolve_capcode("1420016").unwrap().description, "Eigen");
+        assert_eq!(overridden.capcode_conflicts()[0].kept.description, "Eigen");
+        assert_eq!(&*overridden.resolve_capcode("100000").unwrap().source, "capcodelist");
+
+        // A second list: later lists win under "last", and each row knows its list
+        let regional = dir.join("regional.csv");
+        std::fs::write(&regional, "\"1420016\";\"Brandweer\";\"Rotterdam-Rijnmond\";\"Rotterdam\";\"TS 17-4131\";\"\"\n\
+             \"1420099\";\"Ambulance\";\"Rotterdam-Rijnmond\";\"Rotterdam\";\"Post Rotterdam\";\"\"\n").unwrap();
+        let extra = vec![regional, dir.join("missing.csv")];
+        let both = Lookup::load_available(&list, &abbreviations, &CapcodeConfig {
+            extra: extra.clone(),
+            ..Default::default()
+        })
+        .unwrap();
+        let info = both.resolve_capcode("1420016").unwrap();
+        assert_eq!((info.description.as_str(), &*info.source), ("TS 17-4131", "regional"));
+        assert_eq!(&*both.resolve_capcode("1420099").unwrap().source, "regional");
+        assert_eq!(both.capcode_sources().len(), 2, "missing lists are skipped");
+        let first = Lookup::load_available(&list, &abbreviations, &CapcodeConfig {
+            extra,
+            precedence: CapcodePrecedence::First,
+            ..Default::default()
+        })
+        .unwrap();
+        assert_eq!(&*first.resolve_capcode("1420016").unwrap().source, "capcodelist");
+        std::fs::remove_dir_all(dir).ok();
+    }
 }
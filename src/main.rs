@@ -1,34 +1,232 @@
-mod location;
-mod lookup;
-mod parser;
-mod reader;
-mod tui;
-
 use anyhow::Result;
-use std::path::Path;
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
 use std::env;
-use crate::lookup::Lookup;
-use crate::location::LocationLookup;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use p2000_rust::address;
+use p2000_rust::archive::Archive;
+use p2000_rust::audio::AudioPlayer;
+use p2000_rust::calendar::{self, Calendar};
+use p2000_rust::config::Config;
+use p2000_rust::daemon;
+use p2000_rust::data::DataFiles;
+use p2000_rust::enrich;
+use p2000_rust::export;
+use p2000_rust::gazetteer;
+use p2000_rust::feedback::{Feedback, CORRECTIONS_FILE};
+use p2000_rust::lookup::Lookup;
+use p2000_rust::location::LocationLookup;
+use p2000_rust::pager::Pager;
+use p2000_rust::parser::{P2000Message, Parser, TimeSource};
+use p2000_rust::plugin::{self, BuildContext, Registry, Source};
+use p2000_rust::query::{self, QueryArgs};
+use p2000_rust::rules::{self, Rule};
+use p2000_rust::sampler::Sampler;
+use p2000_rust::script::{ScriptConfig, ScriptHook};
+use p2000_rust::recording::{self, RecordingKind};
+use p2000_rust::remote::RemoteSource;
+use p2000_rust::sdr::{ProcessLog, SdrConfig};
+use p2000_rust::translate::Translator;
+use p2000_rust::view::View;
+use p2000_rust::weather::WeatherClient;
+use p2000_rust::{reader, tui};
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
+    let mut args: Vec<String> = env::args().skip(1).collect();
+
+    // Optional --config <path>, anywhere on the command line
+    let config_path = match args.iter().position(|a| a == "--config") {
+        Some(i) if i + 1 < args.len() => {
+            let path = PathBuf::from(args.remove(i + 1));
+            args.remove(i);
+            Some(path)
+        }
+        Some(_) => anyhow::bail!("--config requires a path"),
+        None => None,
+    };
+    let config = Config::load_or_default(config_path.as_deref())?;
+
+    // Optional --view <link>: starts the TUI in a view shared with 'l'
+    let view = match args.iter().position(|a| a == "--view") {
+        Some(i) if i + 1 < args.len() => {
+            let view = View::parse(&args.remove(i + 1))?;
+            args.remove(i);
+            Some(view)
+        }
+        Some(_) => anyhow::bail!("--view requires a link"),
+        None => None,
+    };
+
+    // Optional --kiosk: read-only wallboard layout
+    let kiosk = match args.iter().position(|a| a == "--kiosk") {
+        Some(i) => {
+            args.remove(i);
+            true
+        }
+        None => false,
+    };
+
+    // The archive query needs no lookup tables
+    if args.first().map(String::as_str) == Some("query") {
+        return run_query(&args[1..], &config);
+    }
+
+    if args.first().map(String::as_str) == Some("addresses") {
+        return address_report(&args[1..], &config);
+    }
+
+    if args.first().map(String::as_str) == Some("days") {
+        return day_report(&args[1..], &config);
+    }
+
+    if args.first().map(String::as_str) == Some("verify") {
+        return verify_archive(&args[1..], &config);
+    }
+
+    if args.first().map(String::as_str) == Some("check-data") {
+        return check_data(&config);
+    }
+
+    if args.first().map(String::as_str) == Some("build-gazetteer") {
+        let files = DataFiles::from_config(&config);
+        let count = gazetteer::build(&files)?;
+        println!("Wrote {} place names to {}", count, files.gazetteer.display());
+        return Ok(());
+    }
+
+    // Missing data files only cost the enrichment; the TUI shows a banner and can load them later
+    let data_files = DataFiles::from_config(&config);
+    if let Some(missing) = data_files.missing_summary() {
+        eprintln!(
+            "Warning: missing data files in {}: {}; capcodes and places will not be resolved",
+            data_files.dir().display(),
+            missing
+        );
+    }
+    let (lookup, location_lookup) = data_files.load()?;
+
+    if args.first().map(String::as_str) == Some("daemon") {
+        eprintln!("Running headless...");
+        let ctx = BuildContext {
+            config: &config,
+            lookup: Arc::new(lookup),
+            location_lookup: Arc::new(location_lookup),
+            process_log: ProcessLog::echoing(),
+            rules: Arc::new(RwLock::new(config.rules.clone())),
+        };
+        return daemon::run(&Registry::with_builtins(), &ctx).await;
+    }
 
-    let capcode_path = Path::new("data/capcodelist.csv");
-    let abbreviations_path = Path::new("data/abbrevations.txt");
-    let observations_path = Path::new("data/Observations.csv");
-    let lookup = Lookup::load(capcode_path, abbreviations_path)?;
-    let regios_codes_path = Path::new("data/RegioSCodes.csv");
-    let location_lookup = LocationLookup::load(observations_path, regios_codes_path)?;
+    if args.first().map(String::as_str) == Some("rules") {
+        let ctx = BuildContext {
+            config: &config,
+            lookup: Arc::new(lookup),
+            location_lookup: Arc::new(location_lookup),
+            process_log: ProcessLog::new(),
+            rules: Arc::new(RwLock::new(config.rules.clone())),
+        };
+        return test_rules(&args[1..], &ctx).await;
+    }
 
-    let messages = if args.len() > 1 {
+    if args.first().map(String::as_str) == Some("export") {
+        let redact = match args.iter().position(|a| a == "--redact") {
+            Some(i) => {
+                args.remove(i);
+                true
+            }
+            None => false,
+        };
+        let (Some(format), Some(input), Some(output)) = (args.get(1), args.get(2), args.get(3))
+        else {
+            anyhow::bail!("Usage: p2000-rust export cap [--redact] <input> <output-dir>");
+        };
+        if format != "cap" {
+            anyhow::bail!("Unknown export format '{}'", format);
+        }
+        let messages = reader::read_from_file(Path::new(input), &plugin::source_parser(&config)?).await?;
+        let written = export::write_cap(
+            &messages,
+            &lookup,
+            &location_lookup,
+            Path::new(output),
+            export::DEFAULT_CAP_SENDER,
+            redact,
+        )?;
+        eprintln!("Wrote {} CAP alerts to {}", written, output);
+        return Ok(());
+    }
+
+    // Live TUI: messages from the configured source appear as they are decoded
+    if args.first().map(String::as_str) == Some("live") {
+        let lookup = Arc::new(lookup);
+        let location_lookup = Arc::new(location_lookup);
+        let ctx = BuildContext {
+            config: &config,
+            lookup: lookup.clone(),
+            location_lookup: location_lookup.clone(),
+            process_log: ProcessLog::new(),
+            rules: Arc::new(RwLock::new(config.rules.clone())),
+        };
+        // --remote <url> views another instance's server instead of the configured source
+        let (source, parser): (Box<dyn Source>, Parser) = match args.iter().position(|a| a == "--remote") {
+            Some(i) => {
+                let url = args.get(i + 1).ok_or_else(|| anyhow::anyhow!("--remote requires a URL"))?;
+                let source = RemoteSource::new(url, ctx.process_log.clone())
+                    .with_token(plugin::remote_token(&ctx))
+                    .with_query(plugin::remote_query(&ctx)?);
+                (Box::new(source), Parser::new())
+            }
+            None => {
+                let registry = Registry::with_builtins();
+                (registry.build_source(&ctx)?, plugin::source_parser(&config)?)
+            }
+        };
+        let lines = reader::spawn_source(source);
+        let app = tui_app(&config, Vec::new(), lookup, location_lookup)?
+            .with_process_log(ctx.process_log.clone())
+            .with_view(view)
+            .with_kiosk(kiosk);
+        return tui::run_tui(app, Some((lines, parser)))
+            .await
+            .map_err(|e| anyhow::anyhow!("{}", e));
+    }
+
+    let gui = args.first().map(String::as_str) == Some("gui");
+    if gui {
+        args.remove(0);
+        if cfg!(not(feature = "gui")) {
+            anyhow::bail!("This build has no GUI; rebuild with --features gui");
+        }
+    }
+
+    let mut messages = if let Some(file) = args.first().filter(|f| RecordingKind::of(Path::new(f)).is_some()) {
+        // Captured audio or IQ: decode it first
+        eprintln!("Decoding {}...", file);
+        let sdr_config = SdrConfig::from_options(config.plugins.get("sdr"))?;
+        let parser = Parser::new();
+        recording::decode(Path::new(file), &sdr_config, &ProcessLog::echoing())?
+            .iter()
+            .filter_map(|line| parser.parse_line(line).ok())
+            .collect()
+    } else if let Some(file) = args.first() {
         // Read from file
-        let path = Path::new(&args[1]);
-        reader::read_from_file(path).await?
+        let path = Path::new(file);
+        reader::read_from_file(path, &plugin::source_parser(&config)?).await?
     } else {
         // Read from stdin
         eprintln!("Reading from stdin... (or provide a file path as argument)");
-        reader::read_from_stdin().await?
+        reader::read_from_stdin(&plugin::source_parser(&config)?).await?
+    };
+
+    for msg in &mut messages {
+        config.channels.apply(msg);
+    }
+
+    let messages = match &config.script {
+        Some(script_config) => run_script(messages, script_config, &lookup, &location_lookup)?,
+        None => messages,
     };
 
     if messages.is_empty() {
@@ -37,9 +235,253 @@ async fn main() -> Result<()> {
     }
 
     eprintln!("Loaded {} messages", messages.len());
-    tui::run_tui(messages, lookup, location_lookup)
+    #[cfg(feature = "gui")]
+    if gui {
+        let translator = config.translation.as_ref().map(Translator::from_config).transpose()?;
+        return p2000_rust::gui::run(messages, lookup, location_lookup, translator, config.severity.clone());
+    }
+    let app = tui_app(&config, messages, Arc::new(lookup), Arc::new(location_lookup))?
+        .with_view(view)
+        .with_kiosk(kiosk);
+    tui::run_tui(app, None)
         .await
         .map_err(|e| anyhow::anyhow!("{}", e))?;
 
     Ok(())
 }
+
+/// `p2000-rust query`: prints archived messages (needs the `archive` feature).
+fn run_query(args: &[String], config: &Config) -> Result<()> {
+    let query_args = QueryArgs::parse(args)?;
+    let archive = open_archive(&query_args, config)?;
+    let rows = archive.query(&query_args.filter)?;
+    query::print(&rows, query_args.format, &mut std::io::stdout().lock())
+}
+
+/// The archive given with `--archive`, else the configured one.
+fn open_archive(query_args: &QueryArgs, config: &Config) -> Result<Archive> {
+    let archive_path = query_args
+        .archive
+        .clone()
+        .or_else(|| config.archive.as_ref().map(|a| a.path.clone()))
+        .ok_or_else(|| anyhow::anyhow!("No archive configured; pass --archive <db>"))?;
+    Archive::open(&archive_path)
+}
+
+/// `p2000-rust addresses`: archived addresses that keep coming back.
+fn address_report(args: &[String], config: &Config) -> Result<()> {
+    let mut args = args.to_vec();
+    let min_count = match args.iter().position(|a| a == "--min") {
+        Some(i) if i + 1 < args.len() => {
+            let min = args.remove(i + 1).parse()?;
+            args.remove(i);
+            min
+        }
+        Some(_) => anyhow::bail!("--min requires a count\n{}", address::USAGE),
+        None => 2,
+    };
+    let mut query_args = QueryArgs::parse(&args)?;
+    if query_args.filter.since.is_none() {
+        query_args.filter.since = Some(query::parse_time("30d")?);
+    }
+    // The limit applies to the addresses, not to the messages counted
+    let limit = query_args.filter.limit.take();
+    let rows = open_archive(&query_args, config)?.query(&query_args.filter)?;
+    let mut counts = address::count_addresses(
+        rows.iter().map(|row| (row.timestamp, row.content.as_str(), row.place.as_deref())),
+        min_count,
+    );
+    counts.truncate(limit.unwrap_or(usize::MAX));
+    address::print(&counts, query_args.format, &mut std::io::stdout().lock())
+}
+
+/// `p2000-rust rules test`: what the configured rules would have pushed for past messages.
+async fn test_rules(args: &[String], ctx: &BuildContext<'_>) -> Result<()> {
+    let value = |flag: &str| args.iter().position(|a| a == flag).map(|i| args.get(i + 1));
+    if args.first().map(String::as_str) != Some("test") {
+        anyhow::bail!("{}", rules::USAGE);
+    }
+    let Some(Some(input)) = value("--input") else {
+        anyhow::bail!("--input requires a file\n{}", rules::USAGE);
+    };
+    let examples = match value("--examples") {
+        Some(Some(count)) => count.parse()?,
+        Some(None) => anyhow::bail!("--examples requires a count\n{}", rules::USAGE),
+        None => 3,
+    };
+    if ctx.config.rules.is_empty() {
+        eprintln!("Warning: no [[rules]] configured");
+    }
+    // Lookups over the network would take ages over an archive, and rules don't use them
+    let mut enrichers = Registry::with_builtins().build_enrichers(ctx)?;
+    enrichers.retain(|enricher| !matches!(enricher.name(), "translate" | "weather"));
+    let mut messages = reader::read_from_file(Path::new(input), &plugin::source_parser(ctx.config)?).await?;
+    messages.sort_by_key(|msg| msg.time(TimeSource::Received));
+    let reports = rules::dry_run(
+        &ctx.config.rules,
+        messages.iter().map(|msg| (msg, plugin::run_enrichers(&enrichers, msg))),
+        examples,
+    );
+    rules::print_reports(&reports, messages.len(), &mut std::io::stdout().lock())
+}
+
+/// `p2000-rust days`: each holiday and event in the archive against an ordinary day.
+fn day_report(args: &[String], config: &Config) -> Result<()> {
+    let calendar = Calendar::new(&config.calendar)?;
+    let mut query_args = QueryArgs::parse(args)?;
+    if query_args.filter.since.is_none() {
+        query_args.filter.since = Some(query::parse_time("365d")?);
+    }
+    // The limit applies to the days, not to the messages counted
+    let limit = query_args.filter.limit.take();
+    let rows = open_archive(&query_args, config)?.query(&query_args.filter)?;
+    let mut counts = calendar::count_days(&calendar, rows.iter().map(|row| (row.timestamp, row.place.as_deref())));
+    counts.truncate(limit.unwrap_or(usize::MAX));
+    calendar::print(&counts, query_args.format, &mut std::io::stdout().lock())
+}
+
+/// `p2000-rust verify`: checks archived messages against their hashes and the segment chain.
+fn verify_archive(args: &[String], config: &Config) -> Result<()> {
+    let archive_path = match args {
+        [] => config.archive.as_ref().map(|a| a.path.clone()),
+        [flag, path] if flag == "--archive" => Some(PathBuf::from(path)),
+        _ => anyhow::bail!("Usage: p2000-rust verify [--archive <db>]"),
+    }
+    .ok_or_else(|| anyhow::anyhow!("No archive configured; pass --archive <db>"))?;
+    let report = Archive::open(&archive_path)?.verify()?;
+    print!("{}", report);
+    if !report.is_ok() {
+        anyhow::bail!("{} has been modified", archive_path.display());
+    }
+    Ok(())
+}
+
+/// `p2000-rust check-data`: reports rows the data file loaders would skip or misread.
+fn check_data(config: &Config) -> Result<()> {
+    let data_files = DataFiles::from_config(config);
+    let reports = data_files.check();
+    for report in &reports {
+        print!("{}", report);
+    }
+    let (lookup, _) = data_files.load()?;
+    let conflicts = lookup.capcode_conflicts();
+    if !conflicts.is_empty() {
+        println!(
+            "{} capcodes listed with different details (precedence: {}):",
+            conflicts.len(),
+            format!("{:?}", config.capcodes.precedence).to_lowercase()
+        );
+        for conflict in conflicts {
+            let kept = &conflict.kept;
+            let sources: BTreeSet<&str> = conflict.rows.iter().map(|row| &*row.source).collect();
+            println!(
+                "  {}: {} versions ({}), using {} / {} / {} / {} from {}",
+                conflict.code,
+                conflict.rows.len(),
+                sources.into_iter().collect::<Vec<_>>().join(", "),
+                kept.service,
+                kept.region,
+                kept.place,
+                kept.description,
+                kept.source
+            );
+        }
+    }
+    let failed = reports.iter().filter(|report| !report.is_ok()).count();
+    if failed > 0 {
+        anyhow::bail!("{} data file(s) need attention", failed);
+    }
+    Ok(())
+}
+
+/// Lets the `[script]` hook filter and rewrite loaded messages before display
+/// (needs the `scripting` feature); alert actions only fire in daemon mode.
+fn run_script(
+    messages: Vec<P2000Message>,
+    config: &ScriptConfig,
+    lookup: &Lookup,
+    location_lookup: &LocationLookup,
+) -> Result<Vec<P2000Message>> {
+    let script = ScriptHook::load(config)?;
+    Ok(messages
+        .into_iter()
+        .filter_map(|mut msg| {
+            let enrichment = enrich::enrich(&msg, lookup, location_lookup);
+            match script.run(&mut msg, &enrichment) {
+                Ok(outcome) if !outcome.keep => None,
+                Ok(_) => Some(msg),
+                Err(e) => {
+                    eprintln!("Warning: script failed: {}", e);
+                    Some(msg)
+                }
+            }
+        })
+        .collect())
+}
+
+fn tui_app(
+    config: &Config,
+    messages: Vec<P2000Message>,
+    lookup: Arc<Lookup>,
+    location_lookup: Arc<LocationLookup>,
+) -> Result<tui::App> {
+    let display = &config.display;
+    let mut app = tui::App::new(messages, lookup, location_lookup)
+        .with_region_column(display.region_column)
+        .with_icons(display.icons)
+        .with_flash(Duration::from_secs(display.flash_secs))
+        .with_bell(display.bell)
+        .with_follow_bell(display.follow_bell)
+        .with_sort_time(display.sort_time)
+        .with_idle_fps(display.idle_fps)
+        .with_poll_interval(Duration::from_millis(display.poll_ms))
+        .with_low_power(display.low_power)
+        .with_redaction(display.redact)
+        .with_layout(display.layout)
+        .with_collapse_repeats(display.collapse_repeats)
+        .with_totals(display.totals)
+        .with_theme(config.theme.clone())
+        .with_units(config.units.clone())
+        .with_receiver(config.receiver.clone())
+        .with_channel_names(config.channels.clone())
+        .with_filter_presets(config.filters.clone())
+        .with_severity(config.severity.clone())
+        .with_gaps(&config.gaps)
+        .with_anomaly(&config.anomaly)
+        .with_data_files(DataFiles::from_config(config))
+        .with_feedback(Feedback::load(&config.config_dir().join(CORRECTIONS_FILE))?);
+    if let Some(sampling) = &config.sampling {
+        app = app.with_sampler(Sampler::new(sampling));
+    }
+    if let Some(translation) = &config.translation {
+        app = app.with_translator(Translator::from_config(translation)?);
+    }
+    if let Some(weather) = &config.weather {
+        app = app.with_weather(WeatherClient::new(weather)?);
+    }
+    let mut rules = config.rules.clone();
+    if let Some(pager) = &config.pager {
+        app = app.with_pager(Pager::new(pager));
+        if pager.sound.is_some() {
+            rules.insert(
+                0,
+                Rule {
+                    name: "My pager".to_string(),
+                    capcodes: pager.capcodes.clone(),
+                    sound: pager.sound.clone(),
+                    ..Default::default()
+                },
+            );
+        }
+    }
+    if let Some(archive) = &config.archive {
+        app = app.with_archive(Archive::open(&archive.path)?)?;
+    }
+    if let Some(audio) = &config.audio {
+        match AudioPlayer::start(audio, &rules) {
+            Ok(player) => app = app.with_audio(player),
+            Err(e) => eprintln!("Warning: sounds disabled: {}", e),
+        }
+    }
+    Ok(app)
+}
@@ -0,0 +1,135 @@
+//! A character map of the Netherlands for the split TUI layout: the coast and
+//! borders as dots, with the places of the listed messages on top.
+
+use serde::Deserialize;
+
+/// `layout` under `[display]`, cycled with 'v'.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PaneLayout {
+    /// List above the details
+    #[default]
+    Stacked,
+    /// List on the left, map and details on the right
+    Split,
+}
+
+impl PaneLayout {
+    pub fn next(self) -> Self {
+        match self {
+            PaneLayout::Stacked => PaneLayout::Split,
+            PaneLayout::Split => PaneLayout::Stacked,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            PaneLayout::Stacked => "stacked",
+            PaneLayout::Split => "split",
+        }
+    }
+}
+
+/// Longitude and latitude that the map spans.
+const WEST: f64 = 3.3;
+const EAST: f64 = 7.3;
+const SOUTH: f64 = 50.7;
+const NORTH: f64 = 53.6;
+
+/// Columns per row that keep the country's shape with characters about twice
+/// as tall as wide.
+const ASPECT: f64 = 1.7;
+
+/// Rough coast and land borders as (longitude, latitude), clockwise from Cadzand.
+const OUTLINE: &[(f64, f64)] = &[
+    (3.36, 51.37),
+    (3.55, 51.59),
+    (3.85, 51.83),
+    (4.05, 51.98),
+    (4.25, 52.12),
+    (4.48, 52.33),
+    (4.56, 52.46),
+    (4.62, 52.75),
+    (4.72, 52.96),
+    (4.87, 53.18),
+    (5.20, 53.35),
+    (5.65, 53.43),
+    (6.20, 53.48),
+    (6.90, 53.45),
+    (7.20, 53.25),
+    (7.20, 53.00),
+    (7.05, 52.60),
+    (6.70, 52.50),
+    (7.05, 52.38),
+    (6.80, 52.10),
+    (6.10, 51.85),
+    (6.20, 51.50),
+    (5.95, 51.00),
+    (5.85, 50.76),
+    (5.70, 50.75),
+    (5.65, 51.00),
+    (5.30, 51.25),
+    (4.80, 51.45),
+    (4.40, 51.37),
+    (3.36, 51.37),
+];
+
+/// Draws the map into at most `width` by `height` characters, centered
+/// horizontally. `places` are marked `o` and `selected` `@`; places outside
+/// the country are left out.
+pub fn render(places: &[(f64, f64)], selected: Option<(f64, f64)>, width: usize, height: usize) -> Vec<String> {
+    let cols = width.min((height as f64 * ASPECT).round() as usize);
+    if cols < 2 || height < 2 {
+        return Vec::new();
+    }
+    let mut grid = vec![vec![' '; cols]; height];
+    let plot = |(lon, lat): (f64, f64), mark: char, grid: &mut Vec<Vec<char>>| {
+        if !(WEST..=EAST).contains(&lon) || !(SOUTH..=NORTH).contains(&lat) {
+            return;
+        }
+        let x = ((lon - WEST) / (EAST - WEST) * (cols - 1) as f64).round() as usize;
+        let y = ((NORTH - lat) / (NORTH - SOUTH) * (height - 1) as f64).round() as usize;
+        grid[y][x] = mark;
+    };
+    for pair in OUTLINE.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        // Enough steps to leave no gaps at any size
+        let steps = (cols + height) * 2;
+        for step in 0..=steps {
+            let t = step as f64 / steps as f64;
+            plot((x0 + (x1 - x0) * t, y0 + (y1 - y0) * t), '.', &mut grid);
+        }
+    }
+    for &place in places {
+        plot(place, 'o', &mut grid);
+    }
+    if let Some(place) = selected {
+        plot(place, '@', &mut grid);
+    }
+    let indent = " ".repeat((width - cols) / 2);
+    grid.into_iter()
+        .map(|row| format!("{}{}", indent, row.into_iter().collect::<String>()).trim_end().to_string())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_marks_places() {
+        let amsterdam = (4.90, 52.37);
+        let maastricht = (5.69, 50.85);
+        let rows = render(&[amsterdam, (9.0, 48.0)], Some(maastricht), 60, 12);
+        assert_eq!(rows.len(), 12);
+        assert!(rows.iter().all(|row| row.chars().count() <= 60));
+        let marks = |mark| rows.iter().map(|row| row.matches(mark).count()).sum::<usize>();
+        // Munich is off the map
+        assert_eq!(marks('o'), 1);
+        assert_eq!(marks('@'), 1);
+        // North is up
+        let row_of = |mark| rows.iter().position(|row| row.contains(mark)).unwrap();
+        assert!(row_of('o') < row_of('@'));
+        assert!(render(&[], None, 1, 1).is_empty());
+    }
+}
@@ -0,0 +1,154 @@
+//! Personal pager: pages for the user's own capcodes are kept apart, alert
+//! loudly and wait for an acknowledgement, which is logged with its timing.
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::Deserialize;
+use std::collections::BTreeSet;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::lookup::normalize_code;
+use crate::parser::P2000Message;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct PagerConfig {
+    pub capcodes: Vec<String>,
+    /// Played for every own page (needs `[audio]`)
+    pub sound: Option<PathBuf>,
+    /// Acknowledgements are appended here as tab-separated lines
+    pub log: PathBuf,
+}
+
+impl Default for PagerConfig {
+    fn default() -> Self {
+        PagerConfig {
+            capcodes: Vec::new(),
+            sound: None,
+            log: PathBuf::from("pager-acks.log"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Page {
+    /// Index into the front-end's message list
+    pub message_index: usize,
+    /// When the page arrived; `None` for pages loaded from history
+    pub received: Option<DateTime<Local>>,
+    pub acknowledged: Option<DateTime<Local>>,
+}
+
+impl Page {
+    pub fn is_pending(&self) -> bool {
+        self.received.is_some() && self.acknowledged.is_none()
+    }
+}
+
+pub struct Pager {
+    // Without leading zeros
+    capcodes: BTreeSet<String>,
+    log: PathBuf,
+    pages: Vec<Page>,
+}
+
+impl Pager {
+    pub fn new(config: &PagerConfig) -> Self {
+        Pager {
+            capcodes: config
+                .capcodes
+                .iter()
+                .map(|c| normalize_code(c))
+                .collect(),
+            log: config.log.clone(),
+            pages: Vec::new(),
+        }
+    }
+
+    pub fn is_own(&self, msg: &P2000Message) -> bool {
+        msg.capcodes
+            .iter()
+            .any(|c| self.capcodes.contains(&normalize_code(c)))
+    }
+
+    /// Keeps an own message. Live pages wait for acknowledgement; history doesn't.
+    pub fn record(&mut self, message_index: usize, live: bool) {
+        self.pages.push(Page {
+            message_index,
+            received: live.then(Local::now),
+            acknowledged: None,
+        });
+    }
+
+    /// Own pages, oldest first.
+    pub fn pages(&self) -> &[Page] {
+        &self.pages
+    }
+
+    pub fn pending_count(&self) -> usize {
+        self.pages.iter().filter(|p| p.is_pending()).count()
+    }
+
+    /// Acknowledges the oldest pending page and logs it; returns its message index.
+    pub fn acknowledge(&mut self, messages: &[P2000Message]) -> Result<Option<usize>> {
+        let Some(page) = self.pages.iter_mut().find(|p| p.is_pending()) else {
+            return Ok(None);
+        };
+        let now = Local::now();
+        page.acknowledged = Some(now);
+        let received = page.received.unwrap_or(now);
+        let msg = &messages[page.message_index];
+        let line = format!(
+            "{}\t{}\t{}s\t{}\t{}\n",
+            now.to_rfc3339(),
+            received.to_rfc3339(),
+            (now - received).num_seconds(),
+            msg.capcodes.join(" "),
+            msg.content
+        );
+        std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.log)
+            .and_then(|mut file| file.write_all(line.as_bytes()))
+            .with_context(|| format!("Failed to write {}", self.log.display()))?;
+        Ok(Some(page.message_index))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_acknowledge_oldest_pending() {
+        let log = std::env::temp_dir().join(format!("p2000_pager_test_{}.log", std::process::id()));
+        let mut pager = Pager::new(&PagerConfig {
+            capcodes: vec!["001420028".to_string()],
+            log: log.clone(),
+            ..Default::default()
+        });
+        let parser = Parser::new();
+        let messages: Vec<P2000Message> = [
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568 1420028|ALN|A1 Reanimatie Vlaardingen",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:02:51|1600/2/K/A|00.037|001420028|ALN|A2 Schiedam 17130",
+        ]
+        .iter()
+        .map(|line| parser.parse_line(line).unwrap())
+        .collect();
+        assert!(pager.is_own(&messages[0]) && !pager.is_own(&messages[1]));
+
+        pager.record(0, false);
+        pager.record(2, true);
+        assert_eq!(pager.pending_count(), 1);
+        assert_eq!(pager.acknowledge(&messages).unwrap(), Some(2));
+        assert_eq!(pager.acknowledge(&messages).unwrap(), None);
+
+        let logged = std::fs::read_to_string(&log).unwrap();
+        assert!(logged.trim_end().ends_with("\t001420028\tA2 Schiedam 17130"));
+        std::fs::remove_file(log).ok();
+    }
+}
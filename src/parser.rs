@@ -1,8 +1,14 @@
-use chrono::DateTime;
+use chrono::{DateTime, FixedOffset, Local, NaiveDateTime, TimeZone};
 use regex::Regex;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::fmt;
+use std::sync::{Arc, Mutex};
 use thiserror::Error;
 
+use crate::intern::Interner;
+use crate::tokenize::{tokenize, TokenKind};
+
 #[derive(Error, Debug)]
 pub enum ParseError {
     #[error("Invalid FLEX format: {0}")]
@@ -13,20 +19,136 @@ pub enum ParseError {
     MissingField(String),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct P2000Message {
-    pub protocol: String,
-    pub timestamp: DateTime<chrono::Local>,
-    pub radio_address: String,
-    pub frequency: String,
-    pub capcodes: Vec<String>,
-    pub message_type: String,
+    // Interned: these repeat across nearly every message
+    pub protocol: Arc<str>,
+    pub timestamp: DateTime<Local>,
+    pub radio_address: Arc<str>,
+    pub frequency: Arc<str>,
+    pub capcodes: Vec<Arc<str>>,
+    pub message_type: MessageType,
     pub content: String,
     // Parsed fields
     pub priority: Option<String>,
     pub incident_code: Option<String>,
     pub location: String,
-    pub units: Vec<String>,
+    pub units: Vec<Arc<str>>,
+    /// When the line was read, for live feeds; the decoder's clock may drift
+    #[serde(default)]
+    pub received: Option<DateTime<Local>>,
+    /// The line had no timestamp; `timestamp` is when it was read
+    #[serde(default)]
+    pub synthetic_time: bool,
+    /// Receiver channel, for multi-channel sources
+    #[serde(default)]
+    pub channel: Option<Arc<str>>,
+    /// Station that received it, for archives combining several
+    #[serde(default)]
+    pub receiver: Option<Arc<Receiver>>,
+}
+
+/// FLEX page type: the type field of multimon-ng's output.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum MessageType {
+    /// `ALN`: text, nearly all of P2000
+    Alphanumeric,
+    /// `GPN`: a page to a group of capcodes at once
+    Group,
+    /// `NUM`: digits only
+    Numeric,
+    /// `TON`: a tone without content
+    Tone,
+    /// `BIN`: binary data
+    Binary,
+    /// Any other type as the decoder wrote it, empty for formats without a
+    /// type field; kept as is so `to_line` gives back the original line
+    Other(Arc<str>),
+}
+
+impl MessageType {
+    /// The known type written as `code`.
+    pub fn known(code: &str) -> Option<Self> {
+        match code {
+            "ALN" => Some(MessageType::Alphanumeric),
+            "GPN" => Some(MessageType::Group),
+            "NUM" => Some(MessageType::Numeric),
+            "TON" => Some(MessageType::Tone),
+            "BIN" => Some(MessageType::Binary),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            MessageType::Alphanumeric => "ALN",
+            MessageType::Group => "GPN",
+            MessageType::Numeric => "NUM",
+            MessageType::Tone => "TON",
+            MessageType::Binary => "BIN",
+            MessageType::Other(code) => code,
+        }
+    }
+
+    /// Numeric, group, tone and binary pages: rarely of interest to anyone
+    /// but the services they are for.
+    pub fn is_minor(&self) -> bool {
+        !matches!(self, MessageType::Alphanumeric | MessageType::Other(_))
+    }
+}
+
+impl From<String> for MessageType {
+    fn from(code: String) -> Self {
+        MessageType::known(&code).unwrap_or_else(|| MessageType::Other(code.into()))
+    }
+}
+
+impl From<MessageType> for String {
+    fn from(message_type: MessageType) -> Self {
+        message_type.as_str().to_string()
+    }
+}
+
+impl fmt::Display for MessageType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// `[receiver]` section: the station a live feed comes from.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Receiver {
+    pub callsign: String,
+    /// Where the antenna is, as precise as the operator wants to publish
+    pub location: String,
+    pub antenna: String,
+}
+
+impl fmt::Display for Receiver {
+    /// `PA3XYZ (Vlaardingen, discone)`
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let details: Vec<&str> = [self.location.as_str(), self.antenna.as_str()]
+            .into_iter()
+            .filter(|part| !part.is_empty())
+            .collect();
+        match (self.callsign.is_empty(), details.is_empty()) {
+            (_, true) => write!(f, "{}", self.callsign),
+            (true, false) => write!(f, "{}", details.join(", ")),
+            (false, false) => write!(f, "{} ({})", self.callsign, details.join(", ")),
+        }
+    }
+}
+
+/// Which clock orders messages: the decoder's timestamp or the local time the
+/// line was read.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TimeSource {
+    #[default]
+    Decoded,
+    Received,
 }
 
 impl fmt::Display for P2000Message {
@@ -41,66 +163,343 @@ impl fmt::Display for P2000Message {
     }
 }
 
+impl P2000Message {
+    /// The time `source` reports, falling back to the decoded timestamp for
+    /// messages that were not read live.
+    pub fn time(&self, source: TimeSource) -> DateTime<Local> {
+        match source {
+            TimeSource::Decoded => self.timestamp,
+            TimeSource::Received => self.received.unwrap_or(self.timestamp),
+        }
+    }
+
+    /// A pseudo-message for reporting something about the feed itself (an
+    /// outage, a volume spike) through the alert sinks.
+    pub fn notice(now: DateTime<Local>, content: String) -> Self {
+        let empty: Arc<str> = Arc::from("");
+        P2000Message {
+            protocol: empty.clone(),
+            timestamp: now,
+            radio_address: empty.clone(),
+            frequency: empty.clone(),
+            capcodes: Vec::new(),
+            message_type: MessageType::Other(empty),
+            content,
+            priority: None,
+            incident_code: None,
+            location: String::new(),
+            units: Vec::new(),
+            received: Some(now),
+            synthetic_time: false,
+            channel: None,
+            receiver: None,
+        }
+    }
+
+    /// Renders the message back into the pipe-separated FLEX line format.
+    pub fn to_line(&self) -> String {
+        format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            self.protocol,
+            self.timestamp.with_timezone(&chrono::Utc).format("%Y-%m-%d %H:%M:%S%.f"),
+            self.radio_address,
+            self.frequency,
+            self.capcodes.join(" "),
+            self.message_type,
+            self.content
+        )
+    }
+
+    /// Hex SHA-256 of [`to_line`](Self::to_line): the same for every copy of the
+    /// message, whichever receiver or archive it came from.
+    pub fn content_hash(&self) -> String {
+        line_hash(&self.to_line())
+    }
+}
+
+/// Hex SHA-256 of a feed line, see [`P2000Message::content_hash`].
+pub fn line_hash(line: &str) -> String {
+    Sha256::digest(line.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A field of a feed line.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LineField {
+    Protocol,
+    Timestamp,
+    Address,
+    Frequency,
+    /// Separated by spaces
+    Capcodes,
+    Type,
+    /// The rest of the line, delimiters included; always the last field
+    Content,
+    /// A column this program has no use for
+    Skip,
+}
+
+impl LineField {
+    // Where the field goes in `Parser::parse_line`
+    fn slot(self) -> Option<usize> {
+        match self {
+            LineField::Protocol => Some(0),
+            LineField::Timestamp => Some(1),
+            LineField::Address => Some(2),
+            LineField::Frequency => Some(3),
+            LineField::Capcodes => Some(4),
+            LineField::Type => Some(5),
+            LineField::Content => Some(6),
+            LineField::Skip => None,
+        }
+    }
+}
+
+/// How timestamps are written; `timestamp` in a line format: `"auto"`,
+/// `"multimon"`, `"iso8601"`, `"epoch"`, `"epoch_ms"` or a chrono format.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(from = "String")]
+pub enum TimestampFormat {
+    /// Any of the named formats, recognized per line
+    #[default]
+    Auto,
+    /// `2026-01-01 20:14:32`, optionally with fractional seconds
+    Multimon,
+    /// `2026-01-01T20:14:32Z` or with an offset such as `+01:00`; without
+    /// one, like `Multimon`
+    Iso8601,
+    /// Seconds since 1970, optionally with a fraction
+    Epoch,
+    /// Milliseconds since 1970
+    EpochMillis,
+    /// A chrono format such as `%d-%m-%Y %H:%M:%S`, with `%z` for an offset
+    Custom(String),
+}
+
+impl From<String> for TimestampFormat {
+    fn from(name: String) -> Self {
+        match name.as_str() {
+            "auto" => TimestampFormat::Auto,
+            "multimon" => TimestampFormat::Multimon,
+            "iso8601" => TimestampFormat::Iso8601,
+            "epoch" => TimestampFormat::Epoch,
+            "epoch_ms" => TimestampFormat::EpochMillis,
+            _ => TimestampFormat::Custom(name),
+        }
+    }
+}
+
+/// A parsed timestamp, in UTC when it says which zone it is in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Stamp {
+    Utc(NaiveDateTime),
+    Unzoned(NaiveDateTime),
+}
+
+impl TimestampFormat {
+    fn parse(&self, value: &str) -> Option<Stamp> {
+        let value = value.trim();
+        match self {
+            TimestampFormat::Auto => {
+                let format = if value.bytes().all(|b| b.is_ascii_digit()) && value.len() >= 12 {
+                    TimestampFormat::EpochMillis
+                } else if value.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+                    TimestampFormat::Epoch
+                } else if value.contains('T') {
+                    TimestampFormat::Iso8601
+                } else {
+                    TimestampFormat::Multimon
+                };
+                format.parse(value)
+            }
+            TimestampFormat::Multimon => parse_timestamp(value).map(Stamp::Unzoned),
+            TimestampFormat::Iso8601 => DateTime::parse_from_rfc3339(value)
+                .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+                .map(|time| Stamp::Utc(time.naive_utc()))
+                .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f").map(Stamp::Unzoned))
+                .ok(),
+            TimestampFormat::Epoch => {
+                let (seconds, fraction) = value.split_once('.').unwrap_or((value, ""));
+                if fraction.len() > 9 || !fraction.bytes().all(|b| b.is_ascii_digit()) {
+                    return None;
+                }
+                let nanos = format!("{:0<9}", fraction).parse().ok()?;
+                DateTime::from_timestamp(seconds.parse().ok()?, nanos).map(|time| Stamp::Utc(time.naive_utc()))
+            }
+            TimestampFormat::EpochMillis => {
+                DateTime::from_timestamp_millis(value.parse().ok()?).map(|time| Stamp::Utc(time.naive_utc()))
+            }
+            // With the zone first: parsing as naive time would drop a `%z` offset
+            TimestampFormat::Custom(format) => DateTime::parse_from_str(value, format)
+                .map(|time| Stamp::Utc(time.naive_utc()))
+                .or_else(|_| NaiveDateTime::parse_from_str(value, format).map(Stamp::Unzoned))
+                .ok(),
+        }
+    }
+}
+
+/// How a source writes its lines; `[plugins.<source>.format]`. The default
+/// is multimon-ng's FLEX output:
+/// `FLEX|2026-01-01 20:14:32|1600/2/K/A|03.091|002029575 001503282|ALN|P 2 ...`
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default)]
+pub struct LineFormat {
+    pub delimiter: char,
+    /// In order; fields left out stay empty
+    pub fields: Vec<LineField>,
+    pub timestamp: TimestampFormat,
+    /// Timestamps without a zone are local time instead of UTC
+    pub local_time: bool,
+    /// Lines with an empty timestamp, or all lines when there is no timestamp
+    /// field, get the time they were read instead of being rejected
+    pub synthetic_time: bool,
+}
+
+impl Default for LineFormat {
+    fn default() -> Self {
+        LineFormat {
+            delimiter: '|',
+            fields: vec![
+                LineField::Protocol,
+                LineField::Timestamp,
+                LineField::Address,
+                LineField::Frequency,
+                LineField::Capcodes,
+                LineField::Type,
+                LineField::Content,
+            ],
+            timestamp: TimestampFormat::Auto,
+            local_time: false,
+            synthetic_time: false,
+        }
+    }
+}
+
+impl LineFormat {
+    /// Fails unless there is a timestamp (or synthetic ones), the content
+    /// comes last and no field appears twice.
+    pub fn validate(&self) -> Result<(), ParseError> {
+        let invalid = |reason: String| Err(ParseError::InvalidFormat(reason));
+        if let Some(field) = self
+            .fields
+            .iter()
+            .find(|&&field| field != LineField::Skip && self.fields.iter().filter(|&&f| f == field).count() > 1)
+        {
+            return invalid(format!("{:?} appears twice", field));
+        }
+        if self.fields.last() != Some(&LineField::Content) {
+            return invalid("the content must be the last field".to_string());
+        }
+        if !self.fields.contains(&LineField::Timestamp) && !self.synthetic_time {
+            return invalid("needs a timestamp field".to_string());
+        }
+        Ok(())
+    }
+
+    /// Byte offset of the content in `line`, when it has all the fields.
+    pub fn content_start(&self, line: &str) -> Option<usize> {
+        match self.fields.len() {
+            0 | 1 => Some(0),
+            fields => line.match_indices(self.delimiter).nth(fields - 2).map(|(i, _)| i + self.delimiter.len_utf8()),
+        }
+    }
+}
+
 pub struct Parser {
-    priority_regex: Regex,
+    format: LineFormat,
     incident_code_regex: Regex,
+    interner: Interner,
+    // Local offset for the last seen quarter hour (UTC); resolving the zone costs more than the rest of a parse
+    offset_cache: Mutex<Option<(i64, FixedOffset)>>,
 }
 
 impl Parser {
+    /// A parser for multimon-ng's FLEX lines.
     pub fn new() -> Self {
         Parser {
-            // Matches priority at the start: P1, P2, P3, A0, A1, A2, B
-            priority_regex: Regex::new(r"^([PA]\d|B)\s").unwrap(),
+            format: LineFormat::default(),
             // Matches incident codes like BDH-07, BRT-03, etc.
             incident_code_regex: Regex::new(r"\b([A-Z]{2,3}-\d{2})\b").unwrap(),
+            interner: Interner::new(),
+            offset_cache: Mutex::new(None),
         }
     }
 
+    /// A parser for lines in `format`.
+    pub fn with_format(format: LineFormat) -> Result<Self, ParseError> {
+        format.validate()?;
+        Ok(Parser { format, ..Self::new() })
+    }
+
+    pub fn format(&self) -> &LineFormat {
+        &self.format
+    }
+
+    fn to_local(&self, utc: NaiveDateTime) -> DateTime<Local> {
+        // Zone transitions fall on quarter hours, so the offset is fixed within one
+        let quarter = utc.and_utc().timestamp().div_euclid(900);
+        let mut cache = self.offset_cache.lock().unwrap_or_else(|e| e.into_inner());
+        let offset = match *cache {
+            Some((cached, offset)) if cached == quarter => offset,
+            _ => {
+                let offset = Local.offset_from_utc_datetime(&utc);
+                *cache = Some((quarter, offset));
+                offset
+            }
+        };
+        DateTime::from_naive_utc_and_offset(utc, offset)
+    }
+
     pub fn parse_line(&self, line: &str) -> Result<P2000Message, ParseError> {
-        let parts: Vec<&str> = line.split('|').collect();
-
-        if parts.len() < 7 {
-            return Err(ParseError::InvalidFormat(format!(
-                "Expected at least 7 fields, got {}",
-                parts.len()
-            )));
-        }
-
-        let protocol = parts[0].to_string();
-        let timestamp_str = parts[1];
-        let radio_address = parts[2].to_string();
-        let frequency = parts[3].to_string();
-        let capcodes_str = parts[4];
-        let message_type = parts[5].to_string();
-        let content = parts[6..].join("|").to_string();
-
-        // Parse timestamp
-        let timestamp = chrono::NaiveDateTime::parse_from_str(timestamp_str, "%Y-%m-%d %H:%M:%S")
-            .ok()
-            .map(|ndt| {
-                chrono::DateTime::<chrono::Utc>::from_naive_utc_and_offset(ndt, chrono::Utc)
-                    .with_timezone(&chrono::Local)
-            })
-            .ok_or_else(|| ParseError::InvalidTimestamp(timestamp_str.to_string()))?;
+        // Content is everything after the last delimiter before it, delimiters included
+        let expected = self.format.fields.len();
+        let mut parts = line.splitn(expected, self.format.delimiter);
+        let mut fields = [""; 7];
+        for (i, field) in self.format.fields.iter().enumerate() {
+            let part = parts.next().ok_or_else(|| {
+                ParseError::InvalidFormat(format!("Expected at least {} fields, got {}", expected, i))
+            })?;
+            if let Some(slot) = field.slot() {
+                fields[slot] = part;
+            }
+        }
+        let content = fields[6].to_string();
+
+        let protocol = self.interner.intern(fields[0]);
+        let timestamp_str = fields[1];
+        let radio_address = self.interner.intern(fields[2]);
+        let frequency = self.interner.intern(fields[3]);
+        let capcodes_str = fields[4];
+        let message_type =
+            MessageType::known(fields[5]).unwrap_or_else(|| MessageType::Other(self.interner.intern(fields[5])));
+
+        // Parse timestamp; a line read live gets the exact time it was read in `FeedLine::parse`
+        let synthetic_time = self.format.synthetic_time && timestamp_str.trim().is_empty();
+        let timestamp = match self.format.timestamp.parse(timestamp_str) {
+            _ if synthetic_time => Some(Local::now()),
+            Some(Stamp::Unzoned(ndt)) if self.format.local_time => Local.from_local_datetime(&ndt).earliest(),
+            Some(Stamp::Utc(ndt) | Stamp::Unzoned(ndt)) => Some(self.to_local(ndt)),
+            None => None,
+        }
+        .ok_or_else(|| ParseError::InvalidTimestamp(timestamp_str.to_string()))?;
 
         // Parse capcodes
-        let capcodes: Vec<String> = if capcodes_str.is_empty() {
-            Vec::new()
-        } else {
-            capcodes_str.split_whitespace().map(|s| s.to_string()).collect()
-        };
+        let capcodes: Vec<Arc<str>> = capcodes_str
+            .split_whitespace()
+            .map(|s| self.interner.intern(s))
+            .collect();
 
         // Parse priority from content
-        let priority = self
-            .priority_regex
-            .find(&content)
-            .map(|m| m.as_str().trim().to_string());
+        let priority = parse_priority(&content).map(str::to_string);
 
-        // Parse incident code from content
-        let incident_code = self
-            .incident_code_regex
-            .find(&content)
-            .map(|m| m.as_str().to_string());
+        // Parse incident code from content; every code contains a '-'
+        let incident_code = if content.contains('-') {
+            self.incident_code_regex
+                .find(&content)
+                .map(|m| m.as_str().to_string())
+        } else {
+            None
+        };
 
         // Extract location - usually after the incident code/description
         let location = extract_location(&content);
@@ -120,8 +519,52 @@ impl Parser {
             incident_code,
             location,
             units,
+            received: None,
+            synthetic_time,
+            channel: None,
+            receiver: None,
+        })
+    }
+}
+
+/// `YYYY-MM-DD HH:MM:SS`, scanned by hand; falls back to chrono for anything
+/// unusual, such as fractional seconds.
+fn parse_timestamp(value: &str) -> Option<NaiveDateTime> {
+    fn number(bytes: &[u8]) -> Option<u32> {
+        bytes.iter().try_fold(0u32, |acc, b| {
+            b.is_ascii_digit().then(|| acc * 10 + (b - b'0') as u32)
         })
     }
+
+    let b = value.as_bytes();
+    if b.len() == 19 && b[4] == b'-' && b[7] == b'-' && b[10] == b' ' && b[13] == b':' && b[16] == b':' {
+        let date = chrono::NaiveDate::from_ymd_opt(
+            number(&b[0..4])? as i32,
+            number(&b[5..7])?,
+            number(&b[8..10])?,
+        )?;
+        return date.and_hms_opt(number(&b[11..13])?, number(&b[14..16])?, number(&b[17..19])?);
+    }
+    NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f").ok()
+}
+
+/// Priority at the start: P1, P2, P3, A0, A1, A2, B (optionally "P 2"), followed by whitespace.
+fn parse_priority(content: &str) -> Option<&str> {
+    let b = content.as_bytes();
+    let end = match b.first()? {
+        b'P' | b'A' => {
+            let digit = if b.get(1) == Some(&b' ') { 2 } else { 1 };
+            b.get(digit).filter(|d| d.is_ascii_digit())?;
+            digit + 1
+        }
+        b'B' => 1,
+        _ => return None,
+    };
+    content[end..]
+        .chars()
+        .next()
+        .filter(|c| c.is_whitespace())
+        .map(|_| &content[..end])
 }
 
 impl Default for Parser {
@@ -131,35 +574,27 @@ impl Default for Parser {
 }
 
 fn extract_location(content: &str) -> String {
-    // Location is typically after the incident code and description
-    // We'll look for the last segment that doesn't look like a code
-
-    let parts: Vec<&str> = content.split_whitespace().collect();
-
-    // Start from the end and collect meaningful location parts
+    // Location is typically after the incident code and description, so
+    // collect meaningful words from the end until a number like a rit or
+    // object number
     let mut location_parts = Vec::new();
 
-    for part in parts.iter().rev() {
-        // Stop if we hit a code-like pattern (all digits or numeric codes)
-        if part.len() <= 6 && part.chars().all(|c| c.is_numeric()) {
-            break;
+    for token in tokenize(content).iter().rev().filter(|t| !t.is_punctuation()) {
+        match token.kind {
+            TokenKind::Number if token.text.len() <= 6 => break,
+            // Capcodes, unit numbers and other long codes
+            TokenKind::Number | TokenKind::Capcode | TokenKind::Callsign => continue,
+            // Priority markers and short codes
+            _ if token.text.len() <= 3 => continue,
+            _ => location_parts.push(token.text),
         }
-
-        // Skip priority markers and short codes
-        if part.len() <= 3
-            || (part.len() > 3 && part.chars().all(|c| c.is_numeric() || c == '-'))
-        {
-            continue;
-        }
-
-        location_parts.push(*part);
     }
 
     location_parts.reverse();
     location_parts.join(" ")
 }
 
-fn parse_unit_codes(capcodes: &[String]) -> Vec<String> {
+fn parse_unit_codes(capcodes: &[Arc<str>]) -> Vec<Arc<str>> {
     // In this format, capcodes are device IDs
     // We'll return them as-is; they could be looked up in a database
     capcodes.to_vec()
@@ -175,10 +610,174 @@ mod tests {
         let line = "FLEX|2026-01-01 20:14:32|1600/2/K/A|03.091|002029575 001503282 001503289 001503900|ALN|P 2 BDH-07 Ongeval (los object) Gangetje Leiden 169252";
 
         let msg = parser.parse_line(line).expect("Failed to parse");
-        assert_eq!(msg.protocol, "FLEX");
-        assert_eq!(msg.radio_address, "1600/2/K/A");
-        assert_eq!(msg.frequency, "03.091");
+        assert_eq!(&*msg.protocol, "FLEX");
+        assert_eq!(&*msg.radio_address, "1600/2/K/A");
+        assert_eq!(&*msg.frequency, "03.091");
         assert_eq!(msg.priority, Some("P 2".to_string()));
         assert_eq!(msg.incident_code, Some("BDH-07".to_string()));
     }
+
+    #[test]
+    fn test_spaced_priorities() {
+        let parser = Parser::new();
+        let priority = |content: &str| {
+            let line = format!("FLEX|2026-01-01 20:14:32|1600/2/K/A|03.091|002029575|ALN|{}", content);
+            parser.parse_line(&line).unwrap().priority
+        };
+        assert_eq!(priority("P 1 BDH-01 BR woning").as_deref(), Some("P 1"));
+        assert_eq!(priority("P1 BDH-01 BR woning").as_deref(), Some("P1"));
+        assert_eq!(priority("A 2 Utrecht 630").as_deref(), Some("A 2"));
+        assert_eq!(priority("B 1 Rit 12").as_deref(), Some("B"));
+        // A street or word starting with P is no priority
+        assert_eq!(priority("P Hooftstraat 12"), None);
+        assert_eq!(priority("P 12 BDH-01"), None);
+        assert_eq!(crate::rules::normalize_priority("P 1"), crate::rules::normalize_priority("P1"));
+    }
+
+    #[test]
+    fn test_message_types() {
+        let parser = Parser::new();
+        let aln = parser.parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel").unwrap();
+        assert_eq!(aln.message_type, MessageType::Alphanumeric);
+        assert!(!aln.message_type.is_minor());
+        let num = parser.parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|NUM|0612").unwrap();
+        assert!(num.message_type.is_minor());
+        // Unknown types pass through unchanged
+        let line = "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|SPN|123";
+        let other = parser.parse_line(line).unwrap();
+        assert_eq!(other.message_type, MessageType::Other("SPN".into()));
+        assert!(!other.message_type.is_minor());
+        assert!(other.to_line().ends_with("|SPN|123"));
+        assert_eq!(serde_json::to_string(&num.message_type).unwrap(), "\"NUM\"");
+        let decoded: MessageType = serde_json::from_str("\"GPN\"").unwrap();
+        assert_eq!(decoded, MessageType::Group);
+    }
+
+    #[test]
+    fn test_interned_fields_are_shared() {
+        let parser = Parser::new();
+        let a = parser
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461")
+            .unwrap();
+        let b = parser
+            .parse_line("FLEX|2026-01-01 20:01:17|1600/2/K/A|00.050|001123117|ALN|A2 Utrecht 630")
+            .unwrap();
+        assert!(Arc::ptr_eq(&a.protocol, &b.protocol));
+        assert!(Arc::ptr_eq(&a.capcodes[0], &b.capcodes[0]));
+    }
+
+    #[test]
+    fn test_fast_path_matches_regex() {
+        let regex = Regex::new(r"^([PA] ?\d|B)\s").unwrap();
+        for content in ["P 2 BDH-07", "A1 Duizel", "B Rit", "A2\tX", "P2", "Pa test", "BRT-03", "A 1 x", "B", ""] {
+            assert_eq!(
+                parse_priority(content),
+                regex.find(content).map(|m| m.as_str().trim()),
+                "{:?}",
+                content
+            );
+        }
+
+        assert_eq!(
+            parse_timestamp("2026-01-01 20:14:32"),
+            chrono::NaiveDateTime::parse_from_str("2026-01-01 20:14:32", "%Y-%m-%d %H:%M:%S").ok()
+        );
+        assert!(parse_timestamp("2026-02-30 20:14:32").is_none());
+        assert!(parse_timestamp("2026-01-01 2x:14:32").is_none());
+    }
+
+    #[test]
+    fn test_timestamp_formats() {
+        let utc = |text: &str| Stamp::Utc(NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").unwrap());
+        let unzoned = |text: &str| Stamp::Unzoned(NaiveDateTime::parse_from_str(text, "%Y-%m-%d %H:%M:%S%.f").unwrap());
+        let auto = |value: &str| TimestampFormat::Auto.parse(value);
+        assert_eq!(auto("2026-01-01 20:14:32"), Some(unzoned("2026-01-01 20:14:32")));
+        assert_eq!(auto(" 2026-01-01 20:14:32.250"), Some(unzoned("2026-01-01 20:14:32.250")));
+        assert_eq!(auto("2026-01-01T20:14:32Z"), Some(utc("2026-01-01 20:14:32")));
+        assert_eq!(auto("2026-01-01T21:14:32.5+01:00"), Some(utc("2026-01-01 20:14:32.5")));
+        assert_eq!(auto("2026-01-01T21:14:32+0100"), Some(utc("2026-01-01 20:14:32")));
+        assert_eq!(auto("2026-01-01T20:14:32"), Some(unzoned("2026-01-01 20:14:32")));
+        assert_eq!(auto("1767298472"), Some(utc("2026-01-01 20:14:32")));
+        assert_eq!(auto("1767298472.25"), Some(utc("2026-01-01 20:14:32.25")));
+        assert_eq!(auto("1767298472250"), Some(utc("2026-01-01 20:14:32.250")));
+        for bad in ["", ".", "1.2.3", "yesterday", "2026-01-01T25:00:00Z", "99999999999999999999"] {
+            assert_eq!(auto(bad), None, "{}", bad);
+        }
+        // Pinned per source, other formats no longer pass
+        assert_eq!(TimestampFormat::Epoch.parse("2026-01-01 20:14:32"), None);
+        assert_eq!(TimestampFormat::from("epoch_ms".to_string()).parse("1767298472"), Some(utc("1970-01-21 10:54:58.472")));
+        let custom = |format: &str, value: &str| TimestampFormat::Custom(format.to_string()).parse(value);
+        assert_eq!(custom("%d-%m-%Y %H:%M:%S %z", "01-01-2026 21:14:32 +0100"), Some(utc("2026-01-01 20:14:32")));
+        assert_eq!(custom("%d-%m-%Y %H:%M:%S%:z", "01-01-2026 18:14:32-02:00"), Some(utc("2026-01-01 20:14:32")));
+        assert_eq!(custom("%d-%m-%Y %H:%M:%S", "01-01-2026 20:14:32"), Some(unzoned("2026-01-01 20:14:32")));
+
+        let parser = Parser::new();
+        let iso = parser.parse_line("FLEX|2026-01-01T21:14:32+01:00|1600/2/K/A|00.050|001123117|ALN|A1 Duizel").unwrap();
+        let epoch = parser.parse_line("FLEX|1767298472|1600/2/K/A|00.050|001123117|ALN|A1 Duizel").unwrap();
+        assert_eq!(iso.timestamp, epoch.timestamp);
+    }
+
+    #[test]
+    fn test_custom_line_format() {
+        let format: LineFormat = toml::from_str(
+            r#"
+            delimiter = ";"
+            fields = ["timestamp", "skip", "capcodes", "content"]
+            timestamp = "%d-%m-%Y %H:%M:%S"
+            local_time = true
+            "#,
+        )
+        .unwrap();
+        let parser = Parser::with_format(format).unwrap();
+        let msg = parser.parse_line("01-01-2026 21:14:32;-72dBm;002029575 001503282;P 2 BDH-07 Ongeval; Leiden").unwrap();
+        assert_eq!(msg.timestamp, Local.with_ymd_and_hms(2026, 1, 1, 21, 14, 32).unwrap());
+        assert_eq!(msg.capcodes.len(), 2);
+        assert_eq!(msg.content, "P 2 BDH-07 Ongeval; Leiden");
+        assert_eq!((msg.priority.as_deref(), &*msg.protocol), (Some("P 2"), ""));
+        assert!(matches!(parser.parse_line("01-01-2026 21:14:32;-72dBm"), Err(ParseError::InvalidFormat(_))));
+        assert!(matches!(parser.parse_line("2026-01-01 20:14:32;x;1;A1"), Err(ParseError::InvalidTimestamp(_))));
+
+        let invalid = |fields: &[LineField]| {
+            Parser::with_format(LineFormat { fields: fields.to_vec(), ..Default::default() }).is_err()
+        };
+        assert!(invalid(&[LineField::Content, LineField::Timestamp]));
+        assert!(invalid(&[LineField::Capcodes, LineField::Content]));
+        assert!(invalid(&[LineField::Timestamp, LineField::Type, LineField::Type, LineField::Content]));
+        assert!(!invalid(&[LineField::Timestamp, LineField::Skip, LineField::Skip, LineField::Content]));
+    }
+
+    #[test]
+    fn test_synthetic_timestamps() {
+        let line = "FLEX||1600/2/K/A|00.050|001123117|ALN|A1 Duizel";
+        assert!(matches!(Parser::new().parse_line(line), Err(ParseError::InvalidTimestamp(_))));
+
+        let format = LineFormat { synthetic_time: true, ..Default::default() };
+        let before = Local::now();
+        let msg = Parser::with_format(format).unwrap().parse_line(line).unwrap();
+        assert!(msg.synthetic_time && msg.timestamp >= before);
+        let minimal = LineFormat {
+            fields: vec![LineField::Capcodes, LineField::Content],
+            synthetic_time: true,
+            ..Default::default()
+        };
+        let msg = Parser::with_format(minimal).unwrap().parse_line("001123117|A1 Duizel").unwrap();
+        assert!(msg.synthetic_time);
+        assert_eq!((&*msg.capcodes[0], msg.content.as_str()), ("001123117", "A1 Duizel"));
+        assert!(!Parser::new().parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1").unwrap().synthetic_time);
+    }
+
+    #[test]
+    fn test_fractional_seconds_round_trip() {
+        let parser = Parser::new();
+        let line = "FLEX|2026-01-01 20:14:32.250|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461";
+        let mut msg = parser.parse_line(line).unwrap();
+        assert_eq!(msg.timestamp.timestamp_subsec_millis(), 250);
+        assert_eq!(parser.parse_line(&msg.to_line()).unwrap().timestamp, msg.timestamp);
+
+        assert_eq!(msg.time(TimeSource::Received), msg.timestamp);
+        let received = msg.timestamp + chrono::Duration::seconds(3);
+        msg.received = Some(received);
+        assert_eq!(msg.time(TimeSource::Received), received);
+        assert_eq!(msg.time(TimeSource::Decoded), msg.timestamp);
+    }
 }
@@ -0,0 +1,339 @@
+//! Extension points for the headless pipeline.
+//!
+//! A pipeline is one [`Source`] feeding raw lines, a chain of [`Enricher`]s and a set
+//! of [`Sink`]s. Components are registered by name in a [`Registry`] and picked from
+//! the `[pipeline]` config section, so other crates can add their own:
+//!
+//! ```ignore
+//! let mut registry = Registry::with_builtins();
+//! registry.register_sink("matrix", |ctx| Ok(Box::new(MatrixSink::new(ctx.options("matrix"))?)));
+//! ```
+
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::calendar::Calendar;
+use crate::config::Config;
+use crate::enrich::{self, Enrichment};
+use crate::location::LocationLookup;
+use crate::lookup::Lookup;
+use crate::parser::{LineFormat, P2000Message, Parser, Receiver};
+use crate::reader;
+use crate::remote::RemoteSource;
+use crate::rules::SharedRules;
+use crate::search::Query;
+use crate::scrub::{ScrubbedSource, Scrubber};
+use crate::sdr::{ProcessLog, SdrConfig, SdrSource};
+use crate::translate::Translator;
+use crate::weather::WeatherClient;
+pub use crate::sinks::Sink;
+
+/// Produces raw feed lines, one at a time.
+pub trait Source: Send {
+    fn name(&self) -> &str;
+    /// Blocks until the next line is available; `None` when the feed has ended.
+    fn next_line(&mut self) -> Result<Option<String>>;
+    /// Receiver channel of the line last returned, for sources with several.
+    fn channel(&self) -> Option<Arc<str>> {
+        None
+    }
+    /// Station that received the line last returned, for sources relaying others.
+    fn receiver(&self) -> Option<Arc<Receiver>> {
+        None
+    }
+}
+
+/// Adds derived data to a message. Enrichers run in configured order and may read
+/// what earlier ones produced.
+pub trait Enricher: Send + Sync {
+    fn name(&self) -> &str;
+    fn enrich(&self, msg: &P2000Message, enrichment: &mut Enrichment);
+}
+
+/// Everything a factory may need to build its component.
+pub struct BuildContext<'a> {
+    pub config: &'a Config,
+    pub lookup: Arc<Lookup>,
+    pub location_lookup: Arc<LocationLookup>,
+    /// Where sources that run subprocesses put their stderr
+    pub process_log: ProcessLog,
+    pub rules: SharedRules,
+}
+
+impl BuildContext<'_> {
+    /// Free-form options from the `[plugins.<name>]` config table.
+    pub fn options(&self, name: &str) -> Option<&toml::Table> {
+        self.config.plugins.get(name)
+    }
+}
+
+/// `token` from `[plugins.remote]`, presented to the server by the `remote` source and `live --remote`.
+pub fn remote_token(ctx: &BuildContext) -> Option<String> {
+    ctx.options("remote")
+        .and_then(|o| o.get("token"))
+        .and_then(|v| v.as_str())
+        .map(str::to_string)
+}
+
+/// `query` from `[plugins.remote]`: the messages to ask the server for.
+pub fn remote_query(ctx: &BuildContext) -> Result<Option<Query>> {
+    ctx.options("remote")
+        .and_then(|o| o.get("query"))
+        .and_then(|v| v.as_str())
+        .map(str::parse)
+        .transpose()
+}
+
+/// The source named in `[pipeline]`, defaulting to stdin.
+fn source_name(config: &Config) -> String {
+    config.pipeline.as_ref().and_then(|p| p.source.clone()).unwrap_or_else(|| "stdin".to_string())
+}
+
+/// `[plugins.<source>.format]`, or multimon-ng's FLEX lines.
+fn source_format(config: &Config) -> Result<LineFormat> {
+    match config.plugins.get(&source_name(config)).and_then(|o| o.get("format")) {
+        Some(format) => Ok(format.clone().try_into()?),
+        None => Ok(LineFormat::default()),
+    }
+}
+
+/// The parser for the lines of the source named in `[pipeline]`; log files
+/// and piped input are read with it too.
+pub fn source_parser(config: &Config) -> Result<Parser> {
+    Parser::with_format(source_format(config)?)
+        .map_err(|e| anyhow!("[plugins.{}.format]: {}", source_name(config), e))
+}
+
+pub type SourceFactory = Box<dyn Fn(&BuildContext) -> Result<Box<dyn Source>> + Send + Sync>;
+pub type EnricherFactory = Box<dyn Fn(&BuildContext) -> Result<Box<dyn Enricher>> + Send + Sync>;
+/// Sink factories may yield several instances (e.g. one per configured webhook).
+pub type SinkFactory = Box<dyn Fn(&BuildContext) -> Result<Vec<Box<dyn Sink>>> + Send + Sync>;
+
+/// `[pipeline]` config section; every field falls back to the built-in default.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct PipelineConfig {
+    pub source: Option<String>,
+    pub enrichers: Option<Vec<String>>,
+    pub sinks: Option<Vec<String>>,
+}
+
+#[derive(Default)]
+pub struct Registry {
+    sources: HashMap<String, SourceFactory>,
+    enrichers: HashMap<String, EnricherFactory>,
+    sinks: HashMap<String, SinkFactory>,
+}
+
+impl Registry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registry with the stdin/file/device/sdr sources, lookup enrichers and configured sinks.
+    pub fn with_builtins() -> Self {
+        let mut registry = Self::new();
+        registry.register_source("stdin", |_| Ok(Box::new(reader::StdinSource::new())));
+        registry.register_source("file", |ctx| {
+            let path = ctx
+                .options("file")
+                .and_then(|o| o.get("path"))
+                .and_then(|p| p.as_str())
+                .ok_or_else(|| anyhow!("file source requires [plugins.file] path"))?;
+            Ok(Box::new(reader::FileSource::open(path.as_ref())?))
+        });
+        registry.register_source("device", |ctx| {
+            let path = ctx
+                .options("device")
+                .and_then(|o| o.get("path"))
+                .and_then(|p| p.as_str())
+                .ok_or_else(|| anyhow!("device source requires [plugins.device] path"))?;
+            Ok(Box::new(reader::DeviceSource::open(path)?))
+        });
+        registry.register_source("sdr", |ctx| {
+            let config = SdrConfig::from_options(ctx.options("sdr"))?;
+            Ok(Box::new(SdrSource::start(config, ctx.process_log.clone())?))
+        });
+
+        registry.register_source("remote", |ctx| {
+            let url = ctx
+                .options("remote")
+                .and_then(|o| o.get("url"))
+                .and_then(|v| v.as_str())
+                .ok_or_else(|| anyhow!("remote source requires [plugins.remote] url"))?;
+            Ok(Box::new(
+                RemoteSource::new(url, ctx.process_log.clone())
+                    .with_token(remote_token(ctx))
+                    .with_query(remote_query(ctx)?),
+            ))
+        });
+        registry.register_enricher("capcodes", |ctx| {
+            Ok(Box::new(enrich::CapcodeEnricher::new(ctx.lookup.clone())))
+        });
+        registry.register_enricher("locations", |ctx| {
+            Ok(Box::new(enrich::LocationEnricher::new(ctx.location_lookup.clone())))
+        });
+        registry.register_enricher("abbreviations", |ctx| {
+            Ok(Box::new(enrich::AbbreviationEnricher::new(ctx.lookup.clone())))
+        });
+        registry.register_enricher("region", |ctx| {
+            Ok(Box::new(enrich::RegionEnricher::new(ctx.lookup.clone())))
+        });
+        registry.register_enricher("severity", |ctx| {
+            Ok(Box::new(enrich::SeverityEnricher::new(ctx.config.severity.clone())))
+        });
+        registry.register_enricher("units", |_| Ok(Box::new(enrich::UnitEnricher)));
+        registry.register_enricher("daypart", |_| Ok(Box::new(enrich::DayPartEnricher)));
+        registry.register_enricher("calendar", |ctx| {
+            Ok(Box::new(enrich::CalendarEnricher::new(Arc::new(Calendar::new(&ctx.config.calendar)?))))
+        });
+        registry.register_enricher("geofences", |ctx| {
+            Ok(Box::new(enrich::GeofenceEnricher::new(Arc::new(ctx.config.geofences.clone()))))
+        });
+        registry.register_enricher("translate", |ctx| {
+            let config = ctx.config.translation.clone().unwrap_or_default();
+            let translator = Translator::from_config(&config)?;
+            Ok(Box::new(enrich::TranslationEnricher::new(Arc::new(translator))))
+        });
+        registry.register_enricher("weather", |ctx| {
+            let config = ctx.config.weather.clone().unwrap_or_default();
+            Ok(Box::new(enrich::WeatherEnricher::new(Arc::new(WeatherClient::new(&config)?))))
+        });
+
+        registry.register_sink("ntfy", |ctx| crate::sinks::ntfy_from_config(ctx.config));
+        registry.register_sink("gotify", |ctx| crate::sinks::gotify_from_config(ctx.config));
+        registry.register_sink("webhooks", |ctx| crate::sinks::webhooks_from_config(ctx.config));
+        registry.register_sink("loki", |ctx| crate::sinks::loki_from_config(ctx.config));
+        registry.register_sink("syslog", |ctx| crate::sinks::syslog_from_config(ctx.config));
+        registry.register_sink("influx", |ctx| crate::sinks::influx_from_config(ctx.config));
+        registry.register_sink("server", |ctx| crate::remote::server_from_config(ctx.config, &ctx.rules));
+        registry
+    }
+
+    pub fn register_source<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&BuildContext) -> Result<Box<dyn Source>> + Send + Sync + 'static,
+    {
+        self.sources.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn register_enricher<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&BuildContext) -> Result<Box<dyn Enricher>> + Send + Sync + 'static,
+    {
+        self.enrichers.insert(name.to_string(), Box::new(factory));
+    }
+
+    pub fn register_sink<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&BuildContext) -> Result<Vec<Box<dyn Sink>>> + Send + Sync + 'static,
+    {
+        self.sinks.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// Builds only the source named in `[pipeline]`, defaulting to stdin, with
+    /// the `[[scrub]]` rules for it applied to its lines.
+    pub fn build_source(&self, ctx: &BuildContext) -> Result<Box<dyn Source>> {
+        let source_name = source_name(ctx.config);
+        let source = self
+            .sources
+            .get(&source_name)
+            .ok_or_else(|| anyhow!("Unknown source '{}'", source_name))?(ctx)?;
+        Ok(ScrubbedSource::wrap(source, Scrubber::new(&ctx.config.scrub)?, source_format(ctx.config)?))
+    }
+
+    /// Builds the pipeline named in `[pipeline]`, defaulting to stdin, all
+    /// built-in enrichers (geofences, translation and weather only when
+    /// configured) and every sink that has a config section.
+    pub fn build(&self, ctx: &BuildContext) -> Result<Pipeline> {
+        let pipeline = ctx.config.pipeline.clone().unwrap_or_default();
+
+        let source = self.build_source(ctx)?;
+        let parser = source_parser(ctx.config)?;
+        let enrichers = self.build_enrichers(ctx)?;
+
+        let sink_names = pipeline.sinks.unwrap_or_else(|| {
+            ["ntfy", "gotify", "webhooks", "loki", "syslog", "influx", "server"]
+                .iter()
+                .map(|s| s.to_string())
+                .collect()
+        });
+        let mut sinks = Vec::new();
+        for name in &sink_names {
+            let factory = self
+                .sinks
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown sink '{}'", name))?;
+            sinks.extend(factory(ctx)?);
+        }
+
+        Ok(Pipeline {
+            source,
+            parser,
+            enrichers,
+            sinks,
+        })
+    }
+
+    /// The enrichers of the pipeline named in `[pipeline]`, as `build` sets them up.
+    pub fn build_enrichers(&self, ctx: &BuildContext) -> Result<Vec<Box<dyn Enricher>>> {
+        let pipeline = ctx.config.pipeline.clone().unwrap_or_default();
+        let enricher_names = pipeline.enrichers.unwrap_or_else(|| {
+            let mut names: Vec<String> = [
+                "capcodes",
+                "locations",
+                "abbreviations",
+                "region",
+                "severity",
+                "units",
+                "daypart",
+                "calendar",
+            ]
+                .iter()
+                .map(|s| s.to_string())
+                .collect();
+            if !ctx.config.geofences.is_empty() {
+                names.push("geofences".to_string());
+            }
+            if ctx.config.translation.is_some() {
+                names.push("translate".to_string());
+            }
+            if ctx.config.weather.is_some() {
+                names.push("weather".to_string());
+            }
+            names
+        });
+        let mut enrichers = Vec::new();
+        for name in &enricher_names {
+            let factory = self
+                .enrichers
+                .get(name)
+                .ok_or_else(|| anyhow!("Unknown enricher '{}'", name))?;
+            enrichers.push(factory(ctx)?);
+        }
+        Ok(enrichers)
+    }
+}
+
+pub struct Pipeline {
+    pub source: Box<dyn Source>,
+    pub parser: Parser,
+    pub enrichers: Vec<Box<dyn Enricher>>,
+    pub sinks: Vec<Box<dyn Sink>>,
+}
+
+impl Pipeline {
+    pub fn enrich(&self, msg: &P2000Message) -> Enrichment {
+        run_enrichers(&self.enrichers, msg)
+    }
+}
+
+pub fn run_enrichers(enrichers: &[Box<dyn Enricher>], msg: &P2000Message) -> Enrichment {
+    let mut enrichment = Enrichment::default();
+    for enricher in enrichers {
+        enricher.enrich(msg, &mut enrichment);
+    }
+    enrichment
+}
@@ -0,0 +1,370 @@
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local, NaiveDate, NaiveDateTime, TimeZone};
+#[cfg(feature = "archive")]
+use rusqlite::types::Value;
+use serde::Serialize;
+use std::io::Write;
+use std::path::PathBuf;
+
+use crate::parser::Receiver;
+use crate::search::Query;
+#[cfg(feature = "archive")]
+use crate::search::Subject;
+
+#[cfg(feature = "archive")]
+use crate::{archive::Archive, lookup::normalize_code, rules};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum OutputFormat {
+    #[default]
+    Table,
+    Json,
+    Csv,
+}
+
+#[derive(Debug, Default)]
+pub struct QueryFilter {
+    pub since: Option<DateTime<Local>>,
+    pub until: Option<DateTime<Local>>,
+    pub priority: Option<String>,
+    pub region: Option<String>,
+    pub capcode: Option<String>,
+    pub text: Option<String>,
+    /// Callsign of the receiving station
+    pub receiver: Option<String>,
+    /// Structured search on top of the other criteria (`--where`)
+    pub query: Option<Query>,
+    pub limit: Option<usize>,
+}
+
+/// Parsed `query` subcommand arguments.
+#[derive(Debug, Default)]
+pub struct QueryArgs {
+    pub archive: Option<PathBuf>,
+    pub filter: QueryFilter,
+    pub format: OutputFormat,
+}
+
+pub const USAGE: &str = "Usage: p2000-rust query [--archive <db>] [--since <time>] [--until <time>] \
+[--priority <P1|A1|..>] [--region <text>] [--capcode <code>] [--text <text>] [--receiver <callsign>] \
+[--where <search>] [--limit <n>] [--format table|json|csv]
+Times are 'YYYY-MM-DD', 'YYYY-MM-DD HH:MM' or relative like '24h', '7d', '30m'.
+Searches look like 'prio:A1 AND region:\"Rotterdam-Rijnmond\" AND since:24h', as in the TUI.";
+
+impl QueryArgs {
+    pub fn parse(args: &[String]) -> Result<Self> {
+        let mut parsed = QueryArgs::default();
+        let mut iter = args.iter();
+        while let Some(flag) = iter.next() {
+            let mut value = || {
+                iter.next()
+                    .cloned()
+                    .ok_or_else(|| anyhow!("{} requires a value\n{}", flag, USAGE))
+            };
+            match flag.as_str() {
+                "--archive" => parsed.archive = Some(PathBuf::from(value()?)),
+                "--since" => parsed.filter.since = Some(parse_time(&value()?)?),
+                "--until" => parsed.filter.until = Some(parse_time(&value()?)?),
+                "--priority" => parsed.filter.priority = Some(value()?),
+                "--region" => parsed.filter.region = Some(value()?),
+                "--capcode" => parsed.filter.capcode = Some(value()?),
+                "--text" => parsed.filter.text = Some(value()?),
+                "--receiver" => parsed.filter.receiver = Some(value()?),
+                "--where" => parsed.filter.query = Some(value()?.parse()?),
+                "--limit" => parsed.filter.limit = Some(value()?.parse()?),
+                "--format" => {
+                    parsed.format = match value()?.as_str() {
+                        "table" => OutputFormat::Table,
+                        "json" => OutputFormat::Json,
+                        "csv" => OutputFormat::Csv,
+                        other => bail!("Unknown format '{}'\n{}", other, USAGE),
+                    }
+                }
+                other => bail!("Unknown option '{}'\n{}", other, USAGE),
+            }
+        }
+        Ok(parsed)
+    }
+}
+
+/// Absolute date/time, or a duration before now (`30m`, `24h`, `7d`).
+pub fn parse_time(value: &str) -> Result<DateTime<Local>> {
+    let value = value.trim();
+    if let Some(unit) = value.chars().last().filter(|c| c.is_ascii_alphabetic()) {
+        if let Ok(amount) = value[..value.len() - 1].parse::<i64>() {
+            let duration = match unit {
+                'm' => chrono::Duration::minutes(amount),
+                'h' => chrono::Duration::hours(amount),
+                'd' => chrono::Duration::days(amount),
+                'w' => chrono::Duration::weeks(amount),
+                _ => bail!("Unknown time unit in '{}'", value),
+            };
+            return Ok(Local::now() - duration);
+        }
+    }
+
+    let naive = NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S")
+        .or_else(|_| NaiveDateTime::parse_from_str(value, "%Y-%m-%d %H:%M"))
+        .or_else(|_| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d").map(|d| d.and_hms_opt(0, 0, 0).unwrap())
+        })
+        .map_err(|_| anyhow!("Invalid time '{}'", value))?;
+    Local
+        .from_local_datetime(&naive)
+        .earliest()
+        .ok_or_else(|| anyhow!("Invalid local time '{}'", value))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ArchivedMessage {
+    pub id: i64,
+    pub timestamp: DateTime<Local>,
+    pub priority: Option<String>,
+    pub service: Option<String>,
+    pub region: Option<String>,
+    pub place: Option<String>,
+    pub capcodes: String,
+    pub content: String,
+    /// See [`P2000Message::content_hash`](crate::parser::P2000Message::content_hash);
+    /// missing for messages archived before hashing
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hash: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub receiver: Option<Receiver>,
+}
+
+#[cfg(feature = "archive")]
+impl Archive {
+    /// Messages matching `filter`, newest first.
+    pub fn query(&self, filter: &QueryFilter) -> Result<Vec<ArchivedMessage>> {
+        let mut sql = String::from(
+            "SELECT id, timestamp, priority, service, region, place, capcodes, content, hash,
+                 receiver, receiver_location, receiver_antenna
+             FROM messages WHERE 1 = 1",
+        );
+        let mut params: Vec<Value> = Vec::new();
+
+        if let Some(since) = filter.since {
+            sql.push_str(" AND timestamp >= ?");
+            params.push(Value::Integer(since.timestamp()));
+        }
+        if let Some(until) = filter.until {
+            sql.push_str(" AND timestamp < ?");
+            params.push(Value::Integer(until.timestamp()));
+        }
+        if let Some(priority) = &filter.priority {
+            sql.push_str(" AND UPPER(REPLACE(priority, ' ', '')) = ?");
+            params.push(Value::Text(rules::normalize_priority(priority)));
+        }
+        if let Some(region) = &filter.region {
+            sql.push_str(" AND region LIKE ? ESCAPE '\\'");
+            params.push(Value::Text(contains_pattern(region)));
+        }
+        if let Some(text) = &filter.text {
+            sql.push_str(" AND content LIKE ? ESCAPE '\\'");
+            params.push(Value::Text(contains_pattern(text)));
+        }
+        if let Some(receiver) = &filter.receiver {
+            sql.push_str(" AND receiver = ? COLLATE NOCASE");
+            params.push(Value::Text(receiver.clone()));
+        }
+        if let Some(capcode) = &filter.capcode {
+            // Coarse match here, exact match on the normalized code below
+            sql.push_str(" AND capcodes LIKE ? ESCAPE '\\'");
+            params.push(Value::Text(contains_pattern(&normalize_code(capcode))));
+        }
+        sql.push_str(" ORDER BY timestamp DESC, id DESC");
+
+        let conn = self.connection();
+        let mut stmt = conn.prepare(&sql)?;
+        let rows = stmt.query_map(rusqlite::params_from_iter(params), |row| {
+            let timestamp: i64 = row.get(1)?;
+            let (location, antenna): (Option<String>, Option<String>) = (row.get(10)?, row.get(11)?);
+            let receiver = row.get::<_, Option<String>>(9)?.map(|callsign| Receiver {
+                callsign,
+                location: location.unwrap_or_default(),
+                antenna: antenna.unwrap_or_default(),
+            });
+            Ok(ArchivedMessage {
+                id: row.get(0)?,
+                timestamp: Local
+                    .timestamp_opt(timestamp, 0)
+                    .single()
+                    .unwrap_or_else(Local::now),
+                priority: row.get(2)?,
+                service: row.get(3)?,
+                region: row.get(4)?,
+                place: row.get(5)?,
+                capcodes: row.get(6)?,
+                content: row.get(7)?,
+                hash: row.get(8)?,
+                receiver,
+            })
+        })?;
+
+        let wanted = filter.capcode.as_deref().map(normalize_code);
+        let mut results = Vec::new();
+        for row in rows {
+            let row = row?;
+            if let Some(wanted) = &wanted {
+                if !row
+                    .capcodes
+                    .split_whitespace()
+                    .any(|c| &normalize_code(c) == wanted)
+                {
+                    continue;
+                }
+            }
+            if filter.query.as_ref().is_some_and(|query| !query.matches(&row.subject())) {
+                continue;
+            }
+            results.push(row);
+            if filter.limit.is_some_and(|limit| results.len() >= limit) {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
+
+/// A LIKE pattern for text containing `text`, which may itself hold `%` or `_`;
+/// goes with `ESCAPE '\'`.
+#[cfg(feature = "archive")]
+fn contains_pattern(text: &str) -> String {
+    let mut pattern = String::from("%");
+    for c in text.chars() {
+        if matches!(c, '%' | '_' | '\\') {
+            pattern.push('\\');
+        }
+        pattern.push(c);
+    }
+    pattern.push('%');
+    pattern
+}
+
+#[cfg(feature = "archive")]
+impl ArchivedMessage {
+    /// What a `--where` search sees; archived messages have no channel or
+    /// abbreviation categories.
+    fn subject(&self) -> Subject<'_> {
+        Subject {
+            content: &self.content,
+            priority: self.priority.as_deref(),
+            region: self.region.as_deref(),
+            place: self.place.as_deref(),
+            capcodes: self.capcodes.split_whitespace().collect(),
+            receiver: self.receiver.as_ref().map(|r| r.callsign.as_str()),
+            time: Some(self.timestamp),
+            ..Default::default()
+        }
+    }
+}
+
+pub fn print(rows: &[ArchivedMessage], format: OutputFormat, out: &mut impl Write) -> Result<()> {
+    match format {
+        OutputFormat::Json => {
+            serde_json::to_writer_pretty(&mut *out, rows)?;
+            writeln!(out)?;
+        }
+        OutputFormat::Csv => {
+            let mut writer = csv::Writer::from_writer(out);
+            writer.write_record([
+                "id", "timestamp", "priority", "service", "region", "place", "capcodes", "content", "hash",
+                "receiver",
+            ])?;
+            for row in rows {
+                writer.write_record([
+                    row.id.to_string(),
+                    row.timestamp.format("%Y-%m-%d %H:%M:%S").to_string(),
+                    row.priority.clone().unwrap_or_default(),
+                    row.service.clone().unwrap_or_default(),
+                    row.region.clone().unwrap_or_default(),
+                    row.place.clone().unwrap_or_default(),
+                    row.capcodes.clone(),
+                    row.content.clone(),
+                    row.hash.clone().unwrap_or_default(),
+                    row.receiver.as_ref().map(Receiver::to_string).unwrap_or_default(),
+                ])?;
+            }
+            writer.flush()?;
+        }
+        OutputFormat::Table => {
+            writeln!(
+                out,
+                "{:<19} | {:>4} | {:<24} | {:<18} | Content",
+                "Timestamp", "Prio", "Region", "Place"
+            )?;
+            for row in rows {
+                writeln!(
+                    out,
+                    "{:<19} | {:>4} | {:<24} | {:<18} | {}",
+                    row.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                    row.priority.as_deref().unwrap_or("-"),
+                    truncate(row.region.as_deref().unwrap_or("-"), 24),
+                    truncate(row.place.as_deref().unwrap_or("-"), 18),
+                    row.content
+                )?;
+            }
+            writeln!(out, "{} messages", rows.len())?;
+        }
+    }
+    Ok(())
+}
+
+fn truncate(value: &str, width: usize) -> String {
+    if value.chars().count() <= width {
+        value.to_string()
+    } else {
+        let mut short: String = value.chars().take(width - 1).collect();
+        short.push('…');
+        short
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time() {
+        let day = parse_time("2026-01-01").unwrap();
+        assert_eq!(day.format("%Y-%m-%d %H:%M").to_string(), "2026-01-01 00:00");
+        let minute = parse_time("2026-01-01 20:15").unwrap();
+        assert_eq!(minute.format("%H:%M").to_string(), "20:15");
+
+        let relative = parse_time("24h").unwrap();
+        let expected = Local::now() - chrono::Duration::hours(24);
+        assert!((relative - expected).num_seconds().abs() < 5);
+
+        assert!(parse_time("yesterday").is_err());
+    }
+
+    #[cfg(feature = "archive")]
+    #[test]
+    fn test_text_is_matched_literally() {
+        assert_eq!(contains_pattern(r"100%_a\b"), r"%100\%\_a\\b%");
+
+        let dir = std::env::temp_dir().join(format!("p2000_like_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let archive = Archive::open(&dir.join("archive.db")).unwrap();
+        let parser = crate::parser::Parser::new();
+        for line in [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Rit 100% bezet",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Rit 1003 Utrecht",
+        ] {
+            archive.insert(&parser.parse_line(line).unwrap(), &Default::default()).unwrap();
+        }
+        let texts = |text: &str| {
+            let filter = QueryFilter {
+                text: Some(text.to_string()),
+                ..Default::default()
+            };
+            archive.query(&filter).unwrap().into_iter().map(|row| row.content).collect::<Vec<_>>()
+        };
+        assert_eq!(texts("100%"), ["A1 Rit 100% bezet"]);
+        assert_eq!(texts("Rit 10_"), Vec::<String>::new());
+        assert_eq!(texts("Rit 10").len(), 2);
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
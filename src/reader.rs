@@ -1,15 +1,33 @@
 use anyhow::Result;
 use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use std::fs::File;
+use std::sync::Arc;
 use tokio::io::AsyncBufReadExt;
 
-use crate::parser::{P2000Message, Parser};
+use crate::cache;
+use crate::parser::{P2000Message, ParseError, Parser, Receiver};
+use crate::plugin::Source;
 
-pub async fn read_from_file(path: &Path) -> Result<Vec<P2000Message>> {
+/// Reads and parses a log file with `parser`, using its `.p2k` cache when it
+/// is still valid and was parsed with the same line format.
+pub async fn read_from_file(path: &Path, parser: &Parser) -> Result<Vec<P2000Message>> {
+    match cache::load(path, parser.format()) {
+        Ok(Some(messages)) => return Ok(messages),
+        Ok(None) => {}
+        Err(e) => eprintln!("Warning: Ignoring cache for {}: {}", path.display(), e),
+    }
+
+    let messages = parse_file(path, parser)?;
+    if let Err(e) = cache::store(path, parser.format(), &messages) {
+        eprintln!("Warning: Failed to write cache for {}: {}", path.display(), e);
+    }
+    Ok(messages)
+}
+
+fn parse_file(path: &Path, parser: &Parser) -> Result<Vec<P2000Message>> {
     let file = File::open(path)?;
     let reader = BufReader::new(file);
-    let parser = Parser::new();
     let mut messages = Vec::new();
 
     for line in reader.lines() {
@@ -23,10 +41,9 @@ pub async fn read_from_file(path: &Path) -> Result<Vec<P2000Message>> {
     Ok(messages)
 }
 
-pub async fn read_from_stdin() -> Result<Vec<P2000Message>> {
+pub async fn read_from_stdin(parser: &Parser) -> Result<Vec<P2000Message>> {
     let stdin = tokio::io::stdin();
     let reader = tokio::io::BufReader::new(stdin);
-    let parser = Parser::new();
     let mut messages = Vec::new();
     let mut lines = reader.lines();
 
@@ -39,3 +56,207 @@ pub async fn read_from_stdin() -> Result<Vec<P2000Message>> {
 
     Ok(messages)
 }
+
+/// A raw line from a live source, with the receiver channel it came in on.
+#[derive(Debug, Clone)]
+pub struct FeedLine {
+    pub line: String,
+    pub channel: Option<Arc<str>>,
+    /// Station that received it, for sources relaying another instance
+    pub receiver: Option<Arc<Receiver>>,
+}
+
+impl FeedLine {
+    /// Parses the line, stamping the channel and the time it was read, which
+    /// is also its timestamp when the line had none.
+    pub fn parse(&self, parser: &Parser) -> Result<P2000Message, ParseError> {
+        let mut msg = parser.parse_line(&self.line)?;
+        let now = chrono::Local::now();
+        msg.channel = self.channel.clone();
+        msg.receiver = self.receiver.clone();
+        msg.received = Some(now);
+        if msg.synthetic_time {
+            msg.timestamp = now;
+        }
+        Ok(msg)
+    }
+}
+
+/// Reads `source` on its own thread so callers can wait for a line and a shutdown
+/// signal at the same time. The channel closes when the feed ends; a blocked read
+/// is simply abandoned at exit.
+pub fn spawn_source(mut source: Box<dyn Source>) -> tokio::sync::mpsc::Receiver<Result<FeedLine>> {
+    let (tx, rx) = tokio::sync::mpsc::channel(1024);
+    std::thread::spawn(move || loop {
+        let line = match source.next_line() {
+            Ok(Some(line)) => Ok(FeedLine {
+                line,
+                channel: source.channel(),
+                receiver: source.receiver(),
+            }),
+            Ok(None) => break,
+            Err(e) => Err(e),
+        };
+        let failed = line.is_err();
+        if tx.blocking_send(line).is_err() || failed {
+            break;
+        }
+    });
+    rx
+}
+
+pub struct StdinSource {
+    lines: std::io::Lines<BufReader<std::io::Stdin>>,
+}
+
+impl StdinSource {
+    pub fn new() -> Self {
+        StdinSource {
+            lines: BufReader::new(std::io::stdin()).lines(),
+        }
+    }
+}
+
+impl Default for StdinSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Source for StdinSource {
+    fn name(&self) -> &str {
+        "stdin"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        Ok(self.lines.next().transpose()?)
+    }
+}
+
+pub struct FileSource {
+    lines: std::io::Lines<BufReader<File>>,
+}
+
+impl FileSource {
+    pub fn open(path: &Path) -> Result<Self> {
+        let file = File::open(path)?;
+        Ok(FileSource {
+            lines: BufReader::new(file).lines(),
+        })
+    }
+}
+
+impl Source for FileSource {
+    fn name(&self) -> &str {
+        "file"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        Ok(self.lines.next().transpose()?)
+    }
+}
+
+/// Serial port, FIFO or Windows named pipe carrying decoder output.
+///
+/// Port settings (baud rate etc.) are not touched; configure them beforehand
+/// with `stty` or `mode COM3 BAUD=...`. When the writer goes away (FIFO or
+/// pipe closed) the device is reopened, which waits for the next writer. A
+/// regular file is read once.
+pub struct DeviceSource {
+    path: PathBuf,
+    lines: std::io::Lines<BufReader<File>>,
+    reopen: bool,
+}
+
+impl DeviceSource {
+    pub fn open(path: &str) -> Result<Self> {
+        let path = device_path(path);
+        let lines = BufReader::new(File::open(&path)?).lines();
+        let reopen = reopens(&path);
+        Ok(DeviceSource { path, lines, reopen })
+    }
+}
+
+impl Source for DeviceSource {
+    fn name(&self) -> &str {
+        "device"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            match self.lines.next().transpose()? {
+                Some(line) => return Ok(Some(line)),
+                None if !self.reopen => return Ok(None),
+                None => {
+                    std::thread::sleep(std::time::Duration::from_millis(500));
+                    self.lines = BufReader::new(File::open(&self.path)?).lines();
+                }
+            }
+        }
+    }
+}
+
+/// `COM10` and up only open through the device namespace on Windows, so bare
+/// port names always get the `\\.\` prefix there.
+fn device_path(path: &str) -> PathBuf {
+    let is_port = path.len() > 3
+        && path.get(..3).is_some_and(|prefix| prefix.eq_ignore_ascii_case("COM"))
+        && path[3..].chars().all(|c| c.is_ascii_digit());
+    if cfg!(windows) && is_port {
+        PathBuf::from(format!(r"\\.\{}", path))
+    } else {
+        PathBuf::from(path)
+    }
+}
+
+/// Character devices and FIFOs hit the end when their writer goes away and
+/// get a new one later; a regular file stays at its end.
+#[cfg(unix)]
+fn reopens(path: &Path) -> bool {
+    use std::os::unix::fs::FileTypeExt;
+    std::fs::metadata(path).is_ok_and(|m| m.file_type().is_char_device() || m.file_type().is_fifo())
+}
+
+/// Serial ports and named pipes are no regular files.
+#[cfg(not(unix))]
+fn reopens(path: &Path) -> bool {
+    !std::fs::metadata(path).is_ok_and(|m| m.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_device_source_ends_on_regular_files() {
+        let path = std::env::temp_dir().join(format!("p2000_device_test_{}.txt", std::process::id()));
+        std::fs::write(&path, "first\nsecond\n").unwrap();
+        let mut source = DeviceSource::open(path.to_str().unwrap()).unwrap();
+        assert_eq!(source.next_line().unwrap().as_deref(), Some("first"));
+        assert_eq!(source.next_line().unwrap().as_deref(), Some("second"));
+        assert_eq!(source.next_line().unwrap(), None);
+        std::fs::remove_file(path).ok();
+
+        assert_eq!(device_path("abé1"), PathBuf::from("abé1"));
+        assert_eq!(device_path("/dev/ttyUSB0"), PathBuf::from("/dev/ttyUSB0"));
+    }
+
+    #[test]
+    fn test_parse_file_with_format() {
+        let path = std::env::temp_dir().join(format!("p2000_format_test_{}.txt", std::process::id()));
+        std::fs::write(&path, "01-01-2026 20:14:32;001123117;A1 Duizel Rit: 461\n").unwrap();
+        let format: crate::parser::LineFormat = toml::from_str(
+            r#"
+            delimiter = ";"
+            fields = ["timestamp", "capcodes", "content"]
+            timestamp = "%d-%m-%Y %H:%M:%S"
+            "#,
+        )
+        .unwrap();
+        let messages = parse_file(&path, &Parser::with_format(format).unwrap()).unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].content, "A1 Duizel Rit: 461");
+        assert!(parse_file(&path, &Parser::new()).unwrap().is_empty());
+        std::fs::remove_file(path).ok();
+    }
+}
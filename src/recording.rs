@@ -0,0 +1,350 @@
+//! Offline decoding of captured recordings: WAV audio, or raw 8-bit IQ from
+//! `rtl_sdr` which is FM-demodulated here. Either way the audio is resampled to
+//! the decoder rate and piped through multimon-ng as fast as it reads.
+
+use anyhow::{bail, Context, Result};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::Path;
+use std::process::Stdio;
+
+use crate::sdr::{self, ProcessLog, SdrConfig};
+
+/// Sample rate multimon-ng expects on raw input
+pub const DECODER_RATE: u32 = 22050;
+
+/// Frequency deviation that maps to full scale after demodulation (FLEX uses up to 4.8 kHz)
+const MAX_DEVIATION_HZ: f32 = 6000.0;
+
+/// Bytes read from the recording at a time
+const CHUNK: usize = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecordingKind {
+    Wav,
+    /// Interleaved unsigned 8-bit I/Q, as `rtl_sdr` writes it
+    Iq,
+}
+
+impl RecordingKind {
+    /// By extension: `.wav`, or `.cu8`/`.iq` for IQ captures.
+    pub fn of(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "wav" => Some(RecordingKind::Wav),
+            "cu8" | "iq" => Some(RecordingKind::Iq),
+            _ => None,
+        }
+    }
+}
+
+/// Runs a recording through the decoder and returns its output lines.
+pub fn decode(path: &Path, config: &SdrConfig, log: &ProcessLog) -> Result<Vec<String>> {
+    let kind = RecordingKind::of(path).with_context(|| format!("{} is not a recording", path.display()))?;
+    let mut input = BufReader::new(File::open(path).with_context(|| format!("Failed to open {}", path.display()))?);
+
+    let mut decoder = sdr::command(&config.multimon)?
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to start {}", config.multimon[0]))?;
+    if let Some(stderr) = decoder.stderr.take() {
+        sdr::forward_stderr(sdr::program_name(&config.multimon[0]), stderr, log.clone());
+    }
+    let mut stdin = decoder.stdin.take().expect("piped stdin");
+    let iq_rate = config.iq_sample_rate;
+    let writer = std::thread::spawn(move || -> Result<()> {
+        let mut write = |samples: &[f32]| -> std::io::Result<()> {
+            let bytes: Vec<u8> = samples
+                .iter()
+                .flat_map(|s| ((s.clamp(-1.0, 1.0) * i16::MAX as f32) as i16).to_ne_bytes())
+                .collect();
+            stdin.write_all(&bytes)
+        };
+        match kind {
+            RecordingKind::Wav => stream_wav(&mut input, &mut write),
+            RecordingKind::Iq => stream_iq(&mut input, iq_rate, &mut write),
+        }
+        // Closing stdin lets the decoder finish
+    });
+
+    let stdout = decoder.stdout.take().expect("piped stdout");
+    let lines: Vec<String> = BufReader::new(stdout).lines().collect::<std::io::Result<_>>()?;
+    let status = decoder.wait()?;
+    writer.join().map_err(|_| anyhow::anyhow!("Recording reader panicked"))??;
+    if !status.success() {
+        log.push(format!("{}: {}", sdr::program_name(&config.multimon[0]), status));
+    }
+    Ok(lines)
+}
+
+/// Decodes PCM WAV (8/16/32-bit integer or 32-bit float), downmixed to mono.
+fn stream_wav(input: &mut impl Read, write: &mut impl FnMut(&[f32]) -> std::io::Result<()>) -> Result<()> {
+    let mut header = [0u8; 12];
+    input.read_exact(&mut header).context("Truncated WAV header")?;
+    if &header[0..4] != b"RIFF" || &header[8..12] != b"WAVE" {
+        bail!("Not a WAV file");
+    }
+    let mut format: Option<WavFormat> = None;
+    loop {
+        let mut chunk = [0u8; 8];
+        input.read_exact(&mut chunk).context("WAV file has no data chunk")?;
+        let len = u32::from_le_bytes(chunk[4..8].try_into().unwrap()) as u64;
+        match &chunk[0..4] {
+            b"fmt " => {
+                let mut body = vec![0u8; len as usize];
+                input.read_exact(&mut body)?;
+                format = Some(WavFormat::parse(&body)?);
+            }
+            b"data" => break,
+            _ => {
+                std::io::copy(&mut input.by_ref().take(len), &mut std::io::sink())?;
+            }
+        }
+        if len % 2 == 1 {
+            input.read_exact(&mut [0u8; 1])?;
+        }
+    }
+    let format = format.context("WAV file has no fmt chunk before its data")?;
+
+    let frame = format.bytes_per_sample * format.channels;
+    let mut resampler = Resampler::new(format.sample_rate, DECODER_RATE);
+    let mut buffer = vec![0u8; CHUNK - CHUNK % frame];
+    let mut pending = Vec::new();
+    loop {
+        let n = input.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        pending.extend_from_slice(&buffer[..n]);
+        let whole = pending.len() - pending.len() % frame;
+        let mono: Vec<f32> = pending[..whole]
+            .chunks_exact(frame)
+            .map(|frame| {
+                let sum: f32 = frame.chunks_exact(format.bytes_per_sample).map(|s| format.sample(s)).sum();
+                sum / format.channels as f32
+            })
+            .collect();
+        pending.drain(..whole);
+        write(&resampler.process(&mono))?;
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WavFormat {
+    float: bool,
+    channels: usize,
+    sample_rate: u32,
+    bytes_per_sample: usize,
+}
+
+impl WavFormat {
+    fn parse(body: &[u8]) -> Result<Self> {
+        if body.len() < 16 {
+            bail!("Truncated WAV fmt chunk");
+        }
+        let tag = u16::from_le_bytes([body[0], body[1]]);
+        let channels = u16::from_le_bytes([body[2], body[3]]) as usize;
+        let sample_rate = u32::from_le_bytes(body[4..8].try_into().unwrap());
+        let bits = u16::from_le_bytes([body[14], body[15]]);
+        // 0xFFFE is WAVE_FORMAT_EXTENSIBLE; its subformat is assumed to match the bit depth
+        let float = match (tag, bits) {
+            (1 | 0xFFFE, 8 | 16 | 32) => false,
+            (3, 32) => true,
+            (0xFFFE, _) | (1, _) | (3, _) => bail!("Unsupported WAV sample size: {} bits", bits),
+            _ => bail!("Unsupported WAV encoding {}", tag),
+        };
+        if channels == 0 || sample_rate == 0 {
+            bail!("Invalid WAV format");
+        }
+        Ok(WavFormat {
+            float,
+            channels,
+            sample_rate,
+            bytes_per_sample: bits as usize / 8,
+        })
+    }
+
+    fn sample(&self, bytes: &[u8]) -> f32 {
+        match (self.bytes_per_sample, self.float) {
+            (1, _) => (bytes[0] as f32 - 128.0) / 128.0,
+            (2, _) => i16::from_le_bytes([bytes[0], bytes[1]]) as f32 / 32768.0,
+            (4, true) => f32::from_le_bytes(bytes.try_into().unwrap()),
+            _ => i32::from_le_bytes(bytes.try_into().unwrap()) as f32 / 2_147_483_648.0,
+        }
+    }
+}
+
+/// FM-demodulates a capture centred on the channel.
+fn stream_iq(
+    input: &mut impl Read,
+    sample_rate: u32,
+    write: &mut impl FnMut(&[f32]) -> std::io::Result<()>,
+) -> Result<()> {
+    let mut demodulator = FmDemodulator::new(sample_rate);
+    let mut resampler = Resampler::new(sample_rate, DECODER_RATE);
+    let mut buffer = vec![0u8; CHUNK];
+    let mut odd_byte = None;
+    loop {
+        let n = input.read(&mut buffer)?;
+        if n == 0 {
+            break;
+        }
+        let mut bytes: Vec<u8> = odd_byte.take().into_iter().collect();
+        bytes.extend_from_slice(&buffer[..n]);
+        if bytes.len() % 2 == 1 {
+            odd_byte = bytes.pop();
+        }
+        let audio = demodulator.process(&bytes);
+        write(&resampler.process(&audio))?;
+    }
+    Ok(())
+}
+
+/// Quadrature FM demodulator: the phase step between consecutive samples,
+/// scaled so `MAX_DEVIATION_HZ` is full scale.
+struct FmDemodulator {
+    gain: f32,
+    previous: (f32, f32),
+}
+
+impl FmDemodulator {
+    fn new(sample_rate: u32) -> Self {
+        FmDemodulator {
+            gain: sample_rate as f32 / (2.0 * std::f32::consts::PI * MAX_DEVIATION_HZ),
+            previous: (0.0, 0.0),
+        }
+    }
+
+    fn process(&mut self, iq: &[u8]) -> Vec<f32> {
+        iq.chunks_exact(2)
+            .map(|pair| {
+                let i = (pair[0] as f32 - 127.5) / 127.5;
+                let q = (pair[1] as f32 - 127.5) / 127.5;
+                let (pi, pq) = self.previous;
+                self.previous = (i, q);
+                // arg(z * conj(previous))
+                let phase = (q * pi - i * pq).atan2(i * pi + q * pq);
+                phase * self.gain
+            })
+            .collect()
+    }
+}
+
+/// Averages input over each output period (a crude low-pass) when
+/// downsampling, and holds the last value when upsampling.
+struct Resampler {
+    // Input samples per output sample
+    step: f64,
+    position: f64,
+    next_output: f64,
+    sum: f32,
+    count: u32,
+    last: f32,
+}
+
+impl Resampler {
+    fn new(from: u32, to: u32) -> Self {
+        let step = from as f64 / to as f64;
+        Resampler {
+            step,
+            position: 0.0,
+            next_output: step,
+            sum: 0.0,
+            count: 0,
+            last: 0.0,
+        }
+    }
+
+    fn process(&mut self, input: &[f32]) -> Vec<f32> {
+        let mut output = Vec::with_capacity((input.len() as f64 / self.step) as usize + 1);
+        for &sample in input {
+            self.sum += sample;
+            self.count += 1;
+            self.position += 1.0;
+            while self.position >= self.next_output {
+                if self.count > 0 {
+                    self.last = self.sum / self.count as f32;
+                    self.sum = 0.0;
+                    self.count = 0;
+                }
+                output.push(self.last);
+                self.next_output += self.step;
+            }
+        }
+        output
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn wav(sample_rate: u32, samples: &[i16]) -> Vec<u8> {
+        let data: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(b"RIFF");
+        bytes.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt ");
+        bytes.extend_from_slice(&16u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&sample_rate.to_le_bytes());
+        bytes.extend_from_slice(&(sample_rate * 2).to_le_bytes());
+        bytes.extend_from_slice(&2u16.to_le_bytes());
+        bytes.extend_from_slice(&16u16.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&data);
+        bytes
+    }
+
+    #[test]
+    fn test_wav_is_resampled_to_decoder_rate() {
+        let bytes = wav(44100, &[16384; 44100]);
+        let mut output = Vec::new();
+        stream_wav(&mut bytes.as_slice(), &mut |samples| {
+            output.extend_from_slice(samples);
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(output.len(), DECODER_RATE as usize);
+        assert!(output.iter().all(|s| (s - 0.5).abs() < 1e-3));
+    }
+
+    #[test]
+    fn test_fm_demodulates_a_constant_offset() {
+        // A carrier 3 kHz above the centre demodulates to a constant half of full scale
+        let rate = 240_000;
+        let iq: Vec<u8> = (0..rate / 10)
+            .flat_map(|n| {
+                let phase = 2.0 * std::f32::consts::PI * 3000.0 * n as f32 / rate as f32;
+                [(127.5 + 127.0 * phase.cos()) as u8, (127.5 + 127.0 * phase.sin()) as u8]
+            })
+            .collect();
+        let audio = FmDemodulator::new(rate).process(&iq);
+        let mean = audio[100..].iter().sum::<f32>() / (audio.len() - 100) as f32;
+        assert!((mean - 0.5).abs() < 0.01, "{}", mean);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_decode_pipes_audio_through_the_decoder() {
+        let path = std::env::temp_dir().join(format!("p2000_recording_test_{}.wav", std::process::id()));
+        std::fs::write(&path, wav(22050, &[0; 22050])).unwrap();
+        let config = SdrConfig {
+            multimon: vec![
+                "sh".to_string(),
+                "-c".to_string(),
+                "echo \"FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|$(wc -c)\"".to_string(),
+            ],
+            ..Default::default()
+        };
+        let lines = decode(&path, &config, &ProcessLog::new()).unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].rsplit('|').next().unwrap().trim(), "44100");
+        std::fs::remove_file(path).ok();
+    }
+}
@@ -0,0 +1,1054 @@
+//! Thin viewers for a daemon running elsewhere: the `server` sink streams every
+//! message over a WebSocket, and the `remote` source reads such a stream, so the
+//! Pi next to the antenna does the receiving and a laptop only shows the result.
+//!
+//! The viewer opens with a `subscribe` frame naming its protocol version and,
+//! after a reconnect, the last message it saw; the server answers `welcome`
+//! (or `error` for a version it does not speak), sends the recent messages the
+//! viewer missed, then every new one.
+//!
+//! A `query` in the subscribe frame (see [`crate::search`]) limits the stream
+//! to the messages it matches.
+//!
+//! Plain HTTP requests on the same port get a small dashboard instead: `/`
+//! lists the `[[server.views]]` and `/view/<name>` shows the live messages
+//! matching one of them, for a screen on the wall of a station.
+//!
+//! With `tokens` configured the subscribe frame must carry one of them. Every
+//! token may watch; `admin` tokens may also replace the daemon's alert rules
+//! with a `rules` frame. `[server.tls]` serves wss:// instead (feature `tls`).
+
+use anyhow::Result;
+#[cfg(feature = "remote")]
+use anyhow::{anyhow, bail, Context};
+use serde::Deserialize;
+#[cfg(feature = "remote")]
+use serde::Serialize;
+#[cfg(feature = "remote")]
+use std::collections::VecDeque;
+#[cfg(feature = "remote")]
+use std::io::{ErrorKind, Read, Write};
+#[cfg(feature = "remote")]
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::PathBuf;
+#[cfg(feature = "remote")]
+use std::sync::{mpsc, Arc, Mutex};
+#[cfg(feature = "remote")]
+use std::time::{Duration, Instant};
+#[cfg(feature = "remote")]
+use tungstenite::{stream::MaybeTlsStream, Message, WebSocket};
+
+use crate::config::Config;
+#[cfg(feature = "remote")]
+use crate::enrich::Enrichment;
+#[cfg(feature = "remote")]
+use crate::parser::{P2000Message, Receiver};
+use crate::plugin::Source;
+#[cfg(feature = "remote")]
+use crate::rules::Rule;
+use crate::search::Query;
+#[cfg(feature = "remote")]
+use crate::search::Subject;
+use crate::rules::SharedRules;
+use crate::sdr::ProcessLog;
+use crate::sinks::Sink;
+
+/// Bumped whenever a frame changes incompatibly
+pub const PROTOCOL_VERSION: u32 = 1;
+/// Idle connections are pinged this often, and viewers give up on a server
+/// that stays silent for twice as long
+#[cfg(feature = "remote")]
+const PING_INTERVAL: Duration = Duration::from_secs(20);
+#[cfg(feature = "remote")]
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// How long the server waits for a viewer's frames before passing on messages
+#[cfg(feature = "remote")]
+const POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// `[server]` section: where the daemon accepts viewers.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct ServerConfig {
+    pub listen: String,
+    /// Recent messages kept for viewers that connect or reconnect
+    pub backlog: usize,
+    /// Viewers must present one of these; without any, anyone who can reach
+    /// `listen` may watch, but nobody may change rules
+    pub tokens: Vec<Token>,
+    pub tls: Option<TlsConfig>,
+    /// Dashboard pages at `/view/<name>`
+    pub views: Vec<SavedView>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        ServerConfig {
+            listen: "127.0.0.1:8080".to_string(),
+            backlog: 500,
+            tokens: Vec::new(),
+            tls: None,
+            views: Vec::new(),
+        }
+    }
+}
+
+/// `[[server.tokens]]` entry.
+#[derive(Debug, Clone, Deserialize)]
+pub struct Token {
+    pub token: String,
+    #[serde(default)]
+    pub scope: Scope,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    /// Watch the message stream
+    #[default]
+    Read,
+    /// Also replace the alert rules
+    Admin,
+}
+
+/// `[[server.views]]` entry: a search with its own dashboard page.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SavedView {
+    /// Last part of the page's URL
+    pub name: String,
+    /// Page heading; the name when empty
+    #[serde(default)]
+    pub title: String,
+    pub query: Query,
+}
+
+/// `[server.tls]` section: PEM files, e.g. from Let's Encrypt.
+#[derive(Debug, Clone, Deserialize)]
+pub struct TlsConfig {
+    /// Certificate chain, leaf first
+    pub cert: PathBuf,
+    pub key: PathBuf,
+}
+
+#[cfg(feature = "remote")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ClientFrame {
+    Subscribe {
+        protocol: u32,
+        /// Last message seen, to resume without repeats
+        #[serde(default)]
+        resume: Option<Resume>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        token: Option<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        query: Option<Query>,
+    },
+    /// Replaces the daemon's alert rules until it restarts; admin tokens only
+    Rules { rules: Vec<Rule> },
+}
+
+#[cfg(feature = "remote")]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Resume {
+    session: String,
+    seq: u64,
+}
+
+#[cfg(feature = "remote")]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum ServerFrame {
+    Welcome {
+        protocol: u32,
+        server: String,
+        /// Changes when the server restarts; sequence numbers restart with it
+        session: String,
+    },
+    Message {
+        seq: u64,
+        message: Box<P2000Message>,
+        /// The server's enrichment, for other clients; viewers enrich locally
+        #[serde(default)]
+        enrichment: serde_json::Value,
+    },
+    /// Answer to a `rules` frame
+    Rules {
+        count: usize,
+    },
+    Error {
+        message: String,
+    },
+}
+
+/// `server` sink for every `[server]` section.
+pub fn server_from_config(config: &Config, rules: &SharedRules) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .server
+        .iter()
+        .map(|server| Ok(Box::new(ServerSink::start(server, rules.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+#[cfg(feature = "remote")]
+#[derive(Default)]
+struct Clients {
+    // Serialized `message` frames, oldest first
+    backlog: VecDeque<Backlogged>,
+    next_seq: u64,
+    // With the query each viewer subscribed with
+    streams: Vec<(mpsc::Sender<String>, Option<Query>)>,
+}
+
+#[cfg(feature = "remote")]
+struct Backlogged {
+    seq: u64,
+    frame: String,
+    // Kept to match the queries of viewers that connect later
+    message: P2000Message,
+    enrichment: Enrichment,
+}
+
+/// What every viewer thread shares.
+#[cfg(feature = "remote")]
+struct Shared {
+    clients: Mutex<Clients>,
+    session: String,
+    tokens: Vec<Token>,
+    tls: Option<TlsAcceptor>,
+    rules: SharedRules,
+    views: Vec<SavedView>,
+}
+
+#[cfg(feature = "remote")]
+impl Shared {
+    /// `None` for an unknown token. Without configured tokens everyone may watch.
+    fn scope(&self, token: Option<&str>) -> Option<Scope> {
+        if self.tokens.is_empty() {
+            return Some(Scope::Read);
+        }
+        let token = token?;
+        self.tokens
+            .iter()
+            .find(|known| same_token(&known.token, token))
+            .map(|known| known.scope)
+    }
+
+    /// Answer to a frame sent after the handshake.
+    fn handle(&self, frame: ClientFrame, scope: Scope) -> ServerFrame {
+        match frame {
+            ClientFrame::Subscribe { .. } => ServerFrame::Error {
+                message: "already subscribed".to_string(),
+            },
+            ClientFrame::Rules { .. } if scope != Scope::Admin => ServerFrame::Error {
+                message: "changing rules needs an admin token".to_string(),
+            },
+            ClientFrame::Rules { rules } => {
+                let count = rules.len();
+                *self.rules.write().unwrap_or_else(|e| e.into_inner()) = rules;
+                eprintln!("Alert rules replaced by a viewer ({} rules)", count);
+                ServerFrame::Rules { count }
+            }
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Shared {
+    /// Dashboard pages for plain HTTP requests. The pages themselves are
+    /// public; the messages come over the WebSocket, which checks the token
+    /// the page passes on from its own URL (`/view/grip?token=...`).
+    fn respond(&self, connection: &mut Connection, path: &str) -> Result<()> {
+        let view = path.strip_prefix("/view/").and_then(|name| self.views.iter().find(|v| v.name == name));
+        let (status, body) = match (path, view) {
+            ("/", _) => ("200 OK", self.index_page()),
+            (_, Some(view)) => ("200 OK", view_page(view)?),
+            _ => ("404 Not Found", "<!DOCTYPE html>\n<title>Not found</title>\n<p>No such view</p>\n".to_string()),
+        };
+        write!(
+            connection,
+            "HTTP/1.1 {}\r\nContent-Type: text/html; charset=utf-8\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        )?;
+        connection.flush()?;
+        Ok(())
+    }
+
+    fn index_page(&self) -> String {
+        let links: String = self
+            .views
+            .iter()
+            .map(|view| format!("<li><a href=\"/view/{}\">{}</a></li>\n", html_escape(&view.name), html_escape(view.title())))
+            .collect();
+        format!("<!DOCTYPE html>\n<meta charset=\"utf-8\">\n<title>P2000 views</title>\n<ul>\n{}</ul>\n", links)
+    }
+}
+
+impl SavedView {
+    pub fn title(&self) -> &str {
+        if self.title.is_empty() {
+            &self.name
+        } else {
+            &self.title
+        }
+    }
+}
+
+/// The live list for one saved view: the page subscribes with its query.
+#[cfg(feature = "remote")]
+fn view_page(view: &SavedView) -> Result<String> {
+    let subscribe = serde_json::to_string(&ClientFrame::Subscribe {
+        protocol: PROTOCOL_VERSION,
+        resume: None,
+        token: None,
+        query: Some(view.query.clone()),
+    })?;
+    Ok(DASHBOARD
+        .replace("{{title}}", &html_escape(view.title()))
+        // Keeps a query from closing the script element
+        .replace("{{subscribe}}", &subscribe.replace("</", "<\\/")))
+}
+
+#[cfg(feature = "remote")]
+const DASHBOARD: &str = include_str!("dashboard.html");
+
+#[cfg(feature = "remote")]
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+/// Compares in constant time, so response times do not give a token away.
+#[cfg(feature = "remote")]
+fn same_token(a: &str, b: &str) -> bool {
+    a.len() == b.len() && a.bytes().zip(b.bytes()).fold(0, |diff, (x, y)| diff | (x ^ y)) == 0
+}
+
+/// Streams every message to the connected viewers.
+#[cfg(feature = "remote")]
+pub struct ServerSink {
+    shared: Arc<Shared>,
+    backlog: usize,
+    local_addr: SocketAddr,
+}
+
+#[cfg(feature = "remote")]
+impl ServerSink {
+    /// Listens on `config.listen`; each viewer is served on its own thread.
+    pub fn start(config: &ServerConfig, rules: SharedRules) -> Result<Self> {
+        let tls = config.tls.as_ref().map(TlsAcceptor::load).transpose()?;
+        let listener = TcpListener::bind(&config.listen)
+            .with_context(|| format!("Failed to listen on {}", config.listen))?;
+        let local_addr = listener.local_addr()?;
+        if config.tokens.is_empty() && !local_addr.ip().is_loopback() {
+            eprintln!("Warning: the server on {} accepts viewers without a token", local_addr);
+        }
+        let shared = Arc::new(Shared {
+            clients: Mutex::new(Clients::default()),
+            session: chrono::Utc::now().timestamp_nanos_opt().unwrap_or_default().to_string(),
+            tokens: config.tokens.clone(),
+            tls,
+            rules,
+            views: config.views.clone(),
+        });
+        let accepting = shared.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let shared = accepting.clone();
+                std::thread::spawn(move || {
+                    let peer = stream.peer_addr().map(|a| a.to_string()).unwrap_or_default();
+                    if let Err(e) = serve(stream, &shared) {
+                        eprintln!("Viewer {} disconnected: {}", peer, e);
+                    }
+                });
+            }
+        });
+        Ok(ServerSink {
+            shared,
+            backlog: config.backlog,
+            local_addr,
+        })
+    }
+
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Sink for ServerSink {
+    fn name(&self) -> &str {
+        "server"
+    }
+
+    fn record(&self, msg: &P2000Message, enrichment: &Enrichment) -> Result<()> {
+        let mut clients = self.shared.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let seq = clients.next_seq;
+        clients.next_seq += 1;
+        let frame = serde_json::to_string(&ServerFrame::Message {
+            seq,
+            message: Box::new(msg.clone()),
+            enrichment: serde_json::to_value(enrichment)?,
+        })?;
+        let subject = Subject::message(msg).with_enrichment(enrichment);
+        // Viewers that went away are dropped here
+        clients.streams.retain(|(stream, query)| {
+            query.as_ref().is_some_and(|query| !query.matches(&subject)) || stream.send(frame.clone()).is_ok()
+        });
+        if clients.backlog.len() >= self.backlog {
+            clients.backlog.pop_front();
+        }
+        if self.backlog > 0 {
+            clients.backlog.push_back(Backlogged {
+                seq,
+                frame,
+                message: msg.clone(),
+                enrichment: enrichment.clone(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// A viewer's connection, encrypted when `[server.tls]` is set.
+#[cfg(feature = "remote")]
+enum Connection {
+    Plain(TcpStream),
+    #[cfg(feature = "tls")]
+    Tls(Box<rustls::StreamOwned<rustls::ServerConnection, TcpStream>>),
+}
+
+#[cfg(feature = "remote")]
+impl Connection {
+    fn tcp(&self) -> &TcpStream {
+        match self {
+            Connection::Plain(stream) => stream,
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => &stream.sock,
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Read for Connection {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.read(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.read(buf),
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Write for Connection {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        match self {
+            Connection::Plain(stream) => stream.write(buf),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.write(buf),
+        }
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        match self {
+            Connection::Plain(stream) => stream.flush(),
+            #[cfg(feature = "tls")]
+            Connection::Tls(stream) => stream.flush(),
+        }
+    }
+}
+
+#[cfg(feature = "tls")]
+struct TlsAcceptor(Arc<rustls::ServerConfig>);
+
+#[cfg(feature = "tls")]
+impl TlsAcceptor {
+    fn load(config: &TlsConfig) -> Result<Self> {
+        use rustls::pki_types::pem::PemObject;
+        use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+
+        let certs = CertificateDer::pem_file_iter(&config.cert)
+            .and_then(|certs| certs.collect::<std::result::Result<Vec<_>, _>>())
+            .with_context(|| format!("Failed to read certificates from {}", config.cert.display()))?;
+        let key = PrivateKeyDer::from_pem_file(&config.key)
+            .with_context(|| format!("Failed to read a private key from {}", config.key.display()))?;
+        let server = rustls::ServerConfig::builder()
+            .with_no_client_auth()
+            .with_single_cert(certs, key)?;
+        Ok(TlsAcceptor(Arc::new(server)))
+    }
+
+    fn accept(&self, stream: TcpStream) -> Result<Connection> {
+        let connection = rustls::ServerConnection::new(self.0.clone())?;
+        Ok(Connection::Tls(Box::new(rustls::StreamOwned::new(connection, stream))))
+    }
+}
+
+/// Stand-in without the `tls` feature: `load` fails, so there is never an instance.
+#[cfg(all(feature = "remote", not(feature = "tls")))]
+struct TlsAcceptor {
+    never: std::convert::Infallible,
+}
+
+#[cfg(all(feature = "remote", not(feature = "tls")))]
+impl TlsAcceptor {
+    fn load(_config: &TlsConfig) -> Result<Self> {
+        bail!("This build has no TLS; rebuild with --features tls")
+    }
+
+    fn accept(&self, _stream: TcpStream) -> Result<Connection> {
+        match self.never {}
+    }
+}
+
+/// Handshake, the backlog the viewer missed, then live messages and pings,
+/// answering the viewer's own frames in between.
+#[cfg(feature = "remote")]
+fn serve(stream: TcpStream, shared: &Shared) -> Result<()> {
+    let mut connection = match &shared.tls {
+        Some(tls) => tls.accept(stream)?,
+        None => Connection::Plain(stream),
+    };
+    let head = read_head(&mut connection)?;
+    let (path, websocket) = parse_head(&head)?;
+    if !websocket {
+        return shared.respond(&mut connection, &path);
+    }
+    // The handshake reads the request again
+    let replay = Replay {
+        head: std::io::Cursor::new(head),
+        connection,
+    };
+    let mut socket = tungstenite::accept(replay)?;
+    let subscribe = match socket.read()? {
+        Message::Text(text) => serde_json::from_str::<ClientFrame>(text.as_str()),
+        other => bail!("expected a subscribe frame, got {:?}", other),
+    };
+    let (resume, scope, query) = match subscribe {
+        Ok(ClientFrame::Subscribe { protocol, .. }) if protocol != PROTOCOL_VERSION => {
+            return refuse(
+                &mut socket,
+                format!("protocol {} is not supported; this server speaks {}", protocol, PROTOCOL_VERSION),
+            );
+        }
+        Ok(ClientFrame::Subscribe { resume, token, query, .. }) => match shared.scope(token.as_deref()) {
+            Some(scope) => (resume, scope, query),
+            None => return refuse(&mut socket, "invalid token".to_string()),
+        },
+        Ok(ClientFrame::Rules { .. }) => bail!("expected a subscribe frame"),
+        // Also a query that does not parse
+        Err(e) => return refuse(&mut socket, e.to_string()),
+    };
+    send(
+        &mut socket,
+        &ServerFrame::Welcome {
+            protocol: PROTOCOL_VERSION,
+            server: format!("p2000-rust {}", env!("CARGO_PKG_VERSION")),
+            session: shared.session.clone(),
+        },
+    )?;
+
+    let (tx, rx) = mpsc::channel();
+    let missed: Vec<String> = {
+        let mut clients = shared.clients.lock().unwrap_or_else(|e| e.into_inner());
+        let after = resume.filter(|r| r.session == shared.session).map(|r| r.seq);
+        let missed = clients
+            .backlog
+            .iter()
+            .filter(|b| after.is_none_or(|after| b.seq > after))
+            .filter(|b| {
+                query
+                    .as_ref()
+                    .is_none_or(|query| query.matches(&Subject::message(&b.message).with_enrichment(&b.enrichment)))
+            })
+            .map(|b| b.frame.clone())
+            .collect();
+        clients.streams.push((tx, query));
+        missed
+    };
+    for frame in missed {
+        socket.send(Message::text(frame))?;
+    }
+    socket.get_ref().connection.tcp().set_read_timeout(Some(POLL_INTERVAL))?;
+    let mut last_sent = Instant::now();
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => {
+                let reply = match serde_json::from_str::<ClientFrame>(text.as_str()) {
+                    Ok(frame) => shared.handle(frame, scope),
+                    Err(e) => ServerFrame::Error { message: e.to_string() },
+                };
+                send(&mut socket, &reply)?;
+            }
+            Ok(Message::Close(_)) => return Ok(()),
+            Ok(_) => {}
+            Err(tungstenite::Error::Io(e)) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => return Err(e.into()),
+        }
+        loop {
+            match rx.try_recv() {
+                Ok(frame) => {
+                    socket.send(Message::text(frame))?;
+                    last_sent = Instant::now();
+                }
+                Err(mpsc::TryRecvError::Empty) => break,
+                Err(mpsc::TryRecvError::Disconnected) => return Ok(()),
+            }
+        }
+        if last_sent.elapsed() >= PING_INTERVAL {
+            socket.send(Message::Ping(Default::default()))?;
+            last_sent = Instant::now();
+        }
+    }
+}
+
+/// Longest request head read before giving up on a client
+#[cfg(feature = "remote")]
+const MAX_HEAD: usize = 8192;
+
+/// The HTTP request head, up to and including the blank line.
+#[cfg(feature = "remote")]
+fn read_head(connection: &mut Connection) -> Result<Vec<u8>> {
+    let mut head = Vec::new();
+    let mut byte = [0u8];
+    while !head.ends_with(b"\r\n\r\n") {
+        if head.len() >= MAX_HEAD {
+            bail!("request head over {} bytes", MAX_HEAD);
+        }
+        if connection.read(&mut byte)? == 0 {
+            bail!("connection closed during the request");
+        }
+        head.push(byte[0]);
+    }
+    Ok(head)
+}
+
+/// Path without the query string, and whether this is a WebSocket upgrade.
+#[cfg(feature = "remote")]
+fn parse_head(head: &[u8]) -> Result<(String, bool)> {
+    let head = std::str::from_utf8(head).context("request head is not UTF-8")?;
+    let mut lines = head.lines();
+    let target = lines
+        .next()
+        .and_then(|line| line.split_whitespace().nth(1))
+        .ok_or_else(|| anyhow!("malformed request line"))?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+    let websocket = lines.any(|line| {
+        line.split_once(':').is_some_and(|(name, value)| {
+            name.trim().eq_ignore_ascii_case("upgrade") && value.trim().eq_ignore_ascii_case("websocket")
+        })
+    });
+    Ok((path, websocket))
+}
+
+/// A connection whose request head was already read, handing it out again first.
+#[cfg(feature = "remote")]
+struct Replay {
+    head: std::io::Cursor<Vec<u8>>,
+    connection: Connection,
+}
+
+#[cfg(feature = "remote")]
+impl Read for Replay {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        match self.head.read(buf)? {
+            0 => self.connection.read(buf),
+            n => Ok(n),
+        }
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Write for Replay {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.connection.write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.connection.flush()
+    }
+}
+
+/// Tells the viewer why it is turned away and hangs up.
+#[cfg(feature = "remote")]
+fn refuse<S: Read + Write>(socket: &mut WebSocket<S>, message: String) -> Result<()> {
+    send(socket, &ServerFrame::Error { message: message.clone() })?;
+    socket.close(None).ok();
+    bail!(message)
+}
+
+#[cfg(feature = "remote")]
+fn send<S: Read + Write>(socket: &mut WebSocket<S>, frame: &ServerFrame) -> Result<()> {
+    socket.send(Message::text(serde_json::to_string(frame)?))?;
+    Ok(())
+}
+
+/// Messages from another instance's `server` sink, as feed lines. Reconnects
+/// with backoff when the connection drops and resumes where it left off.
+#[cfg(feature = "remote")]
+pub struct RemoteSource {
+    url: String,
+    token: Option<String>,
+    query: Option<Query>,
+    socket: Option<WebSocket<MaybeTlsStream<TcpStream>>>,
+    // Server session and the last message seen in it
+    session: Option<String>,
+    last_seq: Option<u64>,
+    backoff: Duration,
+    log: ProcessLog,
+    // Of the message last returned
+    channel: Option<Arc<str>>,
+    receiver: Option<Arc<Receiver>>,
+}
+
+#[cfg(feature = "remote")]
+impl RemoteSource {
+    /// Connects on the first read; connection changes go to `log`.
+    pub fn new(url: &str, log: ProcessLog) -> Self {
+        RemoteSource {
+            url: url.to_string(),
+            token: None,
+            query: None,
+            socket: None,
+            session: None,
+            last_seq: None,
+            backoff: Duration::from_secs(1),
+            log,
+            channel: None,
+            receiver: None,
+        }
+    }
+
+    /// Presented to servers that require one.
+    pub fn with_token(mut self, token: Option<String>) -> Self {
+        self.token = token;
+        self
+    }
+
+    /// Asks the server for only the messages matching `query`.
+    pub fn with_query(mut self, query: Option<Query>) -> Self {
+        self.query = query;
+        self
+    }
+
+    /// Opens the socket and completes the handshake. Errors from the server
+    /// itself (an unsupported protocol, a wrong token) are returned as
+    /// `Ok(Err)`: retrying will not help.
+    fn connect(&mut self) -> Result<std::result::Result<(), anyhow::Error>> {
+        let (mut socket, _) = tungstenite::connect(self.url.as_str())?;
+        if let Some(stream) = tcp_stream(socket.get_ref()) {
+            stream.set_read_timeout(Some(PING_INTERVAL * 2))?;
+        }
+        let subscribe = ClientFrame::Subscribe {
+            protocol: PROTOCOL_VERSION,
+            resume: self.session.clone().zip(self.last_seq).map(|(session, seq)| Resume { session, seq }),
+            token: self.token.clone(),
+            query: self.query.clone(),
+        };
+        socket.send(Message::text(serde_json::to_string(&subscribe)?))?;
+        let Message::Text(text) = socket.read()? else {
+            bail!("expected a welcome frame");
+        };
+        match serde_json::from_str::<ServerFrame>(text.as_str())? {
+            ServerFrame::Welcome { protocol, .. } if protocol != PROTOCOL_VERSION => Ok(Err(anyhow!(
+                "{} speaks protocol {}, this viewer {}",
+                self.url,
+                protocol,
+                PROTOCOL_VERSION
+            ))),
+            ServerFrame::Welcome { server, session, .. } => {
+                self.log.push(format!("Connected to {} ({})", self.url, server));
+                if self.session.as_ref().is_some_and(|known| *known != session) {
+                    self.log.push(format!("{} restarted; its recent messages follow", self.url));
+                    self.last_seq = None;
+                }
+                self.session = Some(session);
+                self.socket = Some(socket);
+                self.backoff = Duration::from_secs(1);
+                Ok(Ok(()))
+            }
+            ServerFrame::Error { message } => Ok(Err(anyhow!("{}: {}", self.url, message))),
+            ServerFrame::Message { .. } | ServerFrame::Rules { .. } => bail!("expected a welcome frame"),
+        }
+    }
+
+    fn retry_later(&mut self, error: anyhow::Error) {
+        self.socket = None;
+        self.log.push(format!(
+            "Connection to {} failed: {}; retrying in {}s",
+            self.url,
+            error,
+            self.backoff.as_secs()
+        ));
+        std::thread::sleep(self.backoff);
+        self.backoff = (self.backoff * 2).min(MAX_BACKOFF);
+    }
+}
+
+#[cfg(feature = "remote")]
+fn tcp_stream(stream: &MaybeTlsStream<TcpStream>) -> Option<&TcpStream> {
+    match stream {
+        MaybeTlsStream::Plain(stream) => Some(stream),
+        #[cfg(feature = "tls")]
+        MaybeTlsStream::Rustls(stream) => Some(&stream.sock),
+        _ => None,
+    }
+}
+
+#[cfg(feature = "remote")]
+impl Source for RemoteSource {
+    fn name(&self) -> &str {
+        "remote"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        loop {
+            let Some(socket) = &mut self.socket else {
+                match self.connect() {
+                    Ok(result) => result?,
+                    Err(e) => self.retry_later(e),
+                }
+                continue;
+            };
+            let text = match socket.read() {
+                Ok(Message::Text(text)) => text,
+                Ok(Message::Close(_)) => {
+                    self.retry_later(anyhow!("closed by the server"));
+                    continue;
+                }
+                Ok(_) => continue,
+                Err(e) => {
+                    self.retry_later(e.into());
+                    continue;
+                }
+            };
+            match serde_json::from_str::<ServerFrame>(text.as_str())? {
+                ServerFrame::Message { seq, message, .. } => {
+                    self.last_seq = Some(seq);
+                    self.channel = message.channel.clone();
+                    self.receiver = message.receiver.clone();
+                    return Ok(Some(message.to_line()));
+                }
+                ServerFrame::Error { message } => bail!("{}: {}", self.url, message),
+                ServerFrame::Welcome { .. } | ServerFrame::Rules { .. } => {}
+            }
+        }
+    }
+
+    fn channel(&self) -> Option<Arc<str>> {
+        self.channel.clone()
+    }
+
+    fn receiver(&self) -> Option<Arc<Receiver>> {
+        self.receiver.clone()
+    }
+}
+
+/// Stand-in without the `remote` feature: `start` fails, so there is never an instance.
+#[cfg(not(feature = "remote"))]
+pub struct ServerSink {
+    never: std::convert::Infallible,
+}
+
+#[cfg(not(feature = "remote"))]
+impl ServerSink {
+    pub fn start(_config: &ServerConfig, _rules: SharedRules) -> Result<Self> {
+        anyhow::bail!("This build has no server; rebuild with --features remote")
+    }
+}
+
+#[cfg(not(feature = "remote"))]
+impl Sink for ServerSink {
+    fn name(&self) -> &str {
+        match self.never {}
+    }
+}
+
+/// Stand-in without the `remote` feature: reading fails.
+#[cfg(not(feature = "remote"))]
+pub struct RemoteSource {
+    _log: ProcessLog,
+}
+
+#[cfg(not(feature = "remote"))]
+impl RemoteSource {
+    pub fn new(_url: &str, log: ProcessLog) -> Self {
+        RemoteSource { _log: log }
+    }
+
+    pub fn with_token(self, _token: Option<String>) -> Self {
+        self
+    }
+
+    pub fn with_query(self, _query: Option<Query>) -> Self {
+        self
+    }
+}
+
+#[cfg(not(feature = "remote"))]
+impl Source for RemoteSource {
+    fn name(&self) -> &str {
+        "remote"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        anyhow::bail!("This build has no remote source; rebuild with --features remote")
+    }
+}
+
+#[cfg(all(test, feature = "remote"))]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_viewer_resumes_after_reconnect() {
+        let server = ServerSink::start(
+            &ServerConfig {
+                listen: "127.0.0.1:0".to_string(),
+                ..Default::default()
+            },
+            SharedRules::default(),
+        )
+        .unwrap();
+        let parser = Parser::new();
+        let lines = [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:01:29|1600/2/K/A|00.052|002029568|ALN|A1 Tilburg rit: 632",
+        ];
+        let mut msg = parser.parse_line(lines[0]).unwrap();
+        msg.receiver = Some(Arc::new(Receiver {
+            callsign: "PA3XYZ".to_string(),
+            ..Default::default()
+        }));
+        server.record(&msg, &Enrichment::default()).unwrap();
+
+        let url = format!("ws://{}", server.local_addr());
+        let mut viewer = RemoteSource::new(&url, ProcessLog::new());
+        // Sent from the backlog
+        assert_eq!(viewer.next_line().unwrap().as_deref(), Some(parser.parse_line(lines[0]).unwrap().to_line().as_str()));
+        assert_eq!(viewer.receiver().map(|r| r.callsign.clone()).as_deref(), Some("PA3XYZ"));
+        server.record(&parser.parse_line(lines[1]).unwrap(), &Enrichment::default()).unwrap();
+        assert_eq!(viewer.next_line().unwrap().as_deref(), Some(parser.parse_line(lines[1]).unwrap().to_line().as_str()));
+
+        // Dropped connection: only what was missed comes again
+        viewer.socket = None;
+        server.record(&parser.parse_line(lines[2]).unwrap(), &Enrichment::default()).unwrap();
+        assert_eq!(viewer.next_line().unwrap().as_deref(), Some(parser.parse_line(lines[2]).unwrap().to_line().as_str()));
+
+        // A viewer asking for some messages only gets those
+        let mut filtered = RemoteSource::new(&url, ProcessLog::new()).with_query(Some("prio:A2".parse().unwrap()));
+        assert_eq!(filtered.next_line().unwrap().as_deref(), Some(parser.parse_line(lines[1]).unwrap().to_line().as_str()));
+
+        let mut socket = tungstenite::connect(url.as_str()).unwrap().0;
+        let subscribe = ClientFrame::Subscribe {
+            protocol: PROTOCOL_VERSION + 1,
+            resume: None,
+            token: None,
+            query: None,
+        };
+        socket.send(Message::text(serde_json::to_string(&subscribe).unwrap())).unwrap();
+        assert!(matches!(reply(&mut socket), ServerFrame::Error { .. }));
+    }
+
+    #[test]
+    fn test_tokens_limit_viewers_and_rule_changes() {
+        let rules = SharedRules::default();
+        let server = ServerSink::start(
+            &ServerConfig {
+                listen: "127.0.0.1:0".to_string(),
+                tokens: vec![
+                    Token {
+                        token: "watch".to_string(),
+                        scope: Scope::Read,
+                    },
+                    Token {
+                        token: "manage".to_string(),
+                        scope: Scope::Admin,
+                    },
+                ],
+                ..Default::default()
+            },
+            rules.clone(),
+        )
+        .unwrap();
+        let url = format!("ws://{}", server.local_addr());
+
+        let mut outsider = RemoteSource::new(&url, ProcessLog::new()).with_token(Some("guess".to_string()));
+        assert!(outsider.next_line().unwrap_err().to_string().contains("invalid token"));
+
+        let new_rules = ClientFrame::Rules {
+            rules: vec![Rule {
+                name: "Brand".to_string(),
+                keywords: vec!["brand".to_string()],
+                ..Default::default()
+            }],
+        };
+        let mut viewer = subscribe(&url, "watch");
+        viewer.send(Message::text(serde_json::to_string(&new_rules).unwrap())).unwrap();
+        assert!(matches!(reply(&mut viewer), ServerFrame::Error { .. }));
+        assert!(rules.read().unwrap().is_empty());
+
+        let mut admin = subscribe(&url, "manage");
+        admin.send(Message::text(serde_json::to_string(&new_rules).unwrap())).unwrap();
+        assert!(matches!(reply(&mut admin), ServerFrame::Rules { count: 1 }));
+        assert_eq!(rules.read().unwrap()[0].name, "Brand");
+    }
+
+    #[test]
+    fn test_dashboard_pages() {
+        let server = ServerSink::start(
+            &ServerConfig {
+                listen: "127.0.0.1:0".to_string(),
+                views: vec![SavedView {
+                    name: "grip".to_string(),
+                    title: "GRIP <alle regio's>".to_string(),
+                    query: "grip OR \"</script>\"".parse().unwrap(),
+                }],
+                ..Default::default()
+            },
+            SharedRules::default(),
+        )
+        .unwrap();
+        let get = |path: &str| {
+            let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+            write!(stream, "GET {} HTTP/1.1\r\nHost: pi\r\n\r\n", path).unwrap();
+            let mut response = String::new();
+            stream.read_to_string(&mut response).unwrap();
+            response
+        };
+
+        let index = get("/");
+        assert!(index.starts_with("HTTP/1.1 200 OK"));
+        assert!(index.contains(r#"<a href="/view/grip">GRIP &lt;alle regio's&gt;</a>"#));
+        let page = get("/view/grip?token=abc");
+        assert!(page.contains(r#""query":"grip OR <\/script>""#));
+        assert!(!page.contains("{{"));
+        assert!(get("/view/knrm").starts_with("HTTP/1.1 404"));
+
+        // WebSocket upgrades on the same port still work
+        subscribe(&format!("ws://{}/", server.local_addr()), "any");
+    }
+
+    fn subscribe(url: &str, token: &str) -> WebSocket<MaybeTlsStream<TcpStream>> {
+        let mut socket = tungstenite::connect(url).unwrap().0;
+        let subscribe = ClientFrame::Subscribe {
+            protocol: PROTOCOL_VERSION,
+            resume: None,
+            token: Some(token.to_string()),
+            query: None,
+        };
+        socket.send(Message::text(serde_json::to_string(&subscribe).unwrap())).unwrap();
+        assert!(matches!(reply(&mut socket), ServerFrame::Welcome { .. }));
+        socket
+    }
+
+    fn reply(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>) -> ServerFrame {
+        match socket.read().unwrap() {
+            Message::Text(text) => serde_json::from_str(text.as_str()).unwrap(),
+            other => panic!("expected a frame, got {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,139 @@
+//! Running totals for the current day: urgent messages, reanimations, GRIP
+//! escalations and the busiest region. Messages are counted as they arrive and
+//! the counts start over on the first message (or clock check) of a new day.
+
+use chrono::{DateTime, Local, NaiveDate};
+use std::collections::HashMap;
+use std::fmt;
+
+use crate::parser::{P2000Message, TimeSource};
+use crate::rules;
+use crate::severity;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct DailyTotals {
+    /// Local day being counted; `None` before the first message
+    day: Option<NaiveDate>,
+    pub urgent: usize,
+    pub reanimations: usize,
+    pub grips: usize,
+    regions: HashMap<String, usize>,
+}
+
+impl DailyTotals {
+    /// Counts a message on the local day it was received, or decoded when it
+    /// was not read live. Messages from before the current day are left out.
+    pub fn add(&mut self, msg: &P2000Message, region: Option<&str>) {
+        let day = msg.time(TimeSource::Received).date_naive();
+        self.roll_over(day);
+        if self.day != Some(day) {
+            return;
+        }
+        if msg.priority.as_deref().is_some_and(rules::is_urgent) {
+            self.urgent += 1;
+        }
+        let words: Vec<String> = msg
+            .content
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .map(str::to_lowercase)
+            .collect();
+        if words.iter().any(|w| w == "reanimatie" || w == "rea") {
+            self.reanimations += 1;
+        }
+        if severity::grip_level(&msg.content).is_some() {
+            self.grips += 1;
+        }
+        if let Some(region) = region {
+            *self.regions.entry(region.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Starts over when `now` is past midnight of the day being counted.
+    pub fn roll(&mut self, now: DateTime<Local>) {
+        self.roll_over(now.date_naive());
+    }
+
+    fn roll_over(&mut self, day: NaiveDate) {
+        if self.day.is_none_or(|current| day > current) {
+            *self = DailyTotals {
+                day: Some(day),
+                ..Default::default()
+            };
+        }
+    }
+
+    pub fn day(&self) -> Option<NaiveDate> {
+        self.day
+    }
+
+    /// Region with the most messages, ties going to the first name.
+    pub fn busiest_region(&self) -> Option<(&str, usize)> {
+        self.regions
+            .iter()
+            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+            .map(|(name, count)| (name.as_str(), *count))
+    }
+}
+
+impl fmt::Display for DailyTotals {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let Some(day) = self.day else {
+            return write!(f, "No messages yet");
+        };
+        write!(
+            f,
+            "{}: {} A1/P 1 | {} reanimations | {} GRIP",
+            day.format("%a %d %b"),
+            self.urgent,
+            self.reanimations,
+            self.grips
+        )?;
+        if let Some((region, count)) = self.busiest_region() {
+            write!(f, " | busiest: {} ({})", region, count)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_totals_count_and_reset_at_midnight() {
+        // Feed times are UTC; take them as local so the day is the same in any zone
+        let parser = Parser::new();
+        let parse = |line: &str| {
+            let mut msg = parser.parse_line(line).unwrap();
+            msg.timestamp = Local.from_local_datetime(&msg.timestamp.naive_utc()).unwrap();
+            msg
+        };
+        let mut totals = DailyTotals::default();
+        assert_eq!(totals.to_string(), "No messages yet");
+        let lines = [
+            ("FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|A1 Reanimatie Vlaardingen", "Rotterdam-Rijnmond"),
+            ("FLEX|2026-01-01 20:01:50|1600/2/K/A|00.050|001123117|ALN|P 1 BDH-01 GRIP1 Brand Den Haag", "Haaglanden"),
+            ("FLEX|2026-01-01 20:02:16|1600/2/K/A|00.051|000726119|ALN|A2 REA Rotterdam", "Rotterdam-Rijnmond"),
+            ("FLEX|2026-01-01 20:03:27|1600/2/K/A|00.051|000726119|ALN|B1 Grip op de zaak", "Haaglanden"),
+        ];
+        for (line, region) in lines {
+            totals.add(&parse(line), Some(region));
+        }
+        assert_eq!((totals.urgent, totals.reanimations, totals.grips), (2, 2, 1));
+        assert_eq!(totals.busiest_region(), Some(("Haaglanden", 2)));
+        assert_eq!(
+            totals.to_string(),
+            "Thu 01 Jan: 2 A1/P 1 | 2 reanimations | 1 GRIP | busiest: Haaglanden (2)"
+        );
+
+        // Late messages from the previous day don't count towards the new one
+        totals.roll(Local.with_ymd_and_hms(2026, 1, 2, 0, 0, 1).unwrap());
+        assert_eq!(totals.urgent, 0);
+        totals.add(&parse(lines[0].0), None);
+        assert_eq!(totals.urgent, 0);
+        assert_eq!(totals.day(), NaiveDate::from_ymd_opt(2026, 1, 2));
+    }
+}
@@ -0,0 +1,529 @@
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveTime};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::io::Write;
+use std::path::PathBuf;
+use std::sync::{Arc, RwLock};
+
+use crate::enrich::Enrichment;
+use crate::lookup::normalize_code;
+use crate::parser::{P2000Message, TimeSource};
+use crate::search::{Query, Subject};
+use crate::sinks::AlertPriority;
+
+pub const USAGE: &str = "Usage: p2000-rust rules test --input <file> [--examples <n>]
+Runs the configured [[rules]] over the messages in <file> as the daemon would, quiet hours and \
+cooldowns included, and reports how often each rule would have pushed, with the first <n> \
+pushes (default 3) as examples. Nothing is sent.";
+
+/// A user-defined alert rule. Every non-empty criterion must match; within a
+/// criterion any entry may match.
+#[derive(Debug, Clone, Default, Deserialize, Serialize)]
+#[serde(default)]
+pub struct Rule {
+    pub name: String,
+    pub priorities: Vec<String>,
+    pub keywords: Vec<String>,
+    pub capcodes: Vec<String>,
+    /// Receiver channel names (see the `sdr` source)
+    pub channels: Vec<String>,
+    /// Categories of abbreviations found in the content, e.g. "medical"
+    pub categories: Vec<String>,
+    /// Names of `[[geofences]]` the message must be inside one of
+    pub geofences: Vec<String>,
+    /// Structured search the message must also match, e.g. `prio:A1 AND region:Haaglanden`
+    pub query: Option<Query>,
+    /// Lowest severity score to match; needs the `severity` enricher
+    pub min_severity: Option<u32>,
+    // Sink routing
+    pub topic: Option<String>,
+    pub priority: Option<AlertPriority>,
+    /// WAV or Ogg Vorbis file played on a match when `[audio]` is configured
+    pub sound: Option<PathBuf>,
+    /// Hours without sound or push for this rule, e.g. at night
+    pub quiet: Option<QuietHours>,
+    /// Minutes after a push in which further matches are not sent
+    pub cooldown_mins: Option<u64>,
+    /// One louder push when matches keep coming, even during the cooldown
+    pub escalate: Option<Escalation>,
+}
+
+/// When a burst of matches counts as a growing incident: `count` matches within
+/// `within_mins` minutes.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct Escalation {
+    pub count: usize,
+    pub within_mins: u64,
+    /// Push priority of the escalation
+    #[serde(default = "urgent")]
+    pub priority: AlertPriority,
+}
+
+fn urgent() -> AlertPriority {
+    AlertPriority::Urgent
+}
+
+/// When a rule keeps quiet: from `from` until `until` by the local clock, across
+/// midnight when `from` is later. Messages matching `except` still alert.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+pub struct QuietHours {
+    pub from: NaiveTime,
+    pub until: NaiveTime,
+    /// Structured search for what may still wake you, e.g. `reanimatie OR capcode:1420028`
+    #[serde(default)]
+    pub except: Option<Query>,
+}
+
+impl QuietHours {
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        if self.from <= self.until {
+            self.from <= time && time < self.until
+        } else {
+            time >= self.from || time < self.until
+        }
+    }
+
+    /// Whether a message received during these hours should stay silent.
+    fn silences(&self, msg: &P2000Message, enrichment: &Enrichment) -> bool {
+        self.contains(msg.time(TimeSource::Received).time())
+            && !self
+                .except
+                .as_ref()
+                .is_some_and(|except| except.matches(&Subject::message(msg).with_enrichment(enrichment)))
+    }
+}
+
+/// The daemon's rules; an admin connected to the `server` sink may replace them.
+pub type SharedRules = Arc<RwLock<Vec<Rule>>>;
+
+impl Rule {
+    pub fn matches(&self, msg: &P2000Message, enrichment: &Enrichment) -> bool {
+        if !self.priorities.is_empty() {
+            let Some(priority) = msg.priority.as_deref() else {
+                return false;
+            };
+            let priority = normalize_priority(priority);
+            if !self
+                .priorities
+                .iter()
+                .any(|p| normalize_priority(p) == priority)
+            {
+                return false;
+            }
+        }
+
+        if !self.keywords.is_empty() {
+            let content = msg.content.to_lowercase();
+            if !self
+                .keywords
+                .iter()
+                .any(|k| content.contains(&k.to_lowercase()))
+            {
+                return false;
+            }
+        }
+
+        if !self.capcodes.is_empty()
+            && !self.capcodes.iter().any(|c| {
+                msg.capcodes
+                    .iter()
+                    .any(|m| normalize_code(m) == normalize_code(c))
+            })
+        {
+            return false;
+        }
+
+        if !self.channels.is_empty()
+            && !msg
+                .channel
+                .as_deref()
+                .is_some_and(|channel| self.channels.iter().any(|c| c.eq_ignore_ascii_case(channel)))
+        {
+            return false;
+        }
+
+        if !self.categories.is_empty()
+            && !enrichment.abbreviations.iter().any(|expansion| {
+                expansion
+                    .category
+                    .as_deref()
+                    .is_some_and(|category| self.categories.iter().any(|c| c.eq_ignore_ascii_case(category)))
+            })
+        {
+            return false;
+        }
+
+        if !self.geofences.is_empty()
+            && !enrichment
+                .geofences
+                .iter()
+                .any(|inside| self.geofences.iter().any(|g| g.eq_ignore_ascii_case(inside)))
+        {
+            return false;
+        }
+
+        if let Some(query) = &self.query {
+            if !query.matches(&Subject::message(msg).with_enrichment(enrichment)) {
+                return false;
+            }
+        }
+
+        if self.min_severity.is_some_and(|min| enrichment.severity.unwrap_or(0) < min) {
+            return false;
+        }
+
+        true
+    }
+
+    /// Whether a message should set off this rule's alerts: it matches and does
+    /// not fall in the rule's quiet hours.
+    pub fn alerts(&self, msg: &P2000Message, enrichment: &Enrichment) -> bool {
+        self.matches(msg, enrichment) && !self.quiet.as_ref().is_some_and(|quiet| quiet.silences(msg, enrichment))
+    }
+
+    /// The rule as sent when it escalates: named after the burst, with the
+    /// escalation's priority.
+    pub fn escalated(&self, count: usize) -> Rule {
+        let Some(escalation) = &self.escalate else {
+            return self.clone();
+        };
+        Rule {
+            name: format!("{} ({} in {} min)", self.name, count, escalation.within_mins),
+            priority: Some(escalation.priority),
+            ..self.clone()
+        }
+    }
+
+    /// Alert priority for a match: the rule override, or one derived from the message.
+    pub fn alert_priority(&self, msg: &P2000Message) -> AlertPriority {
+        self.priority
+            .unwrap_or_else(|| AlertPriority::from_message(msg))
+    }
+}
+
+/// Rules that alert for `msg`, leaving out those in their quiet hours.
+pub fn matching_rules<'a>(
+    rules: &'a [Rule],
+    msg: &'a P2000Message,
+    enrichment: &'a Enrichment,
+) -> impl Iterator<Item = &'a Rule> + 'a {
+    rules.iter().filter(move |rule| rule.alerts(msg, enrichment))
+}
+
+/// What to do with a rule match, given the rule's earlier matches.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Decision {
+    Send,
+    /// Send as `Rule::escalated`, for this many matches in the window
+    Escalate(usize),
+    /// Within the cooldown of the previous push
+    Suppress,
+}
+
+/// Recent matches per rule, for cooldowns and escalation. Rules are told apart
+/// by name, so the history outlives a remote admin replacing the rules.
+#[derive(Debug, Default)]
+pub struct AlertThrottle {
+    rules: HashMap<String, RuleHistory>,
+}
+
+#[derive(Debug, Default)]
+struct RuleHistory {
+    sent: Option<DateTime<Local>>,
+    escalated: Option<DateTime<Local>>,
+    // Within the escalation window, oldest first
+    matches: VecDeque<DateTime<Local>>,
+}
+
+impl AlertThrottle {
+    /// Records a match of `rule` at `time`. It escalates once per window when
+    /// the matches reach the escalation count; otherwise it is suppressed during
+    /// the cooldown after the previous push.
+    pub fn decide(&mut self, rule: &Rule, time: DateTime<Local>) -> Decision {
+        if rule.cooldown_mins.is_none() && rule.escalate.is_none() {
+            return Decision::Send;
+        }
+        let history = self.rules.entry(rule.name.clone()).or_default();
+        if let Some(escalation) = &rule.escalate {
+            let window = Duration::minutes(escalation.within_mins as i64);
+            history.matches.push_back(time);
+            while history.matches.front().is_some_and(|&first| time - first >= window) {
+                history.matches.pop_front();
+            }
+            let escalated_recently = history.escalated.is_some_and(|at| time - at < window);
+            if history.matches.len() >= escalation.count && !escalated_recently {
+                history.escalated = Some(time);
+                history.sent = Some(time);
+                return Decision::Escalate(history.matches.len());
+            }
+        }
+        let cooling = rule
+            .cooldown_mins
+            .is_some_and(|mins| history.sent.is_some_and(|sent| time - sent < Duration::minutes(mins as i64)));
+        if cooling {
+            return Decision::Suppress;
+        }
+        history.sent = Some(time);
+        Decision::Send
+    }
+}
+
+/// What a rule would have done over past messages.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RuleReport {
+    pub name: String,
+    pub matches: usize,
+    /// Matches in the rule's quiet hours
+    pub quiet: usize,
+    /// Matches held back by the cooldown
+    pub held_back: usize,
+    pub pushes: usize,
+    /// Pushes that were escalations
+    pub escalations: usize,
+    /// The first pushes, as time and content
+    pub examples: Vec<(DateTime<Local>, String)>,
+}
+
+/// Runs `rules` over past messages, oldest first, the way the daemon would
+/// dispatch them, keeping up to `examples` pushes per rule.
+pub fn dry_run<'a>(
+    rules: &[Rule],
+    messages: impl IntoIterator<Item = (&'a P2000Message, Enrichment)>,
+    examples: usize,
+) -> Vec<RuleReport> {
+    let mut reports: Vec<RuleReport> = rules
+        .iter()
+        .map(|rule| RuleReport {
+            name: rule.name.clone(),
+            ..Default::default()
+        })
+        .collect();
+    let mut throttle = AlertThrottle::default();
+    for (msg, enrichment) in messages {
+        for (rule, report) in rules.iter().zip(&mut reports) {
+            if !rule.matches(msg, &enrichment) {
+                continue;
+            }
+            report.matches += 1;
+            if !rule.alerts(msg, &enrichment) {
+                report.quiet += 1;
+                continue;
+            }
+            match throttle.decide(rule, msg.time(TimeSource::Received)) {
+                Decision::Suppress => {
+                    report.held_back += 1;
+                    continue;
+                }
+                Decision::Escalate(_) => report.escalations += 1,
+                Decision::Send => {}
+            }
+            report.pushes += 1;
+            if report.examples.len() < examples {
+                report.examples.push((msg.time(TimeSource::Received), msg.content.clone()));
+            }
+        }
+    }
+    reports
+}
+
+/// A table of the reports, each followed by its examples.
+pub fn print_reports(reports: &[RuleReport], messages: usize, out: &mut impl Write) -> Result<()> {
+    writeln!(
+        out,
+        "{:>7} | {:>6} | {:>9} | {:>5} | {:>9} | Rule",
+        "Matches", "Pushes", "Escalated", "Quiet", "Held back"
+    )?;
+    for report in reports {
+        writeln!(
+            out,
+            "{:>7} | {:>6} | {:>9} | {:>5} | {:>9} | {}",
+            report.matches, report.pushes, report.escalations, report.quiet, report.held_back, report.name
+        )?;
+        for (time, content) in &report.examples {
+            writeln!(out, "        {} {}", time.format("%Y-%m-%d %H:%M:%S"), content)?;
+        }
+    }
+    writeln!(out, "{} rules over {} messages", reports.len(), messages)?;
+    Ok(())
+}
+
+/// "P 2" and "p2" both become "P2".
+pub fn normalize_priority(priority: &str) -> String {
+    priority
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+/// A1 and P 1: the most urgent ambulance and fire brigade calls.
+pub fn is_urgent(priority: &str) -> bool {
+    matches!(normalize_priority(priority).as_str(), "A1" | "P1")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::enrich::Expansion;
+    use crate::parser::Parser;
+    use chrono::TimeZone;
+
+    #[test]
+    fn test_rule_matching() {
+        let parser = Parser::new();
+        let mut msg = parser
+            .parse_line("FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568 001420028|ALN|A1 Reanimatie Nassaulaan Vlaardingen")
+            .expect("Failed to parse");
+        let mut enrichment = Enrichment::default();
+
+        let rule = Rule {
+            name: "rea".to_string(),
+            priorities: vec!["a1".to_string()],
+            keywords: vec!["reanimatie".to_string()],
+            ..Default::default()
+        };
+        assert!(rule.matches(&msg, &enrichment));
+
+        let capcode_rule = Rule {
+            capcodes: vec!["1420028".to_string()],
+            ..Default::default()
+        };
+        assert!(capcode_rule.matches(&msg, &enrichment));
+
+        let other = Rule {
+            priorities: vec!["P 1".to_string()],
+            ..Default::default()
+        };
+        assert!(!other.matches(&msg, &enrichment));
+
+        let channel_rule = Rule {
+            channels: vec!["p2000".to_string()],
+            ..Default::default()
+        };
+        assert!(!channel_rule.matches(&msg, &enrichment));
+        msg.channel = Some("P2000".into());
+        assert!(channel_rule.matches(&msg, &enrichment));
+
+        let query_rule = Rule {
+            query: Some("prio:A1 AND (reanimatie OR rea) NOT capcode:1420028".parse().unwrap()),
+            ..Default::default()
+        };
+        assert!(!query_rule.matches(&msg, &enrichment));
+
+        let category_rule = Rule {
+            categories: vec!["Medical".to_string()],
+            ..Default::default()
+        };
+        assert!(!category_rule.matches(&msg, &enrichment));
+        enrichment.abbreviations.push(Expansion {
+            abbreviation: "REA".to_string(),
+            expansion: "Reanimatie".to_string(),
+            category: Some("medical".into()),
+        });
+        assert!(category_rule.matches(&msg, &enrichment));
+
+        let severe_rule = Rule {
+            min_severity: Some(50),
+            ..Default::default()
+        };
+        assert!(!severe_rule.matches(&msg, &enrichment));
+        enrichment.severity = Some(55);
+        assert!(severe_rule.matches(&msg, &enrichment));
+
+        // Sent at 20:00 local time, so quiet from 19:00 until 07:00 the next morning
+        msg.timestamp = Local.with_ymd_and_hms(2026, 1, 1, 20, 0, 51).unwrap();
+        let mut night_rule: Rule = toml::from_str(
+            "priorities = [\"A1\"]\nquiet = { from = \"19:00\", until = \"07:00\", except = \"capcode:1420999\" }",
+        )
+        .unwrap();
+        assert!(night_rule.matches(&msg, &enrichment));
+        assert!(!night_rule.alerts(&msg, &enrichment));
+        night_rule.quiet.as_mut().unwrap().except = Some("reanimatie".parse().unwrap());
+        assert!(night_rule.alerts(&msg, &enrichment));
+        let quiet = QuietHours {
+            from: NaiveTime::from_hms_opt(13, 0, 0).unwrap(),
+            until: NaiveTime::from_hms_opt(14, 30, 0).unwrap(),
+            except: None,
+        };
+        assert!(quiet.contains(NaiveTime::from_hms_opt(13, 59, 0).unwrap()));
+        assert!(!quiet.contains(NaiveTime::from_hms_opt(20, 0, 51).unwrap()));
+    }
+
+    #[test]
+    fn test_cooldown_and_escalation() {
+        let rule: Rule = toml::from_str(
+            "name = \"OMS\"\ncooldown_mins = 30\nescalate = { count = 4, within_mins = 10 }",
+        )
+        .unwrap();
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        let mut throttle = AlertThrottle::default();
+        let decisions: Vec<Decision> = ["03:00", "03:02", "03:20", "03:21", "03:22", "03:25", "03:40", "04:00"]
+            .iter()
+            .map(|time| throttle.decide(&rule, at(&format!("2026-01-05 {}", time))))
+            .collect();
+        assert_eq!(
+            decisions,
+            [
+                Decision::Send,
+                Decision::Suppress,
+                Decision::Suppress,
+                Decision::Suppress,
+                Decision::Suppress,
+                // Four since 03:20
+                Decision::Escalate(4),
+                // Cooling down from the escalation
+                Decision::Suppress,
+                Decision::Send,
+            ]
+        );
+        let escalated = rule.escalated(4);
+        assert_eq!(escalated.name, "OMS (4 in 10 min)");
+        assert_eq!(escalated.priority, Some(AlertPriority::Urgent));
+        assert_eq!(AlertThrottle::default().decide(&Rule::default(), at("2026-01-05 03:00")), Decision::Send);
+    }
+
+    #[test]
+    fn test_dry_run() {
+        // Feed times are UTC; take them as local for quiet hours by the local clock
+        let parser = Parser::new();
+        let messages: Vec<P2000Message> = [
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|001420028|ALN|A1 Reanimatie Nassaulaan Vlaardingen",
+            "FLEX|2026-01-01 20:05:00|1600/2/K/A|00.037|001420028|ALN|A1 Reanimatie Hoflaan Vlaardingen",
+            "FLEX|2026-01-01 20:30:00|1600/2/K/A|00.037|001420028|ALN|A2 Zeist 632",
+        ]
+        .iter()
+        .map(|line| {
+            let mut msg = parser.parse_line(line).unwrap();
+            msg.timestamp = Local.from_local_datetime(&msg.timestamp.naive_utc()).unwrap();
+            msg
+        })
+        .collect();
+        let rules = [
+            Rule {
+                name: "Rea".to_string(),
+                keywords: vec!["reanimatie".to_string()],
+                cooldown_mins: Some(10),
+                ..Default::default()
+            },
+            Rule {
+                name: "Zeist".to_string(),
+                keywords: vec!["zeist".to_string()],
+                quiet: Some(toml::from_str("from = \"20:00\"\nuntil = \"07:00\"").unwrap()),
+                ..Default::default()
+            },
+        ];
+        let reports = dry_run(&rules, messages.iter().map(|msg| (msg, Enrichment::default())), 1);
+        assert_eq!((reports[0].matches, reports[0].pushes, reports[0].held_back), (2, 1, 1));
+        assert_eq!(reports[0].examples.len(), 1);
+        assert_eq!((reports[1].matches, reports[1].pushes, reports[1].quiet), (1, 0, 1));
+
+        let mut table = Vec::new();
+        print_reports(&reports[1..], messages.len(), &mut table).unwrap();
+        assert_eq!(
+            String::from_utf8(table).unwrap(),
+            "Matches | Pushes | Escalated | Quiet | Held back | Rule\n      \
+             1 |      0 |         0 |     1 |         0 | Zeist\n1 rules over 3 messages\n"
+        );
+    }
+}
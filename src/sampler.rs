@@ -0,0 +1,122 @@
+//! Ingest sampling for firehose feeds: thins out routine messages so the live
+//! view stays responsive on weak hardware, but never drops urgent ones.
+
+use serde::Deserialize;
+use std::time::{Duration, Instant};
+
+use crate::parser::P2000Message;
+use crate::rules;
+
+/// Length of the `max_per_minute` window
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// `[sampling]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SamplingConfig {
+    /// Keep one in this many other messages; 1 keeps them all
+    pub keep_every: u32,
+    /// At most this many other messages per minute
+    pub max_per_minute: Option<u32>,
+    /// Priorities that are always kept
+    pub always_keep: Vec<String>,
+}
+
+impl Default for SamplingConfig {
+    fn default() -> Self {
+        SamplingConfig {
+            keep_every: 1,
+            max_per_minute: None,
+            always_keep: ["A0", "A1", "P1"].iter().map(|p| p.to_string()).collect(),
+        }
+    }
+}
+
+pub struct Sampler {
+    keep_every: u64,
+    max_per_minute: Option<u32>,
+    // Normalized, see `rules::normalize_priority`
+    always_keep: Vec<String>,
+    // Sampled (not always-kept) messages seen so far
+    seen: u64,
+    window_start: Option<Instant>,
+    window_count: u32,
+    dropped: u64,
+}
+
+impl Sampler {
+    pub fn new(config: &SamplingConfig) -> Self {
+        Sampler {
+            keep_every: config.keep_every.max(1) as u64,
+            max_per_minute: config.max_per_minute,
+            always_keep: config.always_keep.iter().map(|p| rules::normalize_priority(p)).collect(),
+            seen: 0,
+            window_start: None,
+            window_count: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Whether `msg`, arriving at `now`, should be kept; counts it as dropped otherwise.
+    pub fn keep(&mut self, msg: &P2000Message, now: Instant) -> bool {
+        let priority = msg.priority.as_deref().map(rules::normalize_priority);
+        if priority.is_some_and(|p| self.always_keep.contains(&p)) {
+            return true;
+        }
+
+        self.seen += 1;
+        let mut keep = (self.seen - 1).is_multiple_of(self.keep_every);
+        if keep {
+            if let Some(limit) = self.max_per_minute {
+                if self.window_start.is_none_or(|start| now.duration_since(start) >= WINDOW) {
+                    self.window_start = Some(now);
+                    self.window_count = 0;
+                }
+                keep = self.window_count < limit;
+                if keep {
+                    self.window_count += 1;
+                }
+            }
+        }
+        if !keep {
+            self.dropped += 1;
+        }
+        keep
+    }
+
+    pub fn dropped(&self) -> u64 {
+        self.dropped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_samples_routine_messages_only() {
+        let parser = Parser::new();
+        let routine = parser.parse_line("FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630").unwrap();
+        let urgent = parser.parse_line("FLEX|2026-01-01 20:01:50|1600/2/K/A|00.053|002029568|ALN|P 1 BLB-02 BR woning Weert").unwrap();
+        let now = Instant::now();
+
+        let mut sampler = Sampler::new(&SamplingConfig {
+            keep_every: 3,
+            ..Default::default()
+        });
+        let kept: Vec<bool> = (0..6).map(|_| sampler.keep(&routine, now)).collect();
+        assert_eq!(kept, [true, false, false, true, false, false]);
+        assert!(sampler.keep(&urgent, now));
+        assert_eq!(sampler.dropped(), 4);
+
+        let mut limited = Sampler::new(&SamplingConfig {
+            max_per_minute: Some(2),
+            ..Default::default()
+        });
+        let kept: Vec<bool> = (0..3).map(|_| limited.keep(&routine, now)).collect();
+        assert_eq!(kept, [true, true, false]);
+        assert!(limited.keep(&urgent, now));
+        assert!(limited.keep(&routine, now + WINDOW));
+    }
+}
@@ -0,0 +1,258 @@
+use anyhow::Result;
+#[cfg(feature = "scripting")]
+use anyhow::{anyhow, Context};
+#[cfg(feature = "scripting")]
+use rhai::{Array, Dynamic, Engine, Map, Scope, AST};
+use serde::Deserialize;
+#[cfg(feature = "scripting")]
+use std::cell::RefCell;
+use std::path::PathBuf;
+#[cfg(feature = "scripting")]
+use std::rc::Rc;
+
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+
+#[cfg(feature = "scripting")]
+const ENTRY_POINT: &str = "on_message";
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct ScriptConfig {
+    pub path: PathBuf,
+    /// Upper bound on script work per message, so a runaway loop can't stall ingest
+    #[serde(default = "default_max_operations")]
+    pub max_operations: u64,
+}
+
+fn default_max_operations() -> u64 {
+    100_000
+}
+
+/// What the script decided for a message.
+#[derive(Debug, Default)]
+pub struct ScriptOutcome {
+    pub keep: bool,
+    /// The script changed the message, so its enrichment is out of date
+    pub modified: bool,
+    /// Names passed to `alert(name)`; dispatched to the sinks like rule matches.
+    pub alerts: Vec<String>,
+}
+
+/// Runs `fn on_message(msg)` from a user Rhai script for every message.
+///
+/// The script sees the message and its enrichment as a map. Returning `false` drops
+/// the message, returning the (modified) map applies changes to `content`,
+/// `priority`, `location` and `capcodes`, anything else keeps it unchanged. Scripts have no
+/// file or network access; `alert(name)` is the only side effect.
+#[cfg(feature = "scripting")]
+pub struct ScriptHook {
+    engine: Engine,
+    ast: AST,
+    alerts: Rc<RefCell<Vec<String>>>,
+}
+
+#[cfg(feature = "scripting")]
+impl ScriptHook {
+    pub fn load(config: &ScriptConfig) -> Result<Self> {
+        let alerts = Rc::new(RefCell::new(Vec::new()));
+
+        let mut engine = Engine::new();
+        engine.set_max_operations(config.max_operations);
+        engine.set_max_call_levels(32);
+        engine.set_max_string_size(64 * 1024);
+        engine.set_max_array_size(10_000);
+        engine.set_max_map_size(10_000);
+        let sink = alerts.clone();
+        engine.register_fn("alert", move |name: &str| {
+            sink.borrow_mut().push(name.to_string());
+        });
+
+        let ast = engine
+            .compile_file(config.path.clone())
+            .map_err(|e| anyhow!("{}", e))
+            .with_context(|| format!("Failed to compile script {}", config.path.display()))?;
+        if !ast.iter_functions().any(|f| f.name == ENTRY_POINT) {
+            return Err(anyhow!(
+                "Script {} does not define fn {}(msg)",
+                config.path.display(),
+                ENTRY_POINT
+            ));
+        }
+
+        Ok(ScriptHook {
+            engine,
+            ast,
+            alerts,
+        })
+    }
+
+    /// Runs the script for `msg`, applying any transformation in place.
+    pub fn run(&self, msg: &mut P2000Message, enrichment: &Enrichment) -> Result<ScriptOutcome> {
+        self.alerts.borrow_mut().clear();
+
+        let result: Dynamic = self
+            .engine
+            .call_fn(
+                &mut Scope::new(),
+                &self.ast,
+                ENTRY_POINT,
+                (message_map(msg, enrichment),),
+            )
+            .map_err(|e| anyhow!("{}", e))?;
+
+        let (keep, modified) = match result.as_bool() {
+            Ok(keep) => (keep, false),
+            Err(_) => (true, result.try_cast::<Map>().is_some_and(|map| apply_changes(msg, &map))),
+        };
+
+        Ok(ScriptOutcome {
+            keep,
+            modified,
+            alerts: self.alerts.borrow_mut().drain(..).collect(),
+        })
+    }
+}
+
+#[cfg(feature = "scripting")]
+fn message_map(msg: &P2000Message, enrichment: &Enrichment) -> Map {
+    let mut map = Map::new();
+    map.insert("protocol".into(), (&*msg.protocol).into());
+    map.insert(
+        "timestamp".into(),
+        msg.timestamp.format("%Y-%m-%d %H:%M:%S").to_string().into(),
+    );
+    map.insert("radio_address".into(), (&*msg.radio_address).into());
+    map.insert("frequency".into(), (&*msg.frequency).into());
+    map.insert("capcodes".into(), strings(msg.capcodes.iter().map(|c| &**c)).into());
+    map.insert("message_type".into(), msg.message_type.as_str().into());
+    map.insert("content".into(), msg.content.clone().into());
+    map.insert("priority".into(), optional(&msg.priority));
+    map.insert("incident_code".into(), optional(&msg.incident_code));
+    map.insert("location".into(), msg.location.clone().into());
+
+    // Enrichment
+    map.insert(
+        "capcode_descriptions".into(),
+        strings(enrichment.capcodes.iter().map(|c| c.description.as_str())).into(),
+    );
+    map.insert(
+        "services".into(),
+        strings(enrichment.capcodes.iter().map(|c| c.service.as_str())).into(),
+    );
+    map.insert(
+        "regions".into(),
+        strings(enrichment.capcodes.iter().map(|c| c.region.as_str())).into(),
+    );
+    let (place, province) = match &enrichment.location {
+        Some(found) => (
+            found.found_place.clone().into(),
+            found.info.province.name.clone().into(),
+        ),
+        None => (Dynamic::UNIT, Dynamic::UNIT),
+    };
+    map.insert("place".into(), place);
+    map.insert("province".into(), province);
+    map.insert(
+        "region".into(),
+        enrichment.region.clone().map_or(Dynamic::UNIT, Dynamic::from),
+    );
+    map.insert(
+        "abbreviations".into(),
+        strings(enrichment.abbreviations.iter().map(|a| a.abbreviation.as_str())).into(),
+    );
+    map
+}
+
+/// Whether anything changed.
+#[cfg(feature = "scripting")]
+fn apply_changes(msg: &mut P2000Message, map: &Map) -> bool {
+    let before = (msg.content.clone(), msg.priority.clone(), msg.location.clone(), msg.capcodes.clone());
+    if let Some(content) = map.get("content").and_then(|v| v.clone().into_string().ok()) {
+        msg.content = content;
+    }
+    if let Some(priority) = map.get("priority") {
+        msg.priority = priority.clone().into_string().ok();
+    }
+    if let Some(location) = map.get("location").and_then(|v| v.clone().into_string().ok()) {
+        msg.location = location;
+    }
+    if let Some(capcodes) = map.get("capcodes").and_then(|v| v.clone().try_cast::<Array>()) {
+        msg.capcodes = capcodes
+            .into_iter()
+            .filter_map(|code| code.into_string().ok())
+            .map(|code| code.as_str().into())
+            .collect();
+    }
+    before != (msg.content.clone(), msg.priority.clone(), msg.location.clone(), msg.capcodes.clone())
+}
+
+#[cfg(feature = "scripting")]
+fn strings<'a>(values: impl Iterator<Item = &'a str>) -> Array {
+    values.map(|v| Dynamic::from(v.to_string())).collect()
+}
+
+#[cfg(feature = "scripting")]
+fn optional(value: &Option<String>) -> Dynamic {
+    value.clone().map(Dynamic::from).unwrap_or(Dynamic::UNIT)
+}
+
+/// Stand-in without the `scripting` feature: `load` fails, so there is never an instance.
+#[cfg(not(feature = "scripting"))]
+pub struct ScriptHook {
+    never: std::convert::Infallible,
+}
+
+#[cfg(not(feature = "scripting"))]
+impl ScriptHook {
+    pub fn load(_config: &ScriptConfig) -> Result<Self> {
+        anyhow::bail!("This build has no scripting; rebuild with --features scripting")
+    }
+
+    pub fn run(&self, _msg: &mut P2000Message, _enrichment: &Enrichment) -> Result<ScriptOutcome> {
+        match self.never {}
+    }
+}
+
+#[cfg(all(test, feature = "scripting"))]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_script_filter_transform_alert() {
+        let path = std::env::temp_dir().join("p2000_script_test.rhai");
+        std::fs::write(
+            &path,
+            r#"
+            fn on_message(msg) {
+                if msg.content.contains("Proefalarm") { return false; }
+                if msg.priority == "A1" { alert("ambulance"); }
+                msg.content = msg.content.to_upper();
+                msg
+            }
+            "#,
+        )
+        .unwrap();
+        let hook = ScriptHook::load(&ScriptConfig {
+            path: path.clone(),
+            max_operations: default_max_operations(),
+        })
+        .expect("Failed to load script");
+
+        let parser = Parser::new();
+        let mut msg = parser
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461")
+            .unwrap();
+        let outcome = hook.run(&mut msg, &Enrichment::default()).unwrap();
+        assert!(outcome.keep && outcome.modified);
+        assert_eq!(outcome.alerts, vec!["ambulance".to_string()]);
+        assert_eq!(msg.content, "A1 DUIZEL RIT: 461");
+
+        let mut test_page = parser
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|Proefalarm")
+            .unwrap();
+        assert!(!hook.run(&mut test_page, &Enrichment::default()).unwrap().keep);
+
+        std::fs::remove_file(path).ok();
+    }
+}
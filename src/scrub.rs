@@ -0,0 +1,199 @@
+//! `[[scrub]]`: clean-up of message content as it comes in from a source,
+//! before it is parsed. Imperfect decoders leave markers such as `<EOT>`,
+//! control characters and doubled spaces that would otherwise end up in
+//! place names, searches and notifications.
+
+use anyhow::{anyhow, Result};
+use regex::Regex;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::sync::Arc;
+
+use crate::parser::{LineFormat, Receiver};
+use crate::plugin::Source;
+
+/// How multimon-ng writes unprintable characters, and the characters
+/// themselves; tabs are left for the `spaces` preset
+const CONTROL_PATTERN: &str = r"<(NUL|SOH|STX|ETX|EOT|ENQ|ACK|BEL|BS|HT|LF|VT|FF|CR|SO|SI|DLE|DC1|DC2|DC3|DC4|NAK|SYN|ETB|CAN|EM|SUB|ESC|FS|GS|RS|US|DEL)>|[\p{Cc}&&[^\t]]";
+
+/// Common clean-ups, so they need no pattern.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    /// Removes control characters other than tabs and multimon-ng's
+    /// `<EOT>`-style markers
+    Controls,
+    /// Turns runs of spaces and tabs into one space and trims both ends
+    Spaces,
+}
+
+/// One `[[scrub]]` rule: a preset, or a regular expression and what replaces
+/// each match (`$1` for a group).
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct ScrubRule {
+    pub preset: Option<Preset>,
+    pub pattern: Option<String>,
+    pub replace: String,
+    /// Source names (`device`, `sdr`, ...) it applies to; every source when empty
+    pub sources: Vec<String>,
+}
+
+#[derive(Debug)]
+enum Step {
+    Replace(Regex, String),
+    Spaces,
+}
+
+/// The `[[scrub]]` rules, compiled, in config order.
+#[derive(Debug, Default)]
+pub struct Scrubber {
+    steps: Vec<(Step, Vec<String>)>,
+}
+
+impl Scrubber {
+    /// Fails on a rule with both or neither of `preset` and `pattern`, or with
+    /// a pattern that doesn't compile.
+    pub fn new(rules: &[ScrubRule]) -> Result<Self> {
+        let steps = rules
+            .iter()
+            .map(|rule| {
+                let step = match (rule.preset, &rule.pattern) {
+                    (Some(Preset::Controls), None) => {
+                        Step::Replace(Regex::new(CONTROL_PATTERN).expect("valid control pattern"), String::new())
+                    }
+                    (Some(Preset::Spaces), None) => Step::Spaces,
+                    (None, Some(pattern)) => Step::Replace(
+                        Regex::new(pattern).map_err(|e| anyhow!("[[scrub]] pattern '{}': {}", pattern, e))?,
+                        rule.replace.clone(),
+                    ),
+                    _ => return Err(anyhow!("[[scrub]] needs either a preset or a pattern")),
+                };
+                Ok((step, rule.sources.clone()))
+            })
+            .collect::<Result<_>>()?;
+        Ok(Scrubber { steps })
+    }
+
+    /// `content` after the rules that apply to `source`.
+    pub fn scrub<'a>(&self, source: &str, content: &'a str) -> Cow<'a, str> {
+        let mut content = Cow::Borrowed(content);
+        for (step, sources) in &self.steps {
+            if !sources.is_empty() && !sources.iter().any(|s| s == source) {
+                continue;
+            }
+            match step {
+                Step::Replace(regex, replace) => {
+                    if let Cow::Owned(scrubbed) = regex.replace_all(&content, replace.as_str()) {
+                        content = Cow::Owned(scrubbed);
+                    }
+                }
+                Step::Spaces => {
+                    let spaced = content.split([' ', '\t']).filter(|word| !word.is_empty()).collect::<Vec<_>>().join(" ");
+                    if spaced != content {
+                        content = Cow::Owned(spaced);
+                    }
+                }
+            }
+        }
+        content
+    }
+
+    /// A feed line in `format` with its content scrubbed for `source`.
+    /// Lines without content are left as they are.
+    pub fn scrub_line(&self, source: &str, format: &LineFormat, line: String) -> String {
+        let Some(start) = format.content_start(&line) else {
+            return line;
+        };
+        match self.scrub(source, &line[start..]) {
+            Cow::Borrowed(_) => line,
+            Cow::Owned(content) => format!("{}{}", &line[..start], content),
+        }
+    }
+}
+
+/// A source whose lines are scrubbed before anyone parses them.
+pub struct ScrubbedSource {
+    inner: Box<dyn Source>,
+    scrubber: Scrubber,
+    format: LineFormat,
+}
+
+impl ScrubbedSource {
+    /// `source`, which writes lines in `format`, itself when no rule applies to it.
+    pub fn wrap(source: Box<dyn Source>, scrubber: Scrubber, format: LineFormat) -> Box<dyn Source> {
+        let applies = scrubber
+            .steps
+            .iter()
+            .any(|(_, sources)| sources.is_empty() || sources.iter().any(|s| s == source.name()));
+        if applies {
+            Box::new(ScrubbedSource {
+                inner: source,
+                scrubber,
+                format,
+            })
+        } else {
+            source
+        }
+    }
+}
+
+impl Source for ScrubbedSource {
+    fn name(&self) -> &str {
+        self.inner.name()
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        Ok(self.inner.next_line()?.map(|line| self.scrubber.scrub_line(self.inner.name(), &self.format, line)))
+    }
+
+    fn channel(&self) -> Option<Arc<str>> {
+        self.inner.channel()
+    }
+
+    fn receiver(&self) -> Option<Arc<Receiver>> {
+        self.inner.receiver()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scrub_rules() {
+        let rule = |config: &str| -> ScrubRule { toml::from_str(config).unwrap() };
+        let scrubber = Scrubber::new(&[
+            rule("preset = \"controls\""),
+            rule("preset = \"spaces\""),
+            rule("pattern = '\\s*\\(ALN\\)$'\nsources = [\"device\"]"),
+        ])
+        .unwrap();
+
+        let flex = LineFormat::default();
+        let line = "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|A1  Nassaulaan\tVlaardingen (ALN)<EOT>\u{3}";
+        assert_eq!(
+            scrubber.scrub_line("sdr", &flex, line.to_string()),
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|A1 Nassaulaan Vlaardingen (ALN)"
+        );
+        assert_eq!(
+            scrubber.scrub_line("device", &flex, line.to_string()),
+            "FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|A1 Nassaulaan Vlaardingen"
+        );
+        // Separators in the content are content
+        assert_eq!(scrubber.scrub("stdin", "P 1 BDH-01 |  Den Haag"), "P 1 BDH-01 | Den Haag");
+        assert!(matches!(scrubber.scrub("stdin", "A2 Utrecht 630"), Cow::Borrowed(_)));
+        assert_eq!(scrubber.scrub_line("stdin", &flex, "not a feed line  <EOT>".to_string()), "not a feed line  <EOT>");
+
+        let semicolons: LineFormat = toml::from_str("delimiter = ';'\nfields = ['timestamp', 'content']").unwrap();
+        assert_eq!(
+            scrubber.scrub_line("stdin", &semicolons, "2026-01-01 20:00:51;A1  Duizel;".to_string()),
+            "2026-01-01 20:00:51;A1 Duizel;"
+        );
+
+        let bad = |config: &str| Scrubber::new(&[rule(config)]).is_err();
+        assert!(bad("pattern = '(unclosed'"));
+        assert!(bad("replace = 'x'"));
+        assert!(bad("preset = \"spaces\"\npattern = 'x'"));
+    }
+}
@@ -0,0 +1,394 @@
+//! Receiver subprocesses: runs `rtl_fm | multimon-ng` per channel as the feed,
+//! restarts a pair with backoff when either exits, and keeps their stderr in a
+//! log instead of letting it write over the TUI.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::process::{Child, ChildStdout, Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::plugin::Source;
+
+/// Lines kept in a `ProcessLog`
+const LOG_LIMIT: usize = 200;
+
+/// Time an ending pipeline gets to exit on its own before it is killed
+const EXIT_GRACE: Duration = Duration::from_secs(1);
+
+/// `[plugins.sdr]` options: the two command lines, the channels and the
+/// restart backoff.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct SdrConfig {
+    /// `{frequency}` and `{device}` are replaced per channel
+    pub rtl_fm: Vec<String>,
+    pub multimon: Vec<String>,
+    /// One decode chain each; every channel needs its own dongle
+    pub channels: Vec<SdrChannel>,
+    /// First restart delay; doubles after every quick failure
+    pub backoff_secs: u64,
+    pub max_backoff_secs: u64,
+    /// Sample rate of IQ recordings decoded offline (`rtl_sdr -s`)
+    pub iq_sample_rate: u32,
+}
+
+impl Default for SdrConfig {
+    fn default() -> Self {
+        let args = |args: &[&str]| args.iter().map(|s| s.to_string()).collect();
+        SdrConfig {
+            rtl_fm: args(&["rtl_fm", "-d", "{device}", "-f", "{frequency}", "-M", "fm", "-s", "22050", "-"]),
+            multimon: args(&["multimon-ng", "-a", "FLEX", "-t", "raw", "-"]),
+            channels: vec![SdrChannel {
+                name: "P2000".to_string(),
+                frequency: "169.65M".to_string(),
+                device: None,
+            }],
+            backoff_secs: 1,
+            max_backoff_secs: 60,
+            iq_sample_rate: 1_024_000,
+        }
+    }
+}
+
+impl SdrConfig {
+    /// From the `[plugins.sdr]` table, or the defaults without one.
+    pub fn from_options(options: Option<&toml::Table>) -> Result<Self> {
+        match options {
+            Some(options) => Ok(toml::Value::Table(options.clone()).try_into()?),
+            None => Ok(SdrConfig::default()),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SdrChannel {
+    /// Tagged on every message from this channel
+    pub name: String,
+    /// As rtl_fm takes it, e.g. "169.65M"
+    pub frequency: String,
+    /// rtl_fm device index; defaults to the channel's position in the list
+    pub device: Option<u32>,
+}
+
+/// The most recent stderr and restart lines of the receiver processes, shared
+/// between the source thread and the front-end.
+#[derive(Debug, Clone, Default)]
+pub struct ProcessLog {
+    lines: Arc<Mutex<VecDeque<String>>>,
+    // Also write every line to stderr, for headless use
+    echo: bool,
+}
+
+impl ProcessLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A log that also prints each line on stderr.
+    pub fn echoing() -> Self {
+        ProcessLog {
+            echo: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn push(&self, line: impl Into<String>) {
+        let line = line.into();
+        if self.echo {
+            eprintln!("{}", line);
+        }
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == LOG_LIMIT {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// The last `count` lines, oldest first.
+    pub fn tail(&self, count: usize) -> Vec<String> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().skip(lines.len().saturating_sub(count)).cloned().collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.lock().unwrap_or_else(|e| e.into_inner()).len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// Decoder output from one supervised `rtl_fm | multimon-ng` pipeline per
+/// channel, merged in arrival order.
+pub struct SdrSource {
+    lines: mpsc::Receiver<(Arc<str>, String)>,
+    // Channel of the line last returned
+    channel: Option<Arc<str>>,
+}
+
+impl SdrSource {
+    /// Starts every channel; failing to launch one the first time is an error.
+    pub fn start(config: SdrConfig, log: ProcessLog) -> Result<Self> {
+        let (tx, rx) = mpsc::channel();
+        let mut chains = Vec::new();
+        for (i, channel) in config.channels.iter().enumerate() {
+            let device = channel.device.unwrap_or(i as u32).to_string();
+            let rtl_fm = config
+                .rtl_fm
+                .iter()
+                .map(|arg| arg.replace("{frequency}", &channel.frequency).replace("{device}", &device))
+                .collect();
+            let chain = Chain::start(&channel.name, rtl_fm, &config, log.clone())
+                .with_context(|| format!("Channel {}", channel.name))?;
+            chains.push((Arc::<str>::from(channel.name.as_str()), chain));
+        }
+        for (name, mut chain) in chains {
+            let tx = tx.clone();
+            std::thread::spawn(move || loop {
+                let line = chain.next_line();
+                if tx.send((name.clone(), line)).is_err() {
+                    break;
+                }
+            });
+        }
+        Ok(SdrSource { lines: rx, channel: None })
+    }
+}
+
+impl Source for SdrSource {
+    fn name(&self) -> &str {
+        "sdr"
+    }
+
+    fn next_line(&mut self) -> Result<Option<String>> {
+        Ok(self.lines.recv().ok().map(|(channel, line)| {
+            self.channel = Some(channel);
+            line
+        }))
+    }
+
+    fn channel(&self) -> Option<Arc<str>> {
+        self.channel.clone()
+    }
+}
+
+/// One channel's pipeline, restarted with backoff whenever it ends.
+struct Chain {
+    // Prefixes log lines when there are several channels
+    label: String,
+    rtl_fm: Vec<String>,
+    multimon: Vec<String>,
+    backoff_secs: u64,
+    max_backoff_secs: u64,
+    log: ProcessLog,
+    // rtl_fm, multimon-ng
+    children: Vec<Child>,
+    lines: Option<std::io::Lines<BufReader<ChildStdout>>>,
+    started: Instant,
+    backoff: Duration,
+}
+
+impl Chain {
+    fn start(name: &str, rtl_fm: Vec<String>, config: &SdrConfig, log: ProcessLog) -> Result<Self> {
+        let label = if config.channels.len() > 1 { format!("[{}] ", name) } else { String::new() };
+        let mut chain = Chain {
+            label,
+            rtl_fm,
+            multimon: config.multimon.clone(),
+            backoff_secs: config.backoff_secs,
+            max_backoff_secs: config.max_backoff_secs,
+            log,
+            children: Vec::new(),
+            lines: None,
+            started: Instant::now(),
+            backoff: Duration::from_secs(config.backoff_secs),
+        };
+        chain.spawn()?;
+        Ok(chain)
+    }
+
+    fn spawn(&mut self) -> Result<()> {
+        let mut rtl_fm = command(&self.rtl_fm)?
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .with_context(|| format!("Failed to start {}", self.rtl_fm[0]))?;
+        let samples = rtl_fm.stdout.take().expect("piped stdout");
+        let multimon = command(&self.multimon)?
+            .stdin(samples)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut multimon = match multimon {
+            Ok(child) => child,
+            Err(e) => {
+                let _ = rtl_fm.kill();
+                let _ = rtl_fm.wait();
+                return Err(e).with_context(|| format!("Failed to start {}", self.multimon[0]));
+            }
+        };
+
+        for (child, args) in [(&mut rtl_fm, &self.rtl_fm), (&mut multimon, &self.multimon)] {
+            if let Some(stderr) = child.stderr.take() {
+                let prefix = format!("{}{}", self.label, program_name(&args[0]));
+                forward_stderr(prefix, stderr, self.log.clone());
+            }
+        }
+        self.lines = multimon.stdout.take().map(|stdout| BufReader::new(stdout).lines());
+        self.children = vec![rtl_fm, multimon];
+        self.started = Instant::now();
+        Ok(())
+    }
+
+    /// Kills whatever is left of the pipeline and logs how each process ended.
+    fn stop(&mut self) {
+        self.lines = None;
+        let names = [&self.rtl_fm[0], &self.multimon[0]];
+        let deadline = Instant::now() + EXIT_GRACE;
+        for (child, name) in self.children.iter_mut().zip(names) {
+            let status = loop {
+                match child.try_wait() {
+                    Ok(Some(status)) => break status.to_string(),
+                    Ok(None) if Instant::now() < deadline => std::thread::sleep(Duration::from_millis(20)),
+                    _ => {
+                        let _ = child.kill();
+                        break "killed".to_string();
+                    }
+                }
+            };
+            let _ = child.wait();
+            self.log.push(format!("{}{}: {}", self.label, program_name(name), status));
+        }
+        self.children.clear();
+    }
+
+    /// Waits out the backoff and starts again; the delay doubles unless the
+    /// last run was long enough to count as healthy.
+    fn restart(&mut self) {
+        let max = Duration::from_secs(self.max_backoff_secs);
+        if self.started.elapsed() > max {
+            self.backoff = Duration::from_secs(self.backoff_secs);
+        }
+        loop {
+            self.log.push(format!("{}Restarting receiver in {}s", self.label, self.backoff.as_secs()));
+            std::thread::sleep(self.backoff);
+            self.backoff = (self.backoff * 2).min(max);
+            match self.spawn() {
+                Ok(()) => return,
+                Err(e) => self.log.push(format!("{}{:#}", self.label, e)),
+            }
+        }
+    }
+
+    /// The next decoded line; never ends, the pipeline is restarted instead.
+    fn next_line(&mut self) -> String {
+        loop {
+            match self.lines.as_mut().and_then(|lines| lines.next()) {
+                Some(Ok(line)) => return line,
+                Some(Err(e)) => self.log.push(format!("{}Reading decoder output failed: {}", self.label, e)),
+                None => {}
+            }
+            self.stop();
+            self.restart();
+        }
+    }
+}
+
+impl Drop for Chain {
+    fn drop(&mut self) {
+        for child in &mut self.children {
+            let _ = child.kill();
+            let _ = child.wait();
+        }
+    }
+}
+
+pub(crate) fn command(args: &[String]) -> Result<Command> {
+    let (program, rest) = args.split_first().context("Empty receiver command")?;
+    let mut command = Command::new(program);
+    command.args(rest);
+    Ok(command)
+}
+
+pub(crate) fn program_name(program: &str) -> String {
+    std::path::Path::new(program)
+        .file_name()
+        .map_or_else(|| program.to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+pub(crate) fn forward_stderr(name: String, stderr: impl Read + Send + 'static, log: ProcessLog) {
+    std::thread::spawn(move || {
+        for line in BufReader::new(stderr).lines().map_while(|line| line.ok()) {
+            log.push(format!("{}: {}", name, line));
+        }
+    });
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+
+    fn sh(script: &str) -> Vec<String> {
+        vec!["sh".to_string(), "-c".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn test_restarts_after_exit() {
+        let log = ProcessLog::new();
+        let config = SdrConfig {
+            rtl_fm: sh("echo 'FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|A1 Test'; echo 'Found 1 device' >&2"),
+            multimon: vec!["cat".to_string()],
+            backoff_secs: 0,
+            max_backoff_secs: 0,
+            ..Default::default()
+        };
+        let mut source = SdrSource::start(config, log.clone()).unwrap();
+        let first = source.next_line().unwrap().unwrap();
+        assert!(first.ends_with("A1 Test"));
+        assert_eq!(source.channel().as_deref(), Some("P2000"));
+        // The pipeline ends after one line and is started again
+        assert_eq!(source.next_line().unwrap().unwrap(), first);
+        assert!(log.tail(LOG_LIMIT).iter().any(|line| line.starts_with("Restarting receiver")));
+        assert!(log.tail(LOG_LIMIT).iter().any(|line| line == "sh: exit status: 0"));
+
+        let missing = SdrConfig {
+            rtl_fm: vec!["p2000-no-such-program".to_string()],
+            ..Default::default()
+        };
+        assert!(SdrSource::start(missing, log).is_err());
+    }
+
+    #[test]
+    fn test_one_chain_per_channel() {
+        let channel = |name: &str, frequency: &str| SdrChannel {
+            name: name.to_string(),
+            frequency: frequency.to_string(),
+            device: None,
+        };
+        let config = SdrConfig {
+            rtl_fm: sh("echo 'FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568|ALN|{frequency} on {device}'; sleep 2"),
+            multimon: vec!["cat".to_string()],
+            channels: vec![channel("P2000", "169.65M"), channel("Test", "172.45M")],
+            ..Default::default()
+        };
+        let mut source = SdrSource::start(config, ProcessLog::new()).unwrap();
+        let mut seen = Vec::new();
+        for _ in 0..2 {
+            let line = source.next_line().unwrap().unwrap();
+            let content = line.rsplit('|').next().unwrap().to_string();
+            seen.push((source.channel().unwrap().to_string(), content));
+        }
+        seen.sort();
+        assert_eq!(
+            seen,
+            [
+                ("P2000".to_string(), "169.65M on 0".to_string()),
+                ("Test".to_string(), "172.45M on 1".to_string())
+            ]
+        );
+    }
+}
@@ -0,0 +1,528 @@
+//! Structured search, shared by the TUI search, `query --where`, rule `query`s
+//! and viewers of the `server` sink:
+//!
+//! ```text
+//! prio:A1 AND region:"Rotterdam-Rijnmond" AND since:24h
+//! (brand OR rook) NOT capcode:1420059
+//! ```
+//!
+//! A term is `field:value` or a bare word (quoted when it has spaces), which
+//! is looked for in the content, place, priority, channel and region. Terms
+//! next to each other must all match; `AND`, `OR`, `NOT` and parentheses
+//! combine them, operators in capitals so `and`/`or` stay searchable words.
+
+use anyhow::{anyhow, bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::calendar;
+use crate::daypart::Timing;
+use crate::enrich::Enrichment;
+use crate::lookup::normalize_code;
+use crate::parser::P2000Message;
+use crate::query::parse_time;
+use crate::rules::normalize_priority;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Field {
+    Text,
+    Priority,
+    Region,
+    Place,
+    Capcode,
+    Channel,
+    Receiver,
+    /// Category of an abbreviation in the content, e.g. "medical"
+    Category,
+    /// Name of a geofence the message is inside
+    Fence,
+    /// Part of the day ("night") or "dark"/"light"
+    DayPart,
+    /// Name of a holiday or calendar event the message falls on
+    Holiday,
+    Since,
+    Until,
+}
+
+impl Field {
+    const ALL: [Field; 13] = [
+        Field::Text,
+        Field::Priority,
+        Field::Region,
+        Field::Place,
+        Field::Capcode,
+        Field::Channel,
+        Field::Receiver,
+        Field::Category,
+        Field::Fence,
+        Field::DayPart,
+        Field::Holiday,
+        Field::Since,
+        Field::Until,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Field::Text => "text",
+            Field::Priority => "prio",
+            Field::Region => "region",
+            Field::Place => "place",
+            Field::Capcode => "capcode",
+            Field::Channel => "channel",
+            Field::Receiver => "receiver",
+            Field::Category => "category",
+            Field::Fence => "fence",
+            Field::DayPart => "daypart",
+            Field::Holiday => "holiday",
+            Field::Since => "since",
+            Field::Until => "until",
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self> {
+        let name = name.to_lowercase();
+        if name == "priority" {
+            return Ok(Field::Priority);
+        }
+        Field::ALL.into_iter().find(|field| field.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Field::ALL.iter().map(|field| field.name()).collect();
+            anyhow!("unknown field '{}'; expected one of {}", name, names.join(", "))
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Term {
+    pub field: Field,
+    pub value: String,
+    // What the subject is compared with: lowercased, or normalized for
+    // priorities and capcodes
+    needle: String,
+}
+
+impl Term {
+    pub fn new(field: Field, value: &str) -> Result<Self> {
+        let needle = match field {
+            Field::Priority => normalize_priority(value),
+            Field::Capcode => normalize_code(value),
+            Field::Since | Field::Until => {
+                parse_time(value)?;
+                value.to_string()
+            }
+            _ => value.to_lowercase(),
+        };
+        if needle.is_empty() {
+            bail!("{}: needs a value", field.name());
+        }
+        Ok(Term {
+            field,
+            value: value.to_string(),
+            needle,
+        })
+    }
+
+    fn matches(&self, subject: &Subject) -> bool {
+        let contains = |text: Option<&str>| text.is_some_and(|text| text.to_lowercase().contains(&self.needle));
+        let equals = |text: Option<&str>| text.is_some_and(|text| text.to_lowercase() == self.needle);
+        match self.field {
+            Field::Text => {
+                contains(Some(subject.content))
+                    || contains(subject.place)
+                    || contains(subject.priority)
+                    || contains(subject.channel)
+                    || contains(subject.region)
+                    || subject.enriched.iter().any(|text| contains(Some(text)))
+            }
+            Field::Priority => subject.priority.is_some_and(|p| normalize_priority(p) == self.needle),
+            Field::Region => contains(subject.region),
+            Field::Place => contains(subject.place),
+            Field::Capcode => subject.capcodes.iter().any(|c| normalize_code(c) == self.needle),
+            Field::Channel => equals(subject.channel),
+            Field::Receiver => equals(subject.receiver),
+            Field::Category => subject.categories.iter().any(|c| equals(Some(c))),
+            Field::Fence => subject.geofences.iter().any(|g| equals(Some(g))),
+            Field::DayPart => subject
+                .timing
+                .is_some_and(|t| t.day_part.name() == self.needle || t.daylight.name() == self.needle),
+            Field::Holiday => subject.holidays.iter().any(|h| contains(Some(h))),
+            // Relative times count back from the moment of matching
+            Field::Since => subject.time.zip(parse_time(&self.value).ok()).is_some_and(|(t, since)| t >= since),
+            Field::Until => subject.time.zip(parse_time(&self.value).ok()).is_some_and(|(t, until)| t < until),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Vec<Query>),
+    Or(Vec<Query>),
+}
+
+impl Query {
+    pub fn matches(&self, subject: &Subject) -> bool {
+        match self {
+            Query::Term(term) => term.matches(subject),
+            Query::Not(query) => !query.matches(subject),
+            Query::And(queries) => queries.iter().all(|q| q.matches(subject)),
+            Query::Or(queries) => queries.iter().any(|q| q.matches(subject)),
+        }
+    }
+
+    /// Whether this is more than bare words. The TUI keeps its plain substring
+    /// search for those.
+    pub fn is_structured(&self) -> bool {
+        match self {
+            Query::Term(term) => term.field != Field::Text,
+            Query::And(queries) => queries.iter().any(Query::is_structured),
+            Query::Not(_) | Query::Or(_) => true,
+        }
+    }
+}
+
+impl FromStr for Query {
+    type Err = anyhow::Error;
+
+    fn from_str(text: &str) -> Result<Self> {
+        let tokens = tokenize(text)?;
+        let mut parser = QueryParser { tokens, position: 0 };
+        let query = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            None => Ok(query),
+            Some(token) => bail!("unexpected {}", token),
+        }
+    }
+}
+
+impl TryFrom<String> for Query {
+    type Error = anyhow::Error;
+
+    fn try_from(text: String) -> Result<Self> {
+        text.parse()
+    }
+}
+
+impl From<Query> for String {
+    fn from(query: Query) -> String {
+        query.to_string()
+    }
+}
+
+/// Canonical form: explicit `AND`, quotes only where needed.
+impl fmt::Display for Query {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let join = |f: &mut fmt::Formatter, queries: &[Query], operator: &str| {
+            for (i, query) in queries.iter().enumerate() {
+                if i > 0 {
+                    write!(f, " {} ", operator)?;
+                }
+                match query {
+                    Query::And(_) | Query::Or(_) => write!(f, "({})", query)?,
+                    _ => write!(f, "{}", query)?,
+                }
+            }
+            Ok(())
+        };
+        match self {
+            Query::Term(term) => {
+                if term.field != Field::Text {
+                    write!(f, "{}:", term.field.name())?;
+                }
+                let bare = !term.value.is_empty()
+                    && !term.value.contains(|c: char| c.is_whitespace() || "()\":".contains(c))
+                    && !["AND", "OR", "NOT"].contains(&term.value.as_str());
+                if bare {
+                    write!(f, "{}", term.value)
+                } else {
+                    write!(f, "\"{}\"", term.value)
+                }
+            }
+            Query::Not(query) => match **query {
+                Query::And(_) | Query::Or(_) => write!(f, "NOT ({})", query),
+                _ => write!(f, "NOT {}", query),
+            },
+            Query::And(queries) => join(f, queries, "AND"),
+            Query::Or(queries) => join(f, queries, "OR"),
+        }
+    }
+}
+
+/// `text` as a query if it uses fields or operators; `None` for plain words
+/// and for anything that does not parse (yet, while it is being typed).
+pub fn parse_structured(text: &str) -> Option<Query> {
+    text.parse::<Query>().ok().filter(Query::is_structured)
+}
+
+/// What a query looks at. Each front-end fills in what it knows; a term on a
+/// field it leaves empty does not match.
+#[derive(Debug, Clone, Default)]
+pub struct Subject<'a> {
+    pub content: &'a str,
+    pub priority: Option<&'a str>,
+    pub region: Option<&'a str>,
+    pub place: Option<&'a str>,
+    pub capcodes: Vec<&'a str>,
+    pub channel: Option<&'a str>,
+    pub receiver: Option<&'a str>,
+    pub categories: Vec<&'a str>,
+    pub geofences: Vec<&'a str>,
+    pub timing: Option<Timing>,
+    pub holidays: Vec<&'a str>,
+    pub time: Option<DateTime<Local>>,
+    /// What the lookups add for plain words: abbreviation expansions and
+    /// capcode descriptions and places
+    pub enriched: Vec<&'a str>,
+}
+
+impl<'a> Subject<'a> {
+    /// The message's own fields; the place is its location text and the time
+    /// the decoded timestamp, with daylight taken in the middle of the country
+    /// and only the national holidays.
+    pub fn message(msg: &'a P2000Message) -> Self {
+        Subject {
+            content: &msg.content,
+            priority: msg.priority.as_deref(),
+            place: Some(msg.location.as_str()).filter(|place| !place.is_empty()),
+            capcodes: msg.capcodes.iter().map(|c| &**c).collect(),
+            channel: msg.channel.as_deref(),
+            receiver: msg.receiver.as_deref().map(|r| r.callsign.as_str()),
+            timing: Some(Timing::of(msg.timestamp, None)),
+            holidays: calendar::holidays(calendar::day_of(msg.timestamp)),
+            time: Some(msg.timestamp),
+            ..Default::default()
+        }
+    }
+
+    /// Region, matched place, abbreviation categories and expansions, capcode
+    /// descriptions, geofences, timing and calendar days from `enrichment`.
+    pub fn with_enrichment(mut self, enrichment: &'a Enrichment) -> Self {
+        if let Some(region) = enrichment.region.as_deref() {
+            self.region = Some(region);
+        }
+        if let Some(found) = &enrichment.location {
+            self.place = Some(found.info.place());
+        }
+        self.categories = enrichment.abbreviations.iter().filter_map(|e| e.category.as_deref()).collect();
+        self.enriched = enrichment.abbreviations.iter().map(|e| e.expansion.as_str()).collect();
+        for info in &enrichment.capcodes {
+            self.enriched.extend([info.description.as_str(), info.place.as_str()].into_iter().filter(|t| !t.is_empty()));
+        }
+        self.geofences = enrichment.geofences.iter().map(String::as_str).collect();
+        if let Some(timing) = enrichment.timing {
+            self.timing = Some(timing);
+        }
+        if !enrichment.calendar.is_empty() {
+            self.holidays = enrichment.calendar.iter().map(String::as_str).collect();
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    Open,
+    Close,
+    And,
+    Or,
+    Not,
+    Term(Option<String>, String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Token::Open => write!(f, "'('"),
+            Token::Close => write!(f, "')'"),
+            Token::And => write!(f, "AND"),
+            Token::Or => write!(f, "OR"),
+            Token::Not => write!(f, "NOT"),
+            Token::Term(Some(field), value) => write!(f, "'{}:{}'", field, value),
+            Token::Term(None, value) => write!(f, "'{}'", value),
+        }
+    }
+}
+
+fn tokenize(text: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            chars.next();
+            tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            continue;
+        }
+        let mut field = None;
+        let mut word = String::new();
+        let mut quoted = false;
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() || c == '(' || c == ')' {
+                break;
+            }
+            chars.next();
+            match c {
+                '"' => {
+                    quoted = true;
+                    loop {
+                        match chars.next() {
+                            Some('"') => break,
+                            Some(c) => word.push(c),
+                            None => bail!("unterminated quote in '{}'", text),
+                        }
+                    }
+                }
+                ':' if field.is_none() && !quoted => field = Some(std::mem::take(&mut word)),
+                c => word.push(c),
+            }
+        }
+        tokens.push(match (&field, word.as_str(), quoted) {
+            (None, "AND", false) => Token::And,
+            (None, "OR", false) => Token::Or,
+            (None, "NOT", false) => Token::Not,
+            _ => Token::Term(field, word),
+        });
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent: `OR` binds loosest, then `AND` (or nothing), then `NOT`.
+struct QueryParser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl QueryParser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn or(&mut self) -> Result<Query> {
+        let mut queries = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.next();
+            queries.push(self.and()?);
+        }
+        Ok(if queries.len() == 1 { queries.remove(0) } else { Query::Or(queries) })
+    }
+
+    fn and(&mut self) -> Result<Query> {
+        let mut queries = vec![self.not()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => {
+                    self.next();
+                }
+                Some(Token::Open | Token::Not | Token::Term(..)) => {}
+                _ => break,
+            }
+            queries.push(self.not()?);
+        }
+        Ok(if queries.len() == 1 { queries.remove(0) } else { Query::And(queries) })
+    }
+
+    fn not(&mut self) -> Result<Query> {
+        if self.peek() == Some(&Token::Not) {
+            self.next();
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Query> {
+        match self.next() {
+            Some(Token::Open) => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    _ => bail!("missing ')'"),
+                }
+            }
+            Some(Token::Term(field, value)) => {
+                let field = field.as_deref().map(Field::parse).transpose()?.unwrap_or(Field::Text);
+                Ok(Query::Term(Term::new(field, &value)?))
+            }
+            Some(token) => bail!("unexpected {}", token),
+            None => bail!("unexpected end of query"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::daypart::{DayPart, Daylight};
+    use crate::enrich::Expansion;
+    use crate::parser::Parser;
+    use chrono::{Local, TimeZone};
+
+    #[test]
+    fn test_parse_and_match() {
+        let query: Query = "prio:a1 AND region:\"Rotterdam-Rijnmond\" (reanimatie OR rea) NOT capcode:001420059"
+            .parse()
+            .unwrap();
+        assert_eq!(
+            query.to_string(),
+            "prio:a1 AND region:Rotterdam-Rijnmond AND (reanimatie OR rea) AND NOT capcode:001420059"
+        );
+        assert_eq!(query.to_string().parse::<Query>().unwrap(), query);
+        assert!(query.is_structured());
+        assert!(!"brand nassau".parse::<Query>().unwrap().is_structured());
+
+        let mut msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:00:51|1600/2/K/A|00.037|002029568 001420028|ALN|A1 Reanimatie Nassaulaan Vlaardingen")
+            .unwrap();
+        // Dates and dayparts are local, whatever zone the tests run in
+        msg.timestamp = Local.with_ymd_and_hms(2026, 1, 1, 20, 0, 51).unwrap();
+        let mut enrichment = Enrichment {
+            region: Some("Rotterdam-Rijnmond".to_string()),
+            ..Default::default()
+        };
+        assert!(query.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+        // Without the region the front-end does not know, the term fails
+        assert!(!query.matches(&Subject::message(&msg)));
+
+        let muted: Query = "prio:A1 NOT capcode:1420028".parse().unwrap();
+        assert!(!muted.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+
+        enrichment.abbreviations.push(Expansion {
+            abbreviation: "rea".to_string(),
+            expansion: "reanimatie".to_string(),
+            category: Some("medical".into()),
+        });
+        let category: Query = "category:Medical since:2025-12-31 until:2026-01-02".parse().unwrap();
+        assert!(category.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+        let recent: Query = "since:24h".parse().unwrap();
+        assert!(!recent.matches(&Subject::message(&msg)));
+        // Daylight is by the sun in the Netherlands, so give it rather than depend on the zone
+        assert_eq!(Subject::message(&msg).timing.map(|t| t.day_part), Some(DayPart::Evening));
+        enrichment.timing = Some(Timing {
+            day_part: DayPart::Evening,
+            daylight: Daylight::Dark,
+        });
+        let evening: Query = "daypart:evening daypart:Dark".parse().unwrap();
+        assert!(evening.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+        let light: Query = "daypart:light".parse().unwrap();
+        assert!(!light.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+        assert!("holiday:\"new year's day\"".parse::<Query>().unwrap().matches(&Subject::message(&msg)));
+        enrichment.calendar.push("Vlaardingse Nieuwjaarsduik".to_string());
+        let event: Query = "holiday:nieuwjaarsduik".parse().unwrap();
+        assert!(event.matches(&Subject::message(&msg).with_enrichment(&enrichment)));
+
+        for bad in ["prio:", "rit: 461", "(brand", "brand OR", "since:gisteren", "\"open"] {
+            assert!(bad.parse::<Query>().is_err(), "{}", bad);
+        }
+    }
+}
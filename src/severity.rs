@@ -0,0 +1,137 @@
+//! How much a message stands out: a score from its priority, a GRIP level in
+//! the text, how many capcodes were paged and the categories of its
+//! abbreviations. The list can be sorted by it and rules can require a minimum.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+
+use crate::parser::P2000Message;
+use crate::rules::normalize_priority;
+
+/// Weights of the parts of the score; the priority weights are fixed.
+#[derive(Debug, Clone, Deserialize, Serialize)]
+#[serde(default)]
+pub struct SeverityConfig {
+    /// Per GRIP level: GRIP 2 scores twice this
+    pub grip: u32,
+    /// Per capcode paged, up to `max_capcodes` of them
+    pub per_capcode: u32,
+    pub max_capcodes: usize,
+    /// Once per category among the abbreviations in the text, e.g. `{ hazmat = 20 }`
+    pub categories: BTreeMap<String, u32>,
+}
+
+impl Default for SeverityConfig {
+    fn default() -> Self {
+        SeverityConfig {
+            grip: 25,
+            per_capcode: 3,
+            max_capcodes: 10,
+            categories: BTreeMap::new(),
+        }
+    }
+}
+
+/// A score split into its parts.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Severity {
+    pub priority: u32,
+    pub grip: u32,
+    pub capcodes: u32,
+    pub categories: u32,
+}
+
+impl Severity {
+    pub fn total(&self) -> u32 {
+        self.priority + self.grip + self.capcodes + self.categories
+    }
+}
+
+impl fmt::Display for Severity {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (priority {} + GRIP {} + capcodes {} + categories {})",
+            self.total(),
+            self.priority,
+            self.grip,
+            self.capcodes,
+            self.categories
+        )
+    }
+}
+
+impl SeverityConfig {
+    /// Scores `msg`, given the categories of the abbreviations found in it.
+    pub fn assess<'a>(&self, msg: &P2000Message, categories: impl IntoIterator<Item = &'a str>) -> Severity {
+        let categories: BTreeSet<String> = categories.into_iter().map(str::to_lowercase).collect();
+        Severity {
+            priority: priority_weight(msg.priority.as_deref()),
+            grip: grip_level(&msg.content).map_or(0, |level| level * self.grip),
+            capcodes: msg.capcodes.len().min(self.max_capcodes) as u32 * self.per_capcode,
+            categories: self
+                .categories
+                .iter()
+                .filter(|(name, _)| categories.contains(&name.to_lowercase()))
+                .map(|(_, weight)| weight)
+                .sum(),
+        }
+    }
+}
+
+/// A0/A1/P1 weigh 40, A2/P2 20, B/P3 10 and anything else nothing.
+fn priority_weight(priority: Option<&str>) -> u32 {
+    match priority.map(normalize_priority).as_deref() {
+        Some("A0" | "A1" | "P1") => 40,
+        Some("A2" | "P2") => 20,
+        Some("B" | "B1" | "B2" | "P3") => 10,
+        _ => 0,
+    }
+}
+
+/// The GRIP level named in `content`: "GRIP 2" as well as "GRIP2".
+pub fn grip_level(content: &str) -> Option<u32> {
+    let words: Vec<String> = content
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(str::to_lowercase)
+        .collect();
+    words.iter().enumerate().find_map(|(i, w)| match w.strip_prefix("grip")? {
+        "" => words.get(i + 1)?.parse().ok(),
+        rest => rest.parse().ok(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    #[test]
+    fn test_severity() {
+        let parser = Parser::new();
+        let msg = parser
+            .parse_line("FLEX|2026-01-01 20:01:50|1600/2/K/A|00.050|001123117 001123118|ALN|P 1 BDH-01 GRIP2 OGS Den Haag")
+            .unwrap();
+        let config = SeverityConfig {
+            categories: BTreeMap::from([("Hazmat".to_string(), 20), ("medical".to_string(), 5)]),
+            ..Default::default()
+        };
+        let severity = config.assess(&msg, ["hazmat", "hazmat"]);
+        assert_eq!(
+            severity,
+            Severity {
+                priority: 40,
+                grip: 50,
+                capcodes: 6,
+                categories: 20,
+            }
+        );
+        assert_eq!(severity.to_string(), "116 (priority 40 + GRIP 50 + capcodes 6 + categories 20)");
+
+        assert_eq!(grip_level("GRIP 1 Brand Rotterdam"), Some(1));
+        assert_eq!(grip_level("Gripstraat 12 Utrecht"), None);
+        assert_eq!(grip_level("A2 Utrecht 630"), None);
+    }
+}
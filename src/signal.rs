@@ -0,0 +1,17 @@
+//! Shutdown signals shared by the TUI and daemon.
+
+/// Resolves on Ctrl-C, or on SIGTERM on Unix (service managers, `docker stop`).
+pub async fn shutdown() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
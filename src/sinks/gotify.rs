@@ -0,0 +1,54 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{Alert, AlertPriority, HttpClient, Sink};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GotifyConfig {
+    pub server: String,
+    /// Application token
+    pub token: String,
+}
+
+pub struct GotifySink {
+    config: GotifyConfig,
+    http: HttpClient,
+}
+
+impl GotifySink {
+    pub fn new(config: GotifyConfig) -> Result<Self> {
+        Ok(GotifySink {
+            config,
+            http: HttpClient::new()?,
+        })
+    }
+}
+
+impl Sink for GotifySink {
+    fn name(&self) -> &str {
+        "gotify"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<()> {
+        let url = format!("{}/message", self.config.server.trim_end_matches('/'));
+        let body = serde_json::json!({
+            "title": alert.title(),
+            "message": alert.message.content,
+            "priority": gotify_priority(alert.priority),
+        });
+
+        let headers = [("X-Gotify-Key", self.config.token.as_str()), ("Content-Type", "application/json")];
+        self.http.send("POST", &url, &headers, body.to_string())
+    }
+}
+
+// Gotify uses 0-10; clients treat 8+ as high priority
+fn gotify_priority(priority: AlertPriority) -> u8 {
+    match priority {
+        AlertPriority::Min => 0,
+        AlertPriority::Low => 2,
+        AlertPriority::Default => 5,
+        AlertPriority::High => 8,
+        AlertPriority::Urgent => 10,
+    }
+}
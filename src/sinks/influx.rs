@@ -0,0 +1,240 @@
+use anyhow::{anyhow, Result};
+use serde::Deserialize;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use super::{HttpClient, Sink};
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::rules;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct InfluxConfig {
+    /// Base URL, e.g. `http://localhost:8086`
+    pub url: String,
+    /// InfluxDB 2.x org/bucket/token; leave `org` unset to use the 1.x `/write?db=` API
+    pub org: Option<String>,
+    pub bucket: String,
+    pub token: Option<String>,
+    #[serde(default = "default_measurement")]
+    pub measurement: String,
+    #[serde(default = "default_batch_size")]
+    pub batch_size: usize,
+    /// Flush a partial batch once it is this old
+    #[serde(default = "default_flush_secs")]
+    pub flush_secs: u64,
+    #[serde(default = "default_retries")]
+    pub retries: u32,
+    /// Points kept while the server is unreachable; the oldest are dropped beyond this
+    #[serde(default = "default_max_buffer")]
+    pub max_buffer: usize,
+}
+
+fn default_measurement() -> String {
+    "p2000".to_string()
+}
+
+fn default_batch_size() -> usize {
+    100
+}
+
+fn default_flush_secs() -> u64 {
+    10
+}
+
+fn default_retries() -> u32 {
+    3
+}
+
+fn default_max_buffer() -> usize {
+    10_000
+}
+
+struct Batch {
+    lines: Vec<String>,
+    started: Instant,
+}
+
+/// Writes one point per message in line protocol, tagged with region, service and
+/// priority. Works with InfluxDB and anything accepting line protocol (e.g.
+/// Telegraf in front of TimescaleDB).
+pub struct InfluxSink {
+    config: InfluxConfig,
+    batch: Mutex<Batch>,
+    http: HttpClient,
+}
+
+impl InfluxSink {
+    pub fn new(config: InfluxConfig) -> Result<Self> {
+        Ok(InfluxSink {
+            config,
+            batch: Mutex::new(Batch {
+                lines: Vec::new(),
+                started: Instant::now(),
+            }),
+            http: HttpClient::new()?,
+        })
+    }
+
+    fn point(&self, msg: &P2000Message, enrichment: &Enrichment) -> String {
+        let mut line = escape(&self.config.measurement, false);
+
+        let first = enrichment.primary_capcode();
+        let region = enrichment
+            .region
+            .as_deref()
+            .or_else(|| first.map(|c| c.region.as_str()))
+            .or_else(|| enrichment.location.as_ref().map(|l| l.info.province.name.as_str()))
+            .unwrap_or("");
+        let service = first.map(|c| c.service.as_str()).unwrap_or("");
+        let priority = msg
+            .priority
+            .as_deref()
+            .map(rules::normalize_priority)
+            .unwrap_or_default();
+        for (key, value) in [("region", region), ("service", service), ("priority", priority.as_str())] {
+            if !value.is_empty() {
+                line.push_str(&format!(",{}={}", key, escape(value, true)));
+            }
+        }
+
+        line.push_str(&format!(
+            " count=1i,capcodes={}i {}",
+            msg.capcodes.len(),
+            msg.timestamp.timestamp()
+        ));
+        line
+    }
+
+    fn write_url(&self) -> String {
+        let base = self.config.url.trim_end_matches('/');
+        match &self.config.org {
+            Some(org) => format!(
+                "{}/api/v2/write?org={}&bucket={}&precision=s",
+                base, org, self.config.bucket
+            ),
+            None => format!("{}/write?db={}&precision=s", base, self.config.bucket),
+        }
+    }
+
+    fn write(&self, body: &str) -> Result<()> {
+        let url = self.write_url();
+        let mut last_error = None;
+        for attempt in 0..=self.config.retries {
+            if attempt > 0 {
+                std::thread::sleep(Duration::from_millis(500 * 2u64.pow(attempt - 1)));
+            }
+            let authorization = self.config.token.as_ref().map(|token| format!("Token {}", token));
+            let headers: Vec<(&str, &str)> = authorization.iter().map(|a| ("Authorization", a.as_str())).collect();
+            match self.http.send("POST", &url, &headers, body.to_string()) {
+                Ok(_) => return Ok(()),
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(anyhow!(
+            "write failed after {} attempts: {}",
+            self.config.retries + 1,
+            last_error.map(|e| e.to_string()).unwrap_or_default()
+        ))
+    }
+
+    fn flush_batch(&self, batch: &mut Batch) -> Result<()> {
+        batch.started = Instant::now();
+        if batch.lines.is_empty() {
+            return Ok(());
+        }
+        let result = self.write(&batch.lines.join("\n"));
+        match result {
+            Ok(()) => batch.lines.clear(),
+            Err(_) => {
+                // Keep points for the next flush, bounded
+                let excess = batch.lines.len().saturating_sub(self.config.max_buffer);
+                batch.lines.drain(..excess);
+            }
+        }
+        result
+    }
+}
+
+impl Sink for InfluxSink {
+    fn name(&self) -> &str {
+        "influx"
+    }
+
+    fn record(&self, msg: &P2000Message, enrichment: &Enrichment) -> Result<()> {
+        let point = self.point(msg, enrichment);
+        let mut batch = self.batch.lock().unwrap_or_else(|e| e.into_inner());
+        batch.lines.push(point);
+        if batch.lines.len() >= self.config.batch_size
+            || batch.started.elapsed() >= Duration::from_secs(self.config.flush_secs)
+        {
+            self.flush_batch(&mut batch)?;
+        }
+        Ok(())
+    }
+
+    fn flush(&self) -> Result<()> {
+        let mut batch = self.batch.lock().unwrap_or_else(|e| e.into_inner());
+        self.flush_batch(&mut batch)
+    }
+}
+
+/// Line protocol escaping; tag values additionally escape `=`.
+fn escape(value: &str, tag: bool) -> String {
+    let mut out = String::with_capacity(value.len());
+    for c in value.chars() {
+        if c == ',' || c == ' ' || (tag && c == '=') || c == '\\' {
+            out.push('\\');
+        }
+        out.push(c);
+    }
+    out
+}
+
+// The sinks need an HTTP client even though these tests never send
+#[cfg(all(test, feature = "notifications"))]
+mod tests {
+    use super::*;
+    use crate::lookup::CapcodeInfo;
+    use crate::parser::Parser;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_point_line_protocol() {
+        let sink = InfluxSink::new(InfluxConfig {
+            url: "http://localhost:8086".to_string(),
+            org: None,
+            bucket: "p2000".to_string(),
+            token: None,
+            measurement: default_measurement(),
+            batch_size: default_batch_size(),
+            flush_secs: default_flush_secs(),
+            retries: 0,
+            max_buffer: default_max_buffer(),
+        })
+        .unwrap();
+        let msg = Parser::new()
+            .parse_line("FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117 001123118|ALN|A1 Duizel Rit: 461")
+            .unwrap();
+        let enrichment = Enrichment {
+            capcodes: vec![Arc::new(CapcodeInfo {
+                code: "1123117".to_string(),
+                service: "Ambulance".to_string(),
+                region: "Brabant Zuid-Oost".to_string(),
+                place: String::new(),
+                description: String::new(),
+                short: String::new(),
+                source: "capcodelist".into(),
+            })],
+            ..Default::default()
+        };
+
+        assert_eq!(
+            sink.point(&msg, &enrichment),
+            format!(
+                "p2000,region=Brabant\\ Zuid-Oost,service=Ambulance,priority=A1 count=1i,capcodes=2i {}",
+                msg.timestamp.timestamp()
+            )
+        );
+    }
+}
@@ -0,0 +1,75 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+use super::{HttpClient, LogRecord, Sink};
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+use crate::rules;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct LokiConfig {
+    /// Base URL, e.g. `http://localhost:3100`
+    pub url: String,
+    /// Static stream labels; `priority` and `service` are added per message
+    #[serde(default = "default_labels")]
+    pub labels: BTreeMap<String, String>,
+    pub tenant: Option<String>,
+}
+
+fn default_labels() -> BTreeMap<String, String> {
+    BTreeMap::from([("job".to_string(), "p2000".to_string())])
+}
+
+/// Pushes every message as a JSON log line to Loki's push API.
+pub struct LokiSink {
+    config: LokiConfig,
+    http: HttpClient,
+}
+
+impl LokiSink {
+    pub fn new(config: LokiConfig) -> Result<Self> {
+        Ok(LokiSink {
+            config,
+            http: HttpClient::new()?,
+        })
+    }
+}
+
+impl Sink for LokiSink {
+    fn name(&self) -> &str {
+        "loki"
+    }
+
+    fn record(&self, msg: &P2000Message, enrichment: &Enrichment) -> Result<()> {
+        // Keep label cardinality low: priority and service only
+        let mut labels = self.config.labels.clone();
+        if let Some(priority) = &msg.priority {
+            labels.insert("priority".to_string(), rules::normalize_priority(priority));
+        }
+        if let Some(info) = enrichment.primary_capcode() {
+            labels.insert("service".to_string(), info.service.clone());
+        }
+
+        let timestamp_ns = msg
+            .timestamp
+            .timestamp_nanos_opt()
+            .unwrap_or_default()
+            .to_string();
+        let line = LogRecord {
+            message: msg,
+            enrichment,
+        }
+        .to_json();
+        let body = serde_json::json!({
+            "streams": [{ "stream": labels, "values": [[timestamp_ns, line]] }]
+        });
+
+        let url = format!("{}/loki/api/v1/push", self.config.url.trim_end_matches('/'));
+        let mut headers = vec![("Content-Type", "application/json")];
+        if let Some(tenant) = &self.config.tenant {
+            headers.push(("X-Scope-OrgID", tenant));
+        }
+        self.http.send("POST", &url, &headers, body.to_string())
+    }
+}
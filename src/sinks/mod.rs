@@ -0,0 +1,273 @@
+pub mod gotify;
+pub mod influx;
+pub mod loki;
+pub mod ntfy;
+pub mod syslog;
+#[cfg(feature = "notifications")]
+pub mod webhook;
+
+use anyhow::Result;
+#[cfg(not(feature = "notifications"))]
+use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+use crate::config::Config;
+use crate::enrich::Enrichment;
+use crate::parser::{P2000Message, TimeSource};
+use crate::rules::{self, AlertThrottle, Decision, Rule};
+
+/// Push priority, modelled after ntfy's five levels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertPriority {
+    Min,
+    Low,
+    Default,
+    High,
+    Urgent,
+}
+
+impl AlertPriority {
+    /// A0/A1/P1 are urgent, A2/P2 high, B/P3 default; unknown priorities are low.
+    pub fn from_message(msg: &P2000Message) -> Self {
+        match msg.priority.as_deref().map(rules::normalize_priority).as_deref() {
+            Some("A0" | "A1" | "P1") => AlertPriority::Urgent,
+            Some("A2" | "P2") => AlertPriority::High,
+            Some("B" | "P3") => AlertPriority::Default,
+            _ => AlertPriority::Low,
+        }
+    }
+}
+
+pub struct Alert<'a> {
+    pub rule: &'a Rule,
+    pub message: &'a P2000Message,
+    pub enrichment: &'a Enrichment,
+    pub priority: AlertPriority,
+}
+
+impl Alert<'_> {
+    pub fn title(&self) -> String {
+        match &self.message.priority {
+            Some(priority) => format!("{} {}", priority, self.rule.name),
+            None => self.rule.name.clone(),
+        }
+    }
+}
+
+/// A `[[webhooks]]` entry; the sink itself is in `webhook`.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WebhookConfig {
+    pub url: String,
+    #[serde(default = "default_method")]
+    pub method: String,
+    /// Inline minijinja template; without one the whole payload is sent as JSON.
+    pub template: Option<String>,
+    pub template_file: Option<PathBuf>,
+    #[serde(default = "default_content_type")]
+    pub content_type: String,
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+fn default_method() -> String {
+    "POST".to_string()
+}
+
+fn default_content_type() -> String {
+    "application/json".to_string()
+}
+
+/// Output for the headless pipeline. Notification sinks implement `send` and only see
+/// rule matches; log and metrics sinks implement `record` and see every message.
+pub trait Sink: Send + Sync {
+    fn name(&self) -> &str;
+
+    fn send(&self, _alert: &Alert) -> Result<()> {
+        Ok(())
+    }
+
+    fn record(&self, _msg: &P2000Message, _enrichment: &Enrichment) -> Result<()> {
+        Ok(())
+    }
+
+    /// Writes out anything buffered; called when the feed ends.
+    fn flush(&self) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Structured form of a message for log-style sinks.
+#[derive(Serialize)]
+pub struct LogRecord<'a> {
+    pub message: &'a P2000Message,
+    pub enrichment: &'a Enrichment,
+}
+
+impl LogRecord<'_> {
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).unwrap_or_default()
+    }
+}
+
+pub fn ntfy_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .ntfy
+        .iter()
+        .map(|ntfy| Ok(Box::new(ntfy::NtfySink::new(ntfy.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+pub fn gotify_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .gotify
+        .iter()
+        .map(|gotify| Ok(Box::new(gotify::GotifySink::new(gotify.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+#[cfg(feature = "notifications")]
+pub fn webhooks_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .webhooks
+        .iter()
+        .map(|webhook| Ok(Box::new(webhook::WebhookSink::new(webhook.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+/// Webhook templates need the `notifications` feature like the HTTP client.
+#[cfg(not(feature = "notifications"))]
+pub fn webhooks_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    if !config.webhooks.is_empty() {
+        HttpClient::new()?;
+    }
+    Ok(Vec::new())
+}
+
+pub fn loki_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .loki
+        .iter()
+        .map(|loki| Ok(Box::new(loki::LokiSink::new(loki.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+pub fn syslog_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .syslog
+        .iter()
+        .map(|syslog| Ok(Box::new(syslog::SyslogSink::new(syslog.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+pub fn influx_from_config(config: &Config) -> Result<Vec<Box<dyn Sink>>> {
+    config
+        .influx
+        .iter()
+        .map(|influx| Ok(Box::new(influx::InfluxSink::new(influx.clone())?) as Box<dyn Sink>))
+        .collect()
+}
+
+/// Flushes buffered sinks, logging failures.
+pub fn flush(sinks: &[Box<dyn Sink>]) {
+    for sink in sinks {
+        if let Err(e) = sink.flush() {
+            eprintln!("Warning: {} sink failed to flush: {}", sink.name(), e);
+        }
+    }
+}
+
+/// Hands every message to the sinks that record all traffic.
+pub fn record(sinks: &[Box<dyn Sink>], msg: &P2000Message, enrichment: &Enrichment) {
+    for sink in sinks {
+        if let Err(e) = sink.record(msg, enrichment) {
+            eprintln!("Warning: {} sink failed: {}", sink.name(), e);
+        }
+    }
+}
+
+/// Sends an alert to every sink for each rule the message matches, unless the
+/// rule is cooling down. Returns how many matches were held back.
+pub fn dispatch(
+    sinks: &[Box<dyn Sink>],
+    rules: &[Rule],
+    throttle: &mut AlertThrottle,
+    msg: &P2000Message,
+    enrichment: &Enrichment,
+) -> usize {
+    let mut suppressed = 0;
+    for rule in rules::matching_rules(rules, msg, enrichment) {
+        match throttle.decide(rule, msg.time(TimeSource::Received)) {
+            Decision::Send => send(sinks, rule, msg, enrichment),
+            Decision::Escalate(count) => send(sinks, &rule.escalated(count), msg, enrichment),
+            Decision::Suppress => suppressed += 1,
+        }
+    }
+    suppressed
+}
+
+/// Sends one alert for `rule` to every sink, logging failures.
+pub fn send(sinks: &[Box<dyn Sink>], rule: &Rule, msg: &P2000Message, enrichment: &Enrichment) {
+    let alert = Alert {
+        rule,
+        message: msg,
+        enrichment,
+        priority: rule.alert_priority(msg),
+    };
+    for sink in sinks {
+        if let Err(e) = sink.send(&alert) {
+            eprintln!("Warning: {} sink failed: {}", sink.name(), e);
+        }
+    }
+}
+
+/// HTTP transport of the ntfy, Gotify, webhook, Loki and InfluxDB sinks and the
+/// weather lookups. It needs the `notifications` feature; without it `new`
+/// fails, so a configured sink is reported at startup rather than on the first
+/// message.
+pub struct HttpClient {
+    #[cfg(feature = "notifications")]
+    agent: ureq::Agent,
+}
+
+#[cfg(feature = "notifications")]
+impl HttpClient {
+    pub fn new() -> Result<Self> {
+        let agent = ureq::Agent::config_builder()
+            .timeout_global(Some(std::time::Duration::from_secs(10)))
+            .build()
+            .into();
+        Ok(HttpClient { agent })
+    }
+
+    pub fn send(&self, method: &str, url: &str, headers: &[(&str, &str)], body: String) -> Result<()> {
+        let mut request = ureq::http::Request::builder().method(method).uri(url);
+        for (name, value) in headers {
+            request = request.header(*name, *value);
+        }
+        self.agent.run(request.body(body)?)?;
+        Ok(())
+    }
+
+    /// The body of a GET request.
+    pub fn get(&self, url: &str) -> Result<String> {
+        Ok(self.agent.get(url).call()?.body_mut().read_to_string()?)
+    }
+}
+
+#[cfg(not(feature = "notifications"))]
+impl HttpClient {
+    pub fn new() -> Result<Self> {
+        bail!("This build has no HTTP sinks; rebuild with --features notifications")
+    }
+
+    pub fn send(&self, _method: &str, _url: &str, _headers: &[(&str, &str)], _body: String) -> Result<()> {
+        unreachable!("HttpClient::new fails without the notifications feature")
+    }
+
+    pub fn get(&self, _url: &str) -> Result<String> {
+        unreachable!("HttpClient::new fails without the notifications feature")
+    }
+}
@@ -0,0 +1,84 @@
+use anyhow::Result;
+use serde::Deserialize;
+
+use super::{Alert, AlertPriority, HttpClient, Sink};
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct NtfyConfig {
+    #[serde(default = "default_server")]
+    pub server: String,
+    pub topic: String,
+    pub token: Option<String>,
+}
+
+fn default_server() -> String {
+    "https://ntfy.sh".to_string()
+}
+
+pub struct NtfySink {
+    config: NtfyConfig,
+    http: HttpClient,
+}
+
+impl NtfySink {
+    pub fn new(config: NtfyConfig) -> Result<Self> {
+        Ok(NtfySink {
+            config,
+            http: HttpClient::new()?,
+        })
+    }
+}
+
+impl Sink for NtfySink {
+    fn name(&self) -> &str {
+        "ntfy"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<()> {
+        // Rules may route to their own topic
+        let topic = alert.rule.topic.as_deref().unwrap_or(&self.config.topic);
+        let url = format!("{}/{}", self.config.server.trim_end_matches('/'), topic);
+
+        let title = header_text(&alert.title());
+        let mut headers = vec![("Title", title.as_str()), ("Priority", ntfy_priority(alert.priority))];
+        let authorization = self.config.token.as_ref().map(|token| format!("Bearer {}", token));
+        if let Some(authorization) = &authorization {
+            headers.push(("Authorization", authorization));
+        }
+        self.http.send("POST", &url, &headers, alert.message.content.clone())
+    }
+}
+
+/// `text` as a header value: HTTP headers are ASCII, so other text such as a
+/// rule named `Brand Zuid-Holland – Zoetermeer` is RFC 2047 encoded, which ntfy
+/// decodes.
+fn header_text(text: &str) -> String {
+    use base64::Engine;
+
+    if text.is_ascii() && !text.contains(['\r', '\n']) {
+        return text.to_string();
+    }
+    format!("=?UTF-8?B?{}?=", base64::engine::general_purpose::STANDARD.encode(text))
+}
+
+fn ntfy_priority(priority: AlertPriority) -> &'static str {
+    match priority {
+        AlertPriority::Min => "min",
+        AlertPriority::Low => "low",
+        AlertPriority::Default => "default",
+        AlertPriority::High => "high",
+        AlertPriority::Urgent => "urgent",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_text() {
+        assert_eq!(header_text("Brand Delft"), "Brand Delft");
+        assert_eq!(header_text("Brand – Delft"), "=?UTF-8?B?QnJhbmQg4oCTIERlbGZ0?=");
+        assert_eq!(header_text("Café"), "=?UTF-8?B?Q2Fmw6k=?=");
+    }
+}
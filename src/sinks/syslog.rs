@@ -0,0 +1,119 @@
+use anyhow::Result;
+use serde::Deserialize;
+use std::io::Write;
+use std::net::{TcpStream, UdpSocket};
+use std::sync::Mutex;
+
+use super::{AlertPriority, LogRecord, Sink};
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+
+// local0
+const FACILITY: u8 = 16;
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transport {
+    #[default]
+    Udp,
+    Tcp,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct SyslogConfig {
+    /// `host:port` of the syslog server
+    pub address: String,
+    #[serde(default)]
+    pub transport: Transport,
+    #[serde(default = "default_app_name")]
+    pub app_name: String,
+}
+
+fn default_app_name() -> String {
+    "p2000".to_string()
+}
+
+/// Forwards every message as an RFC 5424 line with a JSON body.
+pub struct SyslogSink {
+    config: SyslogConfig,
+    udp: Option<UdpSocket>,
+    tcp: Mutex<Option<TcpStream>>,
+    hostname: String,
+}
+
+impl SyslogSink {
+    pub fn new(config: SyslogConfig) -> Result<Self> {
+        let udp = match config.transport {
+            Transport::Udp => {
+                let socket = UdpSocket::bind("0.0.0.0:0")?;
+                socket.connect(&config.address)?;
+                Some(socket)
+            }
+            Transport::Tcp => None,
+        };
+        let hostname = std::env::var("HOSTNAME").unwrap_or_else(|_| "-".to_string());
+        Ok(SyslogSink {
+            config,
+            udp,
+            tcp: Mutex::new(None),
+            hostname,
+        })
+    }
+
+    fn send_tcp(&self, line: &str) -> Result<()> {
+        let mut guard = self.tcp.lock().unwrap_or_else(|e| e.into_inner());
+        if guard.is_none() {
+            *guard = Some(TcpStream::connect(&self.config.address)?);
+        }
+        // Octet-counting framing (RFC 6587)
+        let framed = format!("{} {}", line.len(), line);
+        if let Some(stream) = guard.as_mut() {
+            if let Err(e) = stream.write_all(framed.as_bytes()) {
+                // Reconnect on the next message
+                *guard = None;
+                return Err(e.into());
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Sink for SyslogSink {
+    fn name(&self) -> &str {
+        "syslog"
+    }
+
+    fn record(&self, msg: &P2000Message, enrichment: &Enrichment) -> Result<()> {
+        let pri = FACILITY * 8 + severity(AlertPriority::from_message(msg));
+        let body = LogRecord {
+            message: msg,
+            enrichment,
+        }
+        .to_json();
+        let line = format!(
+            "<{}>1 {} {} {} - - - {}",
+            pri,
+            msg.timestamp.to_rfc3339(),
+            self.hostname,
+            self.config.app_name,
+            body
+        );
+
+        match &self.udp {
+            Some(socket) => {
+                socket.send(line.as_bytes())?;
+                Ok(())
+            }
+            None => self.send_tcp(&line),
+        }
+    }
+}
+
+fn severity(priority: AlertPriority) -> u8 {
+    match priority {
+        AlertPriority::Urgent => 2,                   // critical
+        AlertPriority::High => 4,                     // warning
+        AlertPriority::Default => 5,                  // notice
+        AlertPriority::Low | AlertPriority::Min => 6, // informational
+    }
+}
@@ -0,0 +1,136 @@
+use anyhow::{Context, Result};
+use minijinja::value::Serde;
+use minijinja::{AutoEscape, Environment};
+use serde::Serialize;
+
+use super::{Alert, AlertPriority, HttpClient, Sink, WebhookConfig};
+use crate::enrich::Enrichment;
+use crate::parser::P2000Message;
+
+const TEMPLATE_NAME: &str = "payload";
+
+/// Template context: `rule`, `topic`, `priority`, `message` and `enrichment`.
+#[derive(Serialize)]
+struct Payload<'a> {
+    rule: &'a str,
+    topic: Option<&'a str>,
+    priority: AlertPriority,
+    message: &'a P2000Message,
+    enrichment: &'a Enrichment,
+}
+
+pub struct WebhookSink {
+    config: WebhookConfig,
+    env: Option<Environment<'static>>,
+    http: HttpClient,
+}
+
+impl WebhookSink {
+    pub fn new(config: WebhookConfig) -> Result<Self> {
+        let source = match (&config.template, &config.template_file) {
+            (Some(template), _) => Some(template.clone()),
+            (None, Some(path)) => Some(
+                std::fs::read_to_string(path)
+                    .with_context(|| format!("Failed to read template {}", path.display()))?,
+            ),
+            (None, None) => None,
+        };
+
+        let env = match source {
+            Some(source) => {
+                let mut env = Environment::new();
+                // JSON payloads render every {{ value }} as a JSON literal, so
+                // message content can't break the document
+                let escape = if config.content_type.contains("json") {
+                    AutoEscape::Json
+                } else {
+                    AutoEscape::None
+                };
+                env.set_auto_escape_callback(move |_| escape.clone());
+                env.add_template_owned(TEMPLATE_NAME, source)
+                    .with_context(|| format!("Invalid webhook template for {}", config.url))?;
+                Some(env)
+            }
+            None => None,
+        };
+
+        Ok(WebhookSink {
+            config,
+            env,
+            http: HttpClient::new()?,
+        })
+    }
+
+    fn render(&self, alert: &Alert) -> Result<String> {
+        let payload = Payload {
+            rule: &alert.rule.name,
+            topic: alert.rule.topic.as_deref(),
+            priority: alert.priority,
+            message: alert.message,
+            enrichment: alert.enrichment,
+        };
+        match &self.env {
+            Some(env) => {
+                let template = env.get_template(TEMPLATE_NAME)?;
+                Ok(template.render(Serde(&payload))?)
+            }
+            None => Ok(serde_json::to_string(&payload)?),
+        }
+    }
+}
+
+impl Sink for WebhookSink {
+    fn name(&self) -> &str {
+        "webhook"
+    }
+
+    fn send(&self, alert: &Alert) -> Result<()> {
+        let body = self.render(alert)?;
+
+        let mut headers = vec![("Content-Type", self.config.content_type.as_str())];
+        headers.extend(self.config.headers.iter().map(|(name, value)| (name.as_str(), value.as_str())));
+        self.http.send(&self.config.method.to_uppercase(), &self.config.url, &headers, body)
+    }
+}
+
+// The sinks need an HTTP client even though these tests never send
+#[cfg(all(test, feature = "notifications"))]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use crate::rules::Rule;
+
+    #[test]
+    fn test_render_json_template() {
+        let config = WebhookConfig {
+            url: "http://localhost/hook".to_string(),
+            method: "POST".to_string(),
+            template: Some(r#"{"text": {{ message.content }}, "rule": {{ rule }}, "n": {{ enrichment.capcodes|length }}}"#.to_string()),
+            template_file: None,
+            content_type: "application/json".to_string(),
+            headers: Default::default(),
+        };
+        let sink = WebhookSink::new(config).expect("Invalid template");
+
+        let msg = Parser::new()
+            .parse_line(r#"FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 "Duizel" Rit: 461"#)
+            .expect("Failed to parse");
+        let rule = Rule {
+            name: "test".to_string(),
+            ..Default::default()
+        };
+        let enrichment = Enrichment::default();
+        let alert = Alert {
+            rule: &rule,
+            message: &msg,
+            enrichment: &enrichment,
+            priority: AlertPriority::Urgent,
+        };
+
+        let body = sink.render(&alert).expect("Failed to render");
+        let json: serde_json::Value = serde_json::from_str(&body).expect("Invalid JSON");
+        assert_eq!(json["text"], r#"A1 "Duizel" Rit: 461"#);
+        assert_eq!(json["rule"], "test");
+        assert_eq!(json["n"], 0);
+    }
+}
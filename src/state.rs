@@ -0,0 +1,1438 @@
+//! Front-end independent message list state: search filtering, selection and
+//! the visible window. The TUI drives it from key presses; other front-ends
+//! can drive it the same way.
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::Arc;
+
+use crate::lookup::{normalize_code, Lookup};
+use crate::parser::{P2000Message, TimeSource};
+use crate::rules;
+use crate::search::{self, Query, Subject};
+use crate::severity::SeverityConfig;
+
+/// Undo steps kept for filter changes
+const HISTORY_LIMIT: usize = 50;
+
+/// Longest silence between two messages of one burst of repeats
+const REPEAT_WINDOW: chrono::Duration = chrono::Duration::minutes(10);
+
+/// Everything that decides which messages are shown and in what order.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FilterState {
+    pub search_query: String,
+    /// Messages whose capcodes are all in here are hidden (stored without leading zeros)
+    pub muted_capcodes: BTreeSet<String>,
+    /// Only messages in this veiligheidsregio are shown
+    pub region: Option<String>,
+    /// Only messages geocoded to this place are shown (following a place)
+    pub place: Option<String>,
+    /// Numeric, group, tone and binary pages are hidden
+    pub hide_minor_pages: bool,
+    /// What plain searches look in
+    pub scope: SearchScope,
+    pub sort: SortOrder,
+}
+
+/// The part of a message a plain search looks in. Field terms
+/// (`place:...`) choose their own field.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SearchScope {
+    /// Content, priority, location, channel, region, abbreviation expansions,
+    /// capcode descriptions and notes
+    #[default]
+    All,
+    Content,
+    /// Capcode numbers, with or without leading zeros
+    Capcodes,
+    /// Descriptions, places and services of the capcodes in the capcode list
+    Descriptions,
+    /// Location and region
+    Location,
+}
+
+impl SearchScope {
+    pub const ALL: [SearchScope; 5] = [
+        SearchScope::All,
+        SearchScope::Content,
+        SearchScope::Capcodes,
+        SearchScope::Descriptions,
+        SearchScope::Location,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            SearchScope::All => "all",
+            SearchScope::Content => "content",
+            SearchScope::Capcodes => "capcodes",
+            SearchScope::Descriptions => "descriptions",
+            SearchScope::Location => "location",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|scope| scope.name() == name)
+    }
+
+    /// The scope after this one in `ALL`, back to `All` after the last.
+    pub fn next(self) -> Self {
+        let position = Self::ALL.iter().position(|scope| *scope == self).unwrap_or(0);
+        Self::ALL[(position + 1) % Self::ALL.len()]
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SortOrder {
+    /// Order of arrival
+    #[default]
+    Arrival,
+    /// By region name, messages without a region last; arrival order within a region
+    Region,
+    /// Oldest first, by the clock set with `AppState::with_time_source`
+    Time,
+    /// Highest severity score first; arrival order among equal scores
+    Severity,
+}
+
+/// Where the user is with an incident (one message).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IncidentStatus {
+    #[default]
+    Open,
+    Acknowledged,
+    Resolved,
+}
+
+impl IncidentStatus {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            IncidentStatus::Open => "open",
+            IncidentStatus::Acknowledged => "acknowledged",
+            IncidentStatus::Resolved => "resolved",
+        }
+    }
+
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "open" => Some(IncidentStatus::Open),
+            "acknowledged" => Some(IncidentStatus::Acknowledged),
+            "resolved" => Some(IncidentStatus::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// Per-message data the front-end supplies or the user sets.
+#[derive(Debug, Clone, Default)]
+struct MessageMeta {
+    // `search_text` of the message and its region
+    search_text: String,
+    // Lowercased descriptions of its capcodes, and the expansions of its
+    // abbreviations, one per line
+    descriptions: String,
+    expansions: String,
+    region: Option<String>,
+    // Place the front-end matched in it
+    place: Option<String>,
+    severity: u32,
+    status: IncidentStatus,
+    note: Option<String>,
+}
+
+pub struct AppState {
+    messages: Vec<P2000Message>,
+    // Parallel to `messages`
+    meta: Vec<MessageMeta>,
+    filter: FilterState,
+    // Last committed filter; undo/redo move between committed states
+    committed: FilterState,
+    undo_stack: Vec<FilterState>,
+    redo_stack: Vec<FilterState>,
+    // Indices into `messages` matching the search, in display order
+    filtered_indices: Vec<usize>,
+    // Position in `filtered_indices`
+    selected_index: usize,
+    scroll_offset: usize,
+    viewport_height: usize,
+    // Multi-selection as indices into `messages`, so it survives filter changes
+    marked: BTreeSet<usize>,
+    // Filtered-list position where a range selection started
+    range_anchor: Option<usize>,
+    bookmarks: BTreeSet<usize>,
+    // Clock behind `SortOrder::Time`
+    time_source: TimeSource,
+    // Lowercased word -> indices of messages it occurs in, ascending. May list
+    // messages that no longer contain the word (an edited note); `matches` decides.
+    words: BTreeMap<String, Vec<usize>>,
+    // Open urgent messages, kept up to date by `add_message` and `set_status`
+    pending: usize,
+    // The search query when it uses fields or operators; plain words are
+    // matched as one substring
+    structured: Option<Query>,
+    // Show bursts of repeated messages as one row
+    collapse_repeats: bool,
+    // Resolves capcodes to the descriptions searches look in
+    lookup: Option<Arc<Lookup>>,
+    // Weights behind `SortOrder::Severity`
+    severity: SeverityConfig,
+    // First message of each burst -> all its messages, in display order
+    bursts: HashMap<usize, Vec<usize>>,
+    // Bursts shown in full, by their first message
+    expanded: BTreeSet<usize>,
+}
+
+impl AppState {
+    pub fn new(messages: Vec<P2000Message>) -> Self {
+        let filtered_indices: Vec<usize> = (0..messages.len()).collect();
+        let mut state = AppState {
+            meta: Vec::with_capacity(messages.len()),
+            messages: Vec::new(),
+            filter: FilterState::default(),
+            committed: FilterState::default(),
+            undo_stack: Vec::new(),
+            redo_stack: Vec::new(),
+            filtered_indices,
+            selected_index: 0,
+            scroll_offset: 0,
+            viewport_height: 10,
+            marked: BTreeSet::new(),
+            range_anchor: None,
+            bookmarks: BTreeSet::new(),
+            time_source: TimeSource::default(),
+            words: BTreeMap::new(),
+            pending: 0,
+            structured: None,
+            collapse_repeats: false,
+            lookup: None,
+            severity: SeverityConfig::default(),
+            bursts: HashMap::new(),
+            expanded: BTreeSet::new(),
+        };
+        for msg in messages {
+            state.add_message(msg, None, None);
+        }
+        state
+    }
+
+    /// Chooses the clock that orders messages when sorting by time.
+    pub fn with_time_source(mut self, source: TimeSource) -> Self {
+        self.time_source = source;
+        self.filter_messages();
+        self
+    }
+
+    /// Shows consecutive messages with the same text and capcodes, at most
+    /// `REPEAT_WINDOW` apart, as one row; see `repeat_count` and `toggle_burst`.
+    pub fn with_collapse_repeats(mut self, collapse: bool) -> Self {
+        self.collapse_repeats = collapse;
+        self.filter_messages();
+        self
+    }
+
+    /// Weighs the severity of every message with `config`.
+    pub fn with_severity(mut self, config: SeverityConfig) -> Self {
+        self.severity = config;
+        for index in 0..self.messages.len() {
+            self.meta[index].severity = self.score(&self.messages[index]);
+        }
+        self.filter_messages();
+        self
+    }
+
+    /// Sets the matched place of each message, in `messages()` order.
+    pub fn with_places(mut self, places: Vec<Option<String>>) -> Self {
+        self.set_places(places);
+        self
+    }
+
+    /// Replaces the matched place of every message, e.g. after the lookups were reloaded.
+    pub fn set_places(&mut self, places: Vec<Option<String>>) {
+        assert_eq!(places.len(), self.messages.len(), "one place per message");
+        for (meta, place) in self.meta.iter_mut().zip(places) {
+            meta.place = place;
+        }
+        self.filter_messages();
+    }
+
+    /// Makes the capcode descriptions and abbreviation expansions from `lookup`
+    /// searchable, for messages added from now on and, once `set_regions` runs,
+    /// for the rest.
+    pub fn with_lookup(mut self, lookup: Arc<Lookup>) -> Self {
+        self.set_lookup(lookup);
+        self
+    }
+
+    /// Replaces the lookup after it was reloaded; follow with `set_regions`.
+    pub fn set_lookup(&mut self, lookup: Arc<Lookup>) {
+        self.lookup = Some(lookup);
+    }
+
+    /// Sets the region of each message, in `messages()` order.
+    pub fn with_regions(mut self, regions: Vec<Option<String>>) -> Self {
+        self.set_regions(regions);
+        self
+    }
+
+    /// Replaces the region of every message, e.g. after the lookups were reloaded.
+    pub fn set_regions(&mut self, regions: Vec<Option<String>>) {
+        assert_eq!(regions.len(), self.messages.len(), "one region per message");
+        for (index, region) in regions.into_iter().enumerate() {
+            let descriptions = self.descriptions(&self.messages[index]);
+            let expansions = self.expansions(&self.messages[index]);
+            let search_text = search_text(&self.messages[index], region.as_deref(), &[&descriptions, &expansions]);
+            self.index_words(index, &search_text);
+            let severity = self.score(&self.messages[index]);
+            let meta = &mut self.meta[index];
+            (meta.search_text, meta.region, meta.severity) = (search_text, region, severity);
+            (meta.descriptions, meta.expansions) = (descriptions, expansions);
+        }
+        self.filter_messages();
+    }
+
+    pub fn messages(&self) -> &[P2000Message] {
+        &self.messages
+    }
+
+    /// Appends a message without a known region.
+    pub fn push(&mut self, msg: P2000Message) {
+        self.push_with_region(msg, None);
+    }
+
+    /// Appends a message without a known place.
+    pub fn push_with_region(&mut self, msg: P2000Message, region: Option<String>) {
+        self.push_located(msg, region, None);
+    }
+
+    /// Appends a message with its region and matched place, showing it right
+    /// away if it matches the current filter.
+    pub fn push_located(&mut self, msg: P2000Message, region: Option<String>, place: Option<String>) {
+        let index = self.add_message(msg, region, place);
+        if !self.matches(index, &self.filter.search_query.to_lowercase()) {
+            return;
+        }
+        let position = match self.filter.sort {
+            SortOrder::Arrival => self.filtered_indices.len(),
+            SortOrder::Region => {
+                let key = self.region_key(index);
+                self.filtered_indices.partition_point(|&i| self.region_key(i) <= key)
+            }
+            SortOrder::Time => {
+                let time = self.messages[index].time(self.time_source);
+                self.filtered_indices
+                    .partition_point(|&i| self.messages[i].time(self.time_source) <= time)
+            }
+            SortOrder::Severity => {
+                let severity = self.meta[index].severity;
+                self.filtered_indices.partition_point(|&i| self.meta[i].severity >= severity)
+            }
+        };
+        if self.collapse_repeats && position > 0 && self.fold_repeat(position - 1, index) {
+            return;
+        }
+        self.filtered_indices.insert(position, index);
+    }
+
+    /// Adds `index` to the burst shown at `position` when it repeats the burst's
+    /// last message; true when it is hidden in the collapsed row.
+    fn fold_repeat(&mut self, position: usize, index: usize) -> bool {
+        let head = self.burst_head(self.filtered_indices[position]);
+        let last = self.bursts.get(&head).and_then(|members| members.last()).copied().unwrap_or(head);
+        if !self.is_repeat(last, index) {
+            return false;
+        }
+        self.bursts.entry(head).or_insert_with(|| vec![head]).push(index);
+        !self.expanded.contains(&head)
+    }
+
+    /// Whether `b` is a retransmission or a repeated alarm of `a`.
+    fn is_repeat(&self, a: usize, b: usize) -> bool {
+        let (a, b) = (&self.messages[a], &self.messages[b]);
+        let same_capcodes = a.capcodes.len() == b.capcodes.len()
+            && a.capcodes.iter().all(|c| b.capcodes.iter().any(|d| normalize_code(c) == normalize_code(d)));
+        a.content == b.content && same_capcodes && (b.timestamp - a.timestamp).abs() <= REPEAT_WINDOW
+    }
+
+    /// First message of the burst that a message belongs to; itself when it
+    /// is no repeat.
+    fn burst_head(&self, message_index: usize) -> usize {
+        if self.bursts.contains_key(&message_index) {
+            return message_index;
+        }
+        self.bursts
+            .iter()
+            .find(|(_, members)| members.contains(&message_index))
+            .map_or(message_index, |(head, _)| *head)
+    }
+
+    /// Messages in the burst of a message, 1 when it has no repeats.
+    pub fn repeat_count(&self, message_index: usize) -> usize {
+        self.bursts.get(&self.burst_head(message_index)).map_or(1, Vec::len)
+    }
+
+    /// Shows the burst at the selection in full, or collapses it again. Returns
+    /// the number of messages in it; 0 when the selection has no repeats.
+    pub fn toggle_burst(&mut self) -> usize {
+        let Some(selected) = self.message_index(self.selected_index) else {
+            return 0;
+        };
+        let head = self.burst_head(selected);
+        let Some(members) = self.bursts.get(&head).cloned() else {
+            return 0;
+        };
+        let Some(position) = self.filtered_indices.iter().position(|&i| i == head) else {
+            return 0;
+        };
+        if self.expanded.remove(&head) {
+            self.filtered_indices.retain(|i| *i == head || !members.contains(i));
+        } else {
+            self.expanded.insert(head);
+            self.filtered_indices.splice(position + 1..position + 1, members[1..].iter().copied());
+        }
+        self.range_anchor = None;
+        self.select(position);
+        members.len()
+    }
+
+    pub fn is_expanded(&self, message_index: usize) -> bool {
+        self.expanded.contains(&self.burst_head(message_index))
+    }
+
+    // Stores a message with its search text and indexes its words
+    fn add_message(&mut self, msg: P2000Message, region: Option<String>, place: Option<String>) -> usize {
+        let index = self.messages.len();
+        let descriptions = self.descriptions(&msg);
+        let expansions = self.expansions(&msg);
+        let search_text = search_text(&msg, region.as_deref(), &[&descriptions, &expansions]);
+        let severity = self.score(&msg);
+        self.index_words(index, &search_text);
+        if is_urgent(&msg) {
+            self.pending += 1;
+        }
+        self.messages.push(msg);
+        self.meta.push(MessageMeta {
+            search_text,
+            descriptions,
+            expansions,
+            region,
+            place,
+            severity,
+            ..Default::default()
+        });
+        index
+    }
+
+    // Severity score, weighing the categories of the abbreviations the lookup finds
+    fn score(&self, msg: &P2000Message) -> u32 {
+        let found = self.lookup.as_ref().map(|lookup| lookup.find_abbreviations(&msg.content)).unwrap_or_default();
+        let categories = found.iter().filter_map(|(_, abbreviation)| abbreviation.category.as_deref());
+        self.severity.assess(msg, categories).total()
+    }
+
+    // Lowercased description, place and service of each capcode in the lookup
+    fn descriptions(&self, msg: &P2000Message) -> String {
+        let Some(lookup) = &self.lookup else {
+            return String::new();
+        };
+        let infos = msg.capcodes.iter().filter_map(|code| lookup.resolve_capcode(code));
+        let lines: Vec<String> = infos
+            .map(|info| {
+                let fields = [&info.description, &info.short, &info.place, &info.service];
+                fields.into_iter().filter(|field| !field.is_empty()).map(String::as_str).collect::<Vec<_>>().join(" ")
+            })
+            .collect();
+        lines.join("\n").to_lowercase()
+    }
+
+    // What the abbreviations in the content stand for, one per line, so
+    // "reanimatie" finds messages that only say "REA"
+    fn expansions(&self, msg: &P2000Message) -> String {
+        let Some(lookup) = &self.lookup else {
+            return String::new();
+        };
+        let found = lookup.find_abbreviations(&msg.content);
+        let expansions: Vec<&str> = found.iter().map(|(_, abbreviation)| abbreviation.expansion.as_str()).collect();
+        expansions.join("\n").to_lowercase()
+    }
+
+    fn index_words(&mut self, index: usize, text: &str) {
+        for word in words(&text.to_lowercase()) {
+            let postings = self.words.entry(word.to_string()).or_default();
+            if postings.last() != Some(&index) {
+                let position = postings.partition_point(|&i| i < index);
+                if postings.get(position) != Some(&index) {
+                    postings.insert(position, index);
+                }
+            }
+        }
+    }
+
+    /// Region of a message, by index into `messages()`.
+    pub fn region(&self, message_index: usize) -> Option<&str> {
+        self.meta.get(message_index)?.region.as_deref()
+    }
+
+    /// Severity score of a message, by index into `messages()`.
+    pub fn severity(&self, message_index: usize) -> u32 {
+        self.meta.get(message_index).map_or(0, |meta| meta.severity)
+    }
+
+    /// Distinct regions across all messages, sorted.
+    pub fn region_names(&self) -> Vec<&str> {
+        let names: BTreeSet<&str> = self.meta.iter().filter_map(|m| m.region.as_deref()).collect();
+        names.into_iter().collect()
+    }
+
+    pub fn status(&self, message_index: usize) -> IncidentStatus {
+        self.meta.get(message_index).map(|m| m.status).unwrap_or_default()
+    }
+
+    pub fn set_status(&mut self, message_index: usize, status: IncidentStatus) {
+        let urgent = self.messages.get(message_index).is_some_and(is_urgent);
+        if let Some(meta) = self.meta.get_mut(message_index) {
+            if urgent {
+                let was_open = meta.status == IncidentStatus::Open;
+                let is_open = status == IncidentStatus::Open;
+                self.pending = self.pending + usize::from(is_open) - usize::from(was_open);
+            }
+            meta.status = status;
+        }
+    }
+
+    /// Gives the action targets `status`, or reopens them when they all have it
+    /// already. Returns the changed message indices so they can be persisted.
+    pub fn toggle_target_status(&mut self, status: IncidentStatus) -> Vec<usize> {
+        let targets = self.action_target_indices();
+        let new_status = if targets.iter().all(|&i| self.status(i) == status) {
+            IncidentStatus::Open
+        } else {
+            status
+        };
+        let changed: Vec<usize> = targets
+            .into_iter()
+            .filter(|&i| self.status(i) != new_status)
+            .collect();
+        for &i in &changed {
+            self.set_status(i, new_status);
+        }
+        changed
+    }
+
+    pub fn note(&self, message_index: usize) -> Option<&str> {
+        self.meta.get(message_index)?.note.as_deref()
+    }
+
+    /// Attaches a note to a message; an empty note removes it.
+    pub fn set_note(&mut self, message_index: usize, note: Option<String>) {
+        let note = note.filter(|n| !n.trim().is_empty());
+        if let (Some(text), true) = (&note, message_index < self.meta.len()) {
+            self.index_words(message_index, text);
+        }
+        if let Some(meta) = self.meta.get_mut(message_index) {
+            meta.note = note;
+        }
+    }
+
+    /// A1/P 1 messages nobody has acknowledged or resolved yet.
+    pub fn pending_count(&self) -> usize {
+        self.pending
+    }
+
+    /// Number of messages matching the current search.
+    pub fn filtered_len(&self) -> usize {
+        self.filtered_indices.len()
+    }
+
+    /// Indices into `messages()` of the matching messages, in display order.
+    pub fn filtered_indices(&self) -> &[usize] {
+        &self.filtered_indices
+    }
+
+    /// Index into `messages()` of the message at a filtered-list position.
+    pub fn message_index(&self, position: usize) -> Option<usize> {
+        self.filtered_indices.get(position).copied()
+    }
+
+    /// Matching messages in display order.
+    pub fn filtered(&self) -> impl Iterator<Item = &P2000Message> {
+        self.filtered_indices.iter().map(|&i| &self.messages[i])
+    }
+
+    /// Matching messages within the viewport, with their position in the filtered list.
+    pub fn visible(&self) -> impl Iterator<Item = (usize, &P2000Message)> {
+        self.filtered()
+            .enumerate()
+            .skip(self.scroll_offset)
+            .take(self.viewport_height)
+    }
+
+    /// Number of rows the front-end can show at once.
+    pub fn set_viewport_height(&mut self, rows: usize) {
+        self.viewport_height = rows;
+        self.ensure_selected_visible();
+    }
+
+    pub fn scroll_offset(&self) -> usize {
+        self.scroll_offset
+    }
+
+    fn ensure_selected_visible(&mut self) {
+        let visible_end = self.scroll_offset + self.viewport_height;
+        if self.selected_index < self.scroll_offset {
+            self.scroll_offset = self.selected_index;
+        } else if self.selected_index >= visible_end {
+            self.scroll_offset = (self.selected_index + 1).saturating_sub(self.viewport_height);
+        }
+    }
+
+    /// Position of the selection in the filtered list.
+    pub fn selected_index(&self) -> usize {
+        self.selected_index
+    }
+
+    pub fn selected_message(&self) -> Option<&P2000Message> {
+        self.filtered_indices
+            .get(self.selected_index)
+            .and_then(|idx| self.messages.get(*idx))
+    }
+
+    /// Selects a position in the filtered list, clamped to its bounds.
+    pub fn select(&mut self, index: usize) {
+        self.selected_index = index.min(self.filtered_indices.len().saturating_sub(1));
+        self.ensure_selected_visible();
+    }
+
+    pub fn move_down(&mut self) {
+        self.select(self.selected_index + 1);
+    }
+
+    pub fn move_up(&mut self) {
+        self.select(self.selected_index.saturating_sub(1));
+    }
+
+    pub fn page_down(&mut self, rows: usize) {
+        self.select(self.selected_index + rows);
+    }
+
+    pub fn page_up(&mut self, rows: usize) {
+        self.select(self.selected_index.saturating_sub(rows));
+    }
+
+    pub fn filter(&self) -> &FilterState {
+        &self.filter
+    }
+
+    pub fn set_filter(&mut self, filter: FilterState) {
+        let previous = std::mem::replace(&mut self.filter, filter);
+        self.refilter(&previous);
+    }
+
+    pub fn search_query(&self) -> &str {
+        &self.filter.search_query
+    }
+
+    pub fn set_search_query(&mut self, query: &str) {
+        let mut filter = self.filter.clone();
+        filter.search_query = query.to_string();
+        self.set_filter(filter);
+    }
+
+    pub fn add_search_char(&mut self, c: char) {
+        let mut filter = self.filter.clone();
+        filter.search_query.push(c);
+        self.set_filter(filter);
+    }
+
+    pub fn remove_search_char(&mut self) {
+        self.filter.search_query.pop();
+        self.filter_messages();
+    }
+
+    pub fn clear_search(&mut self) {
+        self.filter.search_query.clear();
+        self.filter_messages();
+    }
+
+    pub fn set_region_filter(&mut self, region: Option<String>) {
+        let mut filter = self.filter.clone();
+        filter.region = region;
+        self.set_filter(filter);
+    }
+
+    /// Matched place of a message, by index into `messages()`.
+    pub fn place(&self, message_index: usize) -> Option<&str> {
+        self.meta.get(message_index)?.place.as_deref()
+    }
+
+    /// Shows only messages matched to `place`, ignoring case; `None` shows all.
+    pub fn set_place_filter(&mut self, place: Option<String>) {
+        let mut filter = self.filter.clone();
+        filter.place = place;
+        self.set_filter(filter);
+    }
+
+    /// Whether the message is shown while following a place: there is no
+    /// place followed, or it is the one.
+    pub fn at_followed_place(&self, message_index: usize) -> bool {
+        let place = self.place(message_index);
+        self.filter.place.as_deref().is_none_or(|wanted| place.is_some_and(|p| p.eq_ignore_ascii_case(wanted)))
+    }
+
+    /// Steps the region filter through `region_names()`, then back to all regions.
+    pub fn cycle_region_filter(&mut self) {
+        let names = self.region_names();
+        let next = match &self.filter.region {
+            None => names.first(),
+            Some(current) => names.iter().skip_while(|name| *name != current).nth(1),
+        };
+        let next = next.map(|name| name.to_string());
+        self.set_region_filter(next);
+    }
+
+    pub fn set_search_scope(&mut self, scope: SearchScope) {
+        let mut filter = self.filter.clone();
+        filter.scope = scope;
+        self.set_filter(filter);
+    }
+
+    /// Plain searches look in the next `SearchScope`.
+    pub fn cycle_search_scope(&mut self) {
+        self.set_search_scope(self.filter.scope.next());
+    }
+
+    /// Hides or shows numeric, group, tone and binary pages.
+    pub fn toggle_minor_pages(&mut self) {
+        let mut filter = self.filter.clone();
+        filter.hide_minor_pages = !filter.hide_minor_pages;
+        self.set_filter(filter);
+    }
+
+    pub fn set_sort(&mut self, sort: SortOrder) {
+        let mut filter = self.filter.clone();
+        filter.sort = sort;
+        self.set_filter(filter);
+    }
+
+    pub fn toggle_region_sort(&mut self) {
+        self.set_sort(match self.filter.sort {
+            SortOrder::Region => SortOrder::Arrival,
+            SortOrder::Arrival | SortOrder::Time | SortOrder::Severity => SortOrder::Region,
+        });
+    }
+
+    /// Arrival, then region, time and severity order.
+    pub fn cycle_sort(&mut self) {
+        self.set_sort(match self.filter.sort {
+            SortOrder::Arrival => SortOrder::Region,
+            SortOrder::Region => SortOrder::Time,
+            SortOrder::Time => SortOrder::Severity,
+            SortOrder::Severity => SortOrder::Arrival,
+        });
+    }
+
+    /// Records the current filter as an undo step. Front-ends call this once an
+    /// edit is finished (not per keystroke) so undo restores whole filters.
+    pub fn commit_filter(&mut self) {
+        if self.filter == self.committed {
+            return;
+        }
+        let previous = std::mem::replace(&mut self.committed, self.filter.clone());
+        self.undo_stack.push(previous);
+        if self.undo_stack.len() > HISTORY_LIMIT {
+            self.undo_stack.remove(0);
+        }
+        self.redo_stack.clear();
+    }
+
+    /// Returns to the previous committed filter; false when there is none.
+    pub fn undo_filter(&mut self) -> bool {
+        self.commit_filter();
+        let Some(previous) = self.undo_stack.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(&mut self.committed, previous.clone());
+        self.redo_stack.push(current);
+        self.set_filter(previous);
+        true
+    }
+
+    pub fn redo_filter(&mut self) -> bool {
+        let Some(next) = self.redo_stack.pop() else {
+            return false;
+        };
+        let current = std::mem::replace(&mut self.committed, next.clone());
+        self.undo_stack.push(current);
+        self.set_filter(next);
+        true
+    }
+
+    /// Marks or unmarks the selected message.
+    pub fn toggle_mark(&mut self) {
+        if let Some(index) = self.message_index(self.selected_index) {
+            if !self.marked.remove(&index) {
+                self.marked.insert(index);
+            }
+        }
+    }
+
+    /// Starts a range at the selection, or marks everything between the start
+    /// and the selection when a range is already open.
+    pub fn toggle_range(&mut self) {
+        match self.range_anchor.take() {
+            None => self.range_anchor = Some(self.selected_index),
+            Some(anchor) => {
+                let (from, to) = if anchor <= self.selected_index {
+                    (anchor, self.selected_index)
+                } else {
+                    (self.selected_index, anchor)
+                };
+                let end = (to + 1).min(self.filtered_indices.len());
+                self.marked.extend(self.filtered_indices[from.min(end)..end].iter().copied());
+            }
+        }
+    }
+
+    pub fn range_anchor(&self) -> Option<usize> {
+        self.range_anchor
+    }
+
+    /// Whether the message at a filtered-list position is marked or inside the open range.
+    pub fn is_marked_at(&self, position: usize) -> bool {
+        let in_range = self.range_anchor.is_some_and(|anchor| {
+            let (from, to) = (anchor.min(self.selected_index), anchor.max(self.selected_index));
+            (from..=to).contains(&position)
+        });
+        in_range || self.message_index(position).is_some_and(|i| self.marked.contains(&i))
+    }
+
+    pub fn clear_marks(&mut self) {
+        self.marked.clear();
+        self.range_anchor = None;
+    }
+
+    /// Marked messages in list order, or the selected one when nothing is marked.
+    pub fn action_targets(&self) -> Vec<&P2000Message> {
+        if self.marked.is_empty() {
+            self.selected_message().into_iter().collect()
+        } else {
+            self.marked.iter().map(|&i| &self.messages[i]).collect()
+        }
+    }
+
+    pub fn marked_count(&self) -> usize {
+        self.marked.len()
+    }
+
+    fn action_target_indices(&self) -> Vec<usize> {
+        if self.marked.is_empty() {
+            self.message_index(self.selected_index).into_iter().collect()
+        } else {
+            self.marked.iter().copied().collect()
+        }
+    }
+
+    /// Bookmarks the action targets; returns how many were added.
+    pub fn bookmark_targets(&mut self) -> usize {
+        let targets = self.action_target_indices();
+        let before = self.bookmarks.len();
+        self.bookmarks.extend(targets);
+        self.bookmarks.len() - before
+    }
+
+    pub fn is_bookmarked(&self, message_index: usize) -> bool {
+        self.bookmarks.contains(&message_index)
+    }
+
+    /// Hides every message whose capcodes are all among the action targets' capcodes,
+    /// as an undoable filter change. Returns the number of newly muted capcodes.
+    pub fn mute_target_capcodes(&mut self) -> usize {
+        let capcodes: Vec<String> = self
+            .action_targets()
+            .iter()
+            .flat_map(|msg| msg.capcodes.iter())
+            .map(|c| normalize_code(c))
+            .collect();
+        let mut filter = self.filter.clone();
+        let before = filter.muted_capcodes.len();
+        filter.muted_capcodes.extend(capcodes);
+        let added = filter.muted_capcodes.len() - before;
+        self.clear_marks();
+        self.set_filter(filter);
+        self.commit_filter();
+        added
+    }
+
+    /// `query` is the search query, already lowercased.
+    fn matches(&self, index: usize, query: &str) -> bool {
+        let meta = &self.meta[index];
+        let region = self.region(index);
+        if self.filter.region.as_deref().is_some_and(|wanted| region != Some(wanted)) {
+            return false;
+        }
+        if !self.at_followed_place(index) {
+            return false;
+        }
+        if self.filter.hide_minor_pages && self.messages[index].message_type.is_minor() {
+            return false;
+        }
+        let muted = &self.filter.muted_capcodes;
+        if !muted.is_empty()
+            && !self.messages[index].capcodes.is_empty()
+            && self.messages[index].capcodes.iter().all(|c| muted.contains(&normalize_code(c)))
+        {
+            return false;
+        }
+        if let Some(structured) = &self.structured {
+            let msg = &self.messages[index];
+            let subject = Subject {
+                region,
+                time: Some(msg.time(self.time_source)),
+                enriched: vec![&meta.descriptions, &meta.expansions],
+                ..Subject::message(msg)
+            };
+            return structured.matches(&subject);
+        }
+        if query.is_empty() {
+            return true;
+        }
+        let msg = &self.messages[index];
+        match self.filter.scope {
+            SearchScope::All => {
+                meta.search_text.contains(query) || meta.note.as_ref().is_some_and(|n| n.to_lowercase().contains(query))
+            }
+            SearchScope::Content => msg.content.to_lowercase().contains(query),
+            SearchScope::Capcodes => {
+                let digits = match query.trim().trim_start_matches('0') {
+                    "" => query.trim(),
+                    digits => digits,
+                };
+                msg.capcodes.iter().any(|code| code.contains(digits))
+            }
+            SearchScope::Descriptions => meta.descriptions.contains(query),
+            SearchScope::Location => {
+                msg.location.to_lowercase().contains(query) || region.is_some_and(|r| r.to_lowercase().contains(query))
+            }
+        }
+    }
+
+    // Sorts messages without a region after the rest
+    fn region_key(&self, index: usize) -> (bool, &str) {
+        let region = self.region(index);
+        (region.is_none(), region.unwrap_or(""))
+    }
+
+    /// Whether everything hidden by `previous` stays hidden under the current
+    /// filter, so only the shown messages need checking again.
+    fn narrows(&self, previous: &FilterState) -> bool {
+        // More text can loosen a structured query ("... OR ...")
+        let structured = self.structured.is_some() || search::parse_structured(&previous.search_query).is_some();
+        (!structured || previous.search_query == self.filter.search_query)
+            && previous.muted_capcodes.is_subset(&self.filter.muted_capcodes)
+            && (previous.region.is_none() || previous.region == self.filter.region)
+            && (previous.place.is_none() || previous.place == self.filter.place)
+            && (!previous.hide_minor_pages || self.filter.hide_minor_pages)
+            && previous.scope == self.filter.scope
+            && self
+                .filter
+                .search_query
+                .to_lowercase()
+                .contains(&previous.search_query.to_lowercase())
+    }
+
+    /// Updates the list after the filter changed from `previous`. Typing more of a
+    /// query or adding a restriction only re-checks the messages already shown;
+    /// anything that can bring messages back rescans them all.
+    fn refilter(&mut self, previous: &FilterState) {
+        self.structured = search::parse_structured(&self.filter.search_query);
+        if !self.narrows(previous) {
+            return self.filter_messages();
+        }
+        let query = self.filter.search_query.to_lowercase();
+        let mut indices = self.uncollapsed();
+        if self.filter.search_query != previous.search_query
+            || self.filter.muted_capcodes != previous.muted_capcodes
+            || self.filter.region != previous.region
+            || self.filter.place != previous.place
+            || self.filter.hide_minor_pages != previous.hide_minor_pages
+        {
+            indices.retain(|&i| self.matches(i, &query));
+        }
+        if self.filter.sort != previous.sort {
+            if self.filter.sort == SortOrder::Arrival {
+                indices.sort_unstable();
+            } else {
+                self.sort_indices(&mut indices);
+            }
+        }
+        self.set_filtered(indices);
+    }
+
+    // Stable sorts, so arrival order is kept among equal keys
+    fn sort_indices(&self, indices: &mut [usize]) {
+        match self.filter.sort {
+            SortOrder::Arrival => {}
+            SortOrder::Region => indices.sort_by(|&a, &b| self.region_key(a).cmp(&self.region_key(b))),
+            SortOrder::Time => indices.sort_by_key(|&i| self.messages[i].time(self.time_source)),
+            SortOrder::Severity => indices.sort_by_key(|&i| std::cmp::Reverse(self.meta[i].severity)),
+        }
+    }
+
+    /// Messages that can match `query`, from the word index: every match contains
+    /// the query's longest word inside one of its own words. `None` when the
+    /// index would not narrow things down much.
+    fn candidates(&self, query: &str) -> Option<Vec<usize>> {
+        let longest = words(query).max_by_key(|word| word.len())?;
+        let postings: Vec<&Vec<usize>> = self
+            .words
+            .iter()
+            .filter(|(word, _)| word.contains(longest))
+            .map(|(_, postings)| postings)
+            .collect();
+        if postings.iter().map(|p| p.len()).sum::<usize>() > self.messages.len() / 2 {
+            return None;
+        }
+        let mut indices: Vec<usize> = postings.into_iter().flatten().copied().collect();
+        indices.sort_unstable();
+        indices.dedup();
+        Some(indices)
+    }
+
+    fn filter_messages(&mut self) {
+        self.structured = search::parse_structured(&self.filter.search_query);
+        let query = self.filter.search_query.to_lowercase();
+        // Capcode numbers are not in the word index
+        let candidates = match (&self.structured, self.filter.scope) {
+            (Some(_), _) | (None, SearchScope::Capcodes) => None,
+            (None, _) => self.candidates(&query),
+        };
+        let candidates = candidates.unwrap_or_else(|| (0..self.messages.len()).collect());
+        let mut indices: Vec<usize> = candidates.into_iter().filter(|&i| self.matches(i, &query)).collect();
+        self.sort_indices(&mut indices);
+        self.set_filtered(indices);
+    }
+
+    /// `filtered_indices` with the hidden repeats of collapsed bursts put back.
+    fn uncollapsed(&self) -> Vec<usize> {
+        let mut indices = Vec::with_capacity(self.filtered_indices.len());
+        for &i in &self.filtered_indices {
+            match self.bursts.get(&i) {
+                Some(members) if !self.expanded.contains(&i) => indices.extend(members),
+                _ => indices.push(i),
+            }
+        }
+        indices
+    }
+
+    /// Groups runs of repeats in `indices` into bursts and leaves out all but
+    /// the first message of each collapsed one.
+    fn collapse(&mut self, indices: Vec<usize>) -> Vec<usize> {
+        self.bursts.clear();
+        if !self.collapse_repeats {
+            return indices;
+        }
+        let mut shown = Vec::with_capacity(indices.len());
+        // First and last message of the current burst
+        let mut current: Option<(usize, usize)> = None;
+        for i in indices {
+            match current {
+                Some((head, last)) if self.is_repeat(last, i) => {
+                    self.bursts.entry(head).or_insert_with(|| vec![head]).push(i);
+                    if self.expanded.contains(&head) {
+                        shown.push(i);
+                    }
+                    current = Some((head, i));
+                }
+                _ => {
+                    shown.push(i);
+                    current = Some((i, i));
+                }
+            }
+        }
+        shown
+    }
+
+    fn set_filtered(&mut self, indices: Vec<usize>) {
+        self.filtered_indices = self.collapse(indices);
+        self.selected_index = 0;
+        self.scroll_offset = 0;
+        // Positions change with the filter, so an open range no longer makes sense
+        self.range_anchor = None;
+    }
+}
+
+fn is_urgent(msg: &P2000Message) -> bool {
+    msg.priority.as_deref().is_some_and(rules::is_urgent)
+}
+
+/// Lowercased searchable fields of a message, one per line so a query cannot
+/// match across two of them. `enriched` is what the lookups add to it.
+fn search_text(msg: &P2000Message, region: Option<&str>, enriched: &[&str]) -> String {
+    let fields = [
+        Some(msg.content.as_str()),
+        msg.priority.as_deref(),
+        Some(msg.location.as_str()),
+        msg.channel.as_deref(),
+        region,
+    ];
+    let enriched = enriched.iter().copied().filter(|text| !text.is_empty());
+    fields.into_iter().flatten().chain(enriched).collect::<Vec<_>>().join("\n").to_lowercase()
+}
+
+/// Alphanumeric runs, the unit of the word index.
+fn words(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|c: char| !c.is_alphanumeric()).filter(|word| !word.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+
+    fn state() -> AppState {
+        let parser = Parser::new();
+        let messages = [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:01:29|1600/2/K/A|00.057|001220499|ALN|A1 Tilburg rit: 632",
+            "FLEX|2026-01-01 20:01:34|1600/2/K/A|00.060|000723146|ALN|A2 Almere 631",
+        ]
+        .iter()
+        .map(|line| parser.parse_line(line).unwrap())
+        .collect();
+        AppState::new(messages)
+    }
+
+    #[test]
+    fn test_search_and_selection() {
+        let mut state = state();
+        state.set_search_query("a2");
+        assert_eq!(state.filtered_len(), 2);
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.selected_index(), 1);
+        assert_eq!(state.selected_message().unwrap().content, "A2 Almere 631");
+
+        state.clear_search();
+        assert_eq!(state.filtered_len(), 4);
+        assert_eq!(state.selected_index(), 0);
+        state.move_up();
+        assert_eq!(state.selected_index(), 0);
+    }
+
+    #[test]
+    fn test_structured_search() {
+        let mut state = state();
+        state.set_search_query("prio:A1 OR capcode:726119");
+        assert_eq!(state.filtered_len(), 3);
+        // Typing more widens the query; everything is checked again
+        state.set_search_query("prio:A1 OR capcode:726119 OR almere");
+        assert_eq!(state.filtered_len(), 4);
+        state.set_search_query("prio:A1 NOT tilburg");
+        assert_eq!(state.filtered_len(), 1);
+        // Half-typed: matched as plain text until it parses
+        state.set_search_query("prio:");
+        assert_eq!(state.filtered_len(), 0);
+    }
+
+    #[test]
+    fn test_viewport_follows_selection() {
+        let mut state = state();
+        state.set_viewport_height(2);
+        state.page_down(10);
+        assert_eq!(state.selected_index(), 3);
+        assert_eq!(state.scroll_offset(), 2);
+        let visible: Vec<usize> = state.visible().map(|(i, _)| i).collect();
+        assert_eq!(visible, [2, 3]);
+
+        state.set_viewport_height(0);
+        state.page_up(1);
+        assert_eq!(state.visible().count(), 0);
+    }
+
+    #[test]
+    fn test_filter_undo_redo() {
+        let mut state = state();
+        state.set_search_query("a2");
+        state.commit_filter();
+        state.set_search_query("almere");
+        state.commit_filter();
+        state.clear_search();
+        state.commit_filter();
+        assert_eq!(state.filtered_len(), 4);
+
+        assert!(state.undo_filter());
+        assert_eq!(state.search_query(), "almere");
+        assert_eq!(state.filtered_len(), 1);
+        assert!(state.undo_filter());
+        assert_eq!(state.search_query(), "a2");
+        assert!(state.redo_filter());
+        assert_eq!(state.search_query(), "almere");
+
+        // A new edit discards the redo history
+        state.set_search_query("utrecht");
+        state.commit_filter();
+        assert!(!state.redo_filter());
+        assert!(state.undo_filter());
+        assert_eq!(state.search_query(), "almere");
+    }
+
+    #[test]
+    fn test_marks_and_bulk_mute() {
+        let mut state = state();
+        state.toggle_mark();
+        state.move_down();
+        state.toggle_range();
+        state.page_down(2);
+        assert!(state.is_marked_at(2));
+        state.toggle_range();
+        assert_eq!(state.marked_count(), 4);
+        state.move_up();
+        state.toggle_mark();
+        assert_eq!(state.action_targets().len(), 3);
+
+        assert_eq!(state.bookmark_targets(), 3);
+        assert!(state.is_bookmarked(0) && !state.is_bookmarked(2));
+
+        // Mutes the three marked messages' capcodes, leaving the unmarked one
+        assert_eq!(state.mute_target_capcodes(), 3);
+        assert_eq!(state.filtered_len(), 1);
+        assert_eq!(state.marked_count(), 0);
+        assert!(state.undo_filter());
+        assert_eq!(state.filtered_len(), 4);
+    }
+
+    #[test]
+    fn test_region_filter_and_sort() {
+        let regions = ["Brabant Zuid-Oost", "Utrecht", "Midden- en West-Brabant", "Flevoland"];
+        let mut state = state().with_regions(regions.iter().map(|r| Some(r.to_string())).collect());
+        assert_eq!(state.region_names()[0], "Brabant Zuid-Oost");
+
+        state.toggle_region_sort();
+        let sorted: Vec<usize> = state.filtered_indices().to_vec();
+        assert_eq!(sorted, [0, 3, 2, 1]);
+        let parser = Parser::new();
+        let msg = parser.parse_line("FLEX|2026-01-01 20:02:00|1600/2/K/A|00.061|001420999|ALN|A1 Gouda 640").unwrap();
+        state.push_with_region(msg.clone(), Some("Hollands Midden".to_string()));
+        state.push(msg);
+        assert_eq!(state.filtered_indices(), [0, 3, 4, 2, 1, 5]);
+
+        state.cycle_region_filter();
+        assert_eq!(state.filter().region.as_deref(), Some("Brabant Zuid-Oost"));
+        assert_eq!(state.filtered_len(), 1);
+        state.cycle_region_filter();
+        assert_eq!(state.selected_message().unwrap().content, "A2 Almere 631");
+        state.set_region_filter(Some("Utrecht".to_string()));
+        state.cycle_region_filter();
+        assert_eq!(state.filter().region, None);
+        assert_eq!(state.filtered_len(), 6);
+    }
+
+    #[test]
+    fn test_search_scopes() {
+        let dir = std::env::temp_dir().join(format!("p2000_state_scope_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let list = "\"0726119\";\"Ambulance\";\"Utrecht\";\"Utrecht\";\"Post Overvecht\";\"\"\n";
+        std::fs::write(dir.join("capcodes.csv"), list).unwrap();
+        std::fs::write(dir.join("abbreviations.txt"), "A1:Spoedrit met sirene\n").unwrap();
+        let lookup = Lookup::load(&dir.join("capcodes.csv"), &dir.join("abbreviations.txt")).unwrap();
+        std::fs::remove_dir_all(dir).ok();
+        let messages = state().messages().to_vec();
+        let regions = vec![None, Some("Utrecht".to_string()), None, Some("Flevoland".to_string())];
+        let mut state = AppState::new(messages).with_lookup(Arc::new(lookup)).with_regions(regions);
+
+        state.set_search_query("sirene");
+        assert_eq!(state.filtered_len(), 2, "all includes abbreviation expansions");
+        state.set_search_query("sirene prio:a1");
+        assert_eq!(state.filtered_len(), 2, "and so do words in a structured query");
+        state.set_search_query("overvecht region:utrecht");
+        assert_eq!(state.filtered_len(), 1);
+        state.set_search_query("overvecht");
+        assert_eq!(state.filtered_len(), 1, "all includes capcode descriptions");
+        state.set_search_scope(SearchScope::Content);
+        assert_eq!(state.filtered_len(), 0);
+        state.set_search_scope(SearchScope::Descriptions);
+        assert_eq!(state.selected_message().unwrap().content, "A2 Utrecht 630");
+
+        state.set_search_query("1220499");
+        state.cycle_search_scope();
+        assert_eq!(state.filter().scope, SearchScope::Location);
+        assert_eq!(state.filtered_len(), 0);
+        state.set_search_scope(SearchScope::Capcodes);
+        assert_eq!(state.selected_message().unwrap().content, "A1 Tilburg rit: 632");
+
+        state.set_search_query("flevo");
+        state.set_search_scope(SearchScope::Location);
+        assert_eq!(state.filtered_len(), 1, "location includes the region");
+        state.cycle_search_scope();
+        assert_eq!(state.filter().scope, SearchScope::All);
+    }
+
+    #[test]
+    fn test_hide_minor_pages() {
+        let parser = Parser::new();
+        let mut state = state();
+        state.push(parser.parse_line("FLEX|2026-01-01 20:02:00|1600/2/K/A|00.061|001420999|NUM|0612345678").unwrap());
+        state.push(parser.parse_line("FLEX|2026-01-01 20:02:01|1600/2/K/A|00.061|001420999|GPN|Oefening").unwrap());
+        state.toggle_minor_pages();
+        assert_eq!(state.filtered_len(), 4);
+        state.push(parser.parse_line("FLEX|2026-01-01 20:02:02|1600/2/K/A|00.061|001420999|TON|").unwrap());
+        assert_eq!(state.filtered_len(), 4);
+        state.toggle_minor_pages();
+        assert_eq!(state.filtered_len(), 7);
+    }
+
+    #[test]
+    fn test_sort_by_severity() {
+        let mut state = state();
+        state.set_sort(SortOrder::Severity);
+        // A1 before A2, arrival order within each
+        assert_eq!(state.filtered_indices(), [0, 2, 1, 3]);
+        let parser = Parser::new();
+        let grip = parser.parse_line("FLEX|2026-01-01 20:02:00|1600/2/K/A|00.061|001420999|ALN|A2 GRIP 1 Gouda");
+        state.push(grip.unwrap());
+        assert_eq!(state.filtered_indices(), [4, 0, 2, 1, 3]);
+        assert_eq!(state.severity(4), 20 + 25 + 3);
+
+        let mut state = state.with_severity(SeverityConfig {
+            grip: 0,
+            ..Default::default()
+        });
+        assert_eq!(state.filtered_indices(), [0, 2, 1, 3, 4]);
+        state.cycle_sort();
+        assert_eq!(state.filter().sort, SortOrder::Arrival);
+    }
+
+    #[test]
+    fn test_sort_by_received_time() {
+        let parser = Parser::new();
+        let mut late = parser.parse_line("FLEX|2026-01-01 20:05:00|1600/2/K/A|00.061|000726119|ALN|A1 Utrecht 640").unwrap();
+        let mut early = parser.parse_line("FLEX|2026-01-01 20:01:00|1600/2/K/A|00.062|000723146|ALN|A2 Almere 641").unwrap();
+        // The first decoder's clock runs four minutes fast
+        late.received = Some(early.timestamp);
+        early.received = Some(early.timestamp + chrono::Duration::seconds(30));
+
+        let mut state = AppState::new(vec![late.clone(), early.clone()]);
+        state.set_sort(SortOrder::Time);
+        assert_eq!(state.filtered_indices(), [1, 0]);
+        let mut state = AppState::new(vec![late, early]).with_time_source(TimeSource::Received);
+        state.set_sort(SortOrder::Time);
+        assert_eq!(state.filtered_indices(), [0, 1]);
+    }
+
+    #[test]
+    fn test_incident_status() {
+        let mut state = state();
+        assert_eq!(state.pending_count(), 2);
+        assert_eq!(state.toggle_target_status(IncidentStatus::Acknowledged), [0]);
+        assert_eq!(state.pending_count(), 1);
+
+        state.toggle_mark();
+        state.move_down();
+        state.toggle_mark();
+        assert_eq!(state.toggle_target_status(IncidentStatus::Resolved), [0, 1]);
+        assert_eq!(state.status(1), IncidentStatus::Resolved);
+        // All targets resolved already: pressing again reopens them
+        assert_eq!(state.toggle_target_status(IncidentStatus::Resolved), [0, 1]);
+        assert_eq!(state.status(0), IncidentStatus::Open);
+        assert_eq!(state.pending_count(), 2);
+    }
+
+    #[test]
+    fn test_notes_are_searchable() {
+        let mut state = state();
+        state.set_note(3, Some("Second unit requested by phone".to_string()));
+        state.set_note(1, Some("  ".to_string()));
+        assert_eq!(state.note(1), None);
+        state.set_search_query("by phone");
+        assert_eq!(state.filtered_indices(), [3]);
+    }
+
+    #[test]
+    fn test_push_respects_search() {
+        let mut state = state();
+        state.set_search_query("utrecht");
+        let parser = Parser::new();
+        state.push(parser.parse_line("FLEX|2026-01-01 20:02:00|1600/2/K/A|00.061|000726119|ALN|A1 Utrecht 640").unwrap());
+        state.push(parser.parse_line("FLEX|2026-01-01 20:02:01|1600/2/K/A|00.062|000723146|ALN|A2 Almere 641").unwrap());
+        assert_eq!(state.messages().len(), 6);
+        assert_eq!(state.filtered_len(), 2);
+    }
+
+    #[test]
+    fn test_incremental_filtering_keeps_order() {
+        let mut state = state();
+        state.set_sort(SortOrder::Time);
+        state.set_search_query("a");
+        assert_eq!(state.filtered_len(), 4);
+        // Narrowing re-checks the shown messages and keeps their order
+        state.add_search_char('2');
+        assert_eq!(state.filtered_indices(), [1, 3]);
+        state.add_search_char(' ');
+        state.add_search_char('a');
+        assert_eq!(state.filtered_indices(), [3]);
+        // Widening brings hidden messages back
+        state.remove_search_char();
+        state.remove_search_char();
+        assert_eq!(state.filtered_indices(), [1, 3]);
+        state.set_sort(SortOrder::Arrival);
+        state.clear_search();
+        assert_eq!(state.filtered_indices(), [0, 1, 2, 3]);
+    }
+
+    #[test]
+    fn test_collapse_repeats() {
+        let parser = Parser::new();
+        let lines = [
+            "FLEX|2026-01-01 20:01:15|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-01 20:01:16|1600/2/K/A|00.050|001123117|ALN|A1 Duizel Rit: 461",
+            "FLEX|2026-01-01 20:01:17|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:01:18|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            "FLEX|2026-01-01 20:01:19|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+            // Too long after the others to be a retransmission
+            "FLEX|2026-01-01 20:31:19|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630",
+        ];
+        let messages = lines.iter().map(|line| parser.parse_line(line).unwrap()).collect();
+        let mut state = AppState::new(messages).with_collapse_repeats(true);
+        assert_eq!(state.filtered_indices(), [0, 2, 5]);
+        assert_eq!(state.repeat_count(2), 3);
+        assert_eq!(state.repeat_count(5), 1);
+
+        state.move_down();
+        assert_eq!(state.toggle_burst(), 3);
+        assert_eq!(state.filtered_indices(), [0, 2, 3, 4, 5]);
+        assert!(state.is_expanded(4));
+        // Narrowing the search keeps the bursts whole
+        state.set_search_query("a");
+        assert_eq!(state.filtered_indices(), [0, 2, 3, 4, 5]);
+        state.move_down();
+        state.move_down();
+        assert_eq!(state.toggle_burst(), 3);
+        assert_eq!(state.filtered_indices(), [0, 2, 5]);
+        assert_eq!(state.selected_index(), 1);
+        state.set_search_query("a2");
+        assert_eq!(state.filtered_indices(), [2, 5]);
+        assert_eq!(state.repeat_count(2), 3);
+
+        // A live repeat joins the last row
+        state.push(parser.parse_line(lines[5]).unwrap());
+        assert_eq!(state.filtered_indices(), [2, 5]);
+        assert_eq!(state.repeat_count(5), 2);
+    }
+
+    #[test]
+    fn test_word_index_candidates() {
+        let mut state = state();
+        state.set_note(2, Some("Rit verlengd".to_string()));
+        assert_eq!(state.candidates("tilburg"), Some(vec![2]));
+        // A match inside a word and across words still finds its candidates
+        assert_eq!(state.candidates("lmer"), Some(vec![3]));
+        assert_eq!(state.candidates("a2 utr"), Some(vec![1]));
+        // Too common to be worth it
+        assert_eq!(state.candidates("a"), None);
+        assert_eq!(state.candidates(" "), None);
+
+        state.set_search_query("verlengd");
+        assert_eq!(state.filtered_indices(), [2]);
+        state.set_search_query("ALMERE 6");
+        assert_eq!(state.filtered_indices(), [3]);
+    }
+}
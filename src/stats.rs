@@ -0,0 +1,292 @@
+//! Message counts for the stats screen ('S'): the current day or week so far
+//! against the same stretch of the one before, per priority, part of the day
+//! and region, and a day by hour grid of the loaded period.
+
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeZone, Timelike};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::archive::Archive;
+use crate::daypart::{DayPart, Daylight, Timing};
+use crate::query::QueryFilter;
+use crate::rules;
+
+/// Regions listed, busiest first
+const TOP_REGIONS: usize = 10;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Period {
+    Day,
+    Week,
+}
+
+impl Period {
+    pub fn name(self) -> &'static str {
+        match self {
+            Period::Day => "Today vs yesterday",
+            Period::Week => "This week vs last week",
+        }
+    }
+
+    /// Start of the period holding `now` and of the one before it; weeks start
+    /// on Monday.
+    pub fn starts(self, now: DateTime<Local>) -> (DateTime<Local>, DateTime<Local>) {
+        let date = now.date_naive();
+        let (first, length) = match self {
+            Period::Day => (date, chrono::Days::new(1)),
+            Period::Week => {
+                let monday = date - chrono::Days::new(date.weekday().num_days_from_monday().into());
+                (monday, chrono::Days::new(7))
+            }
+        };
+        let midnight = |day: chrono::NaiveDate| {
+            let naive = day.and_hms_opt(0, 0, 0).expect("midnight exists");
+            Local.from_local_datetime(&naive).earliest().unwrap_or(now)
+        };
+        (midnight(first), midnight(first - length))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Counts {
+    pub total: usize,
+    /// By normalized priority ("A1", "P1")
+    pub priorities: BTreeMap<String, usize>,
+    pub day_parts: BTreeMap<DayPart, usize>,
+    /// Between sunset and sunrise in the middle of the country
+    pub dark: usize,
+    pub regions: BTreeMap<String, usize>,
+}
+
+impl Counts {
+    pub fn add(&mut self, time: DateTime<Local>, priority: Option<&str>, region: Option<&str>) {
+        self.total += 1;
+        let timing = Timing::of(time, None);
+        *self.day_parts.entry(timing.day_part).or_default() += 1;
+        if timing.daylight == Daylight::Dark {
+            self.dark += 1;
+        }
+        if let Some(priority) = priority {
+            *self.priorities.entry(rules::normalize_priority(priority)).or_default() += 1;
+        }
+        if let Some(region) = region {
+            *self.regions.entry(region.to_string()).or_default() += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Comparison {
+    pub period: Period,
+    pub now: DateTime<Local>,
+    pub current: Counts,
+    /// The previous period up to the same point as `now` in the current one
+    pub previous: Counts,
+}
+
+impl Comparison {
+    /// Counts `messages`, given as time, priority and region.
+    pub fn new<'a>(
+        period: Period,
+        now: DateTime<Local>,
+        messages: impl IntoIterator<Item = (DateTime<Local>, Option<&'a str>, Option<&'a str>)>,
+    ) -> Self {
+        let (start, previous_start) = period.starts(now);
+        let previous_end = previous_start + (now - start);
+        let mut comparison = Comparison {
+            period,
+            now,
+            current: Counts::default(),
+            previous: Counts::default(),
+        };
+        for (time, priority, region) in messages {
+            if time >= start && time <= now {
+                comparison.current.add(time, priority, region);
+            } else if time >= previous_start && time <= previous_end {
+                comparison.previous.add(time, priority, region);
+            }
+        }
+        comparison
+    }
+
+    /// Counts what the archive holds for both periods.
+    pub fn from_archive(archive: &Archive, period: Period, now: DateTime<Local>) -> Result<Self> {
+        let (_, previous_start) = period.starts(now);
+        let rows = archive.query(&QueryFilter {
+            since: Some(previous_start),
+            until: Some(now + chrono::Duration::seconds(1)),
+            ..Default::default()
+        })?;
+        Ok(Self::new(
+            period,
+            now,
+            rows.iter().map(|row| (row.timestamp, row.priority.as_deref(), row.region.as_deref())),
+        ))
+    }
+
+    /// A table of both counts per priority, part of the day and for the
+    /// busiest regions, each with an up or down indicator.
+    pub fn lines(&self, unicode: bool) -> Vec<String> {
+        let row = |label: &str, current: usize, previous: usize| {
+            format!("{:<24} {:>6} {:>6}  {}", label, current, previous, trend(current, previous, unicode))
+        };
+        let mut lines = vec![
+            format!("{} (until {})", self.period.name(), self.now.format("%a %H:%M")),
+            format!("{:<24} {:>6} {:>6}", "", "now", "before"),
+            row("All messages", self.current.total, self.previous.total),
+            String::new(),
+        ];
+        let priorities: BTreeSet<&String> =
+            self.current.priorities.keys().chain(self.previous.priorities.keys()).collect();
+        for priority in priorities {
+            let count = |counts: &Counts| counts.priorities.get(priority).copied().unwrap_or(0);
+            lines.push(row(priority, count(&self.current), count(&self.previous)));
+        }
+        lines.push(String::new());
+        for part in DayPart::ALL {
+            let count = |counts: &Counts| counts.day_parts.get(&part).copied().unwrap_or(0);
+            let (current, previous) = (count(&self.current), count(&self.previous));
+            if current + previous > 0 {
+                let name = part.name();
+                lines.push(row(&format!("{}{}", name[..1].to_uppercase(), &name[1..]), current, previous));
+            }
+        }
+        lines.push(row("In the dark", self.current.dark, self.previous.dark));
+        lines.push(String::new());
+        let mut regions: Vec<(&String, usize, usize)> = self
+            .current
+            .regions
+            .keys()
+            .chain(self.previous.regions.keys())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .map(|region| {
+                let count = |counts: &Counts| counts.regions.get(region).copied().unwrap_or(0);
+                (region, count(&self.current), count(&self.previous))
+            })
+            .collect();
+        regions.sort_by_key(|&(_, current, previous)| std::cmp::Reverse((current, previous)));
+        for (region, current, previous) in regions.into_iter().take(TOP_REGIONS) {
+            lines.push(row(region, current, previous));
+        }
+        lines
+    }
+}
+
+/// Messages per local day and hour.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HeatGrid {
+    days: BTreeMap<NaiveDate, [usize; 24]>,
+}
+
+impl HeatGrid {
+    pub fn new(times: impl IntoIterator<Item = DateTime<Local>>) -> Self {
+        let mut grid = HeatGrid::default();
+        for time in times {
+            grid.days.entry(time.date_naive()).or_insert([0; 24])[time.hour() as usize] += 1;
+        }
+        grid
+    }
+
+    /// An hour axis and one row per day, the latest `max_days` days, each hour
+    /// shaded by its count against the busiest hour shown.
+    pub fn lines(&self, max_days: usize, unicode: bool) -> Vec<String> {
+        let shades: [char; 5] = if unicode { [' ', '░', '▒', '▓', '█'] } else { [' ', '.', ':', '*', '#'] };
+        let days: Vec<_> = self.days.iter().rev().take(max_days).collect();
+        let busiest = days.iter().flat_map(|(_, hours)| hours.iter()).copied().max().unwrap_or(0);
+        let mut lines = vec![format!("{:<11}0     6     12    18", "")];
+        for (day, hours) in days.into_iter().rev() {
+            let cells: String = hours
+                .iter()
+                .map(|&count| match count {
+                    0 => shades[0],
+                    // 1..=4, so every hour with messages shows
+                    _ => shades[(count * 4).div_ceil(busiest)],
+                })
+                .collect();
+            lines.push(format!("{:<11}{}", day.format("%a %d %b"), cells));
+        }
+        lines
+    }
+}
+
+/// "▲ +3", "▼ -2" or "=".
+pub fn trend(current: usize, previous: usize, unicode: bool) -> String {
+    let (up, down) = if unicode { ("▲", "▼") } else { ("^", "v") };
+    match current.cmp(&previous) {
+        std::cmp::Ordering::Greater => format!("{} +{}", up, current - previous),
+        std::cmp::Ordering::Less => format!("{} -{}", down, previous - current),
+        std::cmp::Ordering::Equal => "=".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_with_same_stretch_of_previous_period() {
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        // A Thursday afternoon
+        let now = at("2026-01-08 15:00");
+        assert_eq!(Period::Day.starts(now), (at("2026-01-08"), at("2026-01-07")));
+        assert_eq!(Period::Week.starts(now), (at("2026-01-05"), at("2025-12-29")));
+
+        let messages = [
+            (at("2026-01-08 09:00"), Some("A1"), Some("Utrecht")),
+            (at("2026-01-08 14:59"), Some("P 1"), Some("Utrecht")),
+            (at("2026-01-08 15:30"), Some("A1"), Some("Utrecht")),
+            (at("2026-01-07 10:00"), Some("A1"), Some("Haaglanden")),
+            // Later in the day than now, so left out of yesterday
+            (at("2026-01-07 20:00"), Some("A1"), Some("Haaglanden")),
+            (at("2026-01-06 10:00"), Some("A2"), None),
+        ];
+        let day = Comparison::new(Period::Day, now, messages);
+        assert_eq!((day.current.total, day.previous.total), (2, 1));
+        assert_eq!(day.current.priorities["P1"], 1);
+        // Dark is by the sun in the middle of the country, so depends on the zone the tests run in
+        let dark = |indices: &[usize]| {
+            indices
+                .iter()
+                .filter(|&&i| Timing::of(messages[i].0, None).daylight == Daylight::Dark)
+                .count()
+        };
+        let (dark_now, dark_before) = (dark(&[0, 1]), dark(&[3]));
+        let dark_trend = trend(dark_now, dark_before, true);
+        let dark_row = format!("{:<24} {:>6} {:>6}  {}", "In the dark", dark_now, dark_before, dark_trend);
+        assert_eq!(
+            day.lines(true)[2..],
+            [
+                "All messages                  2      1  ▲ +1",
+                "",
+                "A1                            1      1  =",
+                "P1                            1      0  ▲ +1",
+                "",
+                "Morning                       1      1  =",
+                "Afternoon                     1      0  ▲ +1",
+                &dark_row,
+                "",
+                "Utrecht                       2      0  ▲ +2",
+                "Haaglanden                    0      1  ▼ -1",
+            ]
+        );
+
+        let week = Comparison::new(Period::Week, now, messages);
+        assert_eq!((week.current.total, week.previous.total), (5, 0));
+        assert_eq!(trend(0, 4, false), "v -4");
+    }
+
+    #[test]
+    fn test_heat_grid_shades_by_busiest_hour() {
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        let mut times = vec![at("2026-01-08 15:10"); 4];
+        times.extend([at("2026-01-08 15:50"), at("2026-01-08 00:05"), at("2026-01-06 23:59")]);
+        let grid = HeatGrid::new(times);
+        let lines = grid.lines(7, false);
+        assert_eq!(lines[0], "           0     6     12    18");
+        assert_eq!(lines[1], format!("Tue 06 Jan {}.", " ".repeat(23)));
+        assert_eq!(lines[2], "Thu 08 Jan .              #        ");
+        // Only the latest days that fit
+        assert_eq!(grid.lines(1, true)[1..], ["Thu 08 Jan ░              █        "]);
+    }
+}
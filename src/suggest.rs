@@ -0,0 +1,142 @@
+//! "Did you mean" suggestions for searches that find nothing: the closest
+//! place names, capcode description words and abbreviations by edit distance.
+
+use std::collections::BTreeSet;
+
+use crate::location::LocationLookup;
+use crate::lookup::Lookup;
+
+/// Words shorter than this are too short to correct or to suggest
+const MIN_LENGTH: usize = 3;
+
+/// Known terms a search could have meant.
+#[derive(Debug, Default)]
+pub struct Vocabulary {
+    // As they are written, and lowercased in the same order
+    terms: Vec<String>,
+    lower: Vec<String>,
+}
+
+impl Vocabulary {
+    /// Place names, the words of capcode descriptions and abbreviations.
+    pub fn new(lookup: &Lookup, location_lookup: &LocationLookup) -> Self {
+        let descriptions = lookup
+            .capcode_infos()
+            .flat_map(|info| info.description.split(|c: char| !c.is_alphabetic()));
+        let terms: BTreeSet<&str> = location_lookup
+            .place_names()
+            .iter()
+            .map(String::as_str)
+            .chain(descriptions)
+            .chain(lookup.abbreviation_names())
+            .filter(|term| term.chars().count() >= MIN_LENGTH)
+            .collect();
+        let terms: Vec<String> = terms.into_iter().map(str::to_string).collect();
+        let lower = terms.iter().map(|term| term.to_lowercase()).collect();
+        Vocabulary { terms, lower }
+    }
+
+    /// `query` with its misspelled words replaced by the closest terms, or the
+    /// closest term to the whole query, such as a place of more than one word.
+    /// `None` when nothing is close enough to be worth suggesting. Field
+    /// values (`place:vlaardngen`) are corrected; field names are kept.
+    pub fn suggest(&self, query: &str) -> Option<String> {
+        let query = query.trim();
+        if let Some(term) = self.closest(query) {
+            return Some(term.to_string());
+        }
+        let mut changed = false;
+        let words: Vec<String> = query
+            .split_whitespace()
+            .map(|word| {
+                let (field, value) = match word.split_once(':') {
+                    Some((field, value)) => (Some(field), value),
+                    None => (None, word),
+                };
+                match (self.closest(value), field) {
+                    (Some(term), _) if term.contains(' ') => word.to_string(),
+                    (Some(term), Some(field)) => {
+                        changed = true;
+                        format!("{}:{}", field, term)
+                    }
+                    (Some(term), None) => {
+                        changed = true;
+                        term.to_string()
+                    }
+                    (None, _) => word.to_string(),
+                }
+            })
+            .collect();
+        changed.then(|| words.join(" "))
+    }
+
+    /// The term nearest to `word`, if within the distance its length allows and
+    /// not `word` itself.
+    fn closest(&self, word: &str) -> Option<&str> {
+        let word = word.trim_matches('"').to_lowercase();
+        let length = word.chars().count();
+        if length < MIN_LENGTH {
+            return None;
+        }
+        let allowed = match length {
+            0..=4 => 1,
+            5..=8 => 2,
+            _ => 3,
+        };
+        let mut best: Option<(usize, &str)> = None;
+        for (term, lower) in self.terms.iter().zip(&self.lower) {
+            if lower.chars().count().abs_diff(length) > allowed {
+                continue;
+            }
+            if *lower == word {
+                return None;
+            }
+            let distance = levenshtein(&word, lower);
+            if distance <= allowed && best.is_none_or(|(nearest, _)| distance < nearest) {
+                best = Some((distance, term));
+            }
+        }
+        best.map(|(_, term)| term)
+    }
+}
+
+/// Edits (insertions, deletions, substitutions) turning `a` into `b`.
+pub fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_suggestions() {
+        assert_eq!(levenshtein("vlaardngen", "vlaardingen"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+
+        let terms: Vec<String> =
+            ["Vlaardingen", "Den Haag", "Ambulance", "MMT", "Rotterdam"].map(String::from).into();
+        let vocabulary = Vocabulary {
+            lower: terms.iter().map(|term| term.to_lowercase()).collect(),
+            terms,
+        };
+        assert_eq!(vocabulary.suggest("vlaardngen").as_deref(), Some("Vlaardingen"));
+        assert_eq!(vocabulary.suggest("a1 rotterdm").as_deref(), Some("a1 Rotterdam"));
+        assert_eq!(vocabulary.suggest("place:vlardingen").as_deref(), Some("place:Vlaardingen"));
+        assert_eq!(vocabulary.suggest("den hag").as_deref(), Some("Den Haag"));
+        assert_eq!(vocabulary.suggest("MMT rotterdam"), None, "nothing misspelled");
+        assert_eq!(vocabulary.suggest("brand"), None, "nothing close");
+    }
+}
@@ -0,0 +1,62 @@
+//! `[theme]` section: how list rows look beyond their content.
+
+use serde::Deserialize;
+use std::time::Duration;
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct Theme {
+    /// Live messages received less than this many minutes ago are bright; 0 disables
+    pub fresh_mins: u64,
+    /// Live messages received more than this many minutes ago are dimmed; 0 disables
+    pub dim_mins: u64,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            fresh_mins: 5,
+            dim_mins: 60,
+        }
+    }
+}
+
+/// Age bucket of a live message.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Freshness {
+    Fresh,
+    Normal,
+    Old,
+}
+
+impl Theme {
+    pub fn freshness(&self, age: Duration) -> Freshness {
+        let minutes = |mins: u64| Duration::from_secs(mins * 60);
+        if self.fresh_mins > 0 && age < minutes(self.fresh_mins) {
+            Freshness::Fresh
+        } else if self.dim_mins > 0 && age >= minutes(self.dim_mins) {
+            Freshness::Old
+        } else {
+            Freshness::Normal
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_freshness_buckets() {
+        let theme = Theme::default();
+        let minutes = |mins: u64| Duration::from_secs(mins * 60);
+        assert_eq!(theme.freshness(minutes(0)), Freshness::Fresh);
+        assert_eq!(theme.freshness(minutes(5)), Freshness::Normal);
+        assert_eq!(theme.freshness(minutes(59)), Freshness::Normal);
+        assert_eq!(theme.freshness(minutes(60)), Freshness::Old);
+
+        let off = Theme { fresh_mins: 0, dim_mins: 0 };
+        assert_eq!(off.freshness(minutes(0)), Freshness::Normal);
+        assert_eq!(off.freshness(minutes(600)), Freshness::Normal);
+    }
+}
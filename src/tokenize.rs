@@ -0,0 +1,131 @@
+//! Splits message content into typed tokens with byte spans. Shared by the
+//! abbreviation scan, location extraction, translation and the highlighting in
+//! the details pane, so they agree on what a word is.
+
+use std::ops::Range;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Word,
+    /// Digits, possibly with `.` or `,` inside: `17128`, `00.051`
+    Number,
+    /// Dutch postcode written as one word: `3135ZH`
+    Postcode,
+    /// Region and vehicle number of a unit: `17-128`, `07-4431`
+    Callsign,
+    /// 7 or 9 digits, the way pagers are addressed
+    Capcode,
+    /// Anything without letters or digits around a token: `(`, `:`
+    Punctuation,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+    /// Byte range of `text` in the tokenized string
+    pub span: Range<usize>,
+    /// Whether whitespace comes before it; `false` for `DIA` in `(DIA:`
+    pub spaced: bool,
+}
+
+impl Token<'_> {
+    pub fn is_punctuation(&self) -> bool {
+        self.kind == TokenKind::Punctuation
+    }
+}
+
+/// Tokens of `text` in order. Each whitespace-separated part gives at most
+/// three: leading punctuation, the core from its first to its last letter or
+/// digit (so `A&A` and `BDH-07` stay whole), and trailing punctuation.
+pub fn tokenize(text: &str) -> Vec<Token<'_>> {
+    let mut tokens = Vec::new();
+    for part in text.split_whitespace() {
+        // `part` borrows from `text`
+        let part_start = part.as_ptr() as usize - text.as_ptr() as usize;
+
+        let core_start = part.find(char::is_alphanumeric).unwrap_or(part.len());
+        let core_end = part
+            .rfind(char::is_alphanumeric)
+            .map(|i| i + part[i..].chars().next().map_or(1, char::len_utf8))
+            .unwrap_or(core_start)
+            .max(core_start);
+        let pieces = [
+            (0..core_start, TokenKind::Punctuation),
+            (core_start..core_end, classify(&part[core_start..core_end])),
+            (core_end..part.len(), TokenKind::Punctuation),
+        ];
+        let mut spaced = true;
+        for (range, kind) in pieces {
+            if range.is_empty() {
+                continue;
+            }
+            tokens.push(Token {
+                kind,
+                text: &part[range.clone()],
+                span: part_start + range.start..part_start + range.end,
+                spaced,
+            });
+            spaced = false;
+        }
+    }
+    tokens
+}
+
+fn classify(core: &str) -> TokenKind {
+    let b = core.as_bytes();
+    if b.iter().all(u8::is_ascii_digit) {
+        return match b.len() {
+            7 | 9 => TokenKind::Capcode,
+            _ => TokenKind::Number,
+        };
+    }
+    if b.iter().all(|c| c.is_ascii_digit() || *c == b'.' || *c == b',') {
+        return TokenKind::Number;
+    }
+    if b.len() == 6 && b[0] != b'0' && b[..4].iter().all(u8::is_ascii_digit) && b[4..].iter().all(u8::is_ascii_alphabetic) {
+        return TokenKind::Postcode;
+    }
+    if let [r1, r2, b'-', vehicle @ ..] = b {
+        if r1.is_ascii_digit() && r2.is_ascii_digit() && (3..=4).contains(&vehicle.len()) && vehicle.iter().all(u8::is_ascii_digit) {
+            return TokenKind::Callsign;
+        }
+    }
+    TokenKind::Word
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_token_kinds_and_spans() {
+        let text = "A1 (DIA: ja) AMBU 17-128 Nassaulaan 3135ZH Vlaardingen 001420028 BDH-07 00.051 A&A";
+        let tokens = tokenize(text);
+        let kinds: Vec<(TokenKind, &str, bool)> = tokens.iter().map(|t| (t.kind, t.text, t.spaced)).collect();
+        use TokenKind::*;
+        assert_eq!(
+            kinds,
+            [
+                (Word, "A1", true),
+                (Punctuation, "(", true),
+                (Word, "DIA", false),
+                (Punctuation, ":", false),
+                (Word, "ja", true),
+                (Punctuation, ")", false),
+                (Word, "AMBU", true),
+                (Callsign, "17-128", true),
+                (Word, "Nassaulaan", true),
+                (Postcode, "3135ZH", true),
+                (Word, "Vlaardingen", true),
+                (Capcode, "001420028", true),
+                (Word, "BDH-07", true),
+                (Number, "00.051", true),
+                (Word, "A&A", true),
+            ]
+        );
+        assert!(tokens.iter().all(|t| &text[t.span.clone()] == t.text));
+        assert_eq!(tokenize("  -- ").iter().map(|t| t.kind).collect::<Vec<_>>(), [Punctuation]);
+        assert_eq!(tokenize("17128")[0].kind, Number);
+    }
+}
@@ -0,0 +1,154 @@
+//! Offline Dutch to English translation of message content, phrase by phrase.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::tokenize::tokenize;
+
+const BUILTIN_DICTIONARY: &str = include_str!("../data/translations.tsv");
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TranslationConfig {
+    /// Extra tab-separated `dutch<TAB>english` entries; they override the built-ins
+    pub dictionary: Option<PathBuf>,
+}
+
+/// Replaces known dispatch phrases, longest first, and keeps everything else
+/// (place names, street names, codes) as it is.
+#[derive(Debug, Default)]
+pub struct Translator {
+    // Lowercased phrase words joined by a single space
+    phrases: HashMap<String, String>,
+    longest_phrase: usize,
+}
+
+impl Translator {
+    pub fn builtin() -> Self {
+        let mut translator = Translator::default();
+        translator.add_entries(BUILTIN_DICTIONARY);
+        translator
+    }
+
+    pub fn from_config(config: &TranslationConfig) -> Result<Self> {
+        let mut translator = Self::builtin();
+        if let Some(path) = &config.dictionary {
+            translator.load(path)?;
+        }
+        Ok(translator)
+    }
+
+    pub fn load(&mut self, path: &Path) -> Result<()> {
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read dictionary {}", path.display()))?;
+        self.add_entries(&content);
+        Ok(())
+    }
+
+    fn add_entries(&mut self, content: &str) {
+        for line in content.lines() {
+            if line.trim().is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let Some((dutch, english)) = line.split_once('\t') else {
+                continue;
+            };
+            let words: Vec<String> = dutch.split_whitespace().map(str::to_lowercase).collect();
+            self.longest_phrase = self.longest_phrase.max(words.len());
+            self.phrases.insert(words.join(" "), english.trim().to_string());
+        }
+    }
+
+    /// English rendering of `content`, or `None` when no phrase was recognised.
+    pub fn translate(&self, content: &str) -> Option<String> {
+        let tokens = chunks(content);
+        let mut output = Vec::with_capacity(tokens.len());
+        let mut translated = false;
+        let mut i = 0;
+        while i < tokens.len() {
+            let mut matched = None;
+            for len in (1..=self.longest_phrase.min(tokens.len() - i)).rev() {
+                let words = &tokens[i..i + len];
+                if words.iter().any(|t| t.core.is_empty()) {
+                    continue;
+                }
+                let key = words.iter().map(|t| t.core.to_lowercase()).collect::<Vec<_>>().join(" ");
+                if let Some(english) = self.phrases.get(&key) {
+                    matched = Some((len, english));
+                    break;
+                }
+            }
+            match matched {
+                Some((len, english)) => {
+                    let last = &tokens[i + len - 1];
+                    output.push(format!("{}{}{}", tokens[i].prefix, english, last.suffix));
+                    translated = true;
+                    i += len;
+                }
+                None => {
+                    output.push(tokens[i].text.to_string());
+                    i += 1;
+                }
+            }
+        }
+        translated.then(|| output.join(" "))
+    }
+}
+
+/// A whitespace-separated word with surrounding punctuation split off, so
+/// "(DIA:" still matches "dia".
+struct Chunk<'a> {
+    text: &'a str,
+    prefix: &'a str,
+    core: &'a str,
+    suffix: &'a str,
+}
+
+/// Groups the shared tokens back into whitespace-separated chunks.
+fn chunks(content: &str) -> Vec<Chunk<'_>> {
+    let mut chunks: Vec<Chunk> = Vec::new();
+    for token in tokenize(content) {
+        match chunks.last_mut() {
+            Some(chunk) if !token.spaced => {
+                let start = chunk.text.as_ptr() as usize - content.as_ptr() as usize;
+                chunk.text = &content[start..token.span.end];
+                if token.is_punctuation() {
+                    chunk.suffix = token.text;
+                } else {
+                    chunk.core = token.text;
+                }
+            }
+            _ => {
+                let (prefix, core) = if token.is_punctuation() { (token.text, "") } else { ("", token.text) };
+                chunks.push(Chunk {
+                    text: token.text,
+                    prefix,
+                    core,
+                    suffix: "",
+                });
+            }
+        }
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_translate_phrases() {
+        let translator = Translator::builtin();
+        assert_eq!(
+            translator.translate("P 1 Ongeval Wegvervoer Letsel A10 Li - Ringweg-West Amsterdam").as_deref(),
+            Some("P 1 road accident with injuries A10 left carriageway - Ringweg-West Amsterdam")
+        );
+        assert_eq!(
+            translator.translate("A1 (DIA: ja) AMBU 17128 Vlaardingen").as_deref(),
+            Some("A1 (DIA: yes) ambulance 17128 Vlaardingen")
+        );
+        assert_eq!(translator.translate("A2 Utrecht 630"), None);
+    }
+}
@@ -1,222 +1,1200 @@
 use ratatui::{
     backend::{Backend, CrosstermBackend},
     crossterm::{
-        event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode},
+        event::{
+            self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind, KeyModifiers,
+        },
         execute,
         terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
     },
-    text::{Line, Span},
+    text::{Line, Span, Text},
     widgets::{Block, Borders, List, ListItem, Paragraph, Wrap},
     Terminal,
 };
+use std::collections::{BTreeMap, HashMap};
 use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc;
 
-use crate::{location::LocationLookup, lookup::Lookup, parser::P2000Message};
+pub use crate::state::AppState;
+use crate::address::{Growth, Incident, IncidentSizes};
+use crate::anomaly::{AnomalyConfig, VolumeDetector};
+use crate::audio::AudioPlayer;
+use crate::channels::ChannelNames;
+use crate::data::DataFiles;
+use crate::feedback::Feedback;
+use crate::gap::{self, GapConfig, GapDetector};
+use crate::icons::{IconStyle, Service};
+use crate::map::{self, PaneLayout};
+use crate::pager::Pager;
+use crate::rollup::DailyTotals;
+use crate::reader::FeedLine;
+use crate::sampler::Sampler;
+use crate::sdr::ProcessLog;
+use crate::severity::SeverityConfig;
+use crate::suggest::Vocabulary;
+use crate::archive::Archive;
+use crate::export::{redact, timeline};
+use crate::state::{IncidentStatus, SearchScope, SortOrder};
+use crate::stats::{Comparison, HeatGrid, Period};
+use crate::theme::{Freshness, Theme};
+use crate::tokenize::{tokenize, TokenKind};
+use crate::units::{self, UnitBoard, UnitsConfig};
+use crate::view::{FilterPreset, View};
+use crate::weather::WeatherClient;
+use crate::parser::{P2000Message, Parser, Receiver, TimeSource};
+use crate::lookup::{normalize_code, AddressKind, CapcodeInfo, Lookup};
+use crate::{enrich, location::LocationLookup, rules, translate::Translator};
 
-pub struct AppState {
-    pub messages: Vec<P2000Message>,
-    pub selected_index: usize,
-    pub search_query: String,
+pub struct App {
+    pub state: AppState,
     pub search_mode: bool,
-    pub filtered_indices: Vec<usize>,
-    pub scroll_offset: usize,
-    pub list_height: u16,
+    /// Note being typed for the selected message
+    pub note_input: Option<String>,
+    /// Query of the abbreviation lookup popup ('a') while it is open
+    pub abbreviation_query: Option<String>,
+    /// Place wrongly matched in the selected message and the one being typed
+    /// instead ('w')
+    pub place_correction: Option<(String, String)>,
+    /// Result of the last bulk action, shown in the help bar
+    pub status: Option<String>,
+    lookup: Arc<Lookup>,
+    location_lookup: Arc<LocationLookup>,
+    translator: Option<Translator>,
+    weather: Option<Arc<WeatherClient>>,
+    region_column: bool,
+    icons: IconStyle,
+    // Parallel to `state.messages()`
+    services: Vec<Option<Service>>,
+    flash: Duration,
+    bell: bool,
+    // Ring for new messages at the followed place
+    follow_bell: bool,
+    // Urgent messages that arrived in live mode, by index into `state.messages()`
+    flashing: Vec<(usize, Instant)>,
+    audio: Option<AudioPlayer>,
+    pager: Option<Pager>,
+    // Weights of the severity score, for new messages
+    severity: SeverityConfig,
+    // Incident tracked in its own pane, with its messages by index into `state.messages()`
+    pinned: Option<(Incident, Vec<usize>)>,
+    // Size of its incident after each message, by index into `state.messages()`
+    growth: Vec<Option<Growth>>,
+    incident_sizes: IncidentSizes,
+    // Persists incident statuses
+    archive: Option<Archive>,
+    // Silence before a message, by index into `state.messages()`
+    gaps: BTreeMap<usize, chrono::Duration>,
+    // Watches the live feed; `None` when gap detection is off
+    gap_detector: Option<GapDetector>,
+    gap_alert: bool,
+    // Hourly volume per region of the live feed; `None` when detection is off
+    volume: Option<VolumeDetector>,
+    volume_alert: bool,
+    // Receiver subprocess stderr; the pane shows one line until expanded
+    process_log: Option<ProcessLog>,
+    log_expanded: bool,
+    // Adds where each enriched field came from to the details ('p')
+    show_provenance: bool,
+    // Where "wrong place" corrections are recorded
+    feedback: Option<Feedback>,
+    // Hides addresses in the list, details, exports and clipboard ('R')
+    redact: bool,
+    // Station that live messages are tagged with
+    receiver: Option<Arc<Receiver>>,
+    // Names live messages' channels after their frequency
+    channel_names: ChannelNames,
+    // `[[filters]]` for 'f', and the one last picked; `None` is everything
+    presets: Vec<FilterPreset>,
+    preset: Option<usize>,
+    sampler: Option<Sampler>,
+    // Row text after the markers, by index into `state.messages()`; it only
+    // depends on the message and the display options, so it is built once
+    row_cache: HashMap<usize, Vec<Span<'static>>>,
+    // Terms for "did you mean", built the first time a search finds nothing
+    vocabulary: Option<Vocabulary>,
+    // The last search that found nothing and what it could have meant
+    suggestion: Option<(String, Option<String>)>,
+    // Something shown changed since the last draw
+    dirty: bool,
+    // Receiver log lines at the last draw
+    drawn_log_len: usize,
+    // Redraw at least this often while nothing happens; `None` never does
+    idle_frame: Option<Duration>,
+    // How long the event loop waits for input before checking the feed
+    poll: Duration,
+    low_power: bool,
+    // Where the lookups come from, for reloading them with 'D'
+    data_files: Option<DataFiles>,
+    // Which data files are missing, shown as a banner
+    data_warning: Option<String>,
+    // Wallboard layout that ignores the keyboard
+    kiosk: bool,
+    // Where the list, details and map go ('v')
+    layout: PaneLayout,
+    // Coordinates of the place matched in each message shown on the map, by
+    // index into `state.messages()`
+    places: HashMap<usize, Option<(f64, f64)>>,
+    // Age thresholds for brightening and dimming live rows
+    theme: Theme,
+    // Today's counts, shown above the list when enabled
+    totals: Option<DailyTotals>,
+    // The stats screen while it is open ('S')
+    stats: Option<(Comparison, HeatGrid)>,
+    units_config: UnitsConfig,
+    // The unit board while it is open ('U')
+    units: Option<UnitBoard>,
+    unit_index: UnitIndex,
+    // The unit cross-reference while it is open ('X'): a message, and which
+    // of its units is shown
+    unit_view: Option<(usize, usize)>,
 }
 
-impl AppState {
-    pub fn new(messages: Vec<P2000Message>) -> Self {
-        let filtered_indices: Vec<usize> = (0..messages.len()).collect();
-        AppState {
-            messages,
-            selected_index: 0,
-            search_query: String::new(),
+/// Width of the region column; fits all but the longest region names
+const REGION_WIDTH: usize = 20;
+
+/// Own pages shown in the pager pane
+const PAGER_ROWS: usize = 4;
+
+/// Receiver log lines shown while the pane is expanded
+const LOG_ROWS: usize = 8;
+
+/// Shortest input poll interval in low-power mode
+const LOW_POWER_POLL: Duration = Duration::from_millis(500);
+
+/// Rows kept in the cache before it starts over
+const ROW_CACHE_LIMIT: usize = 4096;
+
+/// Flashing alternates between highlighted and plain at this interval
+const FLASH_PHASE: Duration = Duration::from_millis(500);
+
+/// Narrower terminals get the stacked layout whatever the setting
+const MIN_SPLIT_WIDTH: u16 = 100;
+
+/// Day label, 24 hours and the borders
+const HEAT_GRID_WIDTH: u16 = 11 + 24 + 2;
+
+/// Lines per message in kiosk mode: the message, where, and a blank line
+const KIOSK_ROW_HEIGHT: usize = 3;
+
+/// The kiosk clock redraws at least this often, also in low-power mode
+const KIOSK_TICK: Duration = Duration::from_secs(1);
+
+impl App {
+    pub fn new(
+        messages: Vec<P2000Message>,
+        lookup: impl Into<Arc<Lookup>>,
+        location_lookup: impl Into<Arc<LocationLookup>>,
+    ) -> Self {
+        let lookup = lookup.into();
+        let location_lookup = location_lookup.into();
+        let (regions, places, services, unit_index) = enrich_messages(&messages, &lookup, &location_lookup);
+        let mut app = App {
+            state: AppState::new(messages).with_lookup(lookup.clone()).with_regions(regions).with_places(places),
             search_mode: false,
-            filtered_indices,
-            scroll_offset: 0,
-            list_height: 10,
+            note_input: None,
+            abbreviation_query: None,
+            place_correction: None,
+            status: None,
+            lookup,
+            location_lookup,
+            translator: None,
+            weather: None,
+            region_column: true,
+            icons: IconStyle::None,
+            services,
+            flash: Duration::from_secs(5),
+            bell: false,
+            follow_bell: false,
+            flashing: Vec::new(),
+            audio: None,
+            pager: None,
+            pinned: None,
+            growth: Vec::new(),
+            incident_sizes: IncidentSizes::default(),
+            severity: SeverityConfig::default(),
+            archive: None,
+            gaps: BTreeMap::new(),
+            gap_detector: None,
+            gap_alert: false,
+            volume: None,
+            volume_alert: false,
+            process_log: None,
+            log_expanded: false,
+            show_provenance: false,
+            feedback: None,
+            redact: false,
+            receiver: None,
+            channel_names: ChannelNames::default(),
+            presets: Vec::new(),
+            preset: None,
+            sampler: None,
+            row_cache: HashMap::new(),
+            vocabulary: None,
+            suggestion: None,
+            dirty: true,
+            drawn_log_len: 0,
+            idle_frame: Some(Duration::from_secs(1)),
+            poll: Duration::from_millis(100),
+            low_power: false,
+            data_files: None,
+            data_warning: None,
+            kiosk: false,
+            layout: PaneLayout::default(),
+            places: HashMap::new(),
+            theme: Theme::default(),
+            totals: None,
+            stats: None,
+            units_config: UnitsConfig::default(),
+            units: None,
+            unit_index,
+            unit_view: None,
+        };
+        app.size_incidents();
+        app
+    }
+
+    /// Warns about missing data files and lets 'D' load the lookups again.
+    pub fn with_data_files(mut self, files: DataFiles) -> Self {
+        self.data_warning = files.missing_summary();
+        self.data_files = Some(files);
+        self
+    }
+
+    /// Lets 'w' record places matched wrongly; they apply on the next data load.
+    pub fn with_feedback(mut self, feedback: Feedback) -> Self {
+        self.feedback = Some(feedback);
+        self
+    }
+
+    /// Reloads the lookups from the data files and re-enriches every message.
+    fn reload_data(&mut self) {
+        let Some(files) = &self.data_files else {
+            return;
+        };
+        let (lookup, location_lookup) = match files.load() {
+            Ok(lookups) => lookups,
+            Err(e) => {
+                self.status = Some(format!("Failed to load data files: {}", e));
+                return;
+            }
+        };
+        self.data_warning = files.missing_summary();
+        self.lookup = Arc::new(lookup);
+        self.location_lookup = Arc::new(location_lookup);
+        self.state.set_lookup(self.lookup.clone());
+        let (regions, places, services, unit_index) =
+            enrich_messages(self.state.messages(), &self.lookup, &self.location_lookup);
+        self.state.set_regions(regions);
+        self.state.set_places(places);
+        self.unit_index = unit_index;
+        if self.totals.is_some() {
+            self.totals = Some(self.count_totals());
+        }
+        self.services = services;
+        self.size_incidents();
+        self.row_cache.clear();
+        self.places.clear();
+        self.vocabulary = None;
+        self.suggestion = None;
+        self.status = Some(match &self.data_warning {
+            None => "Data files loaded".to_string(),
+            Some(_) => "Loaded the data files that are present".to_string(),
+        });
+    }
+
+    /// Thins out routine messages arriving in live mode.
+    pub fn with_sampler(mut self, sampler: Sampler) -> Self {
+        self.sampler = Some(sampler);
+        self
+    }
+
+    /// Adds the receiver log pane, shown once the log has lines ('L' expands it).
+    pub fn with_process_log(mut self, log: ProcessLog) -> Self {
+        self.process_log = Some(log);
+        self
+    }
+
+    /// Marks outages in the list and watches the live feed for new ones.
+    pub fn with_gaps(mut self, config: &GapConfig) -> Self {
+        if let Some(mut detector) = GapDetector::new(config) {
+            self.gaps = detector.scan(self.state.messages(), TimeSource::Received).into_iter().collect();
+        }
+        // History doesn't count: the live feed is timed from now
+        self.gap_detector = GapDetector::new(config).map(|mut detector| {
+            detector.observe(chrono::Local::now());
+            detector
+        });
+        self.gap_alert = config.alert;
+        self
+    }
+
+    /// Watches the live feed for regions sending far more messages than usual.
+    pub fn with_anomaly(mut self, config: &AnomalyConfig) -> Self {
+        self.volume = VolumeDetector::new(config);
+        self.volume_alert = config.alert;
+        self
+    }
+
+    /// Restores and saves acknowledged/resolved statuses in the archive.
+    pub fn with_archive(mut self, archive: Archive) -> io::Result<Self> {
+        for i in 0..self.state.messages().len() {
+            let msg = &self.state.messages()[i];
+            let status = archive.status(msg).map_err(io::Error::other)?;
+            let note = archive.note(msg).map_err(io::Error::other)?;
+            self.state.set_status(i, status);
+            self.state.set_note(i, note);
         }
+        self.archive = Some(archive);
+        Ok(self)
+    }
+
+    /// Adds the "My pager" pane. Own pages already loaded count as history.
+    pub fn with_pager(mut self, mut pager: Pager) -> Self {
+        for (i, msg) in self.state.messages().iter().enumerate() {
+            if pager.is_own(msg) {
+                pager.record(i, false);
+            }
+        }
+        self.pager = Some(pager);
+        self
+    }
+
+    /// Plays rule sounds for messages arriving in live mode.
+    pub fn with_audio(mut self, audio: AudioPlayer) -> Self {
+        self.audio = Some(audio);
+        self
+    }
+
+    /// How long new A1/P 1 messages flash in live mode; zero disables it.
+    pub fn with_flash(mut self, flash: Duration) -> Self {
+        self.flash = flash;
+        self
     }
 
-    pub fn set_list_height(&mut self, height: u16) {
-        self.list_height = height.saturating_sub(2) as u16;
+    /// Rings the terminal bell when a message arrives for the place followed with 'F'.
+    pub fn with_follow_bell(mut self, bell: bool) -> Self {
+        self.follow_bell = bell;
+        self
     }
 
-    fn ensure_selected_visible(&mut self) {
-        let visible_end = self.scroll_offset + self.list_height as usize;
-        if self.selected_index < self.scroll_offset {
-            self.scroll_offset = self.selected_index;
-        } else if self.selected_index >= visible_end {
-            self.scroll_offset = self.selected_index.saturating_sub(self.list_height as usize - 1);
+    /// Rings the terminal bell when an A1/P 1 message arrives in live mode.
+    pub fn with_bell(mut self, bell: bool) -> Self {
+        self.bell = bell;
+        self
+    }
+
+    /// Frames per second drawn while nothing changes; zero only redraws on changes.
+    pub fn with_idle_fps(mut self, fps: f64) -> Self {
+        self.idle_frame = (fps > 0.0).then(|| Duration::from_secs_f64(1.0 / fps));
+        self
+    }
+
+    /// How long to wait for a key press before looking at the feed again.
+    pub fn with_poll_interval(mut self, poll: Duration) -> Self {
+        self.poll = poll;
+        self
+    }
+
+    /// For small boards next to the receiver: polls less often, stops idle
+    /// redraws and flashing, and enriches new messages from their capcodes only.
+    /// Call after the other display options, which it overrides.
+    pub fn with_low_power(mut self, low_power: bool) -> Self {
+        if low_power {
+            self.poll = self.poll.max(LOW_POWER_POLL);
+            self.idle_frame = None;
+            self.flash = Duration::ZERO;
         }
+        self.low_power = low_power;
+        self
+    }
+
+    pub fn poll_interval(&self) -> Duration {
+        self.poll
     }
 
-    pub fn selected_message(&self) -> Option<&P2000Message> {
-        self.filtered_indices
-            .get(self.selected_index)
-            .and_then(|idx| self.messages.get(*idx))
+    /// Bursts of repeated messages as one row with a count ('z' expands them).
+    pub fn with_collapse_repeats(mut self, collapse: bool) -> Self {
+        self.state = self.state.with_collapse_repeats(collapse);
+        self
     }
 
-    pub fn move_down(&mut self) {
-        if self.selected_index < self.filtered_indices.len().saturating_sub(1) {
-            self.selected_index += 1;
-            self.ensure_selected_visible();
+    /// Weights of the severity score behind the severity sort ('o').
+    pub fn with_severity(mut self, config: SeverityConfig) -> Self {
+        self.state = self.state.with_severity(config.clone());
+        self.severity = config;
+        self
+    }
+
+    /// Clock behind the time sort ('o').
+    pub fn with_sort_time(mut self, source: TimeSource) -> Self {
+        self.state = self.state.with_time_source(source);
+        self
+    }
+
+    /// Adds a message that arrived while running, unless the sampler drops it.
+    /// The selection follows new messages while it is on the last one.
+    pub fn push(&mut self, mut msg: P2000Message) {
+        self.dirty = true;
+        if msg.receiver.is_none() {
+            msg.receiver = self.receiver.clone();
+        }
+        self.channel_names.apply(&mut msg);
+        // Own pages are never sampled out
+        let own_page = self.pager.as_ref().is_some_and(|p| p.is_own(&msg));
+        if !own_page && self.sampler.as_mut().is_some_and(|s| !s.keep(&msg, Instant::now())) {
+            return;
+        }
+        let mut enrichment = if self.low_power {
+            enrich::enrich_capcodes(&msg, &self.lookup)
+        } else {
+            enrich::enrich(&msg, &self.lookup, &self.location_lookup)
+        };
+        // For rules with a minimum severity
+        enrichment.severity = Some(self.severity.assess(&msg, categories(&enrichment)).total());
+        let follow = self.state.selected_index() + 1 >= self.state.filtered_len();
+        let urgent = msg.priority.as_deref().is_some_and(rules::is_urgent);
+        let index = self.state.messages().len();
+        if let Some(audio) = &self.audio {
+            audio.alert(&msg, &enrichment);
+        }
+        let own = match &mut self.pager {
+            Some(pager) if pager.is_own(&msg) => {
+                pager.record(index, true);
+                true
+            }
+            _ => false,
+        };
+        self.services.push(Service::of(&msg, &enrichment));
+        for unit in &enrichment.units {
+            self.unit_index.entry(unit.clone()).or_default().push(index);
+        }
+        if let Some(gap) = self.gap_detector.as_mut().and_then(|d| d.observe(msg.time(TimeSource::Received))) {
+            self.gaps.insert(index, gap);
+            self.status = Some(format!("Feed resumed after {} without messages", gap::format_duration(gap)));
+        }
+        if let Some(totals) = &mut self.totals {
+            totals.add(&msg, enrichment.region.as_deref());
+        }
+        let spike = match (&mut self.volume, &enrichment.region) {
+            (Some(detector), Some(region)) => detector.observe(region, msg.time(TimeSource::Received)),
+            _ => None,
+        };
+        if let Some(spike) = &spike {
+            self.status = Some(spike.to_string());
+        }
+        let status = self.archive.as_ref().and_then(|a| a.status(&msg).ok()).unwrap_or_default();
+        let note = self.archive.as_ref().and_then(|a| a.note(&msg).ok()).flatten();
+        let place = enrichment.location.as_ref().map(|found| found.info.place().to_string());
+        let content = msg.content.clone();
+        let growth = Incident::of(&content, place.as_deref()).map(|incident| {
+            let services = enrichment.capcodes.iter().map(|info| info.service.as_str());
+            self.incident_sizes.add(&incident, msg.capcodes.iter().map(|code| &**code), services)
+        });
+        self.growth.push(growth);
+        if let Some((incident, messages)) = &mut self.pinned {
+            if incident.includes(&content, place.as_deref()) {
+                messages.push(index);
+            }
+        }
+        self.state.push_located(msg, enrichment.region, place);
+        let followed = self.state.filter().place.as_ref().filter(|_| self.state.at_followed_place(index));
+        if let Some(followed) = followed {
+            self.status = Some(format!("New at {}: {}", followed, content));
+            if self.follow_bell {
+                ring_bell();
+            }
+        }
+        self.state.set_status(index, status);
+        self.state.set_note(index, note);
+        if (urgent || own) && !self.flash.is_zero() {
+            self.flashing.push((index, Instant::now()));
+        }
+        // Own pages always ring, like a real pager
+        if own || (urgent && self.bell) || (spike.is_some() && self.volume_alert) {
+            ring_bell();
+        }
+        if follow {
+            self.state.select(self.state.filtered_len().saturating_sub(1));
         }
     }
 
-    pub fn move_up(&mut self) {
-        if self.selected_index > 0 {
-            self.selected_index -= 1;
-            self.ensure_selected_visible();
+    /// Reports a live feed that has been silent past the gap threshold, once per gap.
+    pub fn check_gap(&mut self) {
+        let Some(silence) = self.gap_detector.as_mut().and_then(|d| d.check(chrono::Local::now())) else {
+            return;
+        };
+        self.dirty = true;
+        self.status = Some(format!(
+            "No messages for {}; check the receiver",
+            gap::format_duration(silence)
+        ));
+        if self.gap_alert {
+            ring_bell();
         }
     }
 
-    pub fn filter_messages(&mut self) {
-        let query = self.search_query.to_lowercase();
-        self.filtered_indices = (0..self.messages.len())
-            .filter(|&i| {
-                let msg = &self.messages[i];
-                msg.content.to_lowercase().contains(&query)
-                    || msg.priority.as_ref().map_or(false, |p| p.to_lowercase().contains(&query))
-                    || msg.location.to_lowercase().contains(&query)
+    /// One line per enriched field of the selected message: its value, source
+    /// and confidence; empty unless toggled on with 'p'.
+    fn provenance_lines(&self) -> Vec<Line<'static>> {
+        let Some(msg) = self.state.selected_message().filter(|_| self.show_provenance) else {
+            return Vec::new();
+        };
+        let enrichment = enrich::enrich(msg, &self.lookup, &self.location_lookup);
+        let severity = self.severity.assess(msg, categories(&enrichment));
+        let mut lines: Vec<Line<'static>> = enrichment
+            .provenance
+            .iter()
+            .map(|p| {
+                let color = match p.confidence {
+                    c if c < 0.5 => ratatui::style::Color::Red,
+                    c if c < 0.8 => ratatui::style::Color::Yellow,
+                    _ => ratatui::style::Color::Green,
+                };
+                Line::from(vec![
+                    Span::raw(format!("Why {}: {} from {} ", p.field, p.value, p.source)),
+                    Span::styled(
+                        format!("({:.0}%)", p.confidence * 100.0),
+                        ratatui::style::Style::default().fg(color),
+                    ),
+                ])
             })
             .collect();
-        self.selected_index = 0;
-        self.scroll_offset = 0;
+        lines.push(Line::from(format!("Why severity: {}", severity)));
+        lines
     }
 
-    pub fn add_search_char(&mut self, c: char) {
-        self.search_query.push(c);
-        self.filter_messages();
+    fn selected_note(&self) -> Option<&str> {
+        self.state
+            .message_index(self.state.selected_index())
+            .and_then(|i| self.state.note(i))
     }
 
-    pub fn remove_search_char(&mut self) {
-        self.search_query.pop();
-        self.filter_messages();
+    /// Whether a message (by index into `state.messages()`) is still flashing.
+    pub fn is_flashing(&self, message_index: usize) -> bool {
+        self.flash_age(message_index).is_some()
     }
 
-    pub fn clear_search(&mut self) {
-        self.search_query.clear();
-        self.filter_messages();
+    fn flash_age(&self, message_index: usize) -> Option<Duration> {
+        self.flashing
+            .iter()
+            .find(|(i, _)| *i == message_index)
+            .map(|(_, arrived)| arrived.elapsed())
+            .filter(|age| *age < self.flash)
     }
-}
 
-pub struct App {
-    pub state: AppState,
-    lookup: Lookup,
-    location_lookup: LocationLookup,
-}
+    /// Falls back to ASCII on consoles that can't draw the glyphs.
+    pub fn with_icons(mut self, icons: IconStyle) -> Self {
+        self.icons = if icons != IconStyle::None && !supports_unicode() {
+            IconStyle::Ascii
+        } else {
+            icons
+        };
+        self.row_cache.clear();
+        self
+    }
 
-impl App {
-    pub fn new(messages: Vec<P2000Message>, lookup: Lookup, location_lookup: LocationLookup) -> Self {
-        App {
-            state: AppState::new(messages),
-            lookup,
-            location_lookup,
+    /// Tags live messages with the station receiving them.
+    pub fn with_receiver(mut self, receiver: Option<Receiver>) -> Self {
+        self.receiver = receiver.map(Arc::new);
+        self
+    }
+
+    /// Names the channel of live messages after their frequency.
+    pub fn with_channel_names(mut self, names: ChannelNames) -> Self {
+        self.channel_names = names;
+        self
+    }
+
+    /// Searches 'f' cycles through, after showing everything.
+    pub fn with_filter_presets(mut self, presets: Vec<FilterPreset>) -> Self {
+        self.presets = presets;
+        self
+    }
+
+    /// Starts with house numbers and postcodes hidden and coordinates rounded.
+    pub fn with_redaction(mut self, redact: bool) -> Self {
+        self.redact = redact;
+        self.row_cache.clear();
+        self
+    }
+
+    pub fn with_region_column(mut self, show: bool) -> Self {
+        self.region_column = show;
+        self.row_cache.clear();
+        self
+    }
+
+    /// Starts in a view shared by someone else; the help bar lists what it changed.
+    pub fn with_view(mut self, view: Option<View>) -> Self {
+        if let Some(view) = view {
+            let changes = self.apply_view(view);
+            self.status = Some(if changes.is_empty() {
+                "View unchanged".to_string()
+            } else {
+                format!("View: {}", changes.join(", "))
+            });
+        }
+        self
+    }
+
+    /// The filter, sort order and display toggles, as shared with 'l'.
+    pub fn view(&self) -> View {
+        View {
+            filter: self.state.filter().clone(),
+            redact: self.redact,
+            region_column: self.region_column,
+        }
+    }
+
+    /// Switches to `view` as one undo step; returns what changed.
+    pub fn apply_view(&mut self, view: View) -> Vec<String> {
+        let changes = self.view().diff(&view);
+        self.state.commit_filter();
+        self.state.set_filter(view.filter);
+        self.state.commit_filter();
+        self.redact = view.redact;
+        self.region_column = view.region_column;
+        self.row_cache.clear();
+        changes
+    }
+
+    /// List above the details, or the list beside a map and the details on
+    /// terminals at least `MIN_SPLIT_WIDTH` wide.
+    pub fn with_layout(mut self, layout: PaneLayout) -> Self {
+        self.layout = layout;
+        self
+    }
+
+    /// Live messages are bright while fresh and dimmed once old, see `Theme`.
+    pub fn with_theme(mut self, theme: Theme) -> Self {
+        self.theme = theme;
+        self
+    }
+
+    /// A line above the list with today's A1/P 1 messages, reanimations, GRIPs
+    /// and busiest region, kept up to date as messages arrive.
+    pub fn with_totals(mut self, show: bool) -> Self {
+        self.totals = show.then(|| self.count_totals());
+        self
+    }
+
+    /// How long the unit board ('U') assumes a dispatched unit is out.
+    pub fn with_units(mut self, config: UnitsConfig) -> Self {
+        self.units_config = config;
+        self
+    }
+
+    fn count_totals(&self) -> DailyTotals {
+        let mut totals = DailyTotals::default();
+        for (index, msg) in self.state.messages().iter().enumerate() {
+            totals.add(msg, self.state.region(index));
+        }
+        totals
+    }
+
+    /// Starts today's totals over once the clock passes midnight.
+    pub fn roll_totals(&mut self, now: chrono::DateTime<chrono::Local>) {
+        if let Some(totals) = &mut self.totals {
+            let day = totals.day();
+            totals.roll(now);
+            self.dirty |= totals.day() != day;
+        }
+    }
+
+    /// For a monitor on the wall: the latest messages newest first in large
+    /// spaced rows under a region header and a clock, with no details or help.
+    /// Keys do nothing, so only Ctrl-C or a signal ends it.
+    pub fn with_kiosk(mut self, kiosk: bool) -> Self {
+        if kiosk {
+            self.idle_frame = Some(self.idle_frame.map_or(KIOSK_TICK, |frame| frame.min(KIOSK_TICK)));
         }
+        self.kiosk = kiosk;
+        self
+    }
+
+    /// Adds an "English:" line to the detail pane.
+    pub fn with_translator(mut self, translator: Translator) -> Self {
+        self.translator = Some(translator);
+        self
+    }
+
+    /// Adds a "Weather:" line to the detail pane for recent messages at a
+    /// place with coordinates; it is fetched in the background.
+    pub fn with_weather(mut self, client: WeatherClient) -> Self {
+        self.weather = Some(Arc::new(client));
+        self
+    }
+
+    /// Forces the next `needs_redraw`, e.g. after a terminal resize.
+    pub fn mark_dirty(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether the screen is out of date, given when it was last drawn. Flashing
+    /// rows and new receiver log lines count as changes, and the idle frame rate
+    /// sets a floor.
+    pub fn needs_redraw(&self, last_draw: Instant) -> bool {
+        self.dirty
+            || !self.flashing.is_empty()
+            || self.process_log.as_ref().is_some_and(|log| log.len() != self.drawn_log_len)
+            || self.idle_frame.is_some_and(|frame| last_draw.elapsed() >= frame)
     }
 
     pub fn handle_input(&mut self, code: KeyCode) -> bool {
+        if self.kiosk {
+            return false;
+        }
+        self.dirty = true;
+        self.status = None;
+        if self.note_input.is_some() {
+            self.handle_note_input(code);
+            return false;
+        }
+        if self.place_correction.is_some() {
+            self.handle_place_correction(code);
+            return false;
+        }
+        if let Some(query) = &mut self.abbreviation_query {
+            match code {
+                KeyCode::Char(c) => query.push(c),
+                KeyCode::Backspace => {
+                    query.pop();
+                }
+                KeyCode::Esc | KeyCode::Enter => self.abbreviation_query = None,
+                _ => {}
+            }
+            return false;
+        }
+        if let Some((stats, _)) = &self.stats {
+            match code {
+                KeyCode::Char('S') if stats.period == Period::Day => self.open_stats(Period::Week),
+                KeyCode::Char('S') | KeyCode::Char('q') | KeyCode::Esc => self.stats = None,
+                _ => {}
+            }
+            return false;
+        }
+        if self.unit_view.is_some() {
+            match code {
+                KeyCode::Char('X') => self.next_unit(),
+                KeyCode::Char('q') | KeyCode::Esc => self.unit_view = None,
+                _ => {}
+            }
+            return false;
+        }
+        if self.units.is_some() {
+            if let KeyCode::Char('U') | KeyCode::Char('q') | KeyCode::Esc = code {
+                self.units = None;
+            }
+            return false;
+        }
         match code {
+            KeyCode::Esc if self.state.marked_count() > 0 || self.state.range_anchor().is_some() => {
+                self.state.clear_marks();
+            }
             KeyCode::Char('q') | KeyCode::Esc => return true,
             KeyCode::Char('s') => {
-                self.state.search_mode = !self.state.search_mode;
-                if !self.state.search_mode {
-                    self.state.search_query.clear();
-                    self.state.filter_messages();
+                self.search_mode = !self.search_mode;
+                if !self.search_mode {
+                    self.state.clear_search();
+                    self.state.commit_filter();
                 }
             }
-            KeyCode::Char(c) if self.state.search_mode => {
+            KeyCode::Tab => {
+                self.state.cycle_search_scope();
+                self.state.commit_filter();
+                self.status = Some(format!("Searching {} (u to undo)", self.state.filter().scope.name()));
+            }
+            KeyCode::Char(c) if self.search_mode => {
                 self.state.add_search_char(c);
             }
-            KeyCode::Backspace if self.state.search_mode => {
+            KeyCode::Backspace if self.search_mode => {
                 self.state.remove_search_char();
             }
-            KeyCode::Enter if self.state.search_mode => {
-                self.state.search_mode = false;
+            KeyCode::Enter if self.search_mode => {
+                self.search_mode = false;
+                self.state.commit_filter();
             }
-            KeyCode::Up => self.state.move_up(),
-            KeyCode::Down => self.state.move_down(),
-            KeyCode::PageUp => {
-                for _ in 0..10 {
-                    self.state.move_up();
+            KeyCode::Char(' ') => self.state.toggle_mark(),
+            KeyCode::Char('V') => self.state.toggle_range(),
+            KeyCode::Char('e') => {
+                self.status = Some(match self.export_targets() {
+                    Ok((count, path)) => format!("Exported {} messages to {}", count, path.display()),
+                    Err(e) => format!("Export failed: {}", e),
+                });
+            }
+            KeyCode::Char('t') => {
+                self.status = Some(match self.export_timeline() {
+                    Ok(Some((count, path))) => format!("Exported timeline of {} messages to {}", count, path.display()),
+                    Ok(None) => "No message selected".to_string(),
+                    Err(e) => format!("Export failed: {}", e),
+                });
+            }
+            KeyCode::Char('y') => {
+                self.status = Some(match self.copy_targets() {
+                    Ok(count) => format!("Copied {} messages", count),
+                    Err(e) => format!("Copy failed: {}", e),
+                });
+            }
+            KeyCode::Char('l') => {
+                let link = self.view().to_string();
+                self.status = Some(match copy_to_clipboard(&link) {
+                    Ok(()) => format!("Copied view link {}", link),
+                    Err(e) => format!("Copy failed: {}", e),
+                });
+            }
+            KeyCode::Char('b') => {
+                let added = self.state.bookmark_targets();
+                self.status = Some(format!("Bookmarked {} messages", added));
+            }
+            KeyCode::Char('m') => {
+                let muted = self.state.mute_target_capcodes();
+                self.status = Some(format!("Muted {} capcodes (u to undo)", muted));
+            }
+            KeyCode::Char('g') => {
+                self.state.cycle_region_filter();
+                self.state.commit_filter();
+                let region = self.state.filter().region.as_deref().unwrap_or("all regions");
+                self.status = Some(format!("Showing {} (u to undo)", region));
+            }
+            KeyCode::Char('h') => {
+                self.state.toggle_minor_pages();
+                self.state.commit_filter();
+                self.status = Some(
+                    if self.state.filter().hide_minor_pages {
+                        "Hiding numeric, group, tone and binary pages (u to undo)"
+                    } else {
+                        "Showing all page types (u to undo)"
+                    }
+                    .to_string(),
+                );
+            }
+            KeyCode::Char('f') if self.presets.is_empty() => {
+                self.status = Some("No [[filters]] configured".to_string());
+            }
+            KeyCode::Char('f') => {
+                self.preset = match self.preset {
+                    None => Some(0),
+                    Some(i) => Some(i + 1).filter(|next| *next < self.presets.len()),
+                };
+                let preset = self.preset.map(|i| &self.presets[i]);
+                self.state.set_filter(FilterPreset::apply(preset, self.state.filter()));
+                self.state.commit_filter();
+                let name = preset.map_or("All messages", |preset| preset.name.as_str());
+                self.status = Some(format!("Filter: {} (f for the next, u to undo)", name));
+            }
+            KeyCode::Char('F') if self.state.filter().place.is_some() => {
+                self.state.set_place_filter(None);
+                self.state.commit_filter();
+                self.status = Some("Stopped following (u to undo)".to_string());
+            }
+            KeyCode::Char('F') => {
+                let place = self.state.message_index(self.state.selected_index()).and_then(|i| self.state.place(i));
+                match place.map(str::to_string) {
+                    Some(place) => {
+                        self.status = Some(format!("Following {}: only its messages are shown (F to stop)", place));
+                        self.state.set_place_filter(Some(place));
+                        self.state.commit_filter();
+                    }
+                    None => self.status = Some("No place matched in this message".to_string()),
+                }
+            }
+            KeyCode::Char('I') if self.pinned.is_some() => {
+                self.pinned = None;
+                self.status = Some("Unpinned the incident".to_string());
+            }
+            KeyCode::Char('I') => self.pin_incident(),
+            KeyCode::Char('o') => {
+                self.state.cycle_sort();
+                self.state.commit_filter();
+            }
+            // Pending pages come first; otherwise 'a' looks up abbreviations
+            KeyCode::Char('a') if self.pager.as_ref().is_none_or(|pager| pager.pending_count() == 0) => {
+                self.abbreviation_query = Some(String::new());
+            }
+            KeyCode::Char('a') => {
+                if let Some(pager) = &mut self.pager {
+                    self.status = Some(match pager.acknowledge(self.state.messages()) {
+                        Ok(Some(i)) => format!("Acknowledged: {}", self.state.messages()[i].content),
+                        Ok(None) => "No pending pages".to_string(),
+                        Err(e) => format!("Acknowledge failed: {}", e),
+                    });
                 }
             }
-            KeyCode::PageDown => {
-                for _ in 0..10 {
-                    self.state.move_down();
+            KeyCode::Char('n') => {
+                if let Some(i) = self.state.message_index(self.state.selected_index()) {
+                    self.note_input = Some(self.state.note(i).unwrap_or_default().to_string());
                 }
             }
+            KeyCode::Char('w') if self.feedback.is_some() => {
+                if let Some(msg) = self.state.selected_message() {
+                    let full_text = format!("{} {}", msg.location, msg.content);
+                    match self.location_lookup.find_location_by_text(&full_text) {
+                        Some(found) => self.place_correction = Some((found.found_place, String::new())),
+                        None => self.status = Some("No place matched in this message".to_string()),
+                    }
+                }
+            }
+            KeyCode::Char('k') => self.toggle_status(IncidentStatus::Acknowledged),
+            KeyCode::Char('x') => self.toggle_status(IncidentStatus::Resolved),
+            KeyCode::Char('L') => self.log_expanded = !self.log_expanded,
+            KeyCode::Char('p') => self.show_provenance = !self.show_provenance,
+            KeyCode::Char('S') => self.open_stats(Period::Day),
+            KeyCode::Char('U') => self.open_units(),
+            KeyCode::Char('X') => self.open_unit_view(),
+            KeyCode::Char('z') => {
+                let count = self.state.toggle_burst();
+                let selected = self.state.message_index(self.state.selected_index());
+                self.status = Some(match count {
+                    0 => "No repeats of this message".to_string(),
+                    _ if selected.is_some_and(|m| self.state.is_expanded(m)) => {
+                        format!("Showing all {} messages of the burst", count)
+                    }
+                    _ => format!("Collapsed {} messages into one row", count),
+                });
+            }
+            KeyCode::Char('v') => {
+                self.layout = self.layout.next();
+                self.status = Some(format!("Layout: {}", self.layout.name()));
+            }
+            KeyCode::Char('D') => self.reload_data(),
+            KeyCode::Char('R') => {
+                self.redact = !self.redact;
+                self.row_cache.clear();
+                self.status = Some(
+                    if self.redact { "Redaction on: addresses hidden" } else { "Redaction off" }.to_string(),
+                );
+            }
+            KeyCode::Char('M') => {
+                if let Some(audio) = &mut self.audio {
+                    let muted = audio.toggle_mute();
+                    self.status = Some(if muted { "Sound muted" } else { "Sound on" }.to_string());
+                }
+            }
+            KeyCode::Char('u') => {
+                self.state.undo_filter();
+            }
+            KeyCode::Char('r') => {
+                self.state.redo_filter();
+            }
+            KeyCode::Up => self.state.move_up(),
+            KeyCode::Down => self.state.move_down(),
+            KeyCode::PageUp => self.state.page_up(10),
+            KeyCode::PageDown => self.state.page_down(10),
             _ => {}
         }
         false
     }
 
     pub fn draw(&mut self, f: &mut ratatui::Frame) {
-        let chunks = ratatui::layout::Layout::default()
+        self.dirty = false;
+        self.drawn_log_len = self.process_log.as_ref().map_or(0, ProcessLog::len);
+        let flash = self.flash;
+        self.flashing.retain(|(_, arrived)| arrived.elapsed() < flash);
+        if self.kiosk {
+            self.draw_kiosk(f);
+            return;
+        }
+        if let Some((stats, grid)) = &self.stats {
+            self.draw_stats(f, stats, grid);
+            return;
+        }
+        if let Some(board) = &self.units {
+            self.draw_units(f, board);
+            return;
+        }
+        if let Some((message_index, unit)) = self.unit_view {
+            self.draw_unit_view(f, message_index, unit);
+            return;
+        }
+        let provenance = self.provenance_lines();
+        let detail_height = 8
+            + u16::from(self.translator.is_some())
+            + u16::from(self.weather.is_some())
+            + u16::from(self.selected_note().is_some())
+            + u16::from(self.state.selected_message().is_some_and(|msg| msg.receiver.is_some()))
+            + provenance.len() as u16;
+        let mut constraints = Vec::new();
+        if self.data_warning.is_some() {
+            constraints.push(ratatui::layout::Constraint::Length(1));
+        }
+        if self.totals.is_some() {
+            constraints.push(ratatui::layout::Constraint::Length(1));
+        }
+        if self.pager.is_some() {
+            constraints.push(ratatui::layout::Constraint::Length(PAGER_ROWS as u16 + 2));
+        }
+        if self.pinned.is_some() {
+            constraints.push(ratatui::layout::Constraint::Length(3));
+        }
+        let split = self.layout == PaneLayout::Split && f.area().width >= MIN_SPLIT_WIDTH;
+        constraints.push(ratatui::layout::Constraint::Min(1));
+        if !split {
+            constraints.push(ratatui::layout::Constraint::Length(detail_height));
+        }
+        let log_rows = match (&self.process_log, self.log_expanded) {
+            (None, _) => 0,
+            (Some(log), _) if log.is_empty() => 0,
+            (Some(_), false) => 1,
+            (Some(_), true) => LOG_ROWS,
+        };
+        if log_rows > 0 {
+            constraints.push(ratatui::layout::Constraint::Length(log_rows as u16 + 2));
+        }
+        constraints.push(ratatui::layout::Constraint::Length(3));
+        let areas = ratatui::layout::Layout::default()
             .direction(ratatui::layout::Direction::Vertical)
             .margin(1)
-            .constraints([
-                ratatui::layout::Constraint::Min(1),
-                ratatui::layout::Constraint::Length(8),
-                ratatui::layout::Constraint::Length(3),
-            ])
+            .constraints(constraints)
             .split(f.area());
+        let mut chunks = &areas[..];
+        if let Some(warning) = &self.data_warning {
+            let banner = Paragraph::new(format!("Missing data files, press D to reload: {}", warning))
+                .style(ratatui::style::Style::default().fg(ratatui::style::Color::Yellow));
+            f.render_widget(banner, chunks[0]);
+            chunks = &chunks[1..];
+        }
+        if let Some(totals) = &self.totals {
+            let line = Paragraph::new(totals.to_string())
+                .style(ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD));
+            f.render_widget(line, chunks[0]);
+            chunks = &chunks[1..];
+        }
+        if self.pager.is_some() {
+            self.draw_pager(f, chunks[0]);
+            chunks = &chunks[1..];
+        }
+        if self.pinned.is_some() {
+            self.draw_pinned(f, chunks[0]);
+            chunks = &chunks[1..];
+        }
+        let (list_area, detail_area, map_area) = if split {
+            let columns = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Horizontal)
+                .constraints([ratatui::layout::Constraint::Percentage(55), ratatui::layout::Constraint::Percentage(45)])
+                .split(chunks[0]);
+            let right = ratatui::layout::Layout::default()
+                .direction(ratatui::layout::Direction::Vertical)
+                .constraints([
+                    ratatui::layout::Constraint::Min(3),
+                    ratatui::layout::Constraint::Length(detail_height),
+                ])
+                .split(columns[1]);
+            chunks = &chunks[1..];
+            (columns[0], right[1], Some(right[0]))
+        } else {
+            let areas = (chunks[0], chunks[1], None);
+            chunks = &chunks[2..];
+            areas
+        };
 
-        // Capture the list area height
-        self.state.set_list_height(chunks[0].height);
+        // Capture the list area height, minus the borders
+        self.state.set_viewport_height(list_area.height.saturating_sub(2) as usize);
 
         // Message list - only render visible items based on scroll offset
+        if self.row_cache.len() > ROW_CACHE_LIMIT {
+            self.row_cache.clear();
+        }
+        let visible: Vec<usize> = self.state.visible().filter_map(|(i, _)| self.state.message_index(i)).collect();
+        for index in visible {
+            if !self.row_cache.contains_key(&index) {
+                let spans = self.row_spans(index);
+                self.row_cache.insert(index, spans);
+            }
+        }
+        let unicode = supports_unicode();
+        let now = chrono::Local::now();
         let items: Vec<ListItem> = self
             .state
-            .filtered_indices
-            .iter()
-            .skip(self.state.scroll_offset)
-            .take(self.state.list_height as usize)
-            .enumerate()
-            .map(|(display_i, &msg_idx)| {
-                let actual_i = display_i + self.state.scroll_offset;
-                let msg = &self.state.messages[msg_idx];
-                let is_selected = actual_i == self.state.selected_index;
-                let style = if is_selected {
+            .visible()
+            .map(|(i, _)| {
+                let message_index = self.state.message_index(i);
+                let is_selected = i == self.state.selected_index();
+                let mut style = if is_selected {
                     ratatui::style::Style::default()
                         .bg(ratatui::style::Color::DarkGray)
                         .fg(ratatui::style::Color::White)
                 } else {
                     ratatui::style::Style::default()
                 };
+                if let Some(m) = message_index {
+                    style = style.add_modifier(self.age_modifier(m, now));
+                }
+                let status = message_index.map(|m| self.state.status(m)).unwrap_or_default();
+                match status {
+                    IncidentStatus::Open => {}
+                    IncidentStatus::Acknowledged => style = style.add_modifier(ratatui::style::Modifier::DIM),
+                    IncidentStatus::Resolved => {
+                        style = style.add_modifier(ratatui::style::Modifier::DIM | ratatui::style::Modifier::CROSSED_OUT)
+                    }
+                }
+                if let Some(age) = message_index.and_then(|m| self.flash_age(m)) {
+                    style = style.add_modifier(ratatui::style::Modifier::BOLD);
+                    if (age.as_millis() / FLASH_PHASE.as_millis()).is_multiple_of(2) {
+                        style = style.add_modifier(ratatui::style::Modifier::REVERSED);
+                    }
+                }
 
-                let priority_color = match msg.priority.as_deref() {
-                    Some(p) if p.starts_with('A') => ratatui::style::Color::Red,
-                    Some(p) if p.starts_with('P') => ratatui::style::Color::Yellow,
-                    _ => ratatui::style::Color::White,
-                };
-
-                let line = if let Some(priority) = &msg.priority {
-                    Line::from(vec![
-                        Span::styled(
-                            format!("{:>3}", priority),
-                            ratatui::style::Style::default().fg(priority_color),
-                        ),
-                        Span::raw(" | "),
-                        Span::raw(msg.timestamp.format("%H:%M:%S").to_string()),
-                        Span::raw(" | "),
-                        Span::raw(msg.content.clone()),
-                    ])
+                let marker = if self.state.is_marked_at(i) {
+                    "+"
+                } else if message_index.is_some_and(|m| self.state.is_bookmarked(m)) {
+                    "*"
                 } else {
-                    Line::from(vec![
-                        Span::raw(msg.timestamp.format("%H:%M:%S").to_string()),
-                        Span::raw(" | "),
-                        Span::raw(msg.content.clone()),
-                    ])
+                    " "
+                };
+                let has_note = message_index.is_some_and(|m| self.state.note(m).is_some());
+                let note_marker = match (has_note, unicode) {
+                    (false, _) => " ",
+                    (true, true) => "✎",
+                    (true, false) => "n",
                 };
 
-                ListItem::new(line).style(style)
+                let mut spans = vec![Span::raw(marker), Span::raw(note_marker)];
+                let cached = message_index.and_then(|m| self.row_cache.get(&m));
+                spans.extend(cached.into_iter().flatten().map(|span| Span::styled(span.content.as_ref(), span.style)));
+                if let Some(repeats) = message_index.and_then(|m| self.repeat_label(m, unicode)) {
+                    spans.push(Span::styled(
+                        repeats,
+                        ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
+                    ));
+                }
+                ListItem::new(Line::from(spans)).style(style)
             })
             .collect();
 
-        let list = List::new(items)
-            .block(Block::default().borders(Borders::ALL).title("P2000 Messages"));
-        f.render_widget(list, chunks[0]);
+        let mut title = "P2000 Messages".to_string();
+        let pending = self.state.pending_count();
+        if pending > 0 {
+            title.push_str(&format!(" ({} pending)", pending));
+        }
+        if let Some(region) = &self.state.filter().region {
+            title.push_str(&format!(" - {}", region));
+        }
+        if let Some(place) = &self.state.filter().place {
+            title.push_str(&format!(" - following {}", place));
+        }
+        if self.state.filter().hide_minor_pages {
+            title.push_str(" - text pages");
+        }
+        if self.state.filter().scope != SearchScope::All {
+            title.push_str(&format!(" - searching {}", self.state.filter().scope.name()));
+        }
+        if let Some(dropped) = self.sampler.as_ref().map(Sampler::dropped).filter(|d| *d > 0) {
+            title.push_str(&format!(" [{} sampled out]", dropped));
+        }
+        match self.state.filter().sort {
+            SortOrder::Arrival => {}
+            SortOrder::Region => title.push_str(" (by region)"),
+            SortOrder::Time => title.push_str(" (by time)"),
+            SortOrder::Severity => title.push_str(" (by severity)"),
+        }
+        let list = List::new(items).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(list, list_area);
+        if let Some(area) = map_area {
+            self.draw_map(f, area);
+        }
 
         // Detail view
         if let Some(msg) = self.state.selected_message() {
@@ -224,169 +1202,1105 @@ impl App {
                 .format_capcodes(msg)
                 .unwrap_or_else(|| msg.capcodes.join(", "));
 
-            let abbrev_display = self.format_abbreviations(msg);
-
             // Search for place names in the full message (content + location)
             let full_text = format!("{} {}", msg.location, msg.content);
-            let location_display = self
-                .location_lookup
-                .find_location_by_text(&full_text)
-                .map(|found| self.location_lookup.format_found_location(&found))
-                .unwrap_or_else(|| msg.location.clone());
+            let found = self.location_lookup.find_location_by_text(&full_text);
+            let coordinates = found.as_ref().and_then(|found| found.info.latitude.zip(found.info.longitude));
+            let location_display = found
+                .map(|mut found| {
+                    if self.redact {
+                        redact::redact_location(&mut found);
+                    }
+                    self.location_lookup.format_found_location(&found)
+                })
+                .unwrap_or_else(|| self.shown_text(&msg.location));
 
-            let detail_text = format!(
+            let region_display = self
+                .state
+                .message_index(self.state.selected_index())
+                .and_then(|m| self.state.region(m))
+                .unwrap_or("-");
+
+            let mut timestamp_display = msg.timestamp.format("%Y-%m-%d %H:%M:%S%.f").to_string();
+            if msg.synthetic_time {
+                timestamp_display.push_str(" (time of receipt, the decoder gave none)");
+            } else if let Some(received) = msg.received {
+                timestamp_display.push_str(&format!(" (received {})", received.format("%H:%M:%S%.3f")));
+            }
+
+            let frequency_display = match &msg.channel {
+                Some(channel) => format!("{} ({})", msg.frequency, channel),
+                None => msg.frequency.to_string(),
+            };
+
+            let mut detail_text = Text::from(format!(
                 "Priority: {:?} | Code: {:?} | Location: {}\n\
-                Timestamp: {} | Type: {} | Freq: {}\n\
-                Radio Addr: {} | Capcodes: {}\n\
-                Abbreviations: {}\n\
-                Content: {}",
+                Timestamp: {} | Type: {} | Freq: {} | Region: {}\n\
+                Radio Addr: {} | Capcodes: {}",
                 msg.priority,
                 msg.incident_code,
                 location_display,
-                msg.timestamp.format("%Y-%m-%d %H:%M:%S"),
+                timestamp_display,
                 msg.message_type,
-                msg.frequency,
+                frequency_display,
+                region_display,
                 msg.radio_address,
                 capcodes_display,
-                abbrev_display,
-                msg.content
-            );
+            ));
+            detail_text.lines.push(self.abbreviation_line(msg));
+            let content = self.shown_text(&msg.content);
+            detail_text.lines.push(content_line(&content));
+            if let Some(translator) = &self.translator {
+                let english = translator.translate(&content);
+                detail_text.lines.push(Line::from(format!("English: {}", english.as_deref().unwrap_or("-"))));
+            }
+            if let Some(client) = &self.weather {
+                let weather = coordinates.filter(|_| client.applies_to(msg.timestamp)).and_then(|(lat, lon)| {
+                    client.prefetch(lat, lon);
+                    client.cached(lat, lon).flatten()
+                });
+                let weather = weather.map_or_else(|| "-".to_string(), |weather| weather.to_string());
+                detail_text.lines.push(Line::from(format!("Weather: {}", weather)));
+            }
+            if let Some(receiver) = &msg.receiver {
+                detail_text.lines.push(Line::from(format!("Receiver: {}", receiver)));
+            }
+            if let Some(note) = self.selected_note() {
+                detail_text.lines.push(Line::from(format!("Note: {}", note)));
+            }
+            detail_text.lines.extend(provenance);
 
             let detail = Paragraph::new(detail_text)
-                .block(Block::default().borders(Borders::ALL).title("Details"))
+                .block(Block::default().borders(Borders::ALL).title(if self.redact { "Details (redacted)" } else { "Details" }))
                 .wrap(Wrap { trim: true });
-            f.render_widget(detail, chunks[1]);
+            f.render_widget(detail, detail_area);
         }
 
         // Help/search bar
-        let help_text = if self.state.search_mode {
+        let suggestion = self.suggestion();
+        let help_text = if let Some(status) = &self.status {
+            status.clone()
+        } else if self.state.marked_count() > 0 || self.state.range_anchor().is_some() {
+            format!(
+                "{} marked | Space/V: Mark/Range | e: Export | y: Copy | b: Bookmark | m: Mute | Esc: Clear",
+                self.state.marked_count()
+            )
+        } else if let Some(note) = &self.note_input {
+            format!("NOTE: {} (Enter to save, empty to remove, Esc to cancel)", note)
+        } else if let Some((wrong, right)) = &self.place_correction {
+            format!(
+                "WRONG PLACE {}, correct place: {} (Enter to save, empty if none, Esc to cancel)",
+                wrong, right
+            )
+        } else if let (true, Some(suggestion)) = (self.search_mode, &suggestion) {
+            format!("SEARCH: {} (no matches, did you mean: {}?)", self.state.search_query(), suggestion)
+        } else if self.search_mode {
             format!(
                 "SEARCH: {} (Enter to exit, Backspace to delete)",
-                self.state.search_query
+                self.state.search_query()
+            )
+        } else if let Some(suggestion) = &suggestion {
+            format!(
+                "No matches for \"{}\", did you mean: {}? | s: Search | u: Undo",
+                self.state.search_query(),
+                suggestion
             )
         } else {
-            "↑/↓: Navigate | PageUp/Down: Jump | s: Search | q: Quit".to_string()
+            let arrows = if supports_unicode() { "↑/↓" } else { "Up/Down" };
+            format!(
+                "{}: Navigate | s: Search | g/o: Region filter/sort | Space/V: Mark | u/r: Undo/Redo | q: Quit",
+                arrows
+            )
         };
 
         let help = Paragraph::new(help_text)
             .block(Block::default().borders(Borders::ALL).title("Help"))
             .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
-        f.render_widget(help, chunks[2]);
+        f.render_widget(help, chunks[chunks.len() - 1]);
+        if log_rows > 0 {
+            self.draw_process_log(f, chunks[0], log_rows);
+        }
+        if let Some(query) = &self.abbreviation_query {
+            self.draw_abbreviation_popup(f, query);
+        }
     }
 
-    fn format_capcodes(&self, msg: &P2000Message) -> Option<String> {
-        let mut parts = Vec::new();
-        for code in &msg.capcodes {
-            if let Some(info) = self.lookup.resolve_capcode(code) {
-                let mut segment = String::new();
-                if !info.description.is_empty() {
-                    segment.push_str(&info.description);
-                } else if !info.short.is_empty() {
-                    segment.push_str(&info.short);
-                }
-                if !info.place.is_empty() {
-                    if !segment.is_empty() {
-                        segment.push_str(" - ");
-                    }
-                    segment.push_str(&info.place);
-                }
-                if segment.is_empty() {
-                    segment.push_str(&info.code);
-                }
-                parts.push(segment);
+    /// Centered over everything else: the query and the entries it finds by
+    /// abbreviation or by expansion.
+    fn draw_abbreviation_popup(&self, f: &mut ratatui::Frame, query: &str) {
+        let screen = f.area();
+        let width = (screen.width * 7 / 10).max(30).min(screen.width);
+        let height = (screen.height * 6 / 10).max(5).min(screen.height);
+        let area = ratatui::layout::Rect {
+            x: screen.x + (screen.width - width) / 2,
+            y: screen.y + (screen.height - height) / 2,
+            width,
+            height,
+        };
+
+        let mut lines = vec![Line::from(format!("> {}", query))];
+        let hits = self.lookup.search_abbreviations(query);
+        let rows = (height as usize).saturating_sub(3);
+        if hits.is_empty() && !query.trim().is_empty() {
+            lines.push(Line::from("No abbreviation or expansion matches"));
+        }
+        for (abbreviation, entry) in hits.iter().take(rows) {
+            let style = entry
+                .category
+                .as_deref()
+                .map_or(ratatui::style::Style::default(), |category| {
+                    ratatui::style::Style::default().fg(category_color(category))
+                });
+            let mut spans = vec![
+                Span::styled(format!("{:<10} ", abbreviation), style.add_modifier(ratatui::style::Modifier::BOLD)),
+                Span::styled(entry.expansion.clone(), style),
+            ];
+            if let Some(category) = &entry.category {
+                spans.push(Span::raw(format!(" [{}]", category)));
             }
+            lines.push(Line::from(spans));
         }
-        if parts.is_empty() {
-            None
+        if hits.len() > rows {
+            lines[0].spans.push(Span::raw(format!("  ({} of {})", rows, hits.len())));
+        }
+
+        let popup = Paragraph::new(lines).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title("Abbreviation lookup: type an abbreviation or meaning, Esc to close"),
+        );
+        f.render_widget(ratatui::widgets::Clear, area);
+        f.render_widget(popup, area);
+    }
+
+    /// The messages in the list on a map, the selected one as `@`.
+    fn draw_map(&mut self, f: &mut ratatui::Frame, area: ratatui::layout::Rect) {
+        if self.places.len() > ROW_CACHE_LIMIT {
+            self.places.clear();
+        }
+        let shown: Vec<usize> = self.state.visible().filter_map(|(i, _)| self.state.message_index(i)).collect();
+        for &index in &shown {
+            if !self.places.contains_key(&index) {
+                let place = self.place_coordinates(index);
+                self.places.insert(index, place);
+            }
+        }
+        let selected = self.state.message_index(self.state.selected_index());
+        let places: Vec<(f64, f64)> = shown
+            .iter()
+            .filter(|&&index| Some(index) != selected)
+            .filter_map(|index| self.places[index])
+            .collect();
+        let selected = selected.and_then(|index| self.places.get(&index).copied().flatten());
+        let rows = map::render(
+            &places,
+            selected,
+            area.width.saturating_sub(2) as usize,
+            area.height.saturating_sub(2) as usize,
+        );
+        let title = format!("Map ({} of {} placed)", places.len() + usize::from(selected.is_some()), shown.len());
+        let text = Text::from(rows.into_iter().map(Line::from).collect::<Vec<_>>());
+        f.render_widget(Paragraph::new(text).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+
+    /// Where the place matched in a message lies, if the data has coordinates for it.
+    fn place_coordinates(&self, message_index: usize) -> Option<(f64, f64)> {
+        let msg = &self.state.messages()[message_index];
+        let found = self.location_lookup.find_location_by_text(&format!("{} {}", msg.location, msg.content))?;
+        Some((found.info.longitude?, found.info.latitude?))
+    }
+
+    /// " ×3" after the first row of a collapsed burst of repeats.
+    fn repeat_label(&self, message_index: usize, unicode: bool) -> Option<String> {
+        let count = self.state.repeat_count(message_index);
+        if count < 2 || self.state.is_expanded(message_index) {
+            return None;
+        }
+        Some(format!(" {}{}", if unicode { "×" } else { "x" }, count))
+    }
+
+    /// Shows the stats screen for `period`. With an archive it counts what the
+    /// archive holds up to now; otherwise the loaded messages up to the latest.
+    /// The heat grid always shows the loaded messages.
+    fn open_stats(&mut self, period: Period) {
+        let comparison = match &self.archive {
+            Some(archive) => Comparison::from_archive(archive, period, chrono::Local::now()),
+            None => {
+                let messages = self.state.messages();
+                let now = messages
+                    .iter()
+                    .map(|msg| msg.time(TimeSource::Received))
+                    .max()
+                    .unwrap_or_else(chrono::Local::now);
+                let counted = messages
+                    .iter()
+                    .enumerate()
+                    .map(|(i, msg)| (msg.time(TimeSource::Received), msg.priority.as_deref(), self.state.region(i)));
+                Ok(Comparison::new(period, now, counted))
+            }
+        };
+        let grid = HeatGrid::new(self.state.messages().iter().map(|msg| msg.time(TimeSource::Received)));
+        match comparison {
+            Ok(comparison) => self.stats = Some((comparison, grid)),
+            Err(e) => self.status = Some(format!("Stats failed: {}", e)),
+        }
+    }
+
+    /// The stats screen in place of everything else.
+    fn draw_stats(&self, f: &mut ratatui::Frame, stats: &Comparison, grid: &HeatGrid) {
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([ratatui::layout::Constraint::Min(1), ratatui::layout::Constraint::Length(3)])
+            .split(f.area());
+        let columns = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Horizontal)
+            .constraints([ratatui::layout::Constraint::Min(1), ratatui::layout::Constraint::Length(HEAT_GRID_WIDTH)])
+            .split(chunks[0]);
+        let unicode = supports_unicode();
+        let source = if self.archive.is_some() { "Stats (archive)" } else { "Stats (loaded messages)" };
+        let lines: Vec<Line> = stats.lines(unicode).into_iter().map(Line::from).collect();
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(Block::default().borders(Borders::ALL).title(source)),
+            columns[0],
+        );
+        // The axis and the borders take three rows
+        let days = columns[1].height.saturating_sub(3) as usize;
+        let lines: Vec<Line> = grid.lines(days, unicode).into_iter().map(Line::from).collect();
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(Block::default().borders(Borders::ALL).title("Messages per hour")),
+            columns[1],
+        );
+        let next = match stats.period {
+            Period::Day => "S: This week vs last week",
+            Period::Week => "S: Close",
+        };
+        let help = Paragraph::new(format!("{} | Esc: Back to the list", next))
+            .block(Block::default().borders(Borders::ALL).title("Help"))
+            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+        f.render_widget(help, chunks[1]);
+    }
+
+    /// Shows the unit board. Live messages are judged against the clock, messages
+    /// read from a file against the latest one.
+    fn open_units(&mut self) {
+        let messages = self.state.messages();
+        let now = if messages.iter().any(|msg| msg.received.is_some()) {
+            chrono::Local::now()
+        } else {
+            messages.iter().map(|msg| msg.time(TimeSource::Received)).max().unwrap_or_else(chrono::Local::now)
+        };
+        let dispatches = messages
+            .iter()
+            .enumerate()
+            .map(|(i, msg)| (msg.time(TimeSource::Received), msg.content.as_str(), self.state.region(i)));
+        self.units = Some(UnitBoard::new(&self.units_config, now, dispatches));
+    }
+
+    /// The unit board in place of everything else.
+    fn draw_units(&self, f: &mut ratatui::Frame, board: &UnitBoard) {
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([ratatui::layout::Constraint::Min(1), ratatui::layout::Constraint::Length(3)])
+            .split(f.area());
+        let title = format!("Units out ({}, assumed {}m each)", board.len(), self.units_config.assumed_mins);
+        let lines: Vec<Line> = if board.is_empty() {
+            vec![Line::from("No units dispatched recently")]
         } else {
-            Some(parts.join(", "))
+            board.lines().into_iter().map(Line::from).collect()
+        };
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(Block::default().borders(Borders::ALL).title(title)),
+            chunks[0],
+        );
+        let help = Paragraph::new("Esc: Back to the list")
+            .block(Block::default().borders(Borders::ALL).title("Help"))
+            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+        f.render_widget(help, chunks[1]);
+    }
+
+    /// Unit callsigns in a message, in the order they appear.
+    fn message_units(&self, message_index: usize) -> Vec<String> {
+        units::callsigns(&self.state.messages()[message_index].content)
+    }
+
+    /// Shows every loaded dispatch of the first unit in the selected message.
+    fn open_unit_view(&mut self) {
+        let Some(index) = self.state.message_index(self.state.selected_index()) else {
+            return;
+        };
+        if self.message_units(index).is_empty() {
+            self.status = Some("No unit callsigns in this message".to_string());
+        } else {
+            self.unit_view = Some((index, 0));
         }
     }
 
-    fn format_abbreviations(&self, msg: &P2000Message) -> String {
-        let mut seen = std::collections::HashSet::new();
-        let mut expansions = Vec::new();
+    /// Moves on to the message's next unit, closing after the last.
+    fn next_unit(&mut self) {
+        self.unit_view = self.unit_view.and_then(|(index, unit)| {
+            (unit + 1 < self.message_units(index).len()).then_some((index, unit + 1))
+        });
+    }
 
-        // Pre-tokenize once and keep cleaned tokens
-        let raw_tokens: Vec<String> = msg
-            .content
-            .split_whitespace()
-            .map(|raw| raw.trim_matches(|c: char| !c.is_alphanumeric() && c != '&').to_string())
-            .filter(|t| !t.is_empty())
+    /// The unit cross-reference in place of everything else: the unit's
+    /// dispatches, latest first, with the message it was opened from in bold.
+    fn draw_unit_view(&self, f: &mut ratatui::Frame, message_index: usize, unit: usize) {
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([ratatui::layout::Constraint::Min(1), ratatui::layout::Constraint::Length(3)])
+            .split(f.area());
+        let units = self.message_units(message_index);
+        let callsign = &units[unit];
+        let dispatches = self.unit_index.get(callsign).map(Vec::as_slice).unwrap_or_default();
+        let lines: Vec<Line> = dispatches
+            .iter()
+            .rev()
+            .map(|&i| {
+                let msg = &self.state.messages()[i];
+                let text = format!(
+                    "{}  {:<width$.width$}  {}",
+                    msg.time(TimeSource::Received).format("%a %H:%M"),
+                    self.state.region(i).unwrap_or("-"),
+                    msg.content,
+                    width = REGION_WIDTH
+                );
+                let style = if i == message_index {
+                    ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD)
+                } else {
+                    ratatui::style::Style::default()
+                };
+                Line::styled(text, style)
+            })
             .collect();
+        let title = format!(
+            "Unit {}: {} dispatches (unit {} of {} in this message)",
+            callsign,
+            dispatches.len(),
+            unit + 1,
+            units.len()
+        );
+        f.render_widget(
+            Paragraph::new(Text::from(lines)).block(Block::default().borders(Borders::ALL).title(title)),
+            chunks[0],
+        );
+        let next = if unit + 1 < units.len() { "X: Next unit" } else { "X: Close" };
+        let help = Paragraph::new(format!("{} | Esc: Back to the list", next))
+            .block(Block::default().borders(Borders::ALL).title("Help"))
+            .style(ratatui::style::Style::default().fg(ratatui::style::Color::Cyan));
+        f.render_widget(help, chunks[1]);
+    }
 
-        let mut i = 0;
-        while i < raw_tokens.len() {
-            let token = &raw_tokens[i];
+    /// Bold for fresh and dim for old live messages; messages read from a
+    /// file have no receive time and keep the normal style.
+    fn age_modifier(&self, message_index: usize, now: chrono::DateTime<chrono::Local>) -> ratatui::style::Modifier {
+        let Some(received) = self.state.messages()[message_index].received else {
+            return ratatui::style::Modifier::empty();
+        };
+        match self.theme.freshness((now - received).to_std().unwrap_or_default()) {
+            Freshness::Fresh => ratatui::style::Modifier::BOLD,
+            Freshness::Normal => ratatui::style::Modifier::empty(),
+            Freshness::Old => ratatui::style::Modifier::DIM,
+        }
+    }
 
-            // Direct match
-            if let Some(exp) = self.lookup.expand_abbreviation(token) {
-                if seen.insert(token.clone()) {
-                    expansions.push(format!("{}: {}", token, exp));
-                }
+    /// The kiosk layout, see `with_kiosk`.
+    fn draw_kiosk(&mut self, f: &mut ratatui::Frame) {
+        let chunks = ratatui::layout::Layout::default()
+            .direction(ratatui::layout::Direction::Vertical)
+            .margin(1)
+            .constraints([ratatui::layout::Constraint::Length(3), ratatui::layout::Constraint::Min(1)])
+            .split(f.area());
+
+        let header = Block::default().borders(Borders::ALL).title("P2000");
+        let inner = header.inner(chunks[0]);
+        f.render_widget(header, chunks[0]);
+        let region = self.state.filter().region.as_deref().unwrap_or("All regions");
+        f.render_widget(
+            Paragraph::new(region).style(ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD)),
+            inner,
+        );
+        let clock = chrono::Local::now().format("%H:%M").to_string();
+        f.render_widget(Paragraph::new(clock).alignment(ratatui::layout::Alignment::Right), inner);
+
+        // Newest first whatever the sort order, as many as fit
+        let fits = (chunks[1].height.saturating_sub(2) as usize).div_ceil(KIOSK_ROW_HEIGHT);
+        let mut latest = self.state.filtered_indices().to_vec();
+        latest.sort_unstable_by(|a, b| b.cmp(a));
+        let items: Vec<ListItem> = latest.into_iter().take(fits).map(|index| self.kiosk_item(index)).collect();
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL)), chunks[1]);
+    }
+
+    /// One kiosk row: priority, time and text, then the place and region.
+    fn kiosk_item(&self, message_index: usize) -> ListItem<'static> {
+        let msg = &self.state.messages()[message_index];
+        let priority_color = match msg.priority.as_deref() {
+            Some(p) if p.starts_with('A') => ratatui::style::Color::Red,
+            Some(p) if p.starts_with('P') => ratatui::style::Color::Yellow,
+            _ => ratatui::style::Color::White,
+        };
+        let bold = ratatui::style::Style::default().add_modifier(ratatui::style::Modifier::BOLD);
+        let priority = msg.priority.as_deref().unwrap_or("");
+        let mut first = vec![Span::styled(format!("{:<4}", priority), bold.fg(priority_color))];
+        first.push(Span::raw(msg.timestamp.format("%H:%M  ").to_string()));
+        first.push(Span::styled(self.shown_text(&msg.content), bold));
+        if let Some(repeats) = self.repeat_label(message_index, supports_unicode()) {
+            first.push(Span::raw(repeats));
+        }
+
+        let full_text = format!("{} {}", msg.location, msg.content);
+        let place = self.location_lookup.find_location_by_text(&full_text).map(|mut found| {
+            if self.redact {
+                redact::redact_location(&mut found);
+            }
+            found.found_place
+        });
+        let region = self.state.region(message_index);
+        let place = [place.as_deref(), region].into_iter().flatten().collect::<Vec<_>>().join(" - ");
+        let second = Span::styled(
+            format!("    {}", place),
+            ratatui::style::Style::default().fg(ratatui::style::Color::Gray),
+        );
+
+        let mut style = ratatui::style::Style::default().add_modifier(self.age_modifier(message_index, chrono::Local::now()));
+        if let Some(age) = self.flash_age(message_index) {
+            if (age.as_millis() / FLASH_PHASE.as_millis()).is_multiple_of(2) {
+                style = style.add_modifier(ratatui::style::Modifier::REVERSED);
             }
+        }
+        ListItem::new(Text::from(vec![Line::from(first), Line::from(second), Line::default()])).style(style)
+    }
+
+    /// The row text after the mark and note markers, see `row_cache`.
+    fn row_spans(&self, message_index: usize) -> Vec<Span<'static>> {
+        let msg = &self.state.messages()[message_index];
+        let priority_color = match msg.priority.as_deref() {
+            Some(p) if p.starts_with('A') => ratatui::style::Color::Red,
+            Some(p) if p.starts_with('P') => ratatui::style::Color::Yellow,
+            _ => ratatui::style::Color::White,
+        };
+
+        let mut spans = Vec::new();
+        if self.icons != IconStyle::None {
+            spans.push(Span::styled(
+                self.icons.urgency(msg.priority.as_deref()),
+                ratatui::style::Style::default().fg(priority_color),
+            ));
+            spans.push(Span::raw(self.icons.service(self.services[message_index])));
+            spans.push(Span::raw(" "));
+        }
+        if let Some(priority) = &msg.priority {
+            spans.push(Span::styled(
+                format!("{:>3}", priority),
+                ratatui::style::Style::default().fg(priority_color),
+            ));
+            spans.push(Span::raw(" | "));
+        }
+        spans.push(Span::raw(msg.timestamp.format("%H:%M:%S").to_string()));
+        spans.push(Span::raw(" | "));
+        if self.region_column {
+            spans.push(Span::raw(format!(
+                "{:<width$.width$}",
+                self.state.region(message_index).unwrap_or("-"),
+                width = REGION_WIDTH
+            )));
+            spans.push(Span::raw(" | "));
+        }
+        if let Some(gap) = self.gaps.get(&message_index) {
+            spans.push(Span::styled(
+                format!("[gap {}] ", gap::format_duration(*gap)),
+                ratatui::style::Style::default().fg(ratatui::style::Color::Yellow),
+            ));
+        }
+        if let Some(growth) = self.growth[message_index].filter(|g| g.messages > 1) {
+            let (arrow, color) = match (growth.escalated, supports_unicode()) {
+                (true, true) => ("▲ ", ratatui::style::Color::Red),
+                (true, false) => ("^ ", ratatui::style::Color::Red),
+                (false, _) => ("", ratatui::style::Color::DarkGray),
+            };
+            spans.push(Span::styled(
+                format!("[{}{}] ", arrow, incident_size(&growth)),
+                ratatui::style::Style::default().fg(color),
+            ));
+        }
+        spans.push(Span::raw(self.shown_text(&msg.content)));
+        spans
+    }
+
+    /// Sizes the incident of every message from scratch, as after loading the
+    /// data files the places and services can differ.
+    fn size_incidents(&mut self) {
+        self.incident_sizes = IncidentSizes::default();
+        self.growth = (0..self.state.messages().len())
+            .map(|i| {
+                let msg = &self.state.messages()[i];
+                let incident = Incident::of(&msg.content, self.state.place(i))?;
+                let infos: Vec<_> = msg.capcodes.iter().filter_map(|code| self.lookup.resolve_capcode(code)).collect();
+                let services = infos.iter().map(|info| info.service.as_str());
+                Some(self.incident_sizes.add(&incident, msg.capcodes.iter().map(|code| &**code), services))
+            })
+            .collect();
+    }
+
+    /// `text` as displayed and exported: redacted when redaction is on.
+    fn shown_text(&self, text: &str) -> String {
+        if self.redact {
+            redact::redact_text(text)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// The marked (or selected) messages as displayed and exported.
+    fn shown_targets(&self) -> Vec<P2000Message> {
+        self.state
+            .action_targets()
+            .into_iter()
+            .map(|msg| if self.redact { redact::redact(msg) } else { msg.clone() })
+            .collect()
+    }
+
+    /// Latest receiver stderr and restart lines, oldest first.
+    fn draw_process_log(&self, f: &mut ratatui::Frame, area: ratatui::layout::Rect, rows: usize) {
+        let Some(log) = &self.process_log else {
+            return;
+        };
+        let lines: Vec<ListItem> = log.tail(rows).into_iter().map(ListItem::new).collect();
+        let title = if self.log_expanded {
+            format!("Receiver log ({} lines, L: collapse)", log.len())
+        } else {
+            "Receiver log (L: expand)".to_string()
+        };
+        let list = List::new(lines)
+            .block(Block::default().borders(Borders::ALL).title(title))
+            .style(ratatui::style::Style::default().fg(ratatui::style::Color::DarkGray));
+        f.render_widget(list, area);
+    }
 
-            // Combined match with next token (e.g., "P" + "1" => "P1")
-            if i + 1 < raw_tokens.len() {
-                let combined = format!("{}{}", token, raw_tokens[i + 1]);
-                if let Some(exp) = self.lookup.expand_abbreviation(&combined) {
-                    if seen.insert(combined.clone()) {
-                        expansions.push(format!("{}: {}", combined, exp));
+    fn handle_note_input(&mut self, code: KeyCode) {
+        let Some(note) = &mut self.note_input else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => note.push(c),
+            KeyCode::Backspace => {
+                note.pop();
+            }
+            KeyCode::Esc => self.note_input = None,
+            KeyCode::Enter => {
+                let note = self.note_input.take().unwrap_or_default();
+                let Some(i) = self.state.message_index(self.state.selected_index()) else {
+                    return;
+                };
+                self.state.set_note(i, Some(note));
+                if let Some(archive) = &self.archive {
+                    if let Err(e) = archive.set_note(&self.state.messages()[i], self.state.note(i)) {
+                        self.status = Some(format!("Saving note failed: {}", e));
                     }
                 }
             }
+            _ => {}
+        }
+    }
+
+    fn handle_place_correction(&mut self, code: KeyCode) {
+        let Some((_, right)) = &mut self.place_correction else {
+            return;
+        };
+        match code {
+            KeyCode::Char(c) => right.push(c),
+            KeyCode::Backspace => {
+                right.pop();
+            }
+            KeyCode::Esc => self.place_correction = None,
+            KeyCode::Enter => {
+                let (wrong, right) = self.place_correction.take().unwrap_or_default();
+                let (Some(feedback), Some(msg)) = (&mut self.feedback, self.state.selected_message()) else {
+                    return;
+                };
+                let right = Some(right.trim()).filter(|right| !right.is_empty());
+                self.status = Some(match feedback.record_place(&wrong, right, &msg.content) {
+                    Ok(()) => format!("Recorded {} as {}; applies on the next data load (D)", wrong, right.unwrap_or("no place")),
+                    Err(e) => format!("Saving correction failed: {}", e),
+                });
+            }
+            _ => {}
+        }
+    }
 
-            i += 1;
+    fn toggle_status(&mut self, status: IncidentStatus) {
+        let changed = self.state.toggle_target_status(status);
+        let Some(archive) = &self.archive else {
+            return;
+        };
+        for &i in &changed {
+            let msg = &self.state.messages()[i];
+            if let Err(e) = archive.set_status(msg, self.state.status(i)) {
+                self.status = Some(format!("Saving status failed: {}", e));
+                return;
+            }
         }
+    }
 
-        if expansions.is_empty() {
-            "-".to_string()
-        } else {
-            expansions.join("; ")
+    /// Pins the incident of the selected message: the messages naming the same
+    /// street in the same place, including those still to come.
+    fn pin_incident(&mut self) {
+        let Some(index) = self.state.message_index(self.state.selected_index()) else {
+            return;
+        };
+        let content = &self.state.messages()[index].content;
+        let Some(incident) = Incident::of(content, self.state.place(index)) else {
+            self.status = Some("No address in this message to pin".to_string());
+            return;
+        };
+        let messages = self
+            .state
+            .messages()
+            .iter()
+            .enumerate()
+            .filter(|(i, msg)| incident.includes(&msg.content, self.state.place(*i)))
+            .map(|(i, _)| i)
+            .collect();
+        self.status = Some(format!("Pinned {} (I to unpin)", incident.address));
+        self.pinned = Some((incident, messages));
+    }
+
+    /// The pinned incident's latest message and how long it has been going: until
+    /// now for live messages, until the latest one for messages read from a file.
+    fn draw_pinned(&self, f: &mut ratatui::Frame, area: ratatui::layout::Rect) {
+        let Some((incident, indices)) = &self.pinned else {
+            return;
+        };
+        let messages: Vec<&P2000Message> = indices.iter().map(|&i| &self.state.messages()[i]).collect();
+        let first = messages.iter().map(|msg| msg.time(TimeSource::Received)).min();
+        let latest = messages.iter().max_by_key(|msg| msg.time(TimeSource::Received));
+        let (Some(first), Some(latest)) = (first, latest) else {
+            return;
+        };
+        let now = if latest.received.is_some() { chrono::Local::now() } else { latest.time(TimeSource::Received) };
+        let size = indices.iter().rev().find_map(|&i| self.growth[i]).map(|growth| {
+            let arrow = match (growth.escalated, supports_unicode()) {
+                (true, true) => " ▲",
+                (true, false) => " ^",
+                (false, _) => "",
+            };
+            format!(" | {}{}", incident_size(&growth), arrow)
+        });
+        let line = format!(
+            "{} pages{} | running {} | {} {}",
+            indices.len(),
+            size.unwrap_or_default(),
+            gap::format_duration(now - first),
+            latest.timestamp.format("%H:%M:%S"),
+            latest.content
+        );
+        let title = format!("Pinned: {} (I to unpin)", incident.address);
+        let pane = Paragraph::new(line).block(Block::default().borders(Borders::ALL).title(title));
+        f.render_widget(pane, area);
+    }
+
+    /// Latest own pages, newest first; pending ones stand out until acknowledged.
+    fn draw_pager(&self, f: &mut ratatui::Frame, area: ratatui::layout::Rect) {
+        let Some(pager) = &self.pager else {
+            return;
+        };
+        let items: Vec<ListItem> = pager
+            .pages()
+            .iter()
+            .rev()
+            .take(PAGER_ROWS)
+            .map(|page| {
+                let msg = &self.state.messages()[page.message_index];
+                let (status, style) = match (page.is_pending(), page.acknowledged) {
+                    (true, _) => (
+                        "PENDING     ".to_string(),
+                        ratatui::style::Style::default()
+                            .fg(ratatui::style::Color::Red)
+                            .add_modifier(ratatui::style::Modifier::BOLD),
+                    ),
+                    (false, Some(at)) => (format!("ACK {}", at.format("%H:%M:%S")), ratatui::style::Style::default()),
+                    (false, None) => (" ".repeat(12), ratatui::style::Style::default()),
+                };
+                ListItem::new(format!(
+                    "{} | {} | {}",
+                    status,
+                    msg.timestamp.format("%H:%M:%S"),
+                    msg.content
+                ))
+                .style(style)
+            })
+            .collect();
+        let title = format!("My pager ({} pending, a: acknowledge)", pager.pending_count());
+        f.render_widget(List::new(items).block(Block::default().borders(Borders::ALL).title(title)), area);
+    }
+
+    /// Writes the marked (or selected) messages as feed lines to a new file in the working directory.
+    fn export_targets(&self) -> io::Result<(usize, std::path::PathBuf)> {
+        let targets = self.shown_targets();
+        let path = std::path::PathBuf::from(format!(
+            "p2000-selection-{}.txt",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let lines: String = targets.iter().map(|msg| msg.to_line() + "\n").collect();
+        std::fs::write(&path, lines)?;
+        Ok((targets.len(), path))
+    }
+
+    /// Writes the incident thread of the selected message as a Markdown timeline.
+    fn export_timeline(&self) -> io::Result<Option<(usize, std::path::PathBuf)>> {
+        let Some(&anchor) = self.state.filtered_indices().get(self.state.selected_index()) else {
+            return Ok(None);
+        };
+        let messages = self.state.messages();
+        let enrich = |msg: &P2000Message| enrich::enrich(msg, &self.lookup, &self.location_lookup);
+        let thread = timeline::thread(messages, anchor, |msg| timeline::thread_key(msg, &enrich(msg)));
+        let mut enrichments: Vec<enrich::Enrichment> = thread.iter().map(|&i| enrich(&messages[i])).collect();
+        let shown: Vec<P2000Message> = thread
+            .iter()
+            .map(|&i| if self.redact { redact::redact(&messages[i]) } else { messages[i].clone() })
+            .collect();
+        if self.redact {
+            enrichments.iter_mut().for_each(redact::redact_enrichment);
+        }
+        let entries: Vec<(&P2000Message, &enrich::Enrichment)> = shown.iter().zip(&enrichments).collect();
+        let path = std::path::PathBuf::from(format!(
+            "p2000-timeline-{}.md",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        std::fs::write(&path, timeline::to_markdown(&entries))?;
+        Ok(Some((thread.len(), path)))
+    }
+
+    /// Puts the marked (or selected) messages on the clipboard.
+    fn copy_targets(&self) -> io::Result<usize> {
+        let targets = self.shown_targets();
+        let text: Vec<String> = targets.iter().map(|msg| msg.to_string()).collect();
+        copy_to_clipboard(&text.join("\n"))?;
+        Ok(targets.len())
+    }
+
+    /// "Did you mean" for a search that shows nothing, worked out again only
+    /// when the search changes.
+    fn suggestion(&mut self) -> Option<String> {
+        let query = self.state.search_query();
+        if query.trim().is_empty() || self.state.filtered_len() > 0 {
+            return None;
+        }
+        if self.suggestion.as_ref().is_none_or(|(asked, _)| asked != query) {
+            let vocabulary =
+                self.vocabulary.get_or_insert_with(|| Vocabulary::new(&self.lookup, &self.location_lookup));
+            self.suggestion = Some((query.to_string(), vocabulary.suggest(query)));
+        }
+        self.suggestion.as_ref().and_then(|(_, suggestion)| suggestion.clone())
+    }
+
+    /// The capcodes as described in the capcode list. A group call shows as
+    /// its group address followed by the members it was sent to, and long
+    /// addresses are marked. With more than one capcode list each description
+    /// names the list it came from.
+    fn format_capcodes(&self, msg: &P2000Message) -> Option<String> {
+        let several_lists = self.lookup.capcode_sources().len() > 1;
+        let label = |info: &CapcodeInfo| {
+            let label = capcode_label(info);
+            if several_lists {
+                format!("{} [{}]", label, info.source)
+            } else {
+                label
+            }
+        };
+        let (groups, members): (Vec<&Arc<str>>, Vec<&Arc<str>>) =
+            msg.capcodes.iter().partition(|code| AddressKind::of(code) == AddressKind::Group);
+        let mut parts = Vec::new();
+        for code in &members {
+            let long = AddressKind::of(code) == AddressKind::Long;
+            let segment = match self.lookup.resolve_capcode(code) {
+                Some(info) => label(info),
+                None if long => code.to_string(),
+                None => continue,
+            };
+            parts.push(if long { format!("{} (long address)", segment) } else { segment });
+        }
+        if groups.is_empty() {
+            return (!parts.is_empty()).then(|| parts.join(", "));
+        }
+        let groups: Vec<String> = groups
+            .iter()
+            .map(|code| {
+                self.lookup.resolve_capcode(code).map_or_else(|| normalize_code(code), |info| label(info))
+            })
+            .collect();
+        if parts.is_empty() {
+            parts = members.iter().map(|code| code.to_string()).collect();
+        }
+        Some(format!("Group call {} to {}", groups.join(", "), parts.join(", ")))
+    }
+
+    /// Expansions colored by their category in the abbreviations file.
+    fn abbreviation_line(&self, msg: &P2000Message) -> Line<'static> {
+        let mut spans = vec![Span::raw("Abbreviations: ")];
+        let found = self.lookup.find_abbreviations(&msg.content);
+        if found.is_empty() {
+            spans.push(Span::raw("-"));
+        }
+        for (i, (abbreviation, entry)) in found.into_iter().enumerate() {
+            if i > 0 {
+                spans.push(Span::raw("; "));
+            }
+            let style = entry
+                .category
+                .as_deref()
+                .map_or(ratatui::style::Style::default(), |category| {
+                    ratatui::style::Style::default().fg(category_color(category))
+                });
+            spans.push(Span::styled(format!("{}: {}", abbreviation, entry.expansion), style));
         }
+        Line::from(spans)
     }
 }
 
-pub async fn run_tui(
-    messages: Vec<P2000Message>,
-    lookup: Lookup,
-    location_lookup: LocationLookup,
-) -> io::Result<()> {
-    // Setup terminal
-    enable_raw_mode()?;
-    let mut stdout = io::stdout();
-    execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
-    let backend = CrosstermBackend::new(stdout);
-    let mut terminal = Terminal::new(backend)?;
+/// The content with postcodes, unit callsigns and capcodes picked out.
+/// Description (or short name) and place of a capcode list entry.
+fn capcode_label(info: &CapcodeInfo) -> String {
+    let mut label = String::new();
+    if !info.description.is_empty() {
+        label.push_str(&info.description);
+    } else if !info.short.is_empty() {
+        label.push_str(&info.short);
+    }
+    if !info.place.is_empty() {
+        if !label.is_empty() {
+            label.push_str(" - ");
+        }
+        label.push_str(&info.place);
+    }
+    if label.is_empty() {
+        label.push_str(&info.code);
+    }
+    label
+}
+
+fn content_line(content: &str) -> Line<'static> {
+    let mut spans = vec![Span::raw("Content: ")];
+    let mut end = 0;
+    for token in tokenize(content) {
+        let color = match token.kind {
+            TokenKind::Postcode => ratatui::style::Color::Cyan,
+            TokenKind::Callsign => ratatui::style::Color::Green,
+            TokenKind::Capcode => ratatui::style::Color::LightCyan,
+            _ => continue,
+        };
+        spans.push(Span::raw(content[end..token.span.start].to_string()));
+        spans.push(Span::styled(token.text.to_string(), ratatui::style::Style::default().fg(color)));
+        end = token.span.end;
+    }
+    spans.push(Span::raw(content[end..].to_string()));
+    Line::from(spans)
+}
+
+/// The services' own colors for their categories; any other category is magenta.
+fn category_color(category: &str) -> ratatui::style::Color {
+    match category {
+        "medical" | "medisch" | "ambulance" => ratatui::style::Color::Yellow,
+        "fire" | "brand" | "brandweer" => ratatui::style::Color::Red,
+        "police" | "politie" => ratatui::style::Color::Blue,
+        _ => ratatui::style::Color::Magenta,
+    }
+}
 
-    let mut app = App::new(messages, lookup, location_lookup);
-    let result = event_loop(&mut terminal, &mut app).await;
+/// `6 capcodes, 2 services`
+fn incident_size(growth: &Growth) -> String {
+    let plural = |n: usize, what: &str| format!("{} {}{}", n, what, if n == 1 { "" } else { "s" });
+    format!("{}, {}", plural(growth.capcodes, "capcode"), plural(growth.services, "service"))
+}
+
+/// The legacy Windows console host can't draw arrows with its default font;
+/// Windows Terminal and other emulators set one of these variables.
+fn supports_unicode() -> bool {
+    !cfg!(windows)
+        || ["WT_SESSION", "TERM_PROGRAM", "ConEmuANSI"]
+            .iter()
+            .any(|var| std::env::var_os(var).is_some())
+}
+
+/// Raw mode and the alternate screen for as long as it lives. Dropping it,
+/// including while unwinding from a panic, always hands the terminal back.
+struct TerminalGuard;
+
+impl TerminalGuard {
+    fn new() -> io::Result<Self> {
+        enable_raw_mode()?;
+        let guard = TerminalGuard;
+        execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture)?;
+        Ok(guard)
+    }
+}
 
-    // Restore terminal
-    disable_raw_mode()?;
-    execute!(
-        terminal.backend_mut(),
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    // Best effort: errors here have nowhere better to go
+    let _ = disable_raw_mode();
+    let _ = execute!(
+        io::stdout(),
         LeaveAlternateScreen,
-        DisableMouseCapture
-    )?;
-    terminal.show_cursor()?;
+        DisableMouseCapture,
+        crossterm::cursor::Show
+    );
+}
+
+/// Restores the terminal before the panic message is printed (otherwise it is
+/// lost in the alternate screen) and saves the panic with a backtrace to a crash log.
+fn install_panic_hook() {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore_terminal();
+        let log = std::env::temp_dir().join(format!(
+            "p2000-crash-{}.log",
+            chrono::Local::now().format("%Y%m%d-%H%M%S")
+        ));
+        let report = format!("{}\n\n{}", info, std::backtrace::Backtrace::force_capture());
+        default_hook(info);
+        if std::fs::write(&log, report).is_ok() {
+            eprintln!("Crash log written to {}", log.display());
+        }
+    }));
+}
 
+/// Lines from a live source and the parser for its line format.
+pub type LiveFeed = (mpsc::Receiver<anyhow::Result<FeedLine>>, Parser);
+
+/// Runs the TUI until the user quits. With `live`, lines from the feed are
+/// parsed and added as they arrive.
+pub async fn run_tui(mut app: App, live: Option<LiveFeed>) -> io::Result<()> {
+    install_panic_hook();
+    let _guard = TerminalGuard::new()?;
+    let mut terminal = Terminal::new(CrosstermBackend::new(io::stdout()))?;
+
+    // SIGTERM (or SIGINT from outside the terminal) ends the loop so the guard can restore the terminal
+    let stop = Arc::new(AtomicBool::new(false));
+    let signal_stop = stop.clone();
+    let signals = tokio::spawn(async move {
+        crate::signal::shutdown().await;
+        signal_stop.store(true, Ordering::Relaxed);
+    });
+
+    let result = event_loop(&mut terminal, &mut app, &stop, live).await;
+    signals.abort();
     result
 }
 
 async fn event_loop<B: Backend>(
     terminal: &mut Terminal<B>,
     app: &mut App,
+    stop: &AtomicBool,
+    mut live: Option<LiveFeed>,
 ) -> io::Result<()> {
-    loop {
-        terminal.draw(|f| app.draw(f))?;
+    let mut last_draw: Option<Instant> = None;
+    while !stop.load(Ordering::Relaxed) {
+        if let Some((lines, parser)) = &mut live {
+            if drain_feed(lines, parser, app) {
+                app.check_gap();
+                app.roll_totals(chrono::Local::now());
+            } else {
+                app.status = Some("Feed ended".to_string());
+                app.mark_dirty();
+                live = None;
+            }
+        }
+        if last_draw.is_none_or(|at| app.needs_redraw(at)) {
+            terminal.draw(|f| app.draw(f))?;
+            last_draw = Some(Instant::now());
+        }
+
+        if crossterm::event::poll(app.poll_interval())? {
+            match event::read()? {
+                // Windows also reports key releases; acting on those would double every key
+                Event::Key(key) if key.kind == KeyEventKind::Press => {
+                    // Raw mode turns Ctrl-C into a key press instead of a signal
+                    let ctrl_c = key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL);
+                    if ctrl_c || app.handle_input(key.code) {
+                        return Ok(());
+                    }
+                }
+                Event::Resize(..) => app.mark_dirty(),
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Region and service of each message, from a full enrichment.
+/// Unit callsign -> indices of the messages naming it, ascending.
+type UnitIndex = HashMap<String, Vec<usize>>;
 
-        if crossterm::event::poll(std::time::Duration::from_millis(100))? {
-            if let Event::Key(key) = event::read()? {
-                if app.handle_input(key.code) {
-                    return Ok(());
+/// A name (region, place) for each message, in order.
+type MessageNames = Vec<Option<String>>;
+
+/// The region, matched place and service of each message, and the messages
+/// naming each unit.
+fn enrich_messages(
+    messages: &[P2000Message],
+    lookup: &Lookup,
+    location_lookup: &LocationLookup,
+) -> (MessageNames, MessageNames, Vec<Option<Service>>, UnitIndex) {
+    let mut regions = Vec::with_capacity(messages.len());
+    let mut places = Vec::with_capacity(messages.len());
+    let mut services = Vec::with_capacity(messages.len());
+    let mut unit_index = UnitIndex::new();
+    for (index, msg) in messages.iter().enumerate() {
+        let enrichment = enrich::enrich(msg, lookup, location_lookup);
+        services.push(Service::of(msg, &enrichment));
+        for unit in enrichment.units {
+            unit_index.entry(unit).or_default().push(index);
+        }
+        regions.push(enrichment.region);
+        places.push(enrichment.location.map(|found| found.info.place().to_string()));
+    }
+    (regions, places, services, unit_index)
+}
+
+/// Categories of the abbreviations found in a message, for its severity.
+fn categories(enrichment: &enrich::Enrichment) -> impl Iterator<Item = &str> {
+    enrichment.abbreviations.iter().filter_map(|e| e.category.as_deref())
+}
+
+fn ring_bell() {
+    use std::io::Write;
+    let mut stdout = io::stdout();
+    let _ = stdout.write_all(b"\x07").and_then(|_| stdout.flush());
+}
+
+/// Puts `text` on the clipboard through the terminal (OSC 52), which also
+/// works over SSH.
+fn copy_to_clipboard(text: &str) -> io::Result<()> {
+    use base64::Engine;
+    use std::io::Write;
+
+    let encoded = base64::engine::general_purpose::STANDARD.encode(text);
+    let mut stdout = io::stdout();
+    write!(stdout, "\x1b]52;c;{}\x07", encoded)?;
+    stdout.flush()
+}
+
+/// Adds every line waiting on the feed; false once the feed has ended.
+fn drain_feed(lines: &mut mpsc::Receiver<anyhow::Result<FeedLine>>, parser: &Parser, app: &mut App) -> bool {
+    loop {
+        match lines.try_recv() {
+            Ok(Ok(line)) => {
+                if let Ok(msg) = line.parse(parser) {
+                    app.push(msg);
                 }
             }
+            Ok(Err(e)) => app.status = Some(format!("Feed error: {}", e)),
+            Err(mpsc::error::TryRecvError::Empty) => return true,
+            Err(mpsc::error::TryRecvError::Disconnected) => return false,
         }
     }
 }
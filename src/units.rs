@@ -0,0 +1,186 @@
+//! Which units are probably out: every unit callsign in a message counts as a
+//! dispatch, and a unit is taken to be busy for a fixed time after its latest
+//! one. Purely a heuristic, as P2000 never says when a unit is free again.
+
+use chrono::{DateTime, Duration, Local};
+use serde::Deserialize;
+use std::collections::{BTreeMap, HashMap};
+
+use crate::tokenize::{self, TokenKind};
+
+/// Board heading for units in messages without a region
+const UNKNOWN_REGION: &str = "Unknown region";
+
+/// `[units]` section.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct UnitsConfig {
+    /// Minutes a unit is assumed busy after a dispatch
+    pub assumed_mins: u64,
+}
+
+impl Default for UnitsConfig {
+    fn default() -> Self {
+        UnitsConfig { assumed_mins: 45 }
+    }
+}
+
+/// A unit's latest dispatch.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ActiveUnit {
+    pub callsign: String,
+    pub since: DateTime<Local>,
+    /// When it is assumed free again
+    pub until: DateTime<Local>,
+    pub content: String,
+}
+
+/// Units still within their assumed duration at `now`, per region.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct UnitBoard {
+    pub regions: BTreeMap<String, Vec<ActiveUnit>>,
+}
+
+impl UnitBoard {
+    /// Builds the board from `messages`, given as time, content and region.
+    /// A unit dispatched again counts from its latest message.
+    pub fn new<'a>(
+        config: &UnitsConfig,
+        now: DateTime<Local>,
+        messages: impl IntoIterator<Item = (DateTime<Local>, &'a str, Option<&'a str>)>,
+    ) -> Self {
+        let assumed = Duration::minutes(config.assumed_mins as i64);
+        let mut latest: HashMap<String, (DateTime<Local>, &str, Option<&str>)> = HashMap::new();
+        for (time, content, region) in messages {
+            if time > now || now - time >= assumed {
+                continue;
+            }
+            for callsign in callsigns(content) {
+                if latest.get(&callsign).is_none_or(|(seen, _, _)| time >= *seen) {
+                    latest.insert(callsign, (time, content, region));
+                }
+            }
+        }
+        let mut board = UnitBoard::default();
+        for (callsign, (since, content, region)) in latest {
+            board.regions.entry(region.unwrap_or(UNKNOWN_REGION).to_string()).or_default().push(ActiveUnit {
+                callsign,
+                since,
+                until: since + assumed,
+                content: content.to_string(),
+            });
+        }
+        for units in board.regions.values_mut() {
+            units.sort_by(|a, b| b.since.cmp(&a.since).then_with(|| a.callsign.cmp(&b.callsign)));
+        }
+        board
+    }
+
+    pub fn len(&self) -> usize {
+        self.regions.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.regions.is_empty()
+    }
+
+    /// A heading per region, busiest first, with its units, latest dispatch first.
+    pub fn lines(&self) -> Vec<String> {
+        let mut regions: Vec<_> = self.regions.iter().collect();
+        regions.sort_by_key(|(_, units)| std::cmp::Reverse(units.len()));
+        let mut lines = Vec::new();
+        for (region, units) in regions {
+            if !lines.is_empty() {
+                lines.push(String::new());
+            }
+            lines.push(format!("{} ({} out)", region, units.len()));
+            for unit in units {
+                lines.push(format!(
+                    "  {:<8} since {}  free ~{}  {}",
+                    unit.callsign,
+                    unit.since.format("%H:%M"),
+                    unit.until.format("%H:%M"),
+                    unit.content
+                ));
+            }
+        }
+        lines
+    }
+}
+
+/// Unit callsigns in `content`: written out (`17-128`), or the way units are
+/// usually paged, as the region number followed by the vehicle number:
+/// `17128` for an ambulance and `234431` for a fire engine, normalized to
+/// `17-128` and `23-4431`. Numbers after `Rit` or `bon` are ride numbers.
+pub fn callsigns(content: &str) -> Vec<String> {
+    let tokens = tokenize::tokenize(content);
+    let mut found: Vec<String> = Vec::new();
+    let mut previous_word = "";
+    for token in &tokens {
+        let callsign = match token.kind {
+            TokenKind::Callsign => Some(token.text.to_string()),
+            TokenKind::Number if !["rit", "bon"].iter().any(|w| previous_word.eq_ignore_ascii_case(w)) => {
+                paged_callsign(token.text)
+            }
+            _ => None,
+        };
+        if !token.is_punctuation() {
+            previous_word = token.text;
+        }
+        let Some(callsign) = callsign else {
+            continue;
+        };
+        if !found.contains(&callsign) {
+            found.push(callsign);
+        }
+    }
+    found
+}
+
+/// `17128` as `17-128` and `234431` as `23-4431`, for the 25 safety regions.
+fn paged_callsign(number: &str) -> Option<String> {
+    if !matches!(number.len(), 5 | 6) || !number.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let region: u8 = number[..2].parse().ok()?;
+    (1..=25).contains(&region).then(|| format!("{}-{}", &number[..2], &number[2..]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_board_keeps_units_within_assumed_duration() {
+        assert_eq!(callsigns("A1 AMBU 17128 Nassaulaan 17-128 07-4431"), ["17-128", "07-4431"]);
+        assert_eq!(callsigns("P 1 BLB-02 BR woning Hertogstraat Weert 234431"), ["23-4431"]);
+        assert_eq!(callsigns("A1 Ambu 06165 - Zutphen Rit 10681 bon 17124 99123"), ["06-165"]);
+
+        let at = |text: &str| crate::query::parse_time(text).unwrap();
+        let messages = [
+            (at("2026-01-08 13:00"), "A2 AMBU 17-101 Vlaardingen", Some("Rotterdam-Rijnmond")),
+            (at("2026-01-08 14:00"), "A1 AMBU 17-128 Vlaardingen", Some("Rotterdam-Rijnmond")),
+            (at("2026-01-08 14:20"), "A1 AMBU 17128 Schiedam", Some("Rotterdam-Rijnmond")),
+            (at("2026-01-08 14:30"), "P 1 TS 15-4431 Den Haag", Some("Haaglanden")),
+            (at("2026-01-08 14:40"), "B1 AMBU 25-110", None),
+        ];
+        let config = UnitsConfig::default();
+        let board = UnitBoard::new(&config, at("2026-01-08 15:00"), messages);
+        // 17-101 has been out longer than 45 minutes
+        assert_eq!(board.len(), 3);
+        assert_eq!(
+            board.lines(),
+            [
+                "Haaglanden (1 out)",
+                "  15-4431  since 14:30  free ~15:15  P 1 TS 15-4431 Den Haag",
+                "",
+                "Rotterdam-Rijnmond (1 out)",
+                "  17-128   since 14:20  free ~15:05  A1 AMBU 17128 Schiedam",
+                "",
+                "Unknown region (1 out)",
+                "  25-110   since 14:40  free ~15:25  B1 AMBU 25-110",
+            ]
+        );
+        assert!(UnitBoard::new(&config, at("2026-01-08 16:00"), messages).is_empty());
+    }
+}
@@ -0,0 +1,293 @@
+//! Shareable snapshots of what a list shows: the filter, the sort order and the
+//! display toggles as one line, so a colleague can reproduce the same view on
+//! their own instance.
+//!
+//! A snapshot is a URL query string behind a `p2000:view?` prefix, e.g.
+//! `p2000:view?q=brand&region=Rotterdam-Rijnmond&sort=region&redact=1`. Settings
+//! at their default are left out; parsing accepts the bare query string or a
+//! whole URL too, so the same string works after a web dashboard address.
+
+use anyhow::{bail, Result};
+use serde::Deserialize;
+use std::fmt;
+
+use crate::state::{FilterState, SearchScope, SortOrder};
+
+pub const PREFIX: &str = "p2000:view?";
+
+/// `[[filters]]`: a named search and region that the quick-filter key
+/// cycles through, after showing everything.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FilterPreset {
+    pub name: String,
+    pub search: String,
+    pub region: Option<String>,
+}
+
+impl FilterPreset {
+    /// `filter` with this preset's search and region, or with neither for
+    /// `None`; the rest of the filter is kept.
+    pub fn apply(preset: Option<&FilterPreset>, filter: &FilterState) -> FilterState {
+        FilterState {
+            search_query: preset.map_or_else(String::new, |preset| preset.search.clone()),
+            region: preset.and_then(|preset| preset.region.clone()),
+            ..filter.clone()
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct View {
+    pub filter: FilterState,
+    pub redact: bool,
+    pub region_column: bool,
+}
+
+impl Default for View {
+    fn default() -> Self {
+        View {
+            filter: FilterState::default(),
+            redact: false,
+            region_column: true,
+        }
+    }
+}
+
+impl View {
+    pub fn parse(link: &str) -> Result<Self> {
+        let query = link.trim();
+        let query = query.split_once('?').map_or(query, |(_, query)| query);
+        let mut view = View::default();
+        for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(value)?;
+            match key {
+                "q" => view.filter.search_query = value,
+                "scope" => {
+                    view.filter.scope =
+                        SearchScope::parse(&value).ok_or_else(|| anyhow::anyhow!("unknown search scope '{}'", value))?
+                }
+                "region" => view.filter.region = Some(value),
+                "place" => view.filter.place = Some(value),
+                "hide_minor" => view.filter.hide_minor_pages = parse_flag(key, &value)?,
+                "sort" => view.filter.sort = parse_sort(&value)?,
+                "mute" => {
+                    view.filter.muted_capcodes = value.split(',').filter(|c| !c.is_empty()).map(str::to_string).collect()
+                }
+                "redact" => view.redact = parse_flag(key, &value)?,
+                "region_column" => view.region_column = parse_flag(key, &value)?,
+                _ => bail!("unknown view setting '{}'", key),
+            }
+        }
+        Ok(view)
+    }
+
+    /// What changes when going from `self` to `other`, one line per setting.
+    pub fn diff(&self, other: &View) -> Vec<String> {
+        let mut changes = Vec::new();
+        let (a, b) = (&self.filter, &other.filter);
+        if a.search_query != b.search_query {
+            changes.push(format!("search \"{}\" → \"{}\"", a.search_query, b.search_query));
+        }
+        if a.scope != b.scope {
+            changes.push(format!("search in {} → {}", a.scope.name(), b.scope.name()));
+        }
+        if a.region != b.region {
+            changes.push(format!(
+                "region {} → {}",
+                a.region.as_deref().unwrap_or("all"),
+                b.region.as_deref().unwrap_or("all")
+            ));
+        }
+        if a.place != b.place {
+            changes.push(format!(
+                "following {} → {}",
+                a.place.as_deref().unwrap_or("no place"),
+                b.place.as_deref().unwrap_or("no place")
+            ));
+        }
+        if a.hide_minor_pages != b.hide_minor_pages {
+            changes.push(format!("hiding minor pages {}", on_off(b.hide_minor_pages)));
+        }
+        if a.sort != b.sort {
+            changes.push(format!("sort {} → {}", sort_name(a.sort), sort_name(b.sort)));
+        }
+        let muted: Vec<String> = b.muted_capcodes.difference(&a.muted_capcodes).map(|c| format!("+{}", c)).collect();
+        let unmuted: Vec<String> = a.muted_capcodes.difference(&b.muted_capcodes).map(|c| format!("-{}", c)).collect();
+        if !muted.is_empty() || !unmuted.is_empty() {
+            changes.push(format!("muted {}", [muted, unmuted].concat().join(" ")));
+        }
+        if self.redact != other.redact {
+            changes.push(format!("redaction {}", on_off(other.redact)));
+        }
+        if self.region_column != other.region_column {
+            changes.push(format!("region column {}", on_off(other.region_column)));
+        }
+        changes
+    }
+}
+
+impl fmt::Display for View {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let defaults = View::default();
+        let mut pairs = Vec::new();
+        if !self.filter.search_query.is_empty() {
+            pairs.push(format!("q={}", encode(&self.filter.search_query)));
+        }
+        if self.filter.scope != defaults.filter.scope {
+            pairs.push(format!("scope={}", self.filter.scope.name()));
+        }
+        if let Some(region) = &self.filter.region {
+            pairs.push(format!("region={}", encode(region)));
+        }
+        if let Some(place) = &self.filter.place {
+            pairs.push(format!("place={}", encode(place)));
+        }
+        if self.filter.hide_minor_pages {
+            pairs.push("hide_minor=1".to_string());
+        }
+        if self.filter.sort != defaults.filter.sort {
+            pairs.push(format!("sort={}", sort_name(self.filter.sort)));
+        }
+        if !self.filter.muted_capcodes.is_empty() {
+            let muted: Vec<&str> = self.filter.muted_capcodes.iter().map(String::as_str).collect();
+            pairs.push(format!("mute={}", muted.join(",")));
+        }
+        if self.redact != defaults.redact {
+            pairs.push(format!("redact={}", u8::from(self.redact)));
+        }
+        if self.region_column != defaults.region_column {
+            pairs.push(format!("region_column={}", u8::from(self.region_column)));
+        }
+        write!(f, "{}{}", PREFIX, pairs.join("&"))
+    }
+}
+
+fn on_off(flag: bool) -> &'static str {
+    if flag {
+        "on"
+    } else {
+        "off"
+    }
+}
+
+fn sort_name(sort: SortOrder) -> &'static str {
+    match sort {
+        SortOrder::Arrival => "arrival",
+        SortOrder::Region => "region",
+        SortOrder::Time => "time",
+        SortOrder::Severity => "severity",
+    }
+}
+
+fn parse_sort(value: &str) -> Result<SortOrder> {
+    Ok(match value {
+        "arrival" => SortOrder::Arrival,
+        "region" => SortOrder::Region,
+        "time" => SortOrder::Time,
+        "severity" => SortOrder::Severity,
+        _ => bail!("unknown sort order '{}'", value),
+    })
+}
+
+fn parse_flag(key: &str, value: &str) -> Result<bool> {
+    match value {
+        "1" | "true" => Ok(true),
+        "0" | "false" => Ok(false),
+        _ => bail!("{} must be 0 or 1, not '{}'", key, value),
+    }
+}
+
+/// Percent-encodes everything but unreserved URL characters.
+fn encode(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    for byte in text.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~".contains(&byte) {
+            out.push(byte as char);
+        } else {
+            out.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    out
+}
+
+fn decode(text: &str) -> Result<String> {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let hex = text.get(i + 1..i + 3).and_then(|hex| u8::from_str_radix(hex, 16).ok());
+                let Some(byte) = hex else {
+                    bail!("bad escape in '{}'", text);
+                };
+                out.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                out.push(b' ');
+                i += 1;
+            }
+            byte => {
+                out.push(byte);
+                i += 1;
+            }
+        }
+    }
+    Ok(String::from_utf8(out)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_view_link_round_trip_and_diff() {
+        let view = View {
+            filter: FilterState {
+                search_query: "brand & co".to_string(),
+                muted_capcodes: ["1420059", "726119"].map(String::from).into(),
+                region: Some("Rotterdam-Rijnmond".to_string()),
+                place: None,
+                hide_minor_pages: false,
+                scope: SearchScope::Descriptions,
+                sort: SortOrder::Region,
+            },
+            redact: true,
+            region_column: true,
+        };
+        let link = view.to_string();
+        assert_eq!(
+            link,
+            "p2000:view?q=brand%20%26%20co&scope=descriptions&region=Rotterdam-Rijnmond&sort=region&mute=1420059,726119\
+             &redact=1"
+        );
+        assert_eq!(View::parse(&link).unwrap(), view);
+        // After a dashboard address, and with form-style spaces
+        assert_eq!(
+            View::parse(
+                "http://pi:8080/?q=brand+%26+co&scope=descriptions&region=Rotterdam-Rijnmond&sort=region\
+                 &mute=1420059,726119&redact=1"
+            )
+            .unwrap(),
+            view
+        );
+        assert_eq!(View::default().to_string(), PREFIX);
+        assert!(View::parse("p2000:view?sort=random").is_err());
+
+        assert_eq!(
+            View::default().diff(&view),
+            vec![
+                "search \"\" → \"brand & co\"",
+                "search in all → descriptions",
+                "region all → Rotterdam-Rijnmond",
+                "sort arrival → region",
+                "muted +1420059 +726119",
+                "redaction on",
+            ]
+        );
+        assert!(view.diff(&view).is_empty());
+    }
+}
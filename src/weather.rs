@@ -0,0 +1,204 @@
+//! Current weather at the matched place: wind for fires, temperature for
+//! heat-related spikes. Taken from KNMI's HARMONIE model as served by
+//! Open-Meteo, which needs no API key. Needs the `weather` feature; answers
+//! are cached per area for a while, so a busy feed makes few requests.
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::sinks::HttpClient;
+
+/// Upper bounds in km/h of Beaufort 0 to 11; anything faster is 12
+const BEAUFORT_KMH: [f64; 12] = [1.0, 6.0, 12.0, 20.0, 29.0, 39.0, 50.0, 62.0, 75.0, 89.0, 103.0, 118.0];
+
+/// `[weather]` section; its presence enables the `weather` enricher.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default)]
+pub struct WeatherConfig {
+    /// `{lat}` and `{lon}` are filled in; the response is read as Open-Meteo's
+    pub url: String,
+    /// Minutes an answer is reused for places in the same area
+    pub cache_mins: u64,
+    /// Older messages get no weather, as it would be today's and not theirs
+    pub max_age_mins: u64,
+}
+
+impl Default for WeatherConfig {
+    fn default() -> Self {
+        WeatherConfig {
+            url: "https://api.open-meteo.com/v1/forecast?latitude={lat}&longitude={lon}\
+                  &current=temperature_2m,wind_speed_10m,wind_direction_10m,wind_gusts_10m,precipitation\
+                  &models=knmi_seamless"
+                .to_string(),
+            cache_mins: 30,
+            max_age_mins: 60,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+pub struct Weather {
+    pub temperature_c: f64,
+    pub wind_kmh: f64,
+    pub gusts_kmh: f64,
+    /// Degrees the wind comes from, 0 for north
+    pub wind_direction: f64,
+    /// In the last hour
+    pub precipitation_mm: f64,
+}
+
+impl Weather {
+    /// Reads the `current` block of an Open-Meteo response.
+    pub fn parse(json: &str) -> Result<Self> {
+        #[derive(Deserialize)]
+        struct Response {
+            current: Current,
+        }
+        #[derive(Deserialize)]
+        struct Current {
+            temperature_2m: f64,
+            wind_speed_10m: f64,
+            wind_direction_10m: f64,
+            wind_gusts_10m: f64,
+            #[serde(default)]
+            precipitation: f64,
+        }
+        let current = serde_json::from_str::<Response>(json)?.current;
+        Ok(Weather {
+            temperature_c: current.temperature_2m,
+            wind_kmh: current.wind_speed_10m,
+            gusts_kmh: current.wind_gusts_10m,
+            wind_direction: current.wind_direction_10m,
+            precipitation_mm: current.precipitation,
+        })
+    }
+
+    pub fn beaufort(&self) -> usize {
+        BEAUFORT_KMH.iter().take_while(|&&bound| self.wind_kmh >= bound).count()
+    }
+
+    /// Where the wind comes from, as one of eight compass points.
+    pub fn wind_from(&self) -> &'static str {
+        const POINTS: [&str; 8] = ["N", "NE", "E", "SE", "S", "SW", "W", "NW"];
+        POINTS[((self.wind_direction.rem_euclid(360.0) + 22.5) / 45.0) as usize % 8]
+    }
+}
+
+/// `14.2 °C, wind SW 4 Bft (22 km/h, gusts 41), 0.4 mm rain`
+impl fmt::Display for Weather {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.1} °C, wind {} {} Bft ({:.0} km/h, gusts {:.0})",
+            self.temperature_c,
+            self.wind_from(),
+            self.beaufort(),
+            self.wind_kmh,
+            self.gusts_kmh
+        )?;
+        if self.precipitation_mm > 0.0 {
+            write!(f, ", {:.1} mm rain", self.precipitation_mm)?;
+        }
+        Ok(())
+    }
+}
+
+/// Latitude and longitude in tenths of a degree
+type Area = (i32, i32);
+
+// Places within 0.1 degree, about 7 by 11 km, share an answer
+fn area(lat: f64, lon: f64) -> Area {
+    ((lat * 10.0).round() as i32, (lon * 10.0).round() as i32)
+}
+
+/// Fetches and caches the weather per area.
+pub struct WeatherClient {
+    config: WeatherConfig,
+    http: HttpClient,
+    // When each area was asked and the answer; `None` while a background
+    // fetch runs, or when it failed
+    cache: Mutex<HashMap<Area, (Instant, Option<Weather>)>>,
+}
+
+impl WeatherClient {
+    /// Fails when the build has no `weather` feature.
+    pub fn new(config: &WeatherConfig) -> Result<Self> {
+        if !cfg!(feature = "weather") {
+            bail!("This build has no weather lookups; rebuild with --features weather");
+        }
+        Ok(WeatherClient {
+            config: config.clone(),
+            http: HttpClient::new()?,
+            cache: Mutex::new(HashMap::new()),
+        })
+    }
+
+    /// Whether a message from `time` is recent enough for the current weather.
+    pub fn applies_to(&self, time: DateTime<Local>) -> bool {
+        (Local::now() - time).num_minutes() <= self.config.max_age_mins as i64
+    }
+
+    /// The cached answer for the area of `lat`, `lon`: `None` when it is not
+    /// known (yet), `Some(None)` when it was asked but failed or is pending.
+    pub fn cached(&self, lat: f64, lon: f64) -> Option<Option<Weather>> {
+        let cache = self.cache.lock().expect("weather cache poisoned");
+        let ttl = Duration::from_secs(self.config.cache_mins * 60);
+        cache.get(&area(lat, lon)).filter(|(asked, _)| asked.elapsed() < ttl).map(|(_, weather)| *weather)
+    }
+
+    /// The weather at `lat`, `lon`, asking the API when the cache has no
+    /// answer for its area. Blocks for the request.
+    pub fn current(&self, lat: f64, lon: f64) -> Result<Option<Weather>> {
+        if let Some(weather) = self.cached(lat, lon) {
+            return Ok(weather);
+        }
+        let fetched = self.fetch(lat, lon);
+        let weather = fetched.as_ref().ok().copied();
+        self.cache.lock().expect("weather cache poisoned").insert(area(lat, lon), (Instant::now(), weather));
+        fetched.map(Some)
+    }
+
+    /// Asks the API on a background thread unless the area is cached or
+    /// already being asked; `cached` has the answer once it arrives.
+    pub fn prefetch(self: &Arc<Self>, lat: f64, lon: f64) {
+        if self.cached(lat, lon).is_some() {
+            return;
+        }
+        self.cache.lock().expect("weather cache poisoned").insert(area(lat, lon), (Instant::now(), None));
+        let client = Arc::clone(self);
+        std::thread::spawn(move || {
+            let weather = client.fetch(lat, lon).ok();
+            client.cache.lock().expect("weather cache poisoned").insert(area(lat, lon), (Instant::now(), weather));
+        });
+    }
+
+    fn fetch(&self, lat: f64, lon: f64) -> Result<Weather> {
+        let url = self.config.url.replace("{lat}", &format!("{:.4}", lat)).replace("{lon}", &format!("{:.4}", lon));
+        Weather::parse(&self.http.get(&url)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_and_describe() {
+        let json = r#"{"latitude":51.9,"current":{"time":"2026-10-17T14:00","interval":900,
+            "temperature_2m":14.2,"wind_speed_10m":22.3,"wind_direction_10m":228,"wind_gusts_10m":41.0,"precipitation":0.4}}"#;
+        let weather = Weather::parse(json).unwrap();
+        assert_eq!((weather.beaufort(), weather.wind_from()), (4, "SW"));
+        assert_eq!(weather.to_string(), "14.2 °C, wind SW 4 Bft (22 km/h, gusts 41), 0.4 mm rain");
+
+        let calm = Weather { wind_kmh: 0.5, wind_direction: 350.0, precipitation_mm: 0.0, ..weather };
+        assert_eq!(calm.to_string(), "14.2 °C, wind N 0 Bft (0 km/h, gusts 41)");
+        assert_eq!(Weather { wind_kmh: 130.0, ..weather }.beaufort(), 12);
+        assert!(Weather::parse(r#"{"error":true,"reason":"Latitude must be in range"}"#).is_err());
+        assert_eq!(area(51.9123, 4.3456), area(51.8951, 4.3499));
+    }
+}
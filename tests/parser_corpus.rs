@@ -0,0 +1,50 @@
+//! Regression corpus for the parser; run with `cargo test --features corpus`.
+#![cfg(feature = "corpus")]
+
+use p2000_rust::parser::Parser;
+
+fn corpus(name: &str) -> Vec<String> {
+    let path = format!("{}/tests/corpus/{}", env!("CARGO_MANIFEST_DIR"), name);
+    std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", path, e))
+        .lines()
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(str::to_string)
+        .collect()
+}
+
+#[test]
+fn valid_lines_round_trip() {
+    let parser = Parser::new();
+    for entry in corpus("valid.txt") {
+        let (line, written) = entry.split_once('\t').unwrap_or((&entry, &entry));
+        let msg = parser
+            .parse_line(line)
+            .unwrap_or_else(|e| panic!("{}\n  {}", e, line));
+        assert_eq!(msg.to_line(), written);
+    }
+}
+
+#[test]
+fn invalid_lines_are_rejected() {
+    let parser = Parser::new();
+    for line in corpus("invalid.txt") {
+        assert!(parser.parse_line(&line).is_err(), "Accepted: {}", line);
+    }
+}
+
+#[test]
+fn key_fields() {
+    let parser = Parser::new();
+    let optional = |value: &str| (value != "-").then(|| value.to_string());
+    for entry in corpus("fields.tsv") {
+        let fields: Vec<&str> = entry.split('\t').collect();
+        let [line, priority, incident_code, capcodes] = fields[..] else {
+            panic!("Expected 4 tab-separated fields: {}", entry);
+        };
+        let msg = parser.parse_line(line).unwrap();
+        assert_eq!(msg.priority, optional(priority), "{}", line);
+        assert_eq!(msg.incident_code, optional(incident_code), "{}", line);
+        assert_eq!(msg.capcodes.len(), capcodes.parse::<usize>().unwrap(), "{}", line);
+    }
+}
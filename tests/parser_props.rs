@@ -0,0 +1,52 @@
+use p2000_rust::parser::Parser;
+use proptest::prelude::*;
+
+fn field() -> impl Strategy<Value = String> {
+    "[A-Za-z0-9./ ]{0,12}"
+}
+
+fn timestamp() -> impl Strategy<Value = String> {
+    (2000u32..2100, 1u32..=12, 1u32..=28, 0u32..24, 0u32..60, 0u32..60).prop_map(
+        |(y, mo, d, h, mi, s)| format!("{:04}-{:02}-{:02} {:02}:{:02}:{:02}", y, mo, d, h, mi, s),
+    )
+}
+
+fn capcodes() -> impl Strategy<Value = Vec<String>> {
+    prop::collection::vec("[0-9]{9}", 0..6)
+}
+
+proptest! {
+    #[test]
+    fn never_panics(line in "\\PC*") {
+        let _ = Parser::new().parse_line(&line);
+    }
+
+    #[test]
+    fn never_panics_on_pipes(parts in prop::collection::vec("[^|\n]{0,8}", 0..10)) {
+        let _ = Parser::new().parse_line(&parts.join("|"));
+    }
+
+    #[test]
+    fn round_trips_key_fields(
+        protocol in "[A-Z]{3,5}",
+        timestamp in timestamp(),
+        radio_address in field().prop_filter("no pipes", |f| !f.contains('|')),
+        frequency in "[0-9]{2}\\.[0-9]{3}",
+        capcodes in capcodes(),
+        message_type in "[A-Z]{3}",
+        content in "[^\n]{0,80}",
+    ) {
+        let line = format!(
+            "{}|{}|{}|{}|{}|{}|{}",
+            protocol, timestamp, radio_address, frequency, capcodes.join(" "), message_type, content
+        );
+        let msg = Parser::new().parse_line(&line).unwrap();
+        prop_assert_eq!(&*msg.protocol, protocol.as_str());
+        prop_assert_eq!(&*msg.radio_address, radio_address.as_str());
+        prop_assert_eq!(&*msg.frequency, frequency.as_str());
+        prop_assert_eq!(msg.message_type.as_str(), message_type.as_str());
+        prop_assert_eq!(&msg.content, &content);
+        prop_assert_eq!(msg.capcodes.len(), capcodes.len());
+        prop_assert_eq!(msg.to_line(), line);
+    }
+}
@@ -0,0 +1,449 @@
+//! Golden-file tests for TUI rendering. Regenerate with `UPDATE_GOLDEN=1 cargo test --test tui_golden`.
+
+use ratatui::backend::TestBackend;
+use ratatui::crossterm::event::KeyCode;
+use ratatui::Terminal;
+use std::path::{Path, PathBuf};
+
+use p2000_rust::data::DataFiles;
+use p2000_rust::gap::GapConfig;
+use p2000_rust::icons::IconStyle;
+use p2000_rust::location::LocationLookup;
+use p2000_rust::lookup::Lookup;
+use p2000_rust::pager::{Pager, PagerConfig};
+use p2000_rust::parser::Parser;
+use p2000_rust::sdr::ProcessLog;
+use p2000_rust::tui::App;
+use p2000_rust::view::{FilterPreset, View};
+
+const WIDTH: u16 = 100;
+const HEIGHT: u16 = 24;
+
+fn app() -> App {
+    // Timestamps render in local time; pin the zone so snapshots match everywhere
+    std::env::set_var("TZ", "UTC");
+
+    let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let parser = Parser::new();
+    let messages = std::fs::read_to_string(root.join("data/p2000-1.txt"))
+        .unwrap()
+        .lines()
+        .take(40)
+        .filter_map(|line| parser.parse_line(line).ok())
+        .collect();
+    let lookup = Lookup::load(
+        &root.join("data/capcodelist.csv"),
+        &root.join("data/abbrevations.txt"),
+    )
+    .unwrap();
+    let mut location_lookup = LocationLookup::load(
+        &root.join("data/Observations.csv"),
+        &root.join("data/RegioSCodes.csv"),
+    )
+    .unwrap();
+    location_lookup.link_regions(&lookup);
+    App::new(messages, lookup, location_lookup)
+}
+
+fn render(app: &mut App) -> String {
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).unwrap();
+    terminal.draw(|f| app.draw(f)).unwrap();
+    let buffer = terminal.backend().buffer();
+    let mut text = String::new();
+    for y in 0..buffer.area.height {
+        let mut row = String::new();
+        for x in 0..buffer.area.width {
+            row.push_str(buffer[(x, y)].symbol());
+        }
+        text.push_str(row.trim_end());
+        text.push('\n');
+    }
+    text
+}
+
+fn assert_golden(name: &str, actual: &str) {
+    let path: PathBuf = [env!("CARGO_MANIFEST_DIR"), "tests", "golden", &format!("{}.txt", name)]
+        .iter()
+        .collect();
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, actual).unwrap();
+        return;
+    }
+    let expected = std::fs::read_to_string(&path)
+        .unwrap_or_else(|e| panic!("Missing {} ({}); run with UPDATE_GOLDEN=1", path.display(), e));
+    assert!(
+        expected == actual,
+        "{} differs from the golden file\n--- expected\n{}--- actual\n{}",
+        name,
+        expected,
+        actual
+    );
+}
+
+fn press(app: &mut App, keys: &[KeyCode]) {
+    for key in keys {
+        app.handle_input(*key);
+    }
+}
+
+#[test]
+fn list() {
+    let mut app = app();
+    assert_golden("list", &render(&mut app));
+}
+
+#[test]
+fn detail_pane() {
+    let mut app = app();
+    render(&mut app);
+    press(&mut app, &[KeyCode::Down, KeyCode::Down, KeyCode::Down]);
+    assert_golden("detail_pane", &render(&mut app));
+}
+
+#[test]
+fn scrolled() {
+    let mut app = app();
+    render(&mut app);
+    press(&mut app, &[KeyCode::PageDown, KeyCode::PageDown, KeyCode::PageDown]);
+    assert_golden("scrolled", &render(&mut app));
+}
+
+#[test]
+fn search_mode() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('s')]);
+    press(&mut app, &"utrecht".chars().map(KeyCode::Char).collect::<Vec<_>>());
+    assert_golden("search_mode", &render(&mut app));
+}
+
+#[test]
+fn search_filter_applied() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('s')]);
+    press(&mut app, &"p 1".chars().map(KeyCode::Char).collect::<Vec<_>>());
+    press(&mut app, &[KeyCode::Enter, KeyCode::Down]);
+    assert_golden("search_filter_applied", &render(&mut app));
+}
+
+#[test]
+fn filter_presets() {
+    let preset = |name: &str, search: &str, region: Option<&str>| FilterPreset {
+        name: name.to_string(),
+        search: search.to_string(),
+        region: region.map(str::to_string),
+    };
+    let mut app = app().with_filter_presets(vec![
+        preset("My region", "", Some("Rotterdam-Rijnmond")),
+        preset("P1 only", "prio:p1", None),
+    ]);
+    press(&mut app, &[KeyCode::Char('f'), KeyCode::Char('f')]);
+    assert_golden("filter_presets", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('f')]);
+    assert_eq!(app.view().filter, Default::default());
+}
+
+#[test]
+fn follow_place() {
+    let mut app = app();
+    render(&mut app);
+    press(&mut app, &[KeyCode::Char('F')]);
+    let parser = Parser::new();
+    for line in [
+        "FLEX|2026-01-01 20:30:00|1600/2/K/A|00.068|002029572|ALN|A2 Nassaulaan 3135ZH Vlaardingen 630",
+        "FLEX|2026-01-01 20:30:05|1600/2/K/A|00.068|001420999|ALN|A2 Utrecht 630",
+    ] {
+        app.push(parser.parse_line(line).unwrap());
+    }
+    assert_golden("follow_place", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('F')]);
+    assert_eq!(app.view().filter.place, None);
+}
+
+#[test]
+fn pinned_incident() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('s')]);
+    press(&mut app, &"weert".chars().map(KeyCode::Char).collect::<Vec<_>>());
+    press(&mut app, &[KeyCode::Enter]);
+    render(&mut app);
+    // Pinned, then back to all messages
+    press(&mut app, &[KeyCode::Char('I'), KeyCode::Char('s'), KeyCode::Char('s')]);
+    let parser = Parser::new();
+    let line = "FLEX|2026-01-01 20:41:00|1600/2/K/A|00.068|000120999|ALN|P 1 BLB-03 GRIP 1 Hertogstraat Weert";
+    app.push(parser.parse_line(line).unwrap());
+    assert_golden("pinned_incident", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('I')]);
+    assert!(!render(&mut app).contains("Pinned"));
+}
+
+#[test]
+fn search_suggestion() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('s')]);
+    press(&mut app, &"tilbrug".chars().map(KeyCode::Char).collect::<Vec<_>>());
+    assert_golden("search_suggestion", &render(&mut app));
+}
+
+#[test]
+fn marks_and_bookmarks() {
+    let mut app = app();
+    render(&mut app);
+    press(&mut app, &[KeyCode::Char(' '), KeyCode::Down, KeyCode::Char('b'), KeyCode::Down]);
+    press(&mut app, &[KeyCode::Char('V'), KeyCode::Down, KeyCode::Down]);
+    assert_golden("marks_and_bookmarks", &render(&mut app));
+}
+
+#[test]
+fn region_filter_and_sort() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('o'), KeyCode::Char('g'), KeyCode::Char('g')]);
+    assert_golden("region_filter_and_sort", &render(&mut app));
+}
+
+#[test]
+fn service_icons() {
+    let mut app = app().with_icons(IconStyle::Emoji);
+    assert_golden("service_icons", &render(&mut app));
+}
+
+#[test]
+fn live_push_flashes_urgent() {
+    let mut app = app();
+    let parser = Parser::new();
+    let count = app.state.messages().len();
+    app.state.select(count - 1);
+    app.push(parser.parse_line("FLEX|2026-01-01 20:05:00|1600/2/K/A|00.070|001123117|ALN|A1 Duizel Rit: 470").unwrap());
+    app.push(parser.parse_line("FLEX|2026-01-01 20:05:01|1600/2/K/A|00.071|000726119|ALN|A2 Utrecht 671").unwrap());
+    assert!(app.is_flashing(count));
+    assert!(!app.is_flashing(count + 1));
+    // The selection was on the last message, so it follows the feed
+    assert_eq!(app.state.selected_message().unwrap().content, "A2 Utrecht 671");
+}
+
+#[test]
+fn pager_pane() {
+    let log = std::env::temp_dir().join(format!("p2000_pager_golden_{}.log", std::process::id()));
+    let config = PagerConfig {
+        capcodes: vec!["1420999".to_string()],
+        log: log.clone(),
+        ..Default::default()
+    };
+    let mut app = app().with_pager(Pager::new(&config));
+    let parser = Parser::new();
+    app.push(parser.parse_line("FLEX|2026-01-01 20:05:00|1600/2/K/A|00.070|001420999|ALN|A2 Schiedam 17130").unwrap());
+    app.push(parser.parse_line("FLEX|2026-01-01 20:06:00|1600/2/K/A|00.071|001420999|ALN|A1 Maassluis 17131").unwrap());
+    press(&mut app, &[KeyCode::Char('a')]);
+    let rendered = render(&mut app)
+        .lines()
+        .map(|line| match line.find("ACK ") {
+            // Acknowledgement times are wall-clock
+            Some(at) => format!("{}ACK hh:mm:ss{}\n", &line[..at], &line[at + 12..]),
+            None => format!("{}\n", line),
+        })
+        .collect::<String>();
+    assert_golden("pager_pane", &rendered);
+    std::fs::remove_file(log).ok();
+}
+
+#[test]
+fn acknowledge_and_resolve() {
+    let mut app = app();
+    let pending = app.state.pending_count();
+    press(&mut app, &[KeyCode::Char('k'), KeyCode::Down, KeyCode::Char('x')]);
+    assert_eq!(app.state.pending_count(), pending - 2);
+    assert_golden("acknowledge_and_resolve", &render(&mut app));
+}
+
+#[test]
+fn note_on_message() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Down, KeyCode::Char('n')]);
+    press(&mut app, &"Cancelled en route".chars().map(KeyCode::Char).collect::<Vec<_>>());
+    press(&mut app, &[KeyCode::Enter]);
+    assert_golden("note_on_message", &render(&mut app));
+}
+
+#[test]
+fn gap_markers() {
+    let mut app = app().with_gaps(&GapConfig {
+        threshold_secs: 30,
+        alert: false,
+    });
+    press(&mut app, &[KeyCode::PageDown, KeyCode::PageDown]);
+    assert_golden("gap_markers", &render(&mut app));
+}
+
+#[test]
+fn receiver_log_pane() {
+    let log = ProcessLog::new();
+    log.push("rtl_fm: Found 1 device(s)");
+    log.push("multimon-ng: exit status: 1");
+    log.push("Restarting receiver in 2s");
+    let mut app = app().with_process_log(log);
+    assert_golden("receiver_log_collapsed", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('L')]);
+    assert_golden("receiver_log_expanded", &render(&mut app));
+}
+
+#[test]
+fn redraws_only_on_changes() {
+    let log = ProcessLog::new();
+    let mut app = app().with_idle_fps(0.0).with_process_log(log.clone());
+    let start = std::time::Instant::now();
+    assert!(app.needs_redraw(start));
+    render(&mut app);
+    assert!(!app.needs_redraw(start));
+    press(&mut app, &[KeyCode::Down]);
+    assert!(app.needs_redraw(start));
+    render(&mut app);
+    log.push("rtl_fm: Found 1 device(s)");
+    assert!(app.needs_redraw(start));
+    render(&mut app);
+
+    // The idle frame rate redraws once a frame has passed
+    let app = app.with_idle_fps(10.0);
+    assert!(!app.needs_redraw(std::time::Instant::now()));
+    assert!(app.needs_redraw(start - std::time::Duration::from_millis(100)));
+}
+
+#[test]
+fn low_power_mode() {
+    let mut app = app()
+        .with_poll_interval(std::time::Duration::from_millis(100))
+        .with_low_power(true);
+    assert_eq!(app.poll_interval(), std::time::Duration::from_millis(500));
+    let count = app.state.messages().len();
+    let parser = Parser::new();
+    app.push(parser.parse_line("FLEX|2026-01-01 20:05:00|1600/2/K/A|00.070|001420028|ALN|A1 AMBU 17130 Vlaardingen").unwrap());
+    assert!(!app.is_flashing(count));
+    // Capcode-only enrichment still finds the region
+    assert_eq!(app.state.region(count), Some("Rotterdam-Rijnmond"));
+}
+
+#[test]
+fn missing_data_banner() {
+    let mut app = app().with_data_files(DataFiles::in_dir(Path::new("no-such-dir")));
+    assert_golden("missing_data_banner", &render(&mut app));
+    // Still missing: reloading keeps the banner and says so
+    press(&mut app, &[KeyCode::Char('D')]);
+    assert_eq!(app.status.as_deref(), Some("Loaded the data files that are present"));
+}
+
+#[test]
+fn abbreviation_popup() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('a'), KeyCode::Char('r'), KeyCode::Char('e'), KeyCode::Char('a')]);
+    assert_eq!(app.abbreviation_query.as_deref(), Some("rea"));
+    assert_golden("abbreviation_popup", &render(&mut app));
+    // Esc only closes the popup
+    assert!(!app.handle_input(KeyCode::Esc));
+    assert!(app.abbreviation_query.is_none());
+}
+
+#[test]
+fn provenance_panel() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('p')]);
+    assert_golden("provenance_panel", &render(&mut app));
+}
+
+#[test]
+fn redacted_view() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('R')]);
+    assert_golden("redacted_view", &render(&mut app));
+}
+
+#[test]
+fn shared_view_link() {
+    let view = View::parse("p2000:view?region=Brabant%20Zuid-Oost&region_column=0").unwrap();
+    let mut app = app().with_view(Some(view));
+    assert_golden("shared_view_link", &render(&mut app));
+    assert_eq!(app.view().to_string(), "p2000:view?region=Brabant%20Zuid-Oost&region_column=0");
+}
+
+#[test]
+fn kiosk_mode() {
+    let mut app = app().with_kiosk(true);
+    press(&mut app, &[KeyCode::Char('q'), KeyCode::Char('g'), KeyCode::Esc]);
+    assert!(!app.handle_input(KeyCode::Char('q')));
+    // The clock is the last thing on the header row
+    let screen = render(&mut app);
+    let mut lines: Vec<String> = screen.lines().map(str::to_string).collect();
+    let clock = lines[2].len() - "HH:MM│".len();
+    lines[2].replace_range(clock..clock + 5, "HH:MM");
+    assert_golden("kiosk_mode", &(lines.join("\n") + "\n"));
+}
+
+#[test]
+fn split_layout_with_map() {
+    let mut app = app();
+    render(&mut app);
+    press(&mut app, &[KeyCode::Char('v'), KeyCode::Down, KeyCode::Down]);
+    assert_golden("split_layout_with_map", &render(&mut app));
+}
+
+#[test]
+fn collapsed_repeats() {
+    let mut app = app().with_collapse_repeats(true);
+    let parser = Parser::new();
+    let repeat = "FLEX|2026-01-01 20:10:01|1600/2/K/A|00.051|000726119|ALN|A2 Utrecht 630 herhaling";
+    for _ in 0..3 {
+        app.push(parser.parse_line(repeat).unwrap());
+    }
+    let shown = app.state.filtered_len();
+    press(&mut app, &[KeyCode::PageDown, KeyCode::PageDown, KeyCode::PageDown, KeyCode::PageDown]);
+    assert_golden("collapsed_repeats", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('z')]);
+    assert_eq!(app.state.filtered_len(), shown + 2);
+}
+
+#[test]
+fn daily_totals() {
+    let mut app = app().with_totals(true);
+    let parser = Parser::new();
+    app.push(parser.parse_line("FLEX|2026-01-01 20:12:00|1600/2/K/A|00.037|002029568|ALN|A1 Reanimatie Vlaardingen").unwrap());
+    assert_golden("daily_totals", &render(&mut app));
+}
+
+#[test]
+fn stats_screen() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('S')]);
+    assert_golden("stats_screen", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('S'), KeyCode::Char('S')]);
+    assert!(app.status.is_none());
+    assert!(!app.handle_input(KeyCode::Char('S')) && !app.handle_input(KeyCode::Esc));
+}
+
+#[test]
+fn unit_board() {
+    let mut app = app();
+    press(&mut app, &[KeyCode::Char('U')]);
+    assert_golden("unit_board", &render(&mut app));
+    assert!(!app.handle_input(KeyCode::Esc));
+    assert_golden("list", &render(&mut app));
+}
+
+#[test]
+fn unit_cross_reference() {
+    let mut app = app();
+    let parser = Parser::new();
+    app.push(
+        parser
+            .parse_line("FLEX|2026-01-01 20:12:00|1600/2/K/A|00.070|001123117|ALN|A1 AMBU 17128 Schiedamseweg Schiedam 170431")
+            .unwrap(),
+    );
+    app.state.select(app.state.filtered_len() - 1);
+    press(&mut app, &[KeyCode::Char('X')]);
+    assert_golden("unit_cross_reference", &render(&mut app));
+    press(&mut app, &[KeyCode::Char('X')]);
+    assert!(render(&mut app).contains("Unit 17-0431: 2 dispatches (unit 2 of 2 in this message)"));
+    press(&mut app, &[KeyCode::Char('X')]);
+    assert!(render(&mut app).contains("Navigate"));
+
+    // "A1 Duizel Rit: 461" names no unit
+    app.state.select(1);
+    press(&mut app, &[KeyCode::Char('X')]);
+    assert_eq!(app.status.as_deref(), Some("No unit callsigns in this message"));
+}